@@ -5,6 +5,7 @@
 #include <cstdio>
 #include <cstring>
 #include <array>
+#include <vector>
 #include <functional>
 #include <limits>
 #include <iostream>
@@ -44,6 +45,7 @@
 #include "rust_interface/ThreadSafeObserver.hpp"
 #include "rust_interface/TestMultiThreadCallback.hpp"
 #include "rust_interface/Session.hpp"
+#include "rust_interface/TestIterator.hpp"
 
 using namespace rust;
 
@@ -866,6 +868,27 @@ TEST(TestReferences, smokeTest)
 
 TEST(TestOnlyStaticMethods, smokeTest) { EXPECT_EQ(4, TestOnlyStaticMethods::add_func(2, 2)); }
 
+TEST(TestIterator, smokeTest)
+{
+    auto range = TestIterator::range(3);
+    std::vector<int32_t> items;
+    for (auto it = range.begin(); it != range.end(); ++it) {
+        // element is cached, so the second dereference returns the same value
+        EXPECT_EQ(*it, *it);
+        items.push_back(*it);
+    }
+    EXPECT_EQ((std::vector<int32_t>{ 0, 1, 2 }), items);
+
+    auto names = TestIterator::names(2);
+    auto it = names.begin();
+    EXPECT_EQ("0", it->to_std_string());
+    EXPECT_EQ("0", (*it++).to_std_string());
+    EXPECT_EQ("1", (*it).to_std_string());
+    EXPECT_EQ("1", (*it).to_std_string());
+    ++it;
+    EXPECT_TRUE(it == names.end());
+}
+
 #if (defined(HAS_STDCXX_17) && !defined(NO_HAVE_STD17_VARIANT)) || defined(USE_BOOST)
 TEST(TestDummyConstructor, smokeTest)
 {
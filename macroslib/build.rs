@@ -105,6 +105,9 @@ fn test_expectation_{test_name}() {{
            test_something = true;
        }}
    }}
+   if check_python_expectation(&test_name, &test_case) {{
+       test_something = true;
+   }}
    assert!(test_something, "empty test");
 }}
 "##,
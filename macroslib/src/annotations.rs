@@ -0,0 +1,1054 @@
+//! Alternative to `foreign_class!`, `foreign_enum!`, `foreign_fn!` and `foreign_callback!`:
+//! items of ordinary Rust code marked with `#[flapigen::export]`
+//! or `#[flapigen::callback]` attributes
+
+use std::convert::TryInto;
+
+use proc_macro2::Span;
+use quote::ToTokens;
+use syn::{
+    parse_quote, punctuated::Punctuated, spanned::Spanned, visit_mut::VisitMut, Ident, Type,
+};
+
+use crate::{
+    code_parse::{
+        apply_camel_case_aliases, check_enum_items_values, is_enabled_by_cfg, parse_const_value,
+        parse_deprecated, parse_enum_discriminant, parse_fn_args,
+    },
+    error::{DiagnosticError, Result},
+    source_registry::SourceId,
+    typemap::ast::normalize_type,
+    types::{
+        Deprecation, FnArg, FnDecl, ForeignClassInfo, ForeignConst, ForeignEnumInfo,
+        ForeignEnumItem, ForeignFunction, ForeignInterface, ForeignInterfaceMethod, ForeignMethod,
+        ItemToExpand, MethodAccess, MethodVariant, SelfTypeDesc,
+    },
+    FLAGS_DERIVE,
+};
+
+const EXPORT: &str = "export";
+const CALLBACK: &str = "callback";
+const SKIP: &str = "skip";
+const CONSTRUCTOR: &str = "constructor";
+
+/// Find items with `flapigen` attributes in file and its inline modules,
+/// `mod_path` is path of file's module relative to the scanned directory.
+/// Several `impl` blocks for the same type are merged into one class.
+pub(crate) fn parse_annotated_items(
+    src_id: SourceId,
+    mod_path: &[Ident],
+    file: &syn::File,
+) -> Result<Vec<ItemToExpand>> {
+    let mut ret = Vec::new();
+    let mut mod_path = mod_path.to_vec();
+    parse_items(src_id, &mut mod_path, &file.items, &mut ret)?;
+    // the whole class is affected, not only `impl` block with attribute
+    for item in &mut ret {
+        if let ItemToExpand::Class(ref mut fclass) = item {
+            apply_camel_case_aliases(&mut fclass.derive_list, &mut fclass.methods);
+        }
+    }
+    Ok(ret)
+}
+
+fn parse_items(
+    src_id: SourceId,
+    mod_path: &mut Vec<Ident>,
+    items: &[syn::Item],
+    ret: &mut Vec<ItemToExpand>,
+) -> Result<()> {
+    let syn_err = |err| DiagnosticError::from_syn_err(src_id, err);
+    for item in items {
+        if !is_item_enabled_by_cfg(item).map_err(syn_err)? {
+            continue;
+        }
+        let parsed = match item {
+            syn::Item::Mod(item_mod) => {
+                // module in separate file is handled during scan of directory
+                if let Some((_, ref mod_items)) = item_mod.content {
+                    mod_path.push(item_mod.ident.clone());
+                    parse_items(src_id, mod_path, mod_items, ret)?;
+                    mod_path.pop();
+                }
+                continue;
+            }
+            syn::Item::Impl(item_impl) => {
+                match find_flapigen_attr(&item_impl.attrs, EXPORT).map_err(syn_err)? {
+                    Some(args) => {
+                        let mut fclass = parse_impl(item_impl, args, mod_path).map_err(syn_err)?;
+                        fclass.src_id = src_id;
+                        merge_class(ret, fclass)?;
+                        continue;
+                    }
+                    None => continue,
+                }
+            }
+            syn::Item::Enum(item_enum) => {
+                match find_flapigen_attr(&item_enum.attrs, EXPORT).map_err(syn_err)? {
+                    Some(args) => {
+                        let mut fenum = parse_enum(item_enum, args, mod_path).map_err(syn_err)?;
+                        fenum.src_id = src_id;
+                        ItemToExpand::Enum(fenum)
+                    }
+                    None => continue,
+                }
+            }
+            syn::Item::Const(item_const) => {
+                match find_flapigen_attr(&item_const.attrs, EXPORT).map_err(syn_err)? {
+                    Some(_) => {
+                        let mut fconst = parse_const(item_const).map_err(syn_err)?;
+                        fconst.src_id = src_id;
+                        ItemToExpand::Const(Box::new(fconst))
+                    }
+                    None => continue,
+                }
+            }
+            syn::Item::Fn(item_fn) => {
+                match find_flapigen_attr(&item_fn.attrs, EXPORT).map_err(syn_err)? {
+                    Some(args) => {
+                        let method = parse_fn(item_fn, args, mod_path).map_err(syn_err)?;
+                        ItemToExpand::Fn(Box::new(ForeignFunction { src_id, method }))
+                    }
+                    None => continue,
+                }
+            }
+            syn::Item::Trait(item_trait) => {
+                match find_flapigen_attr(&item_trait.attrs, CALLBACK).map_err(syn_err)? {
+                    Some(_) => {
+                        let mut finterface = parse_trait(item_trait, mod_path).map_err(syn_err)?;
+                        finterface.src_id = src_id;
+                        ItemToExpand::Interface(finterface)
+                    }
+                    None => continue,
+                }
+            }
+            _ => continue,
+        };
+        ret.push(parsed);
+    }
+    Ok(())
+}
+
+/// Generated code is not inside module of item, so path to item
+/// should be relative to the scanned directory
+fn path_in_module(mod_path: &[Ident], path: syn::Path) -> syn::Path {
+    let is_absolute =
+        path.leading_colon.is_some() || path.segments.first().is_some_and(|x| x.ident == "crate");
+    if mod_path.is_empty() || is_absolute {
+        return path;
+    }
+    let mut mod_path = mod_path.to_vec();
+    let mut segments = path.segments.into_iter().peekable();
+    while let Some(first) = segments.peek() {
+        if first.ident == "self" {
+            segments.next();
+        } else if first.ident == "super" && !mod_path.is_empty() {
+            mod_path.pop();
+            segments.next();
+        } else {
+            break;
+        }
+    }
+    syn::Path {
+        leading_colon: None,
+        segments: mod_path
+            .into_iter()
+            .map(syn::PathSegment::from)
+            .chain(segments)
+            .collect(),
+    }
+}
+
+/// Arguments of `#[flapigen::export(name = "ForeignName", derive(Clone))]`
+#[derive(Default)]
+struct ExportArgs {
+    name: Option<Ident>,
+    derive_list: Vec<String>,
+}
+
+fn is_flapigen_path(path: &syn::Path, name: &str) -> bool {
+    path.segments.len() == 2
+        && path.segments[0].ident == "flapigen"
+        && path.segments[1].ident == name
+}
+
+/// Find `#[flapigen::name]` or `#[cfg_attr(cond, flapigen::name)]`
+fn find_flapigen_attr(attrs: &[syn::Attribute], name: &str) -> syn::Result<Option<ExportArgs>> {
+    for attr in attrs {
+        if is_flapigen_path(&attr.path, name) {
+            return Ok(Some(parse_export_args(attr.parse_meta()?)?));
+        }
+        if attr.path.is_ident("cfg_attr") {
+            if let syn::Meta::List(list) = attr.parse_meta()? {
+                for nested in list.nested.into_iter().skip(1) {
+                    if let syn::NestedMeta::Meta(meta) = nested {
+                        if is_flapigen_path(meta.path(), name) {
+                            return Ok(Some(parse_export_args(meta)?));
+                        }
+                    }
+                }
+            }
+        }
+    }
+    Ok(None)
+}
+
+fn parse_export_args(meta: syn::Meta) -> syn::Result<ExportArgs> {
+    let mut args = ExportArgs::default();
+    let list = match meta {
+        syn::Meta::Path(_) => return Ok(args),
+        syn::Meta::List(list) => list,
+        syn::Meta::NameValue(x) => {
+            return Err(syn::Error::new(x.span(), "expect list of arguments"));
+        }
+    };
+    for nested in list.nested {
+        match nested {
+            syn::NestedMeta::Meta(syn::Meta::NameValue(syn::MetaNameValue {
+                ref path,
+                lit: syn::Lit::Str(ref lit_str),
+                ..
+            })) if path.is_ident("name") => {
+                args.name = Some(lit_str.parse()?);
+            }
+            syn::NestedMeta::Meta(syn::Meta::List(syn::MetaList {
+                ref path,
+                ref nested,
+                ..
+            })) if path.is_ident("derive") => {
+                for x in nested {
+                    if let syn::NestedMeta::Meta(syn::Meta::Path(ref path)) = x {
+                        args.derive_list.push(path.into_token_stream().to_string());
+                    } else {
+                        return Err(syn::Error::new(x.span(), "Invalid derive format"));
+                    }
+                }
+            }
+            x => {
+                return Err(syn::Error::new(
+                    x.span(),
+                    "expect `name = \"ForeignName\"` or `derive(...)`",
+                ));
+            }
+        }
+    }
+    Ok(args)
+}
+
+/// Items disabled by `#[cfg]` do not exist for Rust compiler, so skip them too
+fn is_item_enabled_by_cfg(item: &syn::Item) -> syn::Result<bool> {
+    let attrs = match item {
+        syn::Item::Impl(x) => &x.attrs,
+        syn::Item::Enum(x) => &x.attrs,
+        syn::Item::Const(x) => &x.attrs,
+        syn::Item::Fn(x) => &x.attrs,
+        syn::Item::Trait(x) => &x.attrs,
+        syn::Item::Mod(x) => &x.attrs,
+        _ => return Ok(true),
+    };
+    is_enabled_by_cfg(attrs)
+}
+
+fn has_flapigen_attr(attrs: &[syn::Attribute], name: &str) -> bool {
+    attrs.iter().any(|a| is_flapigen_path(&a.path, name))
+}
+
+fn doc_comments(attrs: &[syn::Attribute]) -> Vec<String> {
+    attrs
+        .iter()
+        .filter_map(|a| match a.parse_meta() {
+            Ok(syn::Meta::NameValue(syn::MetaNameValue {
+                ref path,
+                lit: syn::Lit::Str(ref lit_str),
+                ..
+            })) if path.is_ident("doc") => Some(lit_str.value()),
+            _ => None,
+        })
+        .collect()
+}
+
+fn deprecated(attrs: &[syn::Attribute]) -> syn::Result<Option<Deprecation>> {
+    match attrs.iter().find(|a| a.path.is_ident("deprecated")) {
+        Some(attr) => Ok(Some(parse_deprecated(&attr.parse_meta()?)?)),
+        None => Ok(None),
+    }
+}
+
+/// Replace `Self` with real type, because of generated code is not inside `impl`
+/// Replace `Self` and type of `impl` as it written in module of item
+/// with path to type relative to the scanned directory
+struct ReplaceSelf<'a> {
+    raw_self_type: &'a Type,
+    self_type: &'a Type,
+}
+
+impl<'a> VisitMut for ReplaceSelf<'a> {
+    fn visit_type_mut(&mut self, ty: &mut Type) {
+        if let Type::Path(syn::TypePath { qself: None, path }) = ty {
+            if path.is_ident("Self") || *ty == *self.raw_self_type {
+                *ty = self.self_type.clone();
+                return;
+            }
+        }
+        syn::visit_mut::visit_type_mut(self, ty);
+    }
+}
+
+fn parse_impl(
+    item_impl: &syn::ItemImpl,
+    args: ExportArgs,
+    mod_path: &[Ident],
+) -> syn::Result<ForeignClassInfo> {
+    if let Some((_, ref trait_path, _)) = item_impl.trait_ {
+        return Err(syn::Error::new(
+            trait_path.span(),
+            "export of trait implementation is not supported",
+        ));
+    }
+    if !item_impl.generics.params.is_empty() {
+        return Err(syn::Error::new(
+            item_impl.generics.span(),
+            "export of generic impl is not supported",
+        ));
+    }
+    let raw_self_type: Type = (*item_impl.self_ty).clone();
+    let type_path = match raw_self_type {
+        Type::Path(syn::TypePath {
+            qself: None,
+            ref path,
+        }) if path.segments.last().is_some_and(|x| x.arguments.is_empty()) => path.clone(),
+        _ => {
+            return Err(syn::Error::new(
+                raw_self_type.span(),
+                "expect name of type without generic parameters",
+            ));
+        }
+    };
+    let class_name = args
+        .name
+        .unwrap_or_else(|| type_path.segments.last().unwrap().ident.clone());
+    let derive_list = args.derive_list;
+    let type_path = path_in_module(mod_path, type_path);
+    let self_type = Type::Path(syn::TypePath {
+        qself: None,
+        path: type_path.clone(),
+    });
+
+    let mut methods = Vec::new();
+    let mut consts = Vec::new();
+    let mut constructor_ret_type = None;
+    for item in &item_impl.items {
+        let method = match item {
+            syn::ImplItem::Method(method) if !is_enabled_by_cfg(&method.attrs)? => continue,
+            syn::ImplItem::Method(method) => method,
+            // constants with not literal value can not be exported, so skip them
+            syn::ImplItem::Const(item_const) => {
+                let is_pub = matches!(item_const.vis, syn::Visibility::Public(_));
+                if is_pub
+                    && !has_flapigen_attr(&item_const.attrs, SKIP)
+                    && is_enabled_by_cfg(&item_const.attrs)?
+                {
+                    if let Ok(value) = parse_const_value(&item_const.expr) {
+                        consts.push(ForeignConst {
+                            src_id: SourceId::none(),
+                            name: item_const.ident.clone(),
+                            ty: item_const.ty.clone(),
+                            rust_path: None,
+                            value,
+                            doc_comments: doc_comments(&item_const.attrs),
+                        });
+                    }
+                }
+                continue;
+            }
+            _ => continue,
+        };
+        let is_pub = matches!(method.vis, syn::Visibility::Public(_));
+        if !is_pub || has_flapigen_attr(&method.attrs, SKIP) {
+            continue;
+        }
+        let sig = &method.sig;
+        if !sig.generics.params.is_empty() {
+            return Err(syn::Error::new(
+                sig.generics.span(),
+                "export of generic method is not supported",
+            ));
+        }
+        if sig.asyncness.is_some() || sig.unsafety.is_some() || sig.abi.is_some() {
+            return Err(syn::Error::new(
+                sig.fn_token.span(),
+                "export of async, unsafe or extern method is not supported",
+            ));
+        }
+        let mut inputs = sig.inputs.clone();
+        let mut output = sig.output.clone();
+        {
+            let mut replace_self = ReplaceSelf {
+                raw_self_type: &raw_self_type,
+                self_type: &self_type,
+            };
+            for arg in &mut inputs {
+                replace_self.visit_fn_arg_mut(arg);
+            }
+            replace_self.visit_return_type_mut(&mut output);
+        }
+        let inputs = parse_fn_args(inputs)?.0;
+        let variant = match inputs.first() {
+            Some(FnArg::SelfArg(_, self_variant)) => MethodVariant::Method(*self_variant),
+            _ => {
+                let ret_self = match output {
+                    syn::ReturnType::Type(_, ref ty) => {
+                        normalize_type(ty) == normalize_type(&self_type)
+                    }
+                    syn::ReturnType::Default => false,
+                };
+                if ret_self || has_flapigen_attr(&method.attrs, CONSTRUCTOR) {
+                    MethodVariant::Constructor
+                } else {
+                    MethodVariant::StaticMethod
+                }
+            }
+        };
+        if variant == MethodVariant::Constructor {
+            let ret_type = match output {
+                syn::ReturnType::Type(_, ref ty) => (**ty).clone(),
+                syn::ReturnType::Default => {
+                    return Err(syn::Error::new(
+                        sig.ident.span(),
+                        "constructor should return value",
+                    ));
+                }
+            };
+            match constructor_ret_type {
+                Some(ref prev_ty) if normalize_type(prev_ty) != normalize_type(&ret_type) => {
+                    return Err(syn::Error::new(
+                        ret_type.span(),
+                        format!(
+                            "mismatched types of construtors: got {} expect {}",
+                            ret_type.into_token_stream(),
+                            prev_ty.into_token_stream()
+                        ),
+                    ));
+                }
+                Some(_) => {}
+                None => constructor_ret_type = Some(ret_type),
+            }
+        }
+        let mut rust_id = type_path.clone();
+        rust_id.segments.push(sig.ident.clone().into());
+        methods.push(ForeignMethod {
+            variant,
+            rust_id,
+            fn_decl: FnDecl {
+                span: sig.ident.span(),
+                inputs,
+                output,
+            },
+            name_alias: None,
+            access: MethodAccess::Public,
+            doc_comments: doc_comments(&method.attrs),
+            inline_block: None,
+            unknown_attrs: vec![],
+            property: None,
+            deprecated: deprecated(&method.attrs)?,
+        });
+    }
+
+    let has_self_methods = methods
+        .iter()
+        .any(|m| matches!(m.variant, MethodVariant::Method(_)));
+    if constructor_ret_type.is_none() && has_self_methods {
+        methods.push(dummy_constructor(item_impl.impl_token.span)?);
+        constructor_ret_type = Some(self_type.clone());
+    }
+    Ok(ForeignClassInfo {
+        src_id: SourceId::none(),
+        name: class_name,
+        methods,
+        self_desc: constructor_ret_type.map(|constructor_ret_type| SelfTypeDesc {
+            self_type,
+            constructor_ret_type,
+        }),
+        foreign_code: String::new(),
+        doc_comments: doc_comments(&item_impl.attrs),
+        derive_list,
+        fields: vec![],
+        consts,
+        is_abstract: false,
+        base_class: None,
+        deprecated: deprecated(&item_impl.attrs)?,
+        is_internal: false,
+        is_module_items_holder: false,
+    })
+}
+
+/// The same as `private constructor = empty;` in `foreign_class!`
+fn dummy_constructor(span: Span) -> syn::Result<ForeignMethod> {
+    let mut dummy_colon2: syn::Token![::] = parse_quote! { :: };
+    dummy_colon2.spans[0] = span;
+    dummy_colon2.spans[1] = span;
+    let dummy_func: syn::ItemFn = parse_quote! {
+        fn constructor() {
+        }
+    };
+    Ok(ForeignMethod {
+        variant: MethodVariant::Constructor,
+        rust_id: syn::Path {
+            leading_colon: Some(dummy_colon2),
+            segments: Punctuated::new(),
+        },
+        fn_decl: dummy_func.sig.try_into()?,
+        name_alias: None,
+        access: MethodAccess::Private,
+        doc_comments: vec![],
+        inline_block: None,
+        unknown_attrs: vec![],
+        property: None,
+        deprecated: None,
+    })
+}
+
+fn merge_class(items: &mut Vec<ItemToExpand>, fclass: ForeignClassInfo) -> Result<()> {
+    let prev = items.iter_mut().find_map(|x| match x {
+        ItemToExpand::Class(ref mut prev) if prev.name == fclass.name => Some(prev),
+        _ => None,
+    });
+    let prev = match prev {
+        Some(x) => x,
+        None => {
+            items.push(ItemToExpand::Class(Box::new(fclass)));
+            return Ok(());
+        }
+    };
+    let ForeignClassInfo {
+        src_id,
+        name,
+        methods,
+        self_desc,
+        doc_comments,
+        derive_list,
+        consts,
+        ..
+    } = fclass;
+    match (&mut prev.self_desc, self_desc) {
+        (Some(ref prev_desc), Some(ref desc))
+            if normalize_type(&prev_desc.constructor_ret_type)
+                != normalize_type(&desc.constructor_ret_type) =>
+        {
+            return Err(DiagnosticError::new(
+                src_id,
+                name.span(),
+                "mismatched types of construtors in several impl blocks",
+            )
+            .add_span_note((prev.src_id, prev.name.span()), "previous impl block"));
+        }
+        (prev_desc @ None, desc @ Some(_)) => *prev_desc = desc,
+        _ => {}
+    }
+    prev.methods.extend(methods);
+    prev.consts.extend(consts);
+    // only one dummy constructor is required
+    let mut has_dummy = false;
+    prev.methods.retain(|m| {
+        if m.is_dummy_constructor() {
+            let first = !has_dummy;
+            has_dummy = true;
+            first
+        } else {
+            true
+        }
+    });
+    let has_real_constructor = prev
+        .methods
+        .iter()
+        .any(|m| m.variant == MethodVariant::Constructor && !m.is_dummy_constructor());
+    if has_real_constructor {
+        prev.methods.retain(|m| !m.is_dummy_constructor());
+    }
+    prev.doc_comments.extend(doc_comments);
+    for derive in derive_list {
+        if !prev.derive_list.contains(&derive) {
+            prev.derive_list.push(derive);
+        }
+    }
+    Ok(())
+}
+
+fn parse_enum(
+    item_enum: &syn::ItemEnum,
+    args: ExportArgs,
+    mod_path: &[Ident],
+) -> syn::Result<ForeignEnumInfo> {
+    if !item_enum.generics.params.is_empty() {
+        return Err(syn::Error::new(
+            item_enum.generics.span(),
+            "export of generic enum is not supported",
+        ));
+    }
+    if args.derive_list.iter().any(|x| x == FLAGS_DERIVE) {
+        return Err(syn::Error::new(
+            item_enum.ident.span(),
+            "Flags is supported only for bitflags types described via foreign_enum!",
+        ));
+    }
+    let enum_ident = &item_enum.ident;
+    let mut items = Vec::with_capacity(item_enum.variants.len());
+    for variant in &item_enum.variants {
+        if !is_enabled_by_cfg(&variant.attrs)? {
+            continue;
+        }
+        if variant.fields != syn::Fields::Unit {
+            return Err(syn::Error::new(
+                variant.span(),
+                "only enums without data are supported",
+            ));
+        }
+        let variant_ident = &variant.ident;
+        let value = match variant.discriminant {
+            Some((_, ref expr)) => Some(parse_enum_discriminant(expr)?),
+            None => None,
+        };
+        items.push(ForeignEnumItem {
+            name: variant_ident.clone(),
+            rust_name: path_in_module(mod_path, parse_quote! { #enum_ident::#variant_ident }),
+            doc_comments: doc_comments(&variant.attrs),
+            fields: syn::Fields::Unit,
+            value,
+            deprecated: deprecated(&variant.attrs)?,
+        });
+    }
+    let enum_info = ForeignEnumInfo {
+        src_id: SourceId::none(),
+        name: args.name.unwrap_or_else(|| enum_ident.clone()),
+        items,
+        doc_comments: doc_comments(&item_enum.attrs),
+        derive_list: args.derive_list,
+    };
+    check_enum_items_values(&enum_info)?;
+    Ok(enum_info)
+}
+
+fn parse_const(item_const: &syn::ItemConst) -> syn::Result<ForeignConst> {
+    Ok(ForeignConst {
+        src_id: SourceId::none(),
+        name: item_const.ident.clone(),
+        ty: (*item_const.ty).clone(),
+        rust_path: None,
+        value: parse_const_value(&item_const.expr)?,
+        doc_comments: doc_comments(&item_const.attrs),
+    })
+}
+
+/// Top level function is exported as function at module level,
+/// like one from `foreign_fn!`
+fn parse_fn(
+    item_fn: &syn::ItemFn,
+    args: ExportArgs,
+    mod_path: &[Ident],
+) -> syn::Result<ForeignMethod> {
+    let sig = &item_fn.sig;
+    if !matches!(item_fn.vis, syn::Visibility::Public(_)) {
+        return Err(syn::Error::new(
+            sig.fn_token.span(),
+            "exported function should be public",
+        ));
+    }
+    if !sig.generics.params.is_empty() {
+        return Err(syn::Error::new(
+            sig.generics.span(),
+            "export of generic function is not supported",
+        ));
+    }
+    if sig.asyncness.is_some() || sig.unsafety.is_some() || sig.abi.is_some() {
+        return Err(syn::Error::new(
+            sig.fn_token.span(),
+            "export of async, unsafe or extern function is not supported",
+        ));
+    }
+    let inputs = parse_fn_args(sig.inputs.clone())?.0;
+    Ok(ForeignMethod {
+        variant: MethodVariant::StaticMethod,
+        rust_id: path_in_module(mod_path, sig.ident.clone().into()),
+        fn_decl: FnDecl {
+            span: sig.ident.span(),
+            inputs,
+            output: sig.output.clone(),
+        },
+        name_alias: args.name,
+        access: MethodAccess::Public,
+        doc_comments: doc_comments(&item_fn.attrs),
+        inline_block: None,
+        unknown_attrs: vec![],
+        property: None,
+        deprecated: deprecated(&item_fn.attrs)?,
+    })
+}
+
+fn parse_trait(item_trait: &syn::ItemTrait, mod_path: &[Ident]) -> syn::Result<ForeignInterface> {
+    if !item_trait.generics.params.is_empty() {
+        return Err(syn::Error::new(
+            item_trait.generics.span(),
+            "generic trait can not be callback",
+        ));
+    }
+    let trait_ident = &item_trait.ident;
+    let trait_path = path_in_module(mod_path, trait_ident.clone().into());
+    let mut items = Vec::new();
+    for item in &item_trait.items {
+        let method = match item {
+            syn::TraitItem::Method(method) if is_enabled_by_cfg(&method.attrs)? => method,
+            _ => continue,
+        };
+        let sig = &method.sig;
+        let inputs = parse_fn_args(sig.inputs.clone())?.0;
+        match inputs.first() {
+            Some(FnArg::SelfArg(..)) => {}
+            _ => {
+                return Err(syn::Error::new(
+                    sig.ident.span(),
+                    "expect &self or &mut self as first argument",
+                ));
+            }
+        }
+        let method_ident = &sig.ident;
+        items.push(ForeignInterfaceMethod {
+            name: method_ident.clone(),
+            rust_name: parse_quote! { #trait_path::#method_ident },
+            fn_decl: FnDecl {
+                span: sig.ident.span(),
+                inputs,
+                output: sig.output.clone(),
+            },
+            doc_comments: doc_comments(&method.attrs),
+            deprecated: deprecated(&method.attrs)?,
+        });
+    }
+    Ok(ForeignInterface {
+        src_id: SourceId::none(),
+        name: trait_ident.clone(),
+        self_type: parse_quote! { #trait_path },
+        doc_comments: doc_comments(&item_trait.attrs),
+        items,
+        closure: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{ForeignConstValue, SelfTypeVariant};
+
+    #[test]
+    fn test_parse_annotated_items() {
+        let file: syn::File = parse_quote! {
+            /// Foo class
+            #[flapigen::export(derive(camelCaseAliases))]
+            impl Foo {
+                pub fn new(x: i32) -> Self { Foo(x) }
+                pub fn get_x(&self) -> i32 { self.0 }
+                fn private_helper(&self) {}
+                #[flapigen::skip]
+                pub fn skipped(&self) {}
+            }
+
+            #[cfg_attr(feature = "bindings", flapigen::export)]
+            impl Foo {
+                pub fn set_x(&mut self, x: i32) { self.0 = x; }
+                pub fn from_other(o: &Self) -> Vec<Self> { vec![Foo(o.0)] }
+            }
+
+            #[flapigen::export]
+            impl Boo {
+                pub fn f(&self) {}
+            }
+
+            #[flapigen::export(name = "FMode")]
+            enum Mode {
+                /// fast
+                Fast,
+                Slow,
+            }
+
+            #[flapigen::callback]
+            trait Observer: Send {
+                fn on_event(&self, x: i32) -> bool;
+            }
+
+            impl Moo {
+                pub fn g(&self) {}
+            }
+        };
+        let items = parse_annotated_items(SourceId::none(), &[], &file).unwrap();
+        assert_eq!(4, items.len());
+
+        let foo = match items[0] {
+            ItemToExpand::Class(ref x) => x,
+            _ => panic!("expect class"),
+        };
+        assert_eq!("Foo", foo.name.to_string());
+        assert_eq!(vec![" Foo class".to_string()], foo.doc_comments);
+        assert!(foo.derive_list.is_empty());
+        let methods: Vec<_> = foo
+            .methods
+            .iter()
+            .map(|m| (m.short_name(), m.variant))
+            .collect();
+        assert_eq!(
+            vec![
+                ("new".to_string(), MethodVariant::Constructor),
+                ("getX".into(), MethodVariant::Method(SelfTypeVariant::Rptr)),
+                (
+                    "setX".into(),
+                    MethodVariant::Method(SelfTypeVariant::RptrMut)
+                ),
+                ("fromOther".into(), MethodVariant::StaticMethod),
+            ],
+            methods
+        );
+        assert_eq!(
+            "Foo",
+            normalize_type(&foo.self_desc.as_ref().unwrap().constructor_ret_type)
+        );
+        match foo.methods[3].fn_decl.inputs[0] {
+            FnArg::Default(ref arg) => {
+                assert_eq!("& Foo", arg.ty.to_token_stream().to_string())
+            }
+            _ => panic!("expect argument"),
+        }
+        assert_eq!(
+            "-> Vec < Foo >",
+            foo.methods[3].fn_decl.output.to_token_stream().to_string()
+        );
+
+        let boo = match items[1] {
+            ItemToExpand::Class(ref x) => x,
+            _ => panic!("expect class"),
+        };
+        assert!(boo.methods[1].is_dummy_constructor());
+        assert_eq!(
+            "Boo",
+            normalize_type(&boo.self_desc.as_ref().unwrap().self_type)
+        );
+
+        let fenum = match items[2] {
+            ItemToExpand::Enum(ref x) => x,
+            _ => panic!("expect enum"),
+        };
+        assert_eq!("FMode", fenum.name.to_string());
+        assert_eq!(
+            "Mode :: Slow",
+            fenum.items[1].rust_name.to_token_stream().to_string()
+        );
+        assert_eq!(vec![" fast".to_string()], fenum.items[0].doc_comments);
+
+        let callback = match items[3] {
+            ItemToExpand::Interface(ref x) => x,
+            _ => panic!("expect callback"),
+        };
+        assert_eq!("Observer", callback.name.to_string());
+        assert_eq!(
+            "Observer :: on_event",
+            callback.items[0].rust_name.to_token_stream().to_string()
+        );
+    }
+
+    #[test]
+    fn test_parse_annotated_items_in_modules() {
+        let file: syn::File = parse_quote! {
+            #[flapigen::export]
+            impl Foo {
+                pub fn new() -> Self { Foo }
+            }
+            mod inner {
+                #[flapigen::export]
+                pub fn f() {}
+                #[flapigen::export]
+                impl super::Boo {
+                    pub fn g(&self) {}
+                }
+                #[flapigen::callback]
+                trait Observer {
+                    fn on_event(&self, x: i32);
+                }
+                #[cfg(feature = "flapigen-test-off")]
+                mod disabled {
+                    #[flapigen::export]
+                    pub fn disabled_fn() {}
+                }
+            }
+        };
+        let mod_path = [Ident::new("outer", Span::call_site())];
+        let items = parse_annotated_items(SourceId::none(), &mod_path, &file).unwrap();
+        let rust_ids: Vec<String> = items
+            .iter()
+            .flat_map(|item| match item {
+                ItemToExpand::Class(ref x) => x.methods.iter().collect::<Vec<_>>(),
+                ItemToExpand::Fn(ref x) => vec![&x.method],
+                _ => vec![],
+            })
+            .filter(|m| !m.is_dummy_constructor())
+            .map(|m| m.rust_id.to_token_stream().to_string())
+            .collect();
+        assert_eq!(
+            vec![
+                "outer :: Foo :: new",
+                "outer :: inner :: f",
+                "outer :: Boo :: g"
+            ],
+            rust_ids
+        );
+        let self_types: Vec<String> = items
+            .iter()
+            .filter_map(|item| match item {
+                ItemToExpand::Class(ref x) => Some(x.self_desc.as_ref().unwrap()),
+                _ => None,
+            })
+            .map(|x| {
+                format!(
+                    "{} / {}",
+                    x.self_type.to_token_stream(),
+                    x.constructor_ret_type.to_token_stream()
+                )
+            })
+            .collect();
+        assert_eq!(
+            vec!["outer :: Foo / outer :: Foo", "outer :: Boo / outer :: Boo"],
+            self_types
+        );
+        let finterface = items
+            .iter()
+            .find_map(|item| match item {
+                ItemToExpand::Interface(ref x) => Some(x),
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(
+            "outer :: inner :: Observer",
+            finterface.self_type.to_token_stream().to_string()
+        );
+        assert_eq!(
+            "outer :: inner :: Observer :: on_event",
+            finterface.items[0].rust_name.to_token_stream().to_string()
+        );
+    }
+
+    #[test]
+    fn test_parse_annotated_enum_discriminants() {
+        let file: syn::File = parse_quote! {
+            #[flapigen::export]
+            enum Opcode {
+                Ping = 0x10,
+                Pong,
+                Close = -1,
+            }
+        };
+        let items = parse_annotated_items(SourceId::none(), &[], &file).unwrap();
+        let fenum = match items[0] {
+            ItemToExpand::Enum(ref x) => x,
+            _ => panic!("expect enum"),
+        };
+        assert_eq!(vec![16, 17, -1], fenum.items_values());
+
+        let file: syn::File = parse_quote! {
+            #[flapigen::export(derive(Flags))]
+            enum Permissions {
+                Read = 1,
+            }
+        };
+        assert!(parse_annotated_items(SourceId::none(), &[], &file).is_err());
+    }
+
+    #[test]
+    fn test_parse_annotated_consts() {
+        let file: syn::File = parse_quote! {
+            #[flapigen::export]
+            impl Packet {
+                /// Max size
+                pub const MAX_SIZE: usize = 1500;
+                pub const MIN_SIZE: usize = Self::MAX_SIZE / 100;
+                const PRIVATE: usize = 1;
+                pub fn new() -> Packet {
+                    Packet
+                }
+            }
+
+            #[flapigen::export]
+            pub const VERSION: &str = "1.0";
+        };
+        let items = parse_annotated_items(SourceId::none(), &[], &file).unwrap();
+        assert_eq!(2, items.len());
+        let class = match items[0] {
+            ItemToExpand::Class(ref x) => x,
+            _ => panic!("expect class"),
+        };
+        assert_eq!(1, class.consts.len());
+        assert_eq!("MAX_SIZE", class.consts[0].name.to_string());
+        assert_eq!(ForeignConstValue::Int(1500), class.consts[0].value);
+        match items[1] {
+            ItemToExpand::Const(ref x) => {
+                assert_eq!(ForeignConstValue::Str("1.0".to_string()), x.value)
+            }
+            _ => panic!("expect const"),
+        }
+    }
+
+    #[test]
+    fn test_parse_annotated_fns() {
+        let file: syn::File = parse_quote! {
+            /// Sum of numbers
+            #[flapigen::export]
+            pub fn add(a: i32, b: i32) -> i32 {
+                a + b
+            }
+
+            #[flapigen::export(name = "writeLog")]
+            pub fn write_log(msg: &str) {}
+
+            pub fn not_exported() {}
+        };
+        let items = parse_annotated_items(SourceId::none(), &[], &file).unwrap();
+        assert_eq!(2, items.len());
+        let fns: Vec<&ForeignMethod> = items
+            .iter()
+            .map(|item| match item {
+                ItemToExpand::Fn(ref x) => &x.method,
+                _ => panic!("expect fn"),
+            })
+            .collect();
+        assert_eq!(MethodVariant::StaticMethod, fns[0].variant);
+        assert_eq!("add", fns[0].short_name().as_str());
+        assert_eq!(vec![" Sum of numbers".to_string()], fns[0].doc_comments);
+        assert_eq!("writeLog", fns[1].short_name().as_str());
+
+        let file: syn::File = parse_quote! {
+            #[flapigen::export]
+            fn private_fn() {}
+        };
+        assert!(parse_annotated_items(SourceId::none(), &[], &file).is_err());
+    }
+
+    #[test]
+    fn test_parse_annotated_items_errors() {
+        let file: syn::File = parse_quote! {
+            #[flapigen::export]
+            impl<T> Foo<T> {
+                pub fn f(&self) {}
+            }
+        };
+        assert!(parse_annotated_items(SourceId::none(), &[], &file).is_err());
+        let file: syn::File = parse_quote! {
+            #[flapigen::export]
+            enum Foo {
+                A(i32),
+            }
+        };
+        assert!(parse_annotated_items(SourceId::none(), &[], &file).is_err());
+        let file: syn::File = parse_quote! {
+            #[flapigen::callback]
+            trait Foo {
+                fn f(x: i32);
+            }
+        };
+        assert!(parse_annotated_items(SourceId::none(), &[], &file).is_err());
+    }
+}
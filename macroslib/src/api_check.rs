@@ -0,0 +1,386 @@
+//! Compare API of generated bindings with stored baseline,
+//! baseline is file written by `Generator::export_model`
+
+use std::fmt;
+
+use rustc_hash::FxHashSet;
+
+use crate::{json::JsonValue, model::MODEL_FORMAT_VERSION};
+
+/// What to do if breaking changes of API found
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ApiCheckMode {
+    /// Report breaking changes as warnings
+    Warn,
+    /// Fail code generation
+    Error,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ApiChangeKind {
+    /// Existing foreign code continues to work
+    Additive,
+    /// Existing foreign code may stop compiling or work incorrectly
+    Breaking,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApiChange {
+    pub kind: ApiChangeKind,
+    pub description: String,
+}
+
+impl fmt::Display for ApiChange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let kind = match self.kind {
+            ApiChangeKind::Additive => "additive",
+            ApiChangeKind::Breaking => "breaking",
+        };
+        write!(f, "{} change: {}", kind, self.description)
+    }
+}
+
+/// Compare two descriptions of bindings, generated by `Generator::export_model`.
+/// Only foreign types are compared, so change of Rust type that maps to the same
+/// foreign type is not reported. Private methods are ignored.
+pub fn compare_models(baseline: &str, current: &str) -> Result<Vec<ApiChange>, String> {
+    let baseline =
+        JsonValue::parse(baseline).map_err(|err| format!("Can not parse baseline: {}", err))?;
+    let current = JsonValue::parse(current)
+        .map_err(|err| format!("Can not parse current description: {}", err))?;
+    for model in &[&baseline, &current] {
+        let version = model.get("format_version").and_then(JsonValue::as_i64);
+        if version != Some(MODEL_FORMAT_VERSION) {
+            return Err(format!(
+                "Unsupported format_version {:?}, expect {}",
+                version, MODEL_FORMAT_VERSION
+            ));
+        }
+    }
+    let old_langs: FxHashSet<&str> = str_array(&baseline, "languages").collect();
+    let langs: Vec<&str> = str_array(&current, "languages")
+        .filter(|x| old_langs.contains(x))
+        .collect();
+    let mut cmp = Comparator {
+        langs,
+        changes: vec![],
+    };
+    cmp.compare_items(
+        json_array(&baseline, "items"),
+        json_array(&current, "items"),
+    );
+    Ok(cmp.changes)
+}
+
+struct Comparator<'a> {
+    /// compare foreign types only for languages present in both models
+    langs: Vec<&'a str>,
+    changes: Vec<ApiChange>,
+}
+
+impl<'a> Comparator<'a> {
+    fn add(&mut self, kind: ApiChangeKind, description: String) {
+        self.changes.push(ApiChange { kind, description });
+    }
+
+    fn compare_items(&mut self, old: &[JsonValue], new: &[JsonValue]) {
+        for old_item in old {
+            let name = json_str(old_item, "name");
+            let kind = json_str(old_item, "kind");
+            let new_item = match find_by_name(new, name) {
+                Some(x) => x,
+                None => {
+                    self.add(
+                        ApiChangeKind::Breaking,
+                        format!("{} {} removed", kind, name),
+                    );
+                    continue;
+                }
+            };
+            let new_kind = json_str(new_item, "kind");
+            if kind != new_kind {
+                self.add(
+                    ApiChangeKind::Breaking,
+                    format!("{} {} became {}", kind, name, new_kind),
+                );
+                continue;
+            }
+            match kind {
+                "class" => self.compare_methods(name, old_item, new_item, false),
+                "callback" => self.compare_methods(name, old_item, new_item, true),
+                "enum" => self.compare_enums(name, old_item, new_item),
+                "const" if old_item.get("value") != new_item.get("value") => {
+                    self.add(
+                        ApiChangeKind::Breaking,
+                        format!("value of const {} changed", name),
+                    );
+                }
+                "fn" => {
+                    let old_sig = self.signature(old_item);
+                    let new_sig = self.signature(new_item);
+                    if old_sig != new_sig {
+                        self.add(
+                            ApiChangeKind::Breaking,
+                            format!(
+                                "signature of fn {} changed from `{}` to `{}`",
+                                name, old_sig, new_sig
+                            ),
+                        );
+                    }
+                }
+                _ => {}
+            }
+        }
+        for new_item in new {
+            let name = json_str(new_item, "name");
+            if find_by_name(old, name).is_none() {
+                let kind = json_str(new_item, "kind");
+                self.add(ApiChangeKind::Additive, format!("{} {} added", kind, name));
+            }
+        }
+    }
+
+    /// methods are compared by name, several methods with the same name
+    /// (overloading) are compared as sets of signatures
+    fn compare_methods(
+        &mut self,
+        class_name: &str,
+        old: &JsonValue,
+        new: &JsonValue,
+        is_callback: bool,
+    ) {
+        let old_methods = public_methods(old);
+        let new_methods = public_methods(new);
+        let kind = if is_callback { "callback" } else { "class" };
+        // foreign code implements callback, so new method breaks it
+        let added_kind = if is_callback {
+            ApiChangeKind::Breaking
+        } else {
+            ApiChangeKind::Additive
+        };
+        let mut names = Vec::<&str>::new();
+        for m in old_methods.iter().chain(new_methods.iter()) {
+            let name = json_str(m, "name");
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+        for name in names {
+            let old_sigs = self.signatures(&old_methods, name);
+            let new_sigs = self.signatures(&new_methods, name);
+            if new_sigs.is_empty() {
+                self.add(
+                    ApiChangeKind::Breaking,
+                    format!("method {} removed from {} {}", name, kind, class_name),
+                );
+                continue;
+            }
+            if old_sigs.is_empty() {
+                self.add(
+                    added_kind,
+                    format!("method {} added to {} {}", name, kind, class_name),
+                );
+                continue;
+            }
+            let removed: Vec<&String> = old_sigs.iter().filter(|x| !new_sigs.contains(x)).collect();
+            let added: Vec<&String> = new_sigs.iter().filter(|x| !old_sigs.contains(x)).collect();
+            if removed.len() == 1 && added.len() == 1 {
+                self.add(
+                    ApiChangeKind::Breaking,
+                    format!(
+                        "signature of {}.{} changed from `{}` to `{}`",
+                        class_name, name, removed[0], added[0]
+                    ),
+                );
+                continue;
+            }
+            for sig in removed {
+                self.add(
+                    ApiChangeKind::Breaking,
+                    format!("{}.{} `{}` removed", class_name, name, sig),
+                );
+            }
+            for sig in added {
+                self.add(
+                    added_kind,
+                    format!("{}.{} `{}` added", class_name, name, sig),
+                );
+            }
+        }
+    }
+
+    fn signatures(&self, methods: &[&JsonValue], name: &str) -> Vec<String> {
+        methods
+            .iter()
+            .filter(|m| json_str(m, "name") == name)
+            .map(|m| self.signature(m))
+            .collect()
+    }
+
+    fn signature(&self, method: &JsonValue) -> String {
+        let mut sig = String::new();
+        match method.get("variant").and_then(JsonValue::as_str) {
+            Some("static_method") => sig.push_str("static "),
+            Some("constructor") => sig.push_str("constructor "),
+            _ => {}
+        }
+        sig.push('(');
+        if let Some(self_variant) = method.get("self_variant").and_then(JsonValue::as_str) {
+            sig.push_str(self_variant);
+        }
+        for arg in json_array(method, "args") {
+            if !sig.ends_with('(') {
+                sig.push_str(", ");
+            }
+            sig.push_str(&self.type_to_str(arg.get("type")));
+        }
+        sig.push(')');
+        match method.get("return") {
+            Some(JsonValue::Null) | None => {}
+            ret_type => {
+                sig.push_str(" -> ");
+                sig.push_str(&self.type_to_str(ret_type));
+            }
+        }
+        sig
+    }
+
+    /// foreign types for all languages, or Rust type if foreign type unknown
+    fn type_to_str(&self, ty: Option<&JsonValue>) -> String {
+        let ty = match ty {
+            Some(x) => x,
+            None => return String::new(),
+        };
+        let rust_ty = json_str(ty, "rust");
+        let foreign = ty.get("foreign");
+        let types: Vec<&str> = self
+            .langs
+            .iter()
+            .map(|lang| {
+                foreign
+                    .and_then(|x| x.get(lang))
+                    .and_then(JsonValue::as_str)
+                    .unwrap_or(rust_ty)
+            })
+            .collect();
+        if types.is_empty() {
+            rust_ty.into()
+        } else {
+            types.join("|")
+        }
+    }
+
+    fn compare_enums(&mut self, enum_name: &str, old: &JsonValue, new: &JsonValue) {
+        let old_items = json_array(old, "items");
+        let new_items = json_array(new, "items");
+        for old_item in old_items {
+            let name = json_str(old_item, "name");
+            match find_by_name(new_items, name) {
+                None => self.add(
+                    ApiChangeKind::Breaking,
+                    format!("item {} removed from enum {}", name, enum_name),
+                ),
+                Some(new_item) => {
+                    let old_val = old_item.get("value").and_then(JsonValue::as_i64);
+                    let new_val = new_item.get("value").and_then(JsonValue::as_i64);
+                    if old_val != new_val {
+                        self.add(
+                            ApiChangeKind::Breaking,
+                            format!(
+                                "value of {}.{} changed from {} to {}",
+                                enum_name,
+                                name,
+                                old_val.unwrap_or(-1),
+                                new_val.unwrap_or(-1)
+                            ),
+                        );
+                    }
+                }
+            }
+        }
+        for new_item in new_items {
+            let name = json_str(new_item, "name");
+            if find_by_name(old_items, name).is_none() {
+                self.add(
+                    ApiChangeKind::Additive,
+                    format!("item {} added to enum {}", name, enum_name),
+                );
+            }
+        }
+    }
+}
+
+fn public_methods(item: &JsonValue) -> Vec<&JsonValue> {
+    json_array(item, "methods")
+        .iter()
+        .filter(|m| m.get("access").and_then(JsonValue::as_str) != Some("private"))
+        .collect()
+}
+
+fn find_by_name<'b>(items: &'b [JsonValue], name: &str) -> Option<&'b JsonValue> {
+    items.iter().find(|x| json_str(x, "name") == name)
+}
+
+fn json_str<'b>(val: &'b JsonValue, key: &str) -> &'b str {
+    val.get(key).and_then(JsonValue::as_str).unwrap_or("")
+}
+
+fn json_array<'b>(val: &'b JsonValue, key: &str) -> &'b [JsonValue] {
+    val.get(key).and_then(JsonValue::as_array).unwrap_or(&[])
+}
+
+fn str_array<'b>(val: &'b JsonValue, key: &str) -> impl Iterator<Item = &'b str> {
+    json_array(val, key).iter().filter_map(JsonValue::as_str)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compare_models() {
+        let baseline = r#"{"format_version":1,"languages":["java"],"items":[
+{"kind":"enum","name":"Mode","items":[{"name":"A","value":0},{"name":"B","value":1}]},
+{"kind":"callback","name":"Observer","methods":[
+  {"name":"onEvent","args":[{"name":"x","type":{"rust":"i32","foreign":{"java":"int"}}}],"return":null}]},
+{"kind":"class","name":"Foo","methods":[
+  {"name":"f","variant":"method","self_variant":"&self","access":"public","args":[],
+   "return":{"rust":"i32","foreign":{"java":"int"}}},
+  {"name":"g","variant":"static_method","self_variant":null,"access":"public","args":[],"return":null},
+  {"name":"h","variant":"static_method","self_variant":null,"access":"private","args":[],"return":null}]},
+{"kind":"class","name":"Boo","methods":[]}
+]}"#;
+        let current = r#"{"format_version":1,"languages":["java","cpp"],"items":[
+{"kind":"enum","name":"Mode","items":[{"name":"C","value":0},{"name":"A","value":1},{"name":"B","value":2}]},
+{"kind":"callback","name":"Observer","methods":[
+  {"name":"onEvent","args":[{"name":"x","type":{"rust":"i32","foreign":{"java":"int"}}}],"return":null},
+  {"name":"onStop","args":[],"return":null}]},
+{"kind":"class","name":"Foo","methods":[
+  {"name":"f","variant":"method","self_variant":"&self","access":"public","args":[],
+   "return":{"rust":"i64","foreign":{"java":"long"}}},
+  {"name":"i","variant":"static_method","self_variant":null,"access":"public","args":[],"return":null}]},
+{"kind":"class","name":"Moo","methods":[]}
+]}"#;
+        let changes: Vec<String> = compare_models(baseline, current)
+            .unwrap()
+            .into_iter()
+            .map(|x| x.to_string())
+            .collect();
+        assert_eq!(
+            vec![
+                "breaking change: value of Mode.A changed from 0 to 1",
+                "breaking change: value of Mode.B changed from 1 to 2",
+                "additive change: item C added to enum Mode",
+                "breaking change: method onStop added to callback Observer",
+                "breaking change: signature of Foo.f changed from `(&self) -> int` to `(&self) -> long`",
+                "breaking change: method g removed from class Foo",
+                "additive change: method i added to class Foo",
+                "breaking change: class Boo removed",
+                "additive change: class Moo added",
+            ],
+            changes
+        );
+        assert_eq!(Ok(vec![]), compare_models(baseline, baseline));
+        assert!(compare_models(baseline, r#"{"format_version":0}"#).is_err());
+    }
+}
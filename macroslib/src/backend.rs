@@ -0,0 +1,542 @@
+//! Interface to add support of new programming language outside of `flapigen`.
+//! Implement `LanguageBackend` and pass it to `Generator` via `LanguageConfig::Custom`.
+
+use std::fmt::Display;
+
+use proc_macro2::{Ident, TokenStream};
+use rustc_hash::FxHashSet;
+use syn::Type;
+
+use crate::{
+    error::{invalid_src_id_span, DiagnosticError, Result, SourceIdSpan},
+    extension::{extend_foreign_class, extend_foreign_enum, ExtHandlers},
+    source_registry::SourceId,
+    typemap::{
+        ast::if_result_return_ok_err_types,
+        utils::{configure_ftype_rule, validate_cfg_options},
+        MapToForeignFlag, TypeMap, TypeMapConvRuleInfo,
+    },
+    types::{
+        FnArg, ForeignClassInfo, ForeignConst, ForeignEnumInfo, ForeignEnumItem, ForeignInterface,
+        ForeignInterfaceMethod, ForeignMethod, ItemToExpand,
+    },
+    ForeignConstValue, GeneratedRustCode, LanguageGenerator, MethodAccess, MethodVariant,
+    SourceCode,
+};
+
+/// Language backend, implemented outside of `flapigen`
+pub trait LanguageBackend {
+    /// Short name of language, like "kotlin", used as part of names
+    /// of Rust module and "types map" source, so it should be valid
+    /// part of Rust identifier
+    fn name(&self) -> &str;
+    /// Rules to convert Rust types to foreign types and back,
+    /// in the same format as for `Generator::merge_type_map`
+    fn type_map_code(&self) -> String;
+    /// Generate foreign code for parsed items and return Rust code
+    /// that should be placed into output file
+    fn expand_items(
+        &self,
+        ctx: &mut BackendContext,
+        items: &[Item],
+    ) -> std::result::Result<Vec<TokenStream>, BackendError>;
+    /// Modify the whole Rust code generated for this language
+    fn post_process_code(
+        &self,
+        generated_code: Vec<u8>,
+    ) -> std::result::Result<Vec<u8>, BackendError> {
+        Ok(generated_code)
+    }
+}
+
+/// Location in parsed source code, to report errors
+#[derive(Debug, Clone, Copy)]
+pub struct SrcLocation(SourceIdSpan);
+
+impl SrcLocation {
+    /// location not related to any parsed source
+    pub fn unknown() -> Self {
+        SrcLocation(invalid_src_id_span())
+    }
+}
+
+/// Error of language backend
+#[derive(Debug)]
+pub struct BackendError(DiagnosticError);
+
+impl BackendError {
+    pub fn new<T: Display>(loc: SrcLocation, msg: T) -> Self {
+        BackendError(DiagnosticError::new2(loc.0, msg))
+    }
+    /// Add note that points to another place in source code
+    pub fn add_note<T: Display>(self, loc: SrcLocation, msg: T) -> Self {
+        BackendError(self.0.add_span_note(loc.0, msg))
+    }
+}
+
+impl From<DiagnosticError> for BackendError {
+    fn from(err: DiagnosticError) -> Self {
+        BackendError(err)
+    }
+}
+
+/// Parsed item to generate code for
+#[derive(Debug, Clone, Copy)]
+pub enum Item<'a> {
+    Class(ClassView<'a>),
+    Enum(EnumView<'a>),
+    Callback(CallbackView<'a>),
+    /// `foreign_const!`
+    Const(ConstView<'a>),
+    /// `foreign_fn!`
+    Fn(MethodView<'a>),
+}
+
+/// `foreign_class!`
+#[derive(Debug, Clone, Copy)]
+pub struct ClassView<'a>(&'a ForeignClassInfo);
+
+impl<'a> ClassView<'a> {
+    pub fn name(&self) -> &'a Ident {
+        &self.0.name
+    }
+    pub fn location(&self) -> SrcLocation {
+        SrcLocation((self.0.src_id, self.0.span()))
+    }
+    /// `self_type` of class
+    pub fn self_type(&self) -> Option<&'a Type> {
+        self.0.self_desc.as_ref().map(|x| &x.self_type)
+    }
+    /// type returned by constructors, may be not the same as `self_type`,
+    /// for example `Rc<RefCell<T>>`
+    pub fn constructor_ret_type(&self) -> Option<&'a Type> {
+        self.0.self_desc.as_ref().map(|x| &x.constructor_ret_type)
+    }
+    pub fn methods(&self) -> impl Iterator<Item = MethodView<'a>> {
+        let src_id = self.0.src_id;
+        self.0.methods.iter().map(move |m| MethodView(m, src_id))
+    }
+    /// code from `foreign_code` section
+    pub fn foreign_code(&self) -> &'a str {
+        &self.0.foreign_code
+    }
+    pub fn doc_comments(&self) -> &'a [String] {
+        &self.0.doc_comments
+    }
+    pub fn derive_list(&self) -> &'a [String] {
+        &self.0.derive_list
+    }
+    pub fn consts(&self) -> impl Iterator<Item = ConstView<'a>> {
+        self.0.consts.iter().map(ConstView)
+    }
+}
+
+/// Constant inside `foreign_class!` or `foreign_const!`
+#[derive(Debug, Clone, Copy)]
+pub struct ConstView<'a>(&'a ForeignConst);
+
+impl<'a> ConstView<'a> {
+    pub fn name(&self) -> &'a Ident {
+        &self.0.name
+    }
+    pub fn location(&self) -> SrcLocation {
+        SrcLocation((self.0.src_id, self.0.span()))
+    }
+    pub fn rust_type(&self) -> &'a Type {
+        &self.0.ty
+    }
+    pub fn value(&self) -> &'a ForeignConstValue {
+        &self.0.value
+    }
+    pub fn doc_comments(&self) -> &'a [String] {
+        &self.0.doc_comments
+    }
+}
+
+/// Method of `foreign_class!`
+#[derive(Debug, Clone, Copy)]
+pub struct MethodView<'a>(&'a ForeignMethod, SourceId);
+
+impl<'a> MethodView<'a> {
+    /// name of method for foreign language, alias or the last part of Rust path
+    pub fn name(&self) -> String {
+        self.0.short_name()
+    }
+    /// path of Rust function, empty for `constructor = empty`
+    pub fn rust_path(&self) -> &'a syn::Path {
+        &self.0.rust_id
+    }
+    pub fn location(&self) -> SrcLocation {
+        SrcLocation((self.1, self.0.span()))
+    }
+    pub fn variant(&self) -> MethodVariant {
+        self.0.variant
+    }
+    pub fn access(&self) -> MethodAccess {
+        self.0.access
+    }
+    /// arguments except `self`
+    pub fn args(&self) -> impl Iterator<Item = ArgView<'a>> {
+        args_view(&self.0.fn_decl.inputs)
+    }
+    /// `None` in case of `()`
+    pub fn output(&self) -> Option<&'a Type> {
+        output_type(&self.0.fn_decl.output)
+    }
+    /// Rust code to call method, `this` is variable for `self`
+    pub fn code_to_call_rust_func(&self) -> String {
+        self.0.generate_code_to_call_rust_func()
+    }
+    pub fn doc_comments(&self) -> &'a [String] {
+        &self.0.doc_comments
+    }
+    /// attributes that `flapigen` does not know,
+    /// see `Generator::register_method_attribute_callback`
+    pub fn unknown_attrs(&self) -> &'a [String] {
+        &self.0.unknown_attrs
+    }
+}
+
+/// Argument of function
+#[derive(Debug, Clone, Copy)]
+pub struct ArgView<'a> {
+    pub name: &'a str,
+    pub ty: &'a Type,
+}
+
+fn args_view(inputs: &[FnArg]) -> impl Iterator<Item = ArgView<'_>> {
+    inputs.iter().filter_map(|arg| match arg {
+        FnArg::SelfArg(..) => None,
+        FnArg::Default(ref arg) => Some(ArgView {
+            name: arg.name.as_str(),
+            ty: &arg.ty,
+        }),
+    })
+}
+
+fn output_type(output: &syn::ReturnType) -> Option<&Type> {
+    match output {
+        syn::ReturnType::Default => None,
+        syn::ReturnType::Type(_, ref ty) => Some(ty),
+    }
+}
+
+/// `foreign_enum!`
+#[derive(Debug, Clone, Copy)]
+pub struct EnumView<'a>(&'a ForeignEnumInfo);
+
+impl<'a> EnumView<'a> {
+    pub fn name(&self) -> &'a Ident {
+        &self.0.name
+    }
+    pub fn location(&self) -> SrcLocation {
+        SrcLocation((self.0.src_id, self.0.span()))
+    }
+    pub fn items(&self) -> impl Iterator<Item = EnumItemView<'a>> {
+        self.0.items.iter().map(EnumItemView)
+    }
+    pub fn doc_comments(&self) -> &'a [String] {
+        &self.0.doc_comments
+    }
+    pub fn derive_list(&self) -> &'a [String] {
+        &self.0.derive_list
+    }
+}
+
+/// Item of `foreign_enum!`
+#[derive(Debug, Clone, Copy)]
+pub struct EnumItemView<'a>(&'a ForeignEnumItem);
+
+impl<'a> EnumItemView<'a> {
+    pub fn name(&self) -> &'a Ident {
+        &self.0.name
+    }
+    /// path to Rust enum's variant
+    pub fn rust_path(&self) -> &'a syn::Path {
+        &self.0.rust_name
+    }
+    pub fn doc_comments(&self) -> &'a [String] {
+        &self.0.doc_comments
+    }
+}
+
+/// `foreign_callback!`
+#[derive(Debug, Clone, Copy)]
+pub struct CallbackView<'a>(&'a ForeignInterface);
+
+impl<'a> CallbackView<'a> {
+    pub fn name(&self) -> &'a Ident {
+        &self.0.name
+    }
+    pub fn location(&self) -> SrcLocation {
+        SrcLocation(self.0.src_id_span())
+    }
+    /// Rust trait that callback implements
+    pub fn self_type(&self) -> &'a syn::TypeTraitObject {
+        &self.0.self_type
+    }
+    pub fn methods(&self) -> impl Iterator<Item = CallbackMethodView<'a>> {
+        self.0.items.iter().map(CallbackMethodView)
+    }
+    pub fn doc_comments(&self) -> &'a [String] {
+        &self.0.doc_comments
+    }
+}
+
+/// Method of `foreign_callback!`
+#[derive(Debug, Clone, Copy)]
+pub struct CallbackMethodView<'a>(&'a ForeignInterfaceMethod);
+
+impl<'a> CallbackMethodView<'a> {
+    /// name of method for foreign language
+    pub fn name(&self) -> &'a Ident {
+        &self.0.name
+    }
+    /// path to trait's method
+    pub fn rust_path(&self) -> &'a syn::Path {
+        &self.0.rust_name
+    }
+    /// arguments except `self`
+    pub fn args(&self) -> impl Iterator<Item = ArgView<'a>> {
+        args_view(&self.0.fn_decl.inputs)
+    }
+    /// `None` in case of `()`
+    pub fn output(&self) -> Option<&'a Type> {
+        output_type(&self.0.fn_decl.output)
+    }
+    pub fn doc_comments(&self) -> &'a [String] {
+        &self.0.doc_comments
+    }
+}
+
+/// Direction of conversation between Rust and foreign type
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConvDirection {
+    RustToForeign,
+    ForeignToRust,
+}
+
+/// Foreign type found in "types map"
+#[derive(Debug, Clone)]
+pub struct ForeignTypeRef {
+    pub name: String,
+    /// Rust type that directly corresponds to foreign type
+    pub rust_ty: Type,
+    /// Rust type and code to convert `rust_ty` to it or from it,
+    /// if foreign type should be passed via intermediate type
+    pub intermediate: Option<(Type, String)>,
+}
+
+/// Code to convert one Rust type to another
+#[derive(Debug, Clone)]
+pub struct ConvCode {
+    /// Items that converting code depends on, they should be placed
+    /// into generated Rust code only once
+    pub deps: Vec<TokenStream>,
+    pub code: String,
+}
+
+/// Access to "types map" and extensions for `LanguageBackend`
+pub struct BackendContext<'a> {
+    conv_map: &'a mut TypeMap,
+    pointer_target_width: usize,
+    remove_not_generated_files: bool,
+    ext_handlers: ExtHandlers<'a>,
+}
+
+impl<'a> BackendContext<'a> {
+    pub fn pointer_target_width(&self) -> usize {
+        self.pointer_target_width
+    }
+    /// Whether user asked to remove files that were not generated
+    /// from output directory
+    pub fn remove_not_generated_files(&self) -> bool {
+        self.remove_not_generated_files
+    }
+    /// Find foreign type for Rust type, it is possible that to find it
+    /// new conversation rules would be created with help of generic rules
+    pub fn map_to_foreign(
+        &mut self,
+        rust_ty: &Type,
+        direction: ConvDirection,
+        loc: SrcLocation,
+    ) -> Option<ForeignTypeRef> {
+        let rust_ty = self.conv_map.find_or_alloc_rust_type(rust_ty, (loc.0).0);
+        let direction = match direction {
+            ConvDirection::RustToForeign => petgraph::Direction::Outgoing,
+            ConvDirection::ForeignToRust => petgraph::Direction::Incoming,
+        };
+        let ftype = self.conv_map.map_through_conversation_to_foreign(
+            rust_ty.to_idx(),
+            direction,
+            MapToForeignFlag::FullSearch,
+            loc.0,
+            calc_this_type_for_method,
+        )?;
+        let ftype = &self.conv_map[ftype];
+        let rule = match direction {
+            petgraph::Direction::Outgoing => ftype.into_from_rust.as_ref(),
+            petgraph::Direction::Incoming => ftype.from_into_rust.as_ref(),
+        }?;
+        Some(ForeignTypeRef {
+            name: ftype.typename().to_string(),
+            rust_ty: self.conv_map[rule.rust_ty].ty.clone(),
+            intermediate: rule.intermediate.as_ref().map(|x| {
+                (
+                    self.conv_map[x.intermediate_ty].ty.clone(),
+                    x.conv_code.as_str().to_string(),
+                )
+            }),
+        })
+    }
+    /// Explanation why `map_to_foreign` failed: generic rules that were tried
+    /// and why they did not match, and the closest foreign types.
+    /// Returns `None` if user did not ask for it via
+    /// `Generator::explain_conversion_failures`
+    pub fn explain_map_to_foreign_failure(
+        &mut self,
+        rust_ty: &Type,
+        direction: ConvDirection,
+        loc: SrcLocation,
+    ) -> Option<String> {
+        let rust_ty = self.conv_map.find_or_alloc_rust_type(rust_ty, (loc.0).0);
+        let direction = match direction {
+            ConvDirection::RustToForeign => petgraph::Direction::Outgoing,
+            ConvDirection::ForeignToRust => petgraph::Direction::Incoming,
+        };
+        self.conv_map
+            .explain_conv_failure(rust_ty.to_idx(), direction)
+    }
+    /// Generate code to convert variable `in_var` of type `from`
+    /// to variable `out_var` of type `to`
+    pub fn convert_rust_types(
+        &mut self,
+        from: &Type,
+        to: &Type,
+        in_var: &str,
+        out_var: &str,
+        func_ret_type: &str,
+        loc: SrcLocation,
+    ) -> std::result::Result<ConvCode, BackendError> {
+        let from = self.conv_map.find_or_alloc_rust_type(from, (loc.0).0);
+        let to = self.conv_map.find_or_alloc_rust_type(to, (loc.0).0);
+        let (deps, code) = self.conv_map.convert_rust_types(
+            from.to_idx(),
+            to.to_idx(),
+            in_var,
+            out_var,
+            func_ret_type,
+            loc.0,
+        )?;
+        Ok(ConvCode { deps, code })
+    }
+    /// Call callbacks registered for class's derives and methods' attributes,
+    /// `reserved_derives` are derives that backend handles by itself
+    pub fn extend_class(
+        &self,
+        class: ClassView,
+        code: &mut Vec<u8>,
+        reserved_derives: &[&str],
+    ) -> std::result::Result<(), BackendError> {
+        extend_foreign_class(
+            class.0,
+            code,
+            reserved_derives,
+            self.ext_handlers.class_ext_handlers,
+            self.ext_handlers.method_ext_handlers,
+        )?;
+        Ok(())
+    }
+    /// Call callbacks registered for enum's derives
+    pub fn extend_enum(
+        &self,
+        fenum: EnumView,
+        code: &mut Vec<u8>,
+    ) -> std::result::Result<(), BackendError> {
+        extend_foreign_enum(fenum.0, code, self.ext_handlers.enum_ext_handlers)?;
+        Ok(())
+    }
+}
+
+pub(crate) fn calc_this_type_for_method(tm: &TypeMap, class: &ForeignClassInfo) -> Option<Type> {
+    let constructor_ret_type = &class.self_desc.as_ref()?.constructor_ret_type;
+    Some(
+        tm.ty_to_rust_type_checked(constructor_ret_type)
+            .and_then(|ty| if_result_return_ok_err_types(&ty))
+            .map(|(ok_ty, _err_ty)| ok_ty)
+            .unwrap_or_else(|| constructor_ret_type.clone()),
+    )
+}
+
+fn merge_rule(conv_map: &mut TypeMap, mut rule: TypeMapConvRuleInfo) -> Result<()> {
+    if rule.c_types.is_some() || rule.generic_c_types.is_some() || !rule.f_code.is_empty() {
+        return Err(DiagnosticError::new(
+            rule.src_id,
+            rule.span,
+            "define_c_type and foreign_code are not supported for custom language",
+        ));
+    }
+    let no_options = FxHashSet::<&'static str>::default();
+    validate_cfg_options(&rule, &no_options)?;
+    configure_ftype_rule(
+        &mut rule.ftype_left_to_right,
+        "=>",
+        rule.src_id,
+        &no_options,
+    )?;
+    configure_ftype_rule(
+        &mut rule.ftype_right_to_left,
+        "<=",
+        rule.src_id,
+        &no_options,
+    )?;
+    conv_map.merge_conv_rule(rule.src_id, rule)
+}
+
+impl LanguageGenerator for Box<dyn LanguageBackend> {
+    fn expand_items(
+        &self,
+        conv_map: &mut TypeMap,
+        pointer_target_width: usize,
+        _code: &[SourceCode],
+        items: Vec<ItemToExpand>,
+        remove_not_generated_files: bool,
+        ext_handlers: ExtHandlers,
+    ) -> Result<GeneratedRustCode> {
+        for rule in conv_map.take_not_merged_not_generic_rules() {
+            merge_rule(conv_map, rule)?;
+        }
+        let items: Vec<Item> = items
+            .iter()
+            .map(|item| match item {
+                ItemToExpand::Class(ref fclass) => Item::Class(ClassView(fclass)),
+                ItemToExpand::Enum(ref fenum) => Item::Enum(EnumView(fenum)),
+                ItemToExpand::Interface(ref finterface) => Item::Callback(CallbackView(finterface)),
+                ItemToExpand::Const(ref fconst) => Item::Const(ConstView(fconst)),
+                ItemToExpand::Fn(ref ffn) => Item::Fn(MethodView(&ffn.method, ffn.src_id)),
+            })
+            .collect();
+        let mut ctx = BackendContext {
+            conv_map,
+            pointer_target_width,
+            remove_not_generated_files,
+            ext_handlers,
+        };
+        // backend can not tell for which item code was generated,
+        // so all code is placed at the top level
+        let code = LanguageBackend::expand_items(&**self, &mut ctx, &items).map_err(|err| err.0)?;
+        Ok(GeneratedRustCode {
+            code,
+            items_code: Vec::new(),
+        })
+    }
+
+    fn post_proccess_code(
+        &self,
+        _conv_map: &mut TypeMap,
+        _pointer_target_width: usize,
+        generated_code: Vec<u8>,
+    ) -> Result<Vec<u8>> {
+        self.post_process_code(generated_code).map_err(|err| err.0)
+    }
+}
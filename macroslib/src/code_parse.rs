@@ -1,11 +1,14 @@
 use bitflags::bitflags;
-use heck::MixedCase;
+use heck::{CamelCase, MixedCase};
 use log::debug;
 use proc_macro2::{Ident, Span, TokenStream};
-use quote::ToTokens;
+use quote::{quote, ToTokens};
 use rustc_hash::FxHashSet;
 use smol_str::SmolStr;
-use std::convert::{TryFrom, TryInto};
+use std::{
+    convert::{TryFrom, TryInto},
+    env,
+};
 use syn::{
     braced, parenthesized,
     parse::{Parse, ParseStream},
@@ -16,16 +19,18 @@ use syn::{
 };
 
 use crate::{
-    error::{DiagnosticError, Result},
+    error::{emit_parse_warning, DiagnosticError, Result},
     namegen::new_unique_name,
     source_registry::SourceId,
     typemap::ast::{normalize_type, DisplayToTokens},
     types::{
-        FnArg, ForeignClassInfo, ForeignEnumInfo, ForeignEnumItem, ForeignInterface,
-        ForeignInterfaceMethod, ForeignMethod, MethodAccess, MethodVariant, NamedArg, SelfTypeDesc,
-        SelfTypeVariant,
+        normalize_closure_type, Deprecation, FnArg, FnDecl, ForeignClassInfo, ForeignConst,
+        ForeignConstValue, ForeignEnumInfo, ForeignEnumItem, ForeignField, ForeignFunction,
+        ForeignInterface, ForeignInterfaceMethod, ForeignMethod, MethodAccess, MethodProperty,
+        MethodVariant, NamedArg, PropertyAccessor, SelfTypeDesc, SelfTypeVariant,
     },
     LanguageConfig, CAMEL_CASE_ALIASES, COPY_TRAIT, FOREIGNER_CODE_DEPRECATED, FOREIGN_CODE,
+    PROPERTY_ATTR,
 };
 
 pub(crate) fn parse_foreigner_class(
@@ -33,26 +38,19 @@ pub(crate) fn parse_foreigner_class(
     config: &LanguageConfig,
     tokens: TokenStream,
 ) -> Result<ForeignClassInfo> {
-    match config {
-        LanguageConfig::CppConfig(_) => {
-            let mut class: CppClass =
-                syn::parse2(tokens).map_err(|err| DiagnosticError::from_syn_err(src_id, err))?;
-            class.0.src_id = src_id;
-            Ok(class.0)
-        }
-        LanguageConfig::JavaConfig(_) => {
-            let mut class: JavaClass =
-                syn::parse2(tokens).map_err(|err| DiagnosticError::from_syn_err(src_id, err))?;
-            class.0.src_id = src_id;
-            Ok(class.0)
-        }
-        LanguageConfig::PythonConfig(_) => {
-            let mut class: PythonClass =
-                syn::parse2(tokens).map_err(|err| DiagnosticError::from_syn_err(src_id, err))?;
-            class.0.src_id = src_id;
-            Ok(class.0)
+    let mut class = match config {
+        LanguageConfig::CppConfig(_) => syn::parse2::<CppClass>(tokens).map(|x| x.0),
+        LanguageConfig::JavaConfig(_) | LanguageConfig::Custom(_) => {
+            syn::parse2::<JavaClass>(tokens).map(|x| x.0)
         }
+        LanguageConfig::PythonConfig(_) => syn::parse2::<PythonClass>(tokens).map(|x| x.0),
+    }
+    .map_err(|err| DiagnosticError::from_syn_err(src_id, err))?;
+    class.src_id = src_id;
+    for fconst in &mut class.consts {
+        fconst.src_id = src_id;
     }
+    Ok(class)
 }
 
 pub(crate) fn parse_foreign_enum(src_id: SourceId, tokens: TokenStream) -> Result<ForeignEnumInfo> {
@@ -62,6 +60,67 @@ pub(crate) fn parse_foreign_enum(src_id: SourceId, tokens: TokenStream) -> Resul
     Ok(f_enum.0)
 }
 
+pub(crate) fn parse_foreign_consts(
+    src_id: SourceId,
+    tokens: TokenStream,
+) -> Result<Vec<ForeignConst>> {
+    let mut consts: ForeignConstsParser =
+        syn::parse2(tokens).map_err(|err| DiagnosticError::from_syn_err(src_id, err))?;
+    for fconst in &mut consts.0 {
+        fconst.src_id = src_id;
+    }
+    Ok(consts.0)
+}
+
+/// `foreign_fn!` uses the same syntax as `foreign_class!` body,
+/// but only static methods are allowed
+pub(crate) fn parse_foreign_fns(
+    src_id: SourceId,
+    config: &LanguageConfig,
+    tokens: TokenStream,
+) -> Result<Vec<ForeignFunction>> {
+    let span = tokens
+        .clone()
+        .into_iter()
+        .next()
+        .map(|x| x.span())
+        .unwrap_or_else(Span::call_site);
+    let class_name = Ident::new(config.module_items_class(), span);
+    let class = parse_foreigner_class(src_id, config, quote! { class #class_name { #tokens } })?;
+    if class.self_desc.is_some()
+        || !class.fields.is_empty()
+        || !class.consts.is_empty()
+        || !class.foreign_code.is_empty()
+    {
+        return Err(DiagnosticError::new(
+            src_id,
+            span,
+            "foreign_fn! can contain only functions, use foreign_class! or foreign_const! instead",
+        ));
+    }
+    class
+        .methods
+        .into_iter()
+        .map(|method| {
+            if method.variant != MethodVariant::StaticMethod {
+                return Err(DiagnosticError::new(
+                    src_id,
+                    method.span(),
+                    "foreign_fn!: constructors and methods with self argument are not allowed",
+                ));
+            }
+            if method.access != MethodAccess::Public || method.property.is_some() {
+                return Err(DiagnosticError::new(
+                    src_id,
+                    method.span(),
+                    "foreign_fn!: function should be public and can not be property",
+                ));
+            }
+            Ok(ForeignFunction { src_id, method })
+        })
+        .collect()
+}
+
 pub(crate) fn parse_foreign_interface(
     src_id: SourceId,
     tokens: TokenStream,
@@ -116,12 +175,16 @@ mod kw {
     custom_keyword!(empty);
     custom_keyword!(interface);
     custom_keyword!(callback);
+    custom_keyword!(extends);
 }
 
 struct Attrs {
     doc_comments: Vec<String>,
     derive_list: Vec<String>,
     unknown_attrs: Vec<String>,
+    /// `false` if item is disabled by `#[cfg(...)]`
+    cfg_enabled: bool,
+    deprecated: Option<Deprecation>,
 }
 
 bitflags! {
@@ -129,6 +192,8 @@ bitflags! {
         const DOC = 1;
         const DERIVE = 2;
         const UNKNOWN = 4;
+        const CFG = 8;
+        const DEPRECATED = 16;
     }
 }
 
@@ -136,6 +201,8 @@ fn parse_attrs(input: ParseStream, flags: ParseAttrsFlags) -> syn::Result<Attrs>
     let mut doc_comments = vec![];
     let mut derive_list = vec![];
     let mut unknown_attrs = vec![];
+    let mut cfg_enabled = true;
+    let mut deprecated = None;
 
     if input.fork().call(syn::Attribute::parse_outer).is_ok() {
         let attr: Vec<syn::Attribute> = input.call(syn::Attribute::parse_outer)?;
@@ -162,6 +229,16 @@ fn parse_attrs(input: ParseStream, flags: ParseAttrsFlags) -> syn::Result<Attrs>
                         }
                     }
                 }
+                syn::Meta::List(ref list)
+                    if list.path.is_ident("cfg") && flags.contains(ParseAttrsFlags::CFG) =>
+                {
+                    cfg_enabled &= eval_cfg(list, &cargo_env)?;
+                }
+                _ if meta.path().is_ident("deprecated")
+                    && flags.contains(ParseAttrsFlags::DEPRECATED) =>
+                {
+                    deprecated = Some(parse_deprecated(&meta)?);
+                }
                 _ if flags.contains(ParseAttrsFlags::UNKNOWN) => {
                     unknown_attrs.push(DisplayToTokens(&meta).to_string());
                 }
@@ -181,20 +258,182 @@ fn parse_attrs(input: ParseStream, flags: ParseAttrsFlags) -> syn::Result<Attrs>
         doc_comments,
         derive_list,
         unknown_attrs,
+        cfg_enabled,
+        deprecated,
     })
 }
 
+/// Parse `#[deprecated]`, `#[deprecated = "note"]`
+/// or `#[deprecated(since = "version", note = "note")]`
+pub(crate) fn parse_deprecated(meta: &syn::Meta) -> syn::Result<Deprecation> {
+    let mut ret = Deprecation::default();
+    match meta {
+        syn::Meta::Path(_) => {}
+        syn::Meta::NameValue(syn::MetaNameValue {
+            lit: syn::Lit::Str(ref note),
+            ..
+        }) => ret.note = Some(note.value()),
+        syn::Meta::List(ref list) => {
+            for nested in &list.nested {
+                match nested {
+                    syn::NestedMeta::Meta(syn::Meta::NameValue(syn::MetaNameValue {
+                        ref path,
+                        lit: syn::Lit::Str(ref value),
+                        ..
+                    })) if path.is_ident("since") => ret.since = Some(value.value()),
+                    syn::NestedMeta::Meta(syn::Meta::NameValue(syn::MetaNameValue {
+                        ref path,
+                        lit: syn::Lit::Str(ref value),
+                        ..
+                    })) if path.is_ident("note") => ret.note = Some(value.value()),
+                    _ => {
+                        return Err(syn::Error::new(
+                            nested.span(),
+                            "expect `since = \"...\"` or `note = \"...\"`",
+                        ))
+                    }
+                }
+            }
+        }
+        _ => {
+            return Err(syn::Error::new(
+                meta.span(),
+                "expect #[deprecated(since = \"...\", note = \"...\")]",
+            ))
+        }
+    }
+    Ok(ret)
+}
+
+/// Check `#[cfg(...)]` attributes of item, see `eval_cfg`
+pub(crate) fn is_enabled_by_cfg(attrs: &[syn::Attribute]) -> syn::Result<bool> {
+    is_enabled_by_cfg_with_env(attrs, &cargo_env)
+}
+
+/// Name of `cfg` option that is equal to name of target language
+/// (`java`, `cpp`, `python` or name of custom backend) for flapigen's macroses
+const FLAPIGEN_LANG_CFG: &str = "flapigen_lang";
+
+/// Check `#[cfg(...)]` attributes of flapigen's macro for language with name `lang_name`,
+/// in addition to options from `cargo` `flapigen_lang = "<lang_name>"` can be used
+pub(crate) fn is_enabled_by_cfg_for_lang(
+    attrs: &[syn::Attribute],
+    lang_name: &str,
+) -> syn::Result<bool> {
+    let lang_var = format!("CARGO_CFG_{}", FLAPIGEN_LANG_CFG.to_uppercase());
+    is_enabled_by_cfg_with_env(attrs, &|name| {
+        if name == lang_var {
+            Some(lang_name.to_string())
+        } else {
+            cargo_env(name)
+        }
+    })
+}
+
+/// Check `#[cfg(...)]` attributes of item, values of environment variables are taken from `env`
+fn is_enabled_by_cfg_with_env(
+    attrs: &[syn::Attribute],
+    env: &dyn Fn(&str) -> Option<String>,
+) -> syn::Result<bool> {
+    for a in attrs {
+        if !a.path.is_ident("cfg") {
+            continue;
+        }
+        match a.parse_meta()? {
+            syn::Meta::List(ref list) => {
+                if !eval_cfg(list, env)? {
+                    return Ok(false);
+                }
+            }
+            _ => return Err(syn::Error::new(a.span(), "expect #[cfg(predicate)]")),
+        }
+    }
+    Ok(true)
+}
+
+/// Environment of build script
+fn cargo_env(name: &str) -> Option<String> {
+    env::var_os(name).map(|x| x.to_string_lossy().into_owned())
+}
+
+/// Evaluate `cfg(predicate)` with help of environment variables,
+/// that `cargo` sets for build scripts: `CARGO_FEATURE_<name>` for features
+/// and `CARGO_CFG_<name>` for other configuration options
+fn eval_cfg(cfg: &syn::MetaList, env: &dyn Fn(&str) -> Option<String>) -> syn::Result<bool> {
+    if cfg.nested.len() != 1 {
+        return Err(syn::Error::new(
+            cfg.span(),
+            "expect exactly one predicate inside cfg",
+        ));
+    }
+    eval_cfg_predicate(&cfg.nested[0], env)
+}
+
+fn eval_cfg_predicate(
+    pred: &syn::NestedMeta,
+    env: &dyn Fn(&str) -> Option<String>,
+) -> syn::Result<bool> {
+    let cfg_env_name = |path: &syn::Path| -> syn::Result<String> {
+        match path.get_ident() {
+            Some(id) => Ok(id.to_string().to_uppercase().replace('-', "_")),
+            None => Err(syn::Error::new(path.span(), "expect name of cfg option")),
+        }
+    };
+    match pred {
+        syn::NestedMeta::Meta(syn::Meta::Path(ref path)) => {
+            Ok(env(&format!("CARGO_CFG_{}", cfg_env_name(path)?)).is_some())
+        }
+        syn::NestedMeta::Meta(syn::Meta::NameValue(syn::MetaNameValue {
+            ref path,
+            lit: syn::Lit::Str(ref value),
+            ..
+        })) => {
+            if path.is_ident("feature") {
+                let feature = value.value().to_uppercase().replace('-', "_");
+                Ok(env(&format!("CARGO_FEATURE_{}", feature)).is_some())
+            } else {
+                // several values are separated by comma, like for `target_feature`
+                let value = value.value();
+                Ok(env(&format!("CARGO_CFG_{}", cfg_env_name(path)?))
+                    .map(|values| values.split(',').any(|x| x == value))
+                    .unwrap_or(false))
+            }
+        }
+        syn::NestedMeta::Meta(syn::Meta::List(ref list)) => {
+            let mut preds = list.nested.iter().map(|x| eval_cfg_predicate(x, env));
+            if list.path.is_ident("all") {
+                preds.try_fold(true, |acc, x| Ok(acc & x?))
+            } else if list.path.is_ident("any") {
+                preds.try_fold(false, |acc, x| Ok(acc | x?))
+            } else if list.path.is_ident("not") && list.nested.len() == 1 {
+                Ok(!eval_cfg_predicate(&list.nested[0], env)?)
+            } else {
+                Err(syn::Error::new(
+                    list.span(),
+                    "expect all(...), any(...) or not(...) here",
+                ))
+            }
+        }
+        _ => Err(syn::Error::new(pred.span(), "unsupported cfg predicate")),
+    }
+}
+
 fn parse_doc_comments(input: ParseStream) -> syn::Result<Vec<String>> {
     let Attrs { doc_comments, .. } = parse_attrs(input, ParseAttrsFlags::DOC)?;
     Ok(doc_comments)
 }
 
-fn do_parse_foreigner_class(_lang: Language, input: ParseStream) -> syn::Result<ForeignClassInfo> {
+fn do_parse_foreigner_class(lang: Language, input: ParseStream) -> syn::Result<ForeignClassInfo> {
     let Attrs {
         doc_comments: class_doc_comments,
         mut derive_list,
         unknown_attrs,
-    } = parse_attrs(&input, ParseAttrsFlags::DERIVE)?;
+        deprecated: class_deprecated,
+        ..
+    } = parse_attrs(
+        &input,
+        ParseAttrsFlags::DERIVE | ParseAttrsFlags::DEPRECATED,
+    )?;
     assert!(unknown_attrs.is_empty());
 
     debug!(
@@ -202,9 +441,21 @@ fn do_parse_foreigner_class(_lang: Language, input: ParseStream) -> syn::Result<
         class_doc_comments
     );
 
+    let is_abstract = if input.peek(Token![abstract]) {
+        input.parse::<Token![abstract]>()?;
+        true
+    } else {
+        false
+    };
     input.parse::<kw::class>()?;
     let class_name: Ident = input.parse()?;
     debug!("class_name {:?}", class_name);
+    let base_class = if input.peek(kw::extends) {
+        input.parse::<kw::extends>()?;
+        Some(input.parse::<Ident>()?)
+    } else {
+        None
+    };
     let content;
     braced!(content in input);
 
@@ -213,8 +464,13 @@ fn do_parse_foreigner_class(_lang: Language, input: ParseStream) -> syn::Result<
     let mut has_dummy_constructor = false;
     let mut constructor_ret_type: Option<Type> = None;
     let mut methods = Vec::with_capacity(10);
+    let mut property_getters = vec![];
+    let mut fields = vec![];
+    let mut consts = vec![];
 
     static CONSTRUCTOR: &str = "constructor";
+    static FIELD: &str = "field";
+    static READONLY_ATTR: &str = "readonly";
     static METHOD_DEPRECATED: &str = "method";
     static STATIC_METHOD_DEPRECATED: &str = "static_method";
     static FN: &str = "fn";
@@ -223,9 +479,24 @@ fn do_parse_foreigner_class(_lang: Language, input: ParseStream) -> syn::Result<
         let Attrs {
             doc_comments: method_doc_comments,
             derive_list: method_derive_list,
-            unknown_attrs: method_unknown_attrs,
-        } = parse_attrs(&&content, ParseAttrsFlags::UNKNOWN | ParseAttrsFlags::DOC)?;
+            unknown_attrs: mut method_unknown_attrs,
+            cfg_enabled,
+            deprecated: method_deprecated,
+        } = parse_attrs(
+            &&content,
+            ParseAttrsFlags::UNKNOWN
+                | ParseAttrsFlags::DOC
+                | ParseAttrsFlags::CFG
+                | ParseAttrsFlags::DEPRECATED,
+        )?;
         assert!(method_derive_list.is_empty());
+        let is_property = match method_unknown_attrs.iter().position(|x| x == PROPERTY_ATTR) {
+            Some(pos) => {
+                method_unknown_attrs.remove(pos);
+                true
+            }
+            None => false,
+        };
         let mut access = if content.peek(kw::private) {
             content.parse::<kw::private>()?;
             MethodAccess::Private
@@ -238,6 +509,21 @@ fn do_parse_foreigner_class(_lang: Language, input: ParseStream) -> syn::Result<
             access = MethodAccess::Protected;
         }
 
+        if content.peek(Token![const]) {
+            if !method_unknown_attrs.is_empty()
+                || is_property
+                || access != MethodAccess::Public
+                || method_deprecated.is_some()
+            {
+                return Err(content.error("only doc comments are supported for const"));
+            }
+            let fconst = parse_foreign_const(&content, method_doc_comments)?;
+            if cfg_enabled {
+                consts.push(fconst);
+            }
+            continue;
+        }
+
         let (func_type_name, func_type_name_span): (String, Span) = if content.peek(Token![fn]) {
             let token = content.parse::<Token![fn]>()?;
             (FN.into(), token.span())
@@ -247,6 +533,12 @@ fn do_parse_foreigner_class(_lang: Language, input: ParseStream) -> syn::Result<
         };
         debug!("may be func_type_name {:?}", func_type_name);
         if func_type_name == "self_type" {
+            if !cfg_enabled {
+                return Err(syn::Error::new(
+                    func_type_name_span,
+                    "self_type can not be disabled by #[cfg], use #[cfg] for the whole class",
+                ));
+            }
             rust_self_type = Some(content.parse::<Type>()?);
             debug!("self_type: {:?}", rust_self_type);
             content.parse::<Token![;]>()?;
@@ -255,18 +547,60 @@ fn do_parse_foreigner_class(_lang: Language, input: ParseStream) -> syn::Result<
 
         if func_type_name == FOREIGNER_CODE_DEPRECATED || func_type_name == FOREIGN_CODE {
             if func_type_name == FOREIGNER_CODE_DEPRECATED {
-                println!(
-                    "cargo:warning={} is deprecated, use {} instead",
-                    FOREIGNER_CODE_DEPRECATED, FOREIGN_CODE
+                emit_parse_warning(
+                    func_type_name_span,
+                    format!(
+                        "{} is deprecated, use {} instead",
+                        FOREIGNER_CODE_DEPRECATED, FOREIGN_CODE
+                    ),
                 );
             }
             let lit: syn::LitStr = content.parse()?;
             debug!("foreigner_code {:?}", lit);
-            foreigner_code.push_str(&lit.value());
+            if cfg_enabled {
+                foreigner_code.push_str(&lit.value());
+            }
             content.parse::<Token![;]>()?;
             continue;
         }
 
+        if func_type_name == FIELD {
+            if is_property {
+                return Err(syn::Error::new(
+                    func_type_name_span,
+                    "field is always property, there is no need for #[property]",
+                ));
+            }
+            let readonly = match method_unknown_attrs.iter().position(|x| x == READONLY_ATTR) {
+                Some(pos) => {
+                    method_unknown_attrs.remove(pos);
+                    true
+                }
+                None => false,
+            };
+            let name: Ident = content.parse()?;
+            content.parse::<Token![:]>()?;
+            let ty: Type = content.parse()?;
+            content.parse::<Token![;]>()?;
+            if !cfg_enabled {
+                continue;
+            }
+            let field = ForeignField { name, ty, readonly };
+            let (getter, setter) = field_accessors(
+                &class_name,
+                &field,
+                access,
+                method_doc_comments,
+                method_unknown_attrs,
+                method_deprecated,
+            );
+            property_getters.push(methods.len());
+            methods.push(getter);
+            methods.extend(setter);
+            fields.push(field);
+            continue;
+        }
+
         if func_type_name == CONSTRUCTOR && has_dummy_constructor {
             return Err(syn::Error::new(
                 func_type_name_span,
@@ -276,6 +610,12 @@ fn do_parse_foreigner_class(_lang: Language, input: ParseStream) -> syn::Result<
 
         if func_type_name == CONSTRUCTOR && content.peek(Token![=]) && content.peek2(kw::empty) {
             debug!("class {} has dummy constructor", class_name);
+            if !cfg_enabled {
+                return Err(syn::Error::new(
+                    func_type_name_span,
+                    "dummy constructor can not be disabled by #[cfg]",
+                ));
+            }
             content.parse::<Token![=]>()?;
             content.parse::<kw::empty>()?;
             if content.peek(Token![->]) {
@@ -288,6 +628,12 @@ fn do_parse_foreigner_class(_lang: Language, input: ParseStream) -> syn::Result<
             if access != MethodAccess::Private {
                 return Err(content.error("dummy constructor should be private"));
             }
+            if is_property {
+                return Err(syn::Error::new(
+                    func_type_name_span,
+                    "#[property] can not be used with constructor",
+                ));
+            }
             if constructor_ret_type.is_none() {
                 if let Some(rust_self_type) = rust_self_type.as_ref() {
                     let self_type: Type = (*rust_self_type).clone();
@@ -323,6 +669,8 @@ fn do_parse_foreigner_class(_lang: Language, input: ParseStream) -> syn::Result<
                 access,
                 doc_comments: method_doc_comments,
                 unknown_attrs: method_unknown_attrs,
+                property: None,
+                deprecated: method_deprecated,
             });
             has_dummy_constructor = true;
             continue;
@@ -336,23 +684,25 @@ fn do_parse_foreigner_class(_lang: Language, input: ParseStream) -> syn::Result<
         }
         let args_parser;
         parenthesized!(args_parser in content);
-        let args_in: Punctuated<syn::FnArg, Token![,]> =
-            args_parser.parse_terminated(syn::FnArg::parse)?;
+        let (args_in, args_defaults) = parse_fn_args_with_defaults(&args_parser)?;
         debug!("func in args {:?}", args_in);
 
         let mut func_type = match func_type_name {
             _ if func_type_name == CONSTRUCTOR => MethodVariant::Constructor,
             _ if func_type_name == STATIC_METHOD_DEPRECATED => {
-                println!(
-                    "cargo:warning={} deprecated, use \"fn\" instead",
-                    STATIC_METHOD_DEPRECATED
+                emit_parse_warning(
+                    func_type_name_span,
+                    format!(
+                        "{} deprecated, use \"fn\" instead",
+                        STATIC_METHOD_DEPRECATED
+                    ),
                 );
                 MethodVariant::StaticMethod
             }
             _ if func_type_name == METHOD_DEPRECATED => {
-                println!(
-                    "cargo:warning={} deprecated, use \"fn\" instead",
-                    METHOD_DEPRECATED
+                emit_parse_warning(
+                    func_type_name_span,
+                    format!("{} deprecated, use \"fn\" instead", METHOD_DEPRECATED),
                 );
                 MethodVariant::Method(SelfTypeVariant::Default)
             }
@@ -430,7 +780,12 @@ fn do_parse_foreigner_class(_lang: Language, input: ParseStream) -> syn::Result<
                 }
             },
         }
-        let (fn_args, has_unnamed_args) = parse_fn_args(args_in)?;
+        let (mut fn_args, has_unnamed_args) = parse_fn_args(args_in)?;
+        for (arg, default) in fn_args.iter_mut().zip(args_defaults) {
+            if let FnArg::Default(ref mut arg) = arg {
+                arg.default = default;
+            }
+        }
         let out_type: syn::ReturnType = content.parse()?;
         debug!("out_type {:?}", out_type);
 
@@ -462,6 +817,9 @@ fn do_parse_foreigner_class(_lang: Language, input: ParseStream) -> syn::Result<
             content.parse::<Token![;]>()?;
         }
 
+        if !cfg_enabled {
+            continue;
+        }
         let ret_type = match out_type {
             syn::ReturnType::Default => None,
             syn::ReturnType::Type(_, ref ptype) => Some((*ptype).clone()),
@@ -496,6 +854,9 @@ fn do_parse_foreigner_class(_lang: Language, input: ParseStream) -> syn::Result<
             }
         }
         let span = func_name.span();
+        if is_property {
+            property_getters.push(methods.len());
+        }
         methods.push(ForeignMethod {
             variant: func_type,
             rust_id: func_name,
@@ -509,6 +870,8 @@ fn do_parse_foreigner_class(_lang: Language, input: ParseStream) -> syn::Result<
             doc_comments: method_doc_comments,
             inline_block,
             unknown_attrs: method_unknown_attrs,
+            property: None,
+            deprecated: method_deprecated,
         });
     }
 
@@ -527,6 +890,32 @@ fn do_parse_foreigner_class(_lang: Language, input: ParseStream) -> syn::Result<
         ));
     }
 
+    if is_abstract {
+        if rust_self_type.is_some() || constructor_ret_type.is_some() || !fields.is_empty() {
+            return Err(syn::Error::new(
+                class_name.span(),
+                "abstract class can not have self_type, constructors and fields, \
+                 they are defined by derived classes",
+            ));
+        }
+        if !consts.is_empty() {
+            return Err(syn::Error::new(
+                class_name.span(),
+                "constants are not supported for abstract class",
+            ));
+        }
+        for m in &methods {
+            let is_method = matches!(m.variant, MethodVariant::Method(_));
+            if !is_method || m.access == MethodAccess::Private || m.async_output().is_some() {
+                return Err(syn::Error::new(
+                    m.span(),
+                    "abstract class can have only public or protected \
+                     not async methods with self argument",
+                ));
+            }
+        }
+    }
+
     let self_desc = match (rust_self_type, constructor_ret_type) {
         (Some(self_type), Some(constructor_ret_type)) => Some(SelfTypeDesc {
             self_type,
@@ -547,16 +936,17 @@ fn do_parse_foreigner_class(_lang: Language, input: ParseStream) -> syn::Result<
         }
     };
 
-    if let Some(pos) = derive_list.iter().position(|x| x == CAMEL_CASE_ALIASES) {
-        derive_list.remove(pos);
-        for m in &mut methods {
-            if m.name_alias.is_none() {
-                m.name_alias = Some(Ident::new(
-                    &m.short_name().to_mixed_case(),
-                    m.rust_id.span(),
-                ));
-            }
-        }
+    let camel_case = derive_list.iter().any(|x| x == CAMEL_CASE_ALIASES);
+    resolve_properties(lang, camel_case, &property_getters, &mut methods)?;
+    apply_camel_case_aliases(&mut derive_list, &mut methods);
+    if is_abstract && !derive_list.is_empty() {
+        return Err(syn::Error::new(
+            class_name.span(),
+            format!(
+                "only {} derive is supported for abstract class",
+                CAMEL_CASE_ALIASES
+            ),
+        ));
     }
 
     Ok(ForeignClassInfo {
@@ -567,9 +957,300 @@ fn do_parse_foreigner_class(_lang: Language, input: ParseStream) -> syn::Result<
         foreign_code: foreigner_code,
         doc_comments: class_doc_comments,
         derive_list,
+        fields,
+        consts,
+        is_abstract,
+        base_class,
+        deprecated: class_deprecated,
+        is_internal: false,
+        is_module_items_holder: false,
     })
 }
 
+struct ForeignConstsParser(Vec<ForeignConst>);
+
+impl Parse for ForeignConstsParser {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut consts = vec![];
+        while !input.is_empty() {
+            let Attrs {
+                doc_comments,
+                cfg_enabled,
+                ..
+            } = parse_attrs(input, ParseAttrsFlags::DOC | ParseAttrsFlags::CFG)?;
+            let fconst = parse_foreign_const(input, doc_comments)?;
+            if cfg_enabled {
+                consts.push(fconst);
+            }
+        }
+        Ok(ForeignConstsParser(consts))
+    }
+}
+
+/// Parse `const NAME: Type = value;`
+fn parse_foreign_const(input: ParseStream, doc_comments: Vec<String>) -> syn::Result<ForeignConst> {
+    input.parse::<Token![const]>()?;
+    let name: Ident = input.parse()?;
+    input.parse::<Token![:]>()?;
+    let ty: Type = input.parse()?;
+    input.parse::<Token![=]>()?;
+    let value: syn::Expr = input.parse()?;
+    input.parse::<Token![;]>()?;
+    let (rust_path, value) = match value {
+        syn::Expr::Assign(syn::ExprAssign { left, right, .. }) => match *left {
+            syn::Expr::Path(syn::ExprPath { path, .. }) => (Some(path), *right),
+            left => return Err(syn::Error::new(left.span(), "expect path to Rust constant")),
+        },
+        value => (None, value),
+    };
+    Ok(ForeignConst {
+        src_id: SourceId::none(),
+        name,
+        ty,
+        rust_path,
+        value: parse_const_value(&value)?,
+        doc_comments,
+    })
+}
+
+/// Value of constant should be literal, because it is used during code generation
+pub(crate) fn parse_const_value(expr: &syn::Expr) -> syn::Result<ForeignConstValue> {
+    let (negative, lit) = match expr {
+        syn::Expr::Lit(syn::ExprLit { lit, .. }) => (false, lit),
+        syn::Expr::Unary(syn::ExprUnary {
+            op: syn::UnOp::Neg(_),
+            expr,
+            ..
+        }) => match **expr {
+            syn::Expr::Lit(syn::ExprLit { ref lit, .. }) => (true, lit),
+            _ => {
+                return Err(syn::Error::new(
+                    expr.span(),
+                    "expect literal as value of constant",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new(
+                expr.span(),
+                "expect literal as value of constant",
+            ))
+        }
+    };
+    match (lit, negative) {
+        (syn::Lit::Int(lit), _) if lit.suffix() == "f32" || lit.suffix() == "f64" => {
+            let digits = format!("{}.0", lit.base10_digits());
+            Ok(ForeignConstValue::Float(if negative {
+                format!("-{}", digits)
+            } else {
+                digits
+            }))
+        }
+        (syn::Lit::Int(lit), _) => {
+            let x: i128 = lit.base10_parse()?;
+            Ok(ForeignConstValue::Int(if negative { -x } else { x }))
+        }
+        (syn::Lit::Float(lit), _) => {
+            let digits = lit.base10_digits().to_string();
+            Ok(ForeignConstValue::Float(if negative {
+                format!("-{}", digits)
+            } else {
+                digits
+            }))
+        }
+        (syn::Lit::Bool(lit), false) => Ok(ForeignConstValue::Bool(lit.value)),
+        (syn::Lit::Str(lit), false) => Ok(ForeignConstValue::Str(lit.value())),
+        _ => Err(syn::Error::new(
+            lit.span(),
+            "only integer, float, bool and string literals are supported as value of constant",
+        )),
+    }
+}
+
+/// Accessors of `field name: Type;`, getter and setter if field is not readonly,
+/// they are ordinary inline methods
+fn field_accessors(
+    class_name: &Ident,
+    field: &ForeignField,
+    access: MethodAccess,
+    doc_comments: Vec<String>,
+    unknown_attrs: Vec<String>,
+    deprecated: Option<Deprecation>,
+) -> (ForeignMethod, Option<ForeignMethod>) {
+    let name = &field.name;
+    let ty = &field.ty;
+    let span = name.span();
+    let getter = ForeignMethod {
+        variant: MethodVariant::Method(SelfTypeVariant::Rptr),
+        rust_id: parse_quote!(#class_name::#name),
+        fn_decl: FnDecl {
+            span,
+            inputs: vec![FnArg::SelfArg(span, SelfTypeVariant::Rptr)],
+            output: parse_quote!(-> #ty),
+        },
+        name_alias: None,
+        access,
+        doc_comments: doc_comments.clone(),
+        inline_block: Some(parse_quote!({ Clone::clone(&this.#name) })),
+        unknown_attrs: unknown_attrs.clone(),
+        property: None,
+        deprecated: deprecated.clone(),
+    };
+    if field.readonly {
+        return (getter, None);
+    }
+    let setter_name = Ident::new(&format!("set_{}", property_name(&name.to_string())), span);
+    let setter = ForeignMethod {
+        variant: MethodVariant::Method(SelfTypeVariant::RptrMut),
+        rust_id: parse_quote!(#class_name::#setter_name),
+        fn_decl: FnDecl {
+            span,
+            inputs: vec![
+                FnArg::SelfArg(span, SelfTypeVariant::RptrMut),
+                FnArg::Default(NamedArg {
+                    name: name.to_string().into(),
+                    span,
+                    ty: ty.clone(),
+                    default: None,
+                }),
+            ],
+            output: syn::ReturnType::Default,
+        },
+        name_alias: None,
+        access,
+        doc_comments,
+        inline_block: Some(parse_quote!({ this.#name = #name; })),
+        unknown_attrs,
+        property: None,
+        deprecated,
+    };
+    (getter, Some(setter))
+}
+
+/// Name of property for getter with such name
+fn property_name(getter_name: &str) -> &str {
+    getter_name
+        .strip_prefix("get_")
+        .or_else(|| getter_name.strip_prefix("is_"))
+        .unwrap_or(getter_name)
+}
+
+/// Handle `#[property]`: getter `fn x(&self) -> T` (or `get_x`, `is_x`)
+/// and optional setter `fn set_x(&mut self, _: T)` are accessors of property `x`.
+/// Names of accessors depend on language: `getX`/`isX` and `setX` for Java,
+/// `x` and `set_x` (or `setX` in case of `camelCaseAliases`) for others.
+fn resolve_properties(
+    lang: Language,
+    camel_case: bool,
+    getters: &[usize],
+    methods: &mut [ForeignMethod],
+) -> syn::Result<()> {
+    let is_method = |m: &ForeignMethod| matches!(m.variant, MethodVariant::Method(_));
+    let rust_name = |m: &ForeignMethod| {
+        m.rust_id
+            .segments
+            .last()
+            .map(|x| x.ident.to_string())
+            .unwrap_or_default()
+    };
+    for &getter_idx in getters {
+        let getter = &methods[getter_idx];
+        if !is_method(getter)
+            || getter.fn_decl.inputs.len() != 1
+            || getter.fn_decl.output == syn::ReturnType::Default
+        {
+            return Err(syn::Error::new(
+                getter.span(),
+                "#[property] expects getter: method with only self argument that returns value",
+            ));
+        }
+        let getter_name = rust_name(getter);
+        let name: SmolStr = property_name(&getter_name).into();
+        let is_bool = match getter.fn_decl.output {
+            syn::ReturnType::Type(_, ref ty) => normalize_type(ty) == "bool",
+            syn::ReturnType::Default => false,
+        };
+        let setter_name = format!("set_{}", name);
+        let setter_idx = methods
+            .iter()
+            .position(|m| is_method(m) && rust_name(m) == setter_name);
+        if let Some(setter_idx) = setter_idx {
+            let setter = &methods[setter_idx];
+            if setter.fn_decl.inputs.len() != 2 || setter.fn_decl.output != syn::ReturnType::Default
+            {
+                return Err(syn::Error::new(
+                    setter.span(),
+                    format!(
+                        "setter of property `{}` should have one argument except self and return nothing",
+                        name
+                    ),
+                ));
+            }
+        }
+        for idx in std::iter::once(getter_idx).chain(setter_idx) {
+            let m = &methods[idx];
+            if m.name_alias.is_some() {
+                return Err(syn::Error::new(
+                    m.span(),
+                    "alias can not be used for accessors of property, their names are generated",
+                ));
+            }
+            if m.property.is_some() {
+                return Err(syn::Error::new(
+                    m.span(),
+                    "method is already accessor of other property",
+                ));
+            }
+        }
+
+        let (getter_alias, setter_alias) = match lang {
+            Language::Java => (
+                format!(
+                    "{}{}",
+                    if is_bool { "is" } else { "get" },
+                    name.to_camel_case()
+                ),
+                format!("set{}", name.to_camel_case()),
+            ),
+            Language::Cpp | Language::Python if camel_case => {
+                (name.to_mixed_case(), format!("set{}", name.to_camel_case()))
+            }
+            Language::Cpp | Language::Python => (name.to_string(), setter_name),
+        };
+        let mut set_accessor = |idx: usize, alias: &str, accessor| {
+            let m = &mut methods[idx];
+            m.name_alias = Some(Ident::new(alias, m.span()));
+            m.property = Some(MethodProperty {
+                name: name.clone(),
+                accessor,
+            });
+        };
+        set_accessor(getter_idx, &getter_alias, PropertyAccessor::Getter);
+        if let Some(setter_idx) = setter_idx {
+            set_accessor(setter_idx, &setter_alias, PropertyAccessor::Setter);
+        }
+    }
+    Ok(())
+}
+
+/// Handle `#[derive(camelCaseAliases)]`: add alias in camel case for each method
+pub(crate) fn apply_camel_case_aliases(
+    derive_list: &mut Vec<String>,
+    methods: &mut [ForeignMethod],
+) {
+    if let Some(pos) = derive_list.iter().position(|x| x == CAMEL_CASE_ALIASES) {
+        derive_list.remove(pos);
+        for m in methods {
+            if m.name_alias.is_none() {
+                m.name_alias = Some(Ident::new(
+                    &m.short_name().to_mixed_case(),
+                    m.rust_id.span(),
+                ));
+            }
+        }
+    }
+}
+
 impl TryFrom<syn::Signature> for crate::types::FnDecl {
     type Error = syn::Error;
     fn try_from(x: syn::Signature) -> std::result::Result<Self, Self::Error> {
@@ -581,6 +1262,45 @@ impl TryFrom<syn::Signature> for crate::types::FnDecl {
     }
 }
 
+type FnArgsIn = Punctuated<syn::FnArg, Token![,]>;
+
+/// Parse arguments of method, where argument may have default value,
+/// `name: Type = expr`, but only if all arguments after it also have it
+fn parse_fn_args_with_defaults(
+    input: ParseStream,
+) -> syn::Result<(FnArgsIn, Vec<Option<syn::Expr>>)> {
+    let mut args = Punctuated::new();
+    let mut defaults = Vec::new();
+    while !input.is_empty() {
+        let arg: syn::FnArg = input.parse()?;
+        let default = if input.peek(Token![=]) {
+            let eq = input.parse::<Token![=]>()?;
+            if let syn::FnArg::Receiver(_) = arg {
+                return Err(syn::Error::new(
+                    eq.span(),
+                    "self argument can not have default value",
+                ));
+            }
+            Some(input.parse::<syn::Expr>()?)
+        } else {
+            if defaults.iter().any(Option::is_some) {
+                return Err(syn::Error::new(
+                    arg.span(),
+                    "argument without default value after argument with default value",
+                ));
+            }
+            None
+        };
+        args.push_value(arg);
+        defaults.push(default);
+        if input.is_empty() {
+            break;
+        }
+        args.push_punct(input.parse::<Token![,]>()?);
+    }
+    Ok((args, defaults))
+}
+
 pub(crate) fn parse_fn_args(
     args: Punctuated<syn::FnArg, Token![,]>,
 ) -> syn::Result<(Vec<FnArg>, bool)> {
@@ -641,8 +1361,9 @@ pub(crate) fn parse_fn_args(
                 }
                 FnArg::Default(NamedArg {
                     name,
-                    ty: *typed_arg.ty,
+                    ty: normalize_closure_type(*typed_arg.ty),
                     span,
+                    default: None,
                 })
             }
         };
@@ -676,6 +1397,7 @@ impl Parse for ForeignEnumInfoParser {
             doc_comments: enum_doc_comments,
             derive_list,
             unknown_attrs,
+            ..
         } = parse_attrs(&input, ParseAttrsFlags::DERIVE)?;
         assert!(unknown_attrs.is_empty());
         input.parse::<Token![enum]>()?;
@@ -685,27 +1407,119 @@ impl Parse for ForeignEnumInfoParser {
         braced!(item_parser in input);
         let mut items = vec![];
         while !item_parser.is_empty() {
-            let doc_comments = parse_doc_comments(&item_parser)?;
+            let Attrs {
+                doc_comments,
+                cfg_enabled,
+                deprecated,
+                ..
+            } = parse_attrs(
+                &item_parser,
+                ParseAttrsFlags::DOC | ParseAttrsFlags::CFG | ParseAttrsFlags::DEPRECATED,
+            )?;
             let f_item_name = item_parser.parse::<Ident>()?;
+            let fields = if item_parser.peek(syn::token::Brace) {
+                syn::Fields::Named(item_parser.parse()?)
+            } else if item_parser.peek(syn::token::Paren) {
+                syn::Fields::Unnamed(item_parser.parse()?)
+            } else {
+                syn::Fields::Unit
+            };
             item_parser.parse::<Token![=]>()?;
             let item_name = item_parser.call(syn::Path::parse_mod_style)?;
+            let value = if item_parser.peek(Token![=]) {
+                item_parser.parse::<Token![=]>()?;
+                let discriminant: syn::Expr = item_parser.parse()?;
+                if fields != syn::Fields::Unit {
+                    return Err(syn::Error::new(
+                        discriminant.span(),
+                        "explicit value is not supported for item with data",
+                    ));
+                }
+                Some(parse_enum_discriminant(&discriminant)?)
+            } else {
+                None
+            };
             item_parser.parse::<Token![,]>()?;
+            if !cfg_enabled {
+                continue;
+            }
 
             items.push(ForeignEnumItem {
                 name: f_item_name,
                 rust_name: item_name,
                 doc_comments,
+                fields,
+                value,
+                deprecated,
             });
         }
 
-        Ok(ForeignEnumInfoParser(ForeignEnumInfo {
+        let enum_info = ForeignEnumInfo {
             src_id: SourceId::none(),
             name: enum_name,
             items,
             doc_comments: enum_doc_comments,
             derive_list,
-        }))
+        };
+        check_enum_items_values(&enum_info)?;
+        Ok(ForeignEnumInfoParser(enum_info))
+    }
+}
+
+/// Parse explicit value of enum item, only integer literals
+/// like `16`, `0x10` or `-1` are supported
+pub(crate) fn parse_enum_discriminant(expr: &syn::Expr) -> syn::Result<i64> {
+    match expr {
+        syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Int(lit),
+            ..
+        }) => lit.base10_parse(),
+        syn::Expr::Unary(syn::ExprUnary {
+            op: syn::UnOp::Neg(_),
+            expr,
+            ..
+        }) => match **expr {
+            syn::Expr::Lit(syn::ExprLit {
+                lit: syn::Lit::Int(ref lit),
+                ..
+            }) => Ok(-lit.base10_parse::<i64>()?),
+            _ => Err(syn::Error::new(
+                expr.span(),
+                "expect integer literal as value of enum item",
+            )),
+        },
+        _ => Err(syn::Error::new(
+            expr.span(),
+            "expect integer literal as value of enum item",
+        )),
+    }
+}
+
+pub(crate) fn check_enum_items_values(enum_info: &ForeignEnumInfo) -> syn::Result<()> {
+    if enum_info.is_flags() && !enum_info.is_c_like() {
+        return Err(syn::Error::new(
+            enum_info.span(),
+            "Flags enum can not contain items with data",
+        ));
+    }
+    if !enum_info.is_c_like() {
+        return Ok(());
+    }
+    let values = enum_info.items_values();
+    for (i, (item, value)) in enum_info.items.iter().zip(values.iter()).enumerate() {
+        if let Some(prev_idx) = values[..i].iter().position(|x| x == value) {
+            let mut err = syn::Error::new(
+                item.name.span(),
+                format!("value {} is already assigned to other item", value),
+            );
+            err.combine(syn::Error::new(
+                enum_info.items[prev_idx].name.span(),
+                "first assignment here",
+            ));
+            return Err(err);
+        }
     }
+    Ok(())
 }
 
 struct ForeignInterfaceParser(ForeignInterface);
@@ -731,7 +1545,15 @@ impl Parse for ForeignInterfaceParser {
         let mut items = vec![];
 
         while !item_parser.is_empty() {
-            let doc_comments = parse_doc_comments(&item_parser)?;
+            let Attrs {
+                doc_comments,
+                cfg_enabled,
+                deprecated,
+                ..
+            } = parse_attrs(
+                &item_parser,
+                ParseAttrsFlags::DOC | ParseAttrsFlags::CFG | ParseAttrsFlags::DEPRECATED,
+            )?;
             let func_name = item_parser.parse::<Ident>()?;
             if func_name == "self_type" {
                 let traits: syn::TypeTraitObject = item_parser.parse()?;
@@ -781,6 +1603,9 @@ impl Parse for ForeignInterfaceParser {
             let fn_args = parse_fn_args(args_in)?.0;
             let out_type: syn::ReturnType = item_parser.parse()?;
             item_parser.parse::<Token![;]>()?;
+            if !cfg_enabled {
+                continue;
+            }
             let span = rust_func_name.span();
             items.push(ForeignInterfaceMethod {
                 name: func_name,
@@ -791,6 +1616,7 @@ impl Parse for ForeignInterfaceParser {
                     output: out_type,
                 },
                 doc_comments,
+                deprecated,
             });
         }
 
@@ -804,6 +1630,7 @@ impl Parse for ForeignInterfaceParser {
             self_type,
             doc_comments: interface_doc_comments,
             items,
+            closure: None,
         }))
     }
 }
@@ -859,6 +1686,138 @@ mod tests {
         test_parse::<JavaClass>(mac.tokens);
     }
 
+    #[test]
+    fn test_parse_foreign_class_with_default_args() {
+        let _ = env_logger::try_init();
+        let mac: syn::Macro = parse_quote! {
+            foreign_class!(class Foo {
+                self_type Foo;
+                constructor Foo::new() -> Foo;
+                fn Foo::connect(&self, host: &str, timeout_ms: u32 = 5000);
+            })
+        };
+        let class: JavaClass = test_parse(mac.tokens);
+        let connect = &class.0.methods[1];
+        let defaults: Vec<_> = connect
+            .arg_defaults_without_self()
+            .map(|x| x.map(|x| x.into_token_stream().to_string()))
+            .collect();
+        assert_eq!(vec![None, Some("5000".to_string())], defaults);
+        let overloads = connect.default_args_overloads();
+        assert_eq!(1, overloads.len());
+        assert_eq!(
+            vec!["host"],
+            overloads[0].arg_names_without_self().collect::<Vec<_>>()
+        );
+
+        let mac: syn::Macro = parse_quote! {
+            foreign_class!(class Foo {
+                self_type Foo;
+                constructor Foo::new() -> Foo;
+                fn Foo::connect(&self, timeout_ms: u32 = 5000, host: &str);
+            })
+        };
+        let err = syn::parse2::<JavaClass>(mac.tokens).err().unwrap();
+        assert_eq!(
+            "argument without default value after argument with default value",
+            err.to_string()
+        );
+    }
+
+    #[test]
+    fn test_parse_foreign_class_with_property() {
+        let _ = env_logger::try_init();
+        let mac: syn::Macro = parse_quote! {
+            foreign_class!(class Foo {
+                self_type Foo;
+                constructor Foo::new() -> Foo;
+                #[property]
+                fn Foo::get_line_width(&self) -> f64;
+                fn Foo::set_line_width(&mut self, _: f64);
+            })
+        };
+        let names = |methods: &[ForeignMethod]| -> Vec<String> {
+            methods.iter().skip(1).map(|m| m.short_name()).collect()
+        };
+        let class: PythonClass = test_parse(mac.tokens.clone());
+        assert_eq!(
+            vec!["line_width", "set_line_width"],
+            names(&class.0.methods)
+        );
+        assert_eq!(
+            Some(PropertyAccessor::Setter),
+            class.0.methods[2].property.as_ref().map(|p| p.accessor)
+        );
+        let class: CppClass = test_parse(mac.tokens.clone());
+        assert_eq!(
+            vec!["line_width", "set_line_width"],
+            names(&class.0.methods)
+        );
+        let class: JavaClass = test_parse(mac.tokens);
+        assert_eq!(
+            vec!["getLineWidth", "setLineWidth"],
+            names(&class.0.methods)
+        );
+
+        let mac: syn::Macro = parse_quote! {
+            foreign_class!(class Foo {
+                self_type Foo;
+                constructor Foo::new() -> Foo;
+                #[property]
+                fn Foo::x(&self) -> i32;
+                fn Foo::set_x(&mut self, _: i32) -> bool;
+            })
+        };
+        let err = syn::parse2::<JavaClass>(mac.tokens).err().unwrap();
+        assert_eq!(
+            "setter of property `x` should have one argument except self and return nothing",
+            err.to_string()
+        );
+    }
+
+    #[test]
+    fn test_parse_foreign_class_with_fields() {
+        let _ = env_logger::try_init();
+        let mac: syn::Macro = parse_quote! {
+            foreign_class!(class Point {
+                self_type Point;
+                constructor Point::new() -> Point;
+                field x: f64;
+                #[readonly]
+                field id: u64;
+            })
+        };
+        let class: JavaClass = test_parse(mac.tokens.clone());
+        let class = class.0;
+        assert_eq!(
+            vec!["x", "id"],
+            class
+                .fields
+                .iter()
+                .map(|f| f.name.to_string())
+                .collect::<Vec<_>>()
+        );
+        assert!(class.fields[1].readonly);
+        assert_eq!(
+            vec!["getX", "setX", "getId"],
+            class
+                .methods
+                .iter()
+                .skip(1)
+                .map(|m| m.short_name())
+                .collect::<Vec<_>>()
+        );
+
+        let mac: syn::Macro = parse_quote! {
+            foreign_class!(class Point {
+                self_type Point;
+                #[property]
+                field x: f64;
+            })
+        };
+        assert!(syn::parse2::<CppClass>(mac.tokens).is_err());
+    }
+
     #[test]
     fn test_parse_foreign_enum() {
         let _ = env_logger::try_init();
@@ -871,6 +1830,559 @@ mod tests {
         };
         let enum_ = parse_foreign_enum(SourceId::none(), mac.tokens).unwrap();
         assert_eq!("MyEnum", enum_.name.to_string());
+        assert!(enum_.is_c_like());
+    }
+
+    #[test]
+    fn test_parse_foreign_enum_with_values() {
+        let _ = env_logger::try_init();
+        let mac: syn::Macro = parse_quote! {
+            foreign_enum!(enum Opcode {
+                PING = Opcode::Ping = 0x10,
+                PONG = Opcode::Pong,
+                CLOSE = Opcode::Close = 0x7f,
+                ERROR = Opcode::Error = -2,
+            })
+        };
+        let enum_ = parse_foreign_enum(SourceId::none(), mac.tokens).unwrap();
+        assert_eq!(Some(16), enum_.items[0].value);
+        assert_eq!(None, enum_.items[1].value);
+        assert_eq!(vec![16, 17, 127, -2], enum_.items_values());
+        assert!(!enum_.is_flags());
+
+        let mac: syn::Macro = parse_quote! {
+            foreign_enum!(#[derive(Flags)] enum Permissions {
+                READ = Permissions::READ,
+                WRITE = Permissions::WRITE,
+                READ_WRITE = Permissions::READ_WRITE = 3,
+                EXEC = Permissions::EXEC,
+            })
+        };
+        let enum_ = parse_foreign_enum(SourceId::none(), mac.tokens).unwrap();
+        assert!(enum_.is_flags());
+        assert_eq!(vec![1, 2, 3, 4], enum_.items_values());
+
+        let mac: syn::Macro = parse_quote! {
+            foreign_enum!(enum Opcode {
+                PING = Opcode::Ping = 1,
+                PONG = Opcode::Pong = 1,
+            })
+        };
+        assert!(parse_foreign_enum(SourceId::none(), mac.tokens).is_err());
+
+        let mac: syn::Macro = parse_quote! {
+            foreign_enum!(enum Event {
+                Moved(i32) = Event::Moved = 1,
+            })
+        };
+        assert!(parse_foreign_enum(SourceId::none(), mac.tokens).is_err());
+
+        let mac: syn::Macro = parse_quote! {
+            foreign_enum!(enum Opcode {
+                PING = Opcode::Ping = 1 + 1,
+            })
+        };
+        assert!(parse_foreign_enum(SourceId::none(), mac.tokens).is_err());
+    }
+
+    #[test]
+    fn test_iterator_classes() {
+        let _ = env_logger::try_init();
+        let mac: syn::Macro = parse_quote! {
+            foreigner_class!(class Foo {
+                self_type Foo;
+                constructor Foo::new() -> Foo;
+                fn Foo::names(&self) -> Box<dyn Iterator<Item = String>>;
+                fn Foo::other_names(&self) -> Box<dyn Iterator<Item = String>>;
+                fn Foo::chunks(&self) -> Box<dyn Iterator<Item = Vec<u8>> + Send>;
+                fn Foo::f(&self) -> Box<dyn Display>;
+            })
+        };
+        let class = test_parse::<JavaClass>(mac.tokens).0;
+        assert!(class.iterator_item().is_none());
+        let iter_classes = class.iterator_classes();
+        assert_eq!(
+            vec!["StringIterator", "VecU8SendIterator"],
+            iter_classes
+                .iter()
+                .map(|x| x.name.to_string())
+                .collect::<Vec<_>>()
+        );
+        let iter_class = &iter_classes[1];
+        assert_eq!(
+            normalize_type(&parse_type!(
+                std::iter::Peekable<Box<dyn Iterator<Item = Vec<u8>> + Send>>
+            )),
+            normalize_type(&iter_class.self_type_as_ty())
+        );
+        assert_eq!(
+            normalize_type(&parse_type!(Vec<u8>)),
+            normalize_type(iter_class.iterator_item().unwrap())
+        );
+        assert_eq!(
+            vec!["", "rustHasNext", "rustNext"],
+            iter_class
+                .methods
+                .iter()
+                .map(|m| m.short_name().to_string())
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_async_methods() {
+        let _ = env_logger::try_init();
+        let mac: syn::Macro = parse_quote! {
+            foreigner_class!(class Foo {
+                self_type Foo;
+                constructor Foo::new() -> Foo;
+                fn Foo::fetch(&self, url: &str) -> impl Future<Output = Result<Vec<u8>, String>>;
+                fn Foo::f(&self) -> i32;
+                fn Foo::close(&mut self) -> impl Future<Output = ()> + Send;
+                fn Foo::load(&self) -> impl Future<Output = Vec<u8>>;
+            })
+        };
+        let class = test_parse::<JavaClass>(mac.tokens).0;
+        assert_eq!(
+            vec!["VecU8FutureCompleter", "VoidFutureCompleter"],
+            class
+                .future_completers()
+                .iter()
+                .map(|x| x.name.to_string())
+                .collect::<Vec<_>>()
+        );
+        assert!(class.with_async_methods(None).is_err());
+        let spawner: syn::Expr = parse_quote!(tokio::spawn);
+        let class = class.with_async_methods(Some(&spawner)).unwrap();
+        assert_eq!(
+            vec!["new", "f", "rustFetch", "rustClose", "rustLoad"],
+            class
+                .methods
+                .iter()
+                .map(|m| m.short_name().to_string())
+                .collect::<Vec<_>>()
+        );
+        let fetch = &class.methods[2];
+        assert_eq!(MethodAccess::Private, fetch.access);
+        assert_eq!(syn::ReturnType::Default, fetch.fn_decl.output);
+        assert_eq!(
+            vec!["url", "completer"],
+            fetch.arg_names_without_self().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_abstract_class() {
+        let _ = env_logger::try_init();
+        let mac: syn::Macro = parse_quote! {
+            foreigner_class!(abstract class Shape {
+                fn Shape::area(&self) -> f64;
+                protected fn Shape::name(&self) -> String;
+            })
+        };
+        let base = test_parse::<JavaClass>(mac.tokens).0;
+        assert!(base.is_abstract);
+        assert!(base.self_desc.is_none());
+        base.validate_class().unwrap();
+
+        let mac: syn::Macro = parse_quote! {
+            foreigner_class!(class Circle extends Shape {
+                self_type Circle;
+                constructor Circle::new() -> Circle;
+                fn Circle::name(&self) -> String;
+                fn Circle::radius(&self) -> f64;
+            })
+        };
+        let mut class = test_parse::<JavaClass>(mac.tokens).0;
+        assert!(!class.is_abstract);
+        assert_eq!("Shape", class.base_class.as_ref().unwrap().to_string());
+        class.inherit_methods(&base);
+        assert_eq!(
+            vec!["new", "name", "radius", "area"],
+            class
+                .methods
+                .iter()
+                .map(|m| m.short_name().to_string())
+                .collect::<Vec<_>>()
+        );
+
+        let mac: syn::Macro = parse_quote! {
+            foreigner_class!(abstract class Shape {
+                fn Shape::new() -> Shape;
+            })
+        };
+        assert!(syn::parse2::<JavaClass>(mac.tokens).is_err());
+        let mac: syn::Macro = parse_quote! {
+            foreigner_class!(abstract class Shape {
+                self_type Shape;
+                constructor Shape::new() -> Shape;
+            })
+        };
+        assert!(syn::parse2::<JavaClass>(mac.tokens).is_err());
+    }
+
+    #[test]
+    fn test_derived_protocols() {
+        let _ = env_logger::try_init();
+        let mac: syn::Macro = parse_quote! {
+            foreigner_class!(#[derive(Eq, Hash, Debug, PartialOrd)] class Foo {
+                self_type Foo;
+                constructor Foo::new() -> Foo;
+            })
+        };
+        let class = test_parse::<JavaClass>(mac.tokens).0;
+        let protocols = class.derived_protocols().unwrap();
+        assert!(protocols.eq);
+        assert!(protocols.hash);
+        assert_eq!(Some("Debug"), protocols.to_string);
+        assert_eq!(Some("PartialOrd"), protocols.cmp);
+        let class = class.with_derived_protocol_methods().unwrap();
+        assert_eq!(
+            vec!["new", "rustEq", "rustHash", "rustToString", "rustCompare"],
+            class
+                .methods
+                .iter()
+                .map(|m| m.short_name().to_string())
+                .collect::<Vec<_>>()
+        );
+
+        let mac: syn::Macro = parse_quote! {
+            foreigner_class!(#[derive(Hash)] class Foo {
+                self_type Foo;
+                constructor Foo::new() -> Foo;
+            })
+        };
+        let class = test_parse::<JavaClass>(mac.tokens).0;
+        assert!(class.derived_protocols().is_err());
+
+        let mac: syn::Macro = parse_quote! {
+            foreigner_class!(#[derive(Eq)] class Foo {
+                static_method f();
+            })
+        };
+        let class = test_parse::<JavaClass>(mac.tokens).0;
+        assert!(class.derived_protocols().is_err());
+    }
+
+    #[test]
+    fn test_parse_consts() {
+        let _ = env_logger::try_init();
+        let mac: syn::Macro = parse_quote! {
+            foreigner_class!(class Packet {
+                /// Max size
+                const MAX_SIZE: usize = Packet::MAX_SIZE = 1500;
+                const MIN_DELTA: i32 = -5;
+                const RATIO: f64 = 1f64;
+                const NAME: &str = "packet";
+                fn Packet::len(&self) -> usize;
+            })
+        };
+        let class = test_parse::<JavaClass>(mac.tokens).0;
+        assert_eq!(4, class.consts.len());
+        assert_eq!(vec![" Max size"], class.consts[0].doc_comments);
+        assert_eq!(ForeignConstValue::Int(1500), class.consts[0].value);
+        assert!(class.consts[0].rust_path.is_some());
+        assert_eq!(ForeignConstValue::Int(-5), class.consts[1].value);
+        assert!(class.consts[1].rust_path.is_none());
+        assert_eq!(
+            ForeignConstValue::Float("1.0".to_string()),
+            class.consts[2].value
+        );
+        assert_eq!(
+            ForeignConstValue::Str("packet".to_string()),
+            class.consts[3].value
+        );
+        assert_eq!(1, class.methods.len());
+
+        let mac: syn::Macro = parse_quote! {
+            foreign_const!(
+                const DEBUG: bool = false;
+                const EPS: f32 = -0.5;
+            )
+        };
+        let consts = parse_foreign_consts(SourceId::none(), mac.tokens).unwrap();
+        assert_eq!(2, consts.len());
+        assert_eq!(ForeignConstValue::Bool(false), consts[0].value);
+        assert_eq!(
+            ForeignConstValue::Float("-0.5".to_string()),
+            consts[1].value
+        );
+
+        let mac: syn::Macro = parse_quote! {
+            foreign_const!(const SIZE: usize = 2 * 750;)
+        };
+        assert!(parse_foreign_consts(SourceId::none(), mac.tokens).is_err());
+    }
+
+    #[test]
+    fn test_eval_cfg() {
+        let _ = env_logger::try_init();
+        let vars: rustc_hash::FxHashMap<&str, &str> = [
+            ("CARGO_FEATURE_FLAPIGEN_TEST_ON", "1"),
+            ("CARGO_CFG_FLAPIGEN_TEST_OS", "linux"),
+            ("CARGO_CFG_FLAPIGEN_TEST_FEATURE", "sse,avx"),
+            ("CARGO_CFG_FLAPIGEN_TEST_FLAG", ""),
+        ]
+        .iter()
+        .cloned()
+        .collect();
+        let env = |name: &str| vars.get(name).map(|x| x.to_string());
+        let eval = |attr: syn::Attribute| is_enabled_by_cfg_with_env(&[attr], &env).unwrap();
+        assert!(eval(parse_quote! { #[cfg(feature = "flapigen-test-on")] }));
+        assert!(!eval(
+            parse_quote! { #[cfg(feature = "flapigen-test-off")] }
+        ));
+        assert!(eval(parse_quote! { #[cfg(flapigen_test_os = "linux")] }));
+        assert!(!eval(parse_quote! { #[cfg(flapigen_test_os = "windows")] }));
+        assert!(eval(parse_quote! { #[cfg(flapigen_test_feature = "avx")] }));
+        assert!(eval(parse_quote! { #[cfg(flapigen_test_flag)] }));
+        assert!(!eval(parse_quote! { #[cfg(flapigen_test_no_flag)] }));
+        assert!(eval(parse_quote! {
+            #[cfg(all(flapigen_test_flag, not(feature = "flapigen-test-off")))]
+        }));
+        assert!(!eval(parse_quote! {
+            #[cfg(any(flapigen_test_no_flag, flapigen_test_os = "macos"))]
+        }));
+        assert!(is_enabled_by_cfg_with_env(
+            &[parse_quote! { #[cfg(unknown(flapigen_test_flag))] }],
+            &env
+        )
+        .is_err());
+
+        let for_lang =
+            |attr: syn::Attribute, lang: &str| is_enabled_by_cfg_for_lang(&[attr], lang).unwrap();
+        assert!(for_lang(
+            parse_quote! { #[cfg(flapigen_lang = "java")] },
+            "java"
+        ));
+        assert!(!for_lang(
+            parse_quote! { #[cfg(flapigen_lang = "java")] },
+            "cpp"
+        ));
+        assert!(for_lang(
+            parse_quote! { #[cfg(not(flapigen_lang = "python"))] },
+            "cpp"
+        ));
+
+        // variables for features below are not set, so the global environment can be used
+        assert!(cargo_env("CARGO_FEATURE_FLAPIGEN_TEST_OFF").is_none());
+
+        let mac: syn::Macro = parse_quote! {
+            foreign_class!(class Foo {
+                self_type Foo;
+                constructor Foo::new() -> Foo;
+                #[cfg(feature = "flapigen-test-off")]
+                constructor Foo::with_size(_: usize) -> Foo;
+                #[cfg(not(feature = "flapigen-test-off"))]
+                fn Foo::f(&self);
+                #[cfg(feature = "flapigen-test-off")]
+                fn Foo::g(&self);
+                #[cfg(feature = "flapigen-test-off")]
+                field x: i32;
+                #[cfg(feature = "flapigen-test-off")]
+                const SIZE: usize = 1;
+            })
+        };
+        let class: ForeignClassInfo = parse_foreigner_class(
+            SourceId::none(),
+            &LanguageConfig::JavaConfig(crate::JavaConfig::new(
+                "/tmp".into(),
+                "org.example".into(),
+            )),
+            mac.tokens,
+        )
+        .unwrap();
+        let names: Vec<_> = class.methods.iter().map(|m| m.short_name()).collect();
+        assert_eq!(vec!["new", "f"], names);
+        assert!(class.fields.is_empty());
+        assert!(class.consts.is_empty());
+
+        let mac: syn::Macro = parse_quote! {
+            foreign_callback!(callback Observer {
+                self_type Observer;
+                onA = Observer::on_a(&self);
+                #[cfg(feature = "flapigen-test-off")]
+                onB = Observer::on_b(&self);
+            })
+        };
+        let callback = parse_foreign_interface(SourceId::none(), mac.tokens).unwrap();
+        assert_eq!(1, callback.items.len());
+    }
+
+    #[test]
+    fn test_parse_deprecated() {
+        let _ = env_logger::try_init();
+        let parse = |attr: syn::Attribute| parse_deprecated(&attr.parse_meta().unwrap());
+        assert_eq!(
+            Deprecation::default(),
+            parse(parse_quote! { #[deprecated] }).unwrap()
+        );
+        let d = parse(parse_quote! { #[deprecated = "use g"] }).unwrap();
+        assert_eq!("use g", d.message());
+        let d = parse(parse_quote! { #[deprecated(since = "1.2", note = "use g")] }).unwrap();
+        assert_eq!(Some("1.2"), d.since.as_deref());
+        assert_eq!("since 1.2: use g", d.message());
+        assert!(parse(parse_quote! { #[deprecated(reason = "x")] }).is_err());
+
+        let mac: syn::Macro = parse_quote! {
+            foreign_class!(
+                #[deprecated(note = "use Bar")]
+                class Foo {
+                    self_type Foo;
+                    constructor Foo::new() -> Foo;
+                    #[deprecated(since = "0.2")]
+                    fn Foo::f(&self);
+                    fn Foo::g(&self);
+                }
+            )
+        };
+        let class: ForeignClassInfo = parse_foreigner_class(
+            SourceId::none(),
+            &LanguageConfig::JavaConfig(crate::JavaConfig::new(
+                "/tmp".into(),
+                "org.example".into(),
+            )),
+            mac.tokens,
+        )
+        .unwrap();
+        assert_eq!("use Bar", class.deprecated.unwrap().message());
+        let deprecated: Vec<_> = class
+            .methods
+            .iter()
+            .map(|m| m.deprecated.as_ref().map(Deprecation::message))
+            .collect();
+        assert_eq!(vec![None, Some("since 0.2".to_string()), None], deprecated);
+    }
+
+    #[test]
+    fn test_parse_closure_args() {
+        let _ = env_logger::try_init();
+        let mac: syn::Macro = parse_quote! {
+            foreign_class!(class Foo {
+                self_type Foo;
+                constructor Foo::new() -> Foo;
+                fn Foo::map(&self, f: impl Fn(i32) -> i32) -> i32;
+                fn Foo::visit(&self, f: &dyn Fn(i32, &str));
+                fn Foo::run(&self, f: Box<dyn Fn()>, g: Box<dyn Fn(i32) -> i32 + Send>);
+            })
+        };
+        let class: ForeignClassInfo = parse_foreigner_class(
+            SourceId::none(),
+            &LanguageConfig::JavaConfig(crate::JavaConfig::new(
+                "/tmp".into(),
+                "org.example".into(),
+            )),
+            mac.tokens,
+        )
+        .unwrap();
+        let arg_types: Vec<String> = class
+            .methods
+            .iter()
+            .flat_map(|m| m.fn_decl.inputs.iter())
+            .filter_map(|arg| arg.as_named_arg().ok())
+            .map(|arg| arg.ty.to_token_stream().to_string())
+            .collect();
+        assert_eq!(
+            vec![
+                "Box < dyn Fn (i32) -> i32 + Send >",
+                "& dyn Fn (i32 , & str)",
+                "Box < dyn Fn () >",
+                "Box < dyn Fn (i32) -> i32 + Send >",
+            ],
+            arg_types
+        );
+        let callbacks: Vec<String> = class
+            .closure_callbacks()
+            .iter()
+            .map(|c| c.name.to_string())
+            .collect();
+        assert_eq!(
+            vec!["I32ToI32Closure", "I32StrClosure", "VoidClosure"],
+            callbacks
+        );
+    }
+
+    #[test]
+    fn test_parse_foreign_fns() {
+        let _ = env_logger::try_init();
+        let config =
+            LanguageConfig::JavaConfig(crate::JavaConfig::new("/tmp".into(), "org.example".into()));
+        let mac: syn::Macro = parse_quote! {
+            foreign_fn!(
+                /// Sum of numbers
+                fn add(a: i32, b: i32 = 1) -> i32;
+                fn utils::log(msg: &str); alias write_log;
+            )
+        };
+        let fns = parse_foreign_fns(SourceId::none(), &config, mac.tokens).unwrap();
+        assert_eq!(2, fns.len());
+        assert_eq!(MethodVariant::StaticMethod, fns[0].method.variant);
+        assert_eq!(
+            vec![" Sum of numbers".to_string()],
+            fns[0].method.doc_comments
+        );
+        assert_eq!("add", fns[0].method.short_name().as_str());
+        assert_eq!("write_log", fns[1].method.short_name().as_str());
+
+        let mac: syn::Macro = parse_quote! {
+            foreign_fn!(fn Foo::get(&self) -> i32;)
+        };
+        assert!(parse_foreign_fns(SourceId::none(), &config, mac.tokens).is_err());
+
+        let mac: syn::Macro = parse_quote! {
+            foreign_fn!(const SIZE: usize = 1;)
+        };
+        assert!(parse_foreign_fns(SourceId::none(), &config, mac.tokens).is_err());
+    }
+
+    #[test]
+    fn test_parse_foreign_enum_with_data() {
+        let _ = env_logger::try_init();
+        let mac: syn::Macro = parse_quote! {
+            foreign_enum!(enum Event {
+                Connected { peer: String } = Event::Connected,
+                Data(Vec<u8>, u32) = Event::Data,
+                Closed = Event::Closed,
+            })
+        };
+        let enum_ = parse_foreign_enum(SourceId::none(), mac.tokens).unwrap();
+        assert!(!enum_.is_c_like());
+        let fields = |idx: usize| -> Vec<(String, String)> {
+            enum_.items[idx]
+                .fields()
+                .into_iter()
+                .map(|(name, ty)| (name.to_string(), normalize_type(ty).to_string()))
+                .collect()
+        };
+        assert_eq!(vec![("peer".to_string(), "String".to_string())], fields(0));
+        assert_eq!(
+            vec![
+                ("field0".to_string(), "Vec < u8 >".to_string()),
+                ("field1".to_string(), "u32".to_string())
+            ],
+            fields(1)
+        );
+        assert!(fields(2).is_empty());
+
+        let raw_class = enum_.raw_class();
+        assert_eq!("EventRaw", raw_class.name.to_string());
+        assert_eq!(
+            vec![
+                "tag",
+                "new_Connected",
+                "get_Connected_peer",
+                "new_Data",
+                "get_Data_field0",
+                "get_Data_field1",
+                "new_Closed",
+                "free"
+            ],
+            raw_class
+                .methods
+                .iter()
+                .map(|m| m.short_name())
+                .collect::<Vec<_>>()
+        );
     }
 
     #[test]
@@ -95,6 +95,20 @@ pub trait SwigForeignEnum {
     fn from_u32(_: u32) -> Self;
 }
 
+/// Receives result of Rust future returned by async method
+#[allow(dead_code)]
+pub trait SwigFutureCompleter<T>: Send {
+    fn complete(&self, value: T);
+    fn fail(&self, error: String);
+}
+
+/// Receives notification about completion of Rust future without value
+#[allow(dead_code)]
+pub trait SwigVoidFutureCompleter: Send {
+    fn complete(&self);
+    fn fail(&self, error: String);
+}
+
 #[allow(dead_code)]
 #[swig_code = "let mut {to_var}: {to_var_type} = {from_var}.swig_into();"]
 trait SwigInto<T> {
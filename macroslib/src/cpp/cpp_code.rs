@@ -8,7 +8,10 @@ use syn::spanned::Spanned;
 
 use crate::{
     code_parse::parse_fn_args,
-    cpp::{map_type::map_repr_c_type, CppContext, CppForeignMethodSignature, MergeCItemsFlags},
+    cpp::{
+        map_type::map_repr_c_type, CppContext, CppForeignMethodSignature, CppForeignTypeInfo,
+        MergeCItemsFlags,
+    },
     error::{panic_on_syn_error, DiagnosticError},
     file_cache::FileWriteCache,
     namegen::new_unique_name,
@@ -17,7 +20,7 @@ use crate::{
         ast::DisplayToTokens, CItem, CItems, TypeConvCodeSubstParam, FROM_VAR_TEMPLATE,
         TO_VAR_TEMPLATE, TO_VAR_TYPE_TEMPLATE,
     },
-    types::{FnArg, ForeignClassInfo, ForeignEnumInfo},
+    types::{Deprecation, FnArg, ForeignClassInfo, ForeignEnumInfo, ForeignMethod},
     WRITE_TO_MEM_FAILED_MSG,
 };
 
@@ -38,6 +41,40 @@ pub(in crate::cpp) fn doc_comments_to_c_comments(
     comments
 }
 
+/// `[[deprecated("..")]] ` attribute (with trailing space) or empty string
+pub(in crate::cpp) fn cpp_deprecated_attr(deprecated: Option<&Deprecation>) -> String {
+    match deprecated {
+        Some(d) => {
+            let msg = d.message();
+            if msg.is_empty() {
+                "[[deprecated]] ".into()
+            } else {
+                format!(
+                    "[[deprecated(\"{}\")]] ",
+                    msg.replace('\\', "\\\\").replace('"', "\\\"")
+                )
+            }
+        }
+        None => String::new(),
+    }
+}
+
+/// Generated code have to call deprecated items, so we suppress warnings about it
+pub(in crate::cpp) const CPP_IGNORE_DEPRECATED_BEGIN: &str = r#"
+#if defined(_MSC_VER)
+#pragma warning(push)
+#pragma warning(disable: 4996)
+#elif defined(__GNUC__)
+#pragma GCC diagnostic push
+#pragma GCC diagnostic ignored "-Wdeprecated-declarations"
+#endif"#;
+pub(in crate::cpp) const CPP_IGNORE_DEPRECATED_END: &str = r#"#if defined(_MSC_VER)
+#pragma warning(pop)
+#elif defined(__GNUC__)
+#pragma GCC diagnostic pop
+#endif
+"#;
+
 pub(in crate::cpp) fn c_generate_args_with_types<'a, NI>(
     f_method: &CppForeignMethodSignature,
     name_iter: NI,
@@ -77,21 +114,95 @@ pub(in crate::cpp) fn cpp_generate_args_with_types<'a, NI: Iterator<Item = &'a s
             ret.push_str(", ");
         }
 
-        write!(
-            &mut ret,
-            "{} {}",
-            if let Some(conv) = f_type_info.cpp_converter.as_ref() {
-                conv.typename.clone()
-            } else {
-                f_type_info.as_ref().name.clone()
-            },
-            arg_name,
-        )
-        .expect(WRITE_TO_MEM_FAILED_MSG);
+        write!(&mut ret, "{} {}", cpp_arg_type(f_type_info), arg_name)
+            .expect(WRITE_TO_MEM_FAILED_MSG);
     }
     ret
 }
 
+pub(in crate::cpp) fn cpp_arg_type(f_type_info: &CppForeignTypeInfo) -> &str {
+    if let Some(conv) = f_type_info.cpp_converter.as_ref() {
+        conv.typename.as_str()
+    } else {
+        f_type_info.as_ref().name.as_str()
+    }
+}
+
+/// The same as `cpp_generate_args_with_types`, but with default values
+/// of arguments, so should be used only for declaration of method
+pub(in crate::cpp) fn cpp_generate_args_with_defaults(
+    f_method: &CppForeignMethodSignature,
+    method: &ForeignMethod,
+    src_id: SourceId,
+) -> Result<String, DiagnosticError> {
+    let mut ret = String::new();
+    let args = f_method
+        .input
+        .iter()
+        .zip(method.arg_names_without_self())
+        .zip(method.arg_defaults_without_self());
+    for ((f_type_info, arg_name), default) in args {
+        if f_type_info.input_to_output {
+            continue;
+        }
+        if !ret.is_empty() {
+            ret.push_str(", ");
+        }
+        write!(&mut ret, "{} {}", cpp_arg_type(f_type_info), arg_name)
+            .expect(WRITE_TO_MEM_FAILED_MSG);
+        if let Some(default) = default {
+            let value = default_value_to_cpp(default).ok_or_else(|| {
+                DiagnosticError::new(
+                    src_id,
+                    default.span(),
+                    format!(
+                        "default value `{}` can not be expressed in C++, \
+                         only literals are supported",
+                        DisplayToTokens(default)
+                    ),
+                )
+            })?;
+            write!(&mut ret, " = {}", value).expect(WRITE_TO_MEM_FAILED_MSG);
+        }
+    }
+    Ok(ret)
+}
+
+fn default_value_to_cpp(expr: &syn::Expr) -> Option<String> {
+    match expr {
+        syn::Expr::Lit(syn::ExprLit { lit, .. }) => match lit {
+            syn::Lit::Bool(x) => Some(x.value.to_string()),
+            syn::Lit::Int(x) => Some(x.base10_digits().into()),
+            syn::Lit::Float(x) => {
+                let mut value = x.base10_digits().to_string();
+                if !value.contains(['.', 'e', 'E']) {
+                    value.push_str(".0");
+                }
+                if x.suffix() == "f32" {
+                    value.push('f');
+                }
+                Some(value)
+            }
+            syn::Lit::Str(x) => {
+                let value = x.value();
+                if value.is_ascii() {
+                    Some(format!("{:?}", value))
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        },
+        syn::Expr::Unary(syn::ExprUnary {
+            op: syn::UnOp::Neg(_),
+            expr,
+            ..
+        }) => default_value_to_cpp(expr).map(|x| format!("-{}", x)),
+        syn::Expr::Paren(syn::ExprParen { expr, .. }) => default_value_to_cpp(expr),
+        _ => None,
+    }
+}
+
 pub(in crate::cpp) fn convert_args<'a, NI: Iterator<Item = &'a str>>(
     f_method: &CppForeignMethodSignature,
     known_names: &mut FxHashSet<SmolStr>,
@@ -163,6 +274,11 @@ pub(in crate::cpp) fn c_header_name(class: &ForeignClassInfo) -> String {
     format!("c_{}.h", class.name)
 }
 
+/// C++ header with functions declared via `foreign_fn!`
+pub(in crate::cpp) const MODULE_FNS_CPP_HEADER: &str = "module_functions.hpp";
+/// C header with functions declared via `foreign_fn!`
+pub(in crate::cpp) const MODULE_FNS_C_HEADER: &str = "c_module_functions.h";
+
 pub(in crate::cpp) fn cpp_header_name_for_enum(enum_info: &ForeignEnumInfo) -> String {
     format!("{}.hpp", enum_info.name)
 }
@@ -1,4 +1,4 @@
-use std::{borrow::Cow, io::Write};
+use std::{borrow::Cow, fmt::Write as FmtWrite, io::Write};
 
 use log::debug;
 use petgraph::Direction;
@@ -10,26 +10,34 @@ use syn::{spanned::Spanned, Ident, Type};
 
 use crate::{
     cpp::{
-        c_func_name, cpp_code, do_c_func_name, map_type::map_type, CppContext,
-        CppForeignMethodSignature, CppForeignTypeInfo, MethodContext,
+        c_func_name, cpp_code, do_c_func_name, finterface,
+        map_type::{map_repr_c_type, map_type},
+        CppContext, CppForeignMethodSignature, CppForeignTypeInfo, MethodContext,
     },
     error::{panic_on_syn_error, DiagnosticError, Result},
     extension::extend_foreign_class,
     file_cache::FileWriteCache,
     namegen::new_unique_name,
     typemap::{
-        ast::{list_lifetimes, strip_lifetimes},
+        ast::{list_lifetimes, normalize_type, strip_lifetimes},
         ty::RustType,
         utils::{
-            convert_to_heap_pointer, create_suitable_types_for_constructor_and_self,
+            check_overloads_distinguishable, convert_to_heap_pointer,
+            create_suitable_types_for_constructor_and_self,
             foreign_from_rust_convert_method_output, foreign_to_rust_convert_method_inputs,
             unpack_from_heap_pointer,
         },
         ForeignTypeInfo, TypeConvCodeSubstParam, TypeMap, FROM_VAR_TEMPLATE, TO_VAR_TEMPLATE,
         TO_VAR_TYPE_TEMPLATE,
     },
-    types::{ForeignClassInfo, MethodAccess, MethodVariant, SelfTypeVariant},
-    KNOWN_CLASS_DERIVES, PLAIN_CLASS, SMART_PTR_COPY_TRAIT, WRITE_TO_MEM_FAILED_MSG,
+    types::{
+        c_like_string_literal, future_completer_name, future_value_type, DerivedProtocols,
+        ForeignClassInfo, ForeignConstValue, ForeignField, ForeignMethod, ItemToExpand,
+        MethodAccess, MethodVariant, SelfTypeVariant, DERIVED_COMPARE_METHOD, DERIVED_EQ_METHOD,
+        DERIVED_HASH_METHOD, DERIVED_TO_STRING_METHOD,
+    },
+    DisplayToTokens, KNOWN_CLASS_DERIVES, PLAIN_CLASS, REPR_C, SMART_PTR_COPY_TRAIT,
+    WRITE_TO_MEM_FAILED_MSG,
 };
 
 pub(in crate::cpp) fn generate(ctx: &mut CppContext, class: &ForeignClassInfo) -> Result<()> {
@@ -37,6 +45,18 @@ pub(in crate::cpp) fn generate(ctx: &mut CppContext, class: &ForeignClassInfo) -
         "generate: begin for {}, this_type_for_method {:?}",
         class.name, class.self_desc
     );
+    // closures are passed as `std::function`, wrapped into callbacks
+    for callback in class.closure_callbacks() {
+        if ctx
+            .generated_closure_callbacks
+            .insert(callback.name.to_string().into())
+        {
+            finterface::generate_interface(ctx, &callback)?;
+        }
+    }
+    if class.is_abstract {
+        return generate_abstract_class(ctx, class);
+    }
     let has_methods = class.methods.iter().any(|m| match m.variant {
         MethodVariant::Method(_) => true,
         _ => false,
@@ -58,20 +78,460 @@ May be you need to use `private constructor = empty;` syntax?",
         ));
     }
 
+    // operators for derived traits use private Rust methods
+    let class = &*class.with_derived_protocol_methods()?;
+    let protocols = class.derived_protocols()?;
+    // async methods pass result to C++ callback, that fulfills `std::promise`
+    for completer in class.future_completers() {
+        if ctx
+            .generated_future_completers
+            .insert(completer.name.to_string().into())
+        {
+            finterface::generate_interface(ctx, &completer)?;
+        }
+    }
+    let async_methods: Vec<&ForeignMethod> = class
+        .methods
+        .iter()
+        .filter(|m| m.async_output().is_some())
+        .collect();
+    let class = &*class.with_async_methods(ctx.conv_map.async_spawner())?;
+
     let mut m_sigs = find_suitable_foreign_types_for_methods(ctx, class)?;
+    check_overloads(class, &m_sigs)?;
     let mut req_includes = cpp_code::cpp_list_required_includes(&mut m_sigs);
+    if protocols.to_string.is_some() {
+        req_includes.push("<ostream>".into());
+    }
+    if protocols.hash {
+        req_includes.push("<cstddef>".into());
+        req_includes.push("<functional>".into());
+    }
+    if class.iterator_item().is_some() {
+        req_includes.push("\"rust_foreign_iterator.hpp\"".into());
+    }
+    let async_methods_code = generate_cpp_async_methods(ctx, class, &async_methods, &m_sigs)?;
+    if !async_methods.is_empty() {
+        req_includes.push("<future>".into());
+        req_includes.push("<memory>".into());
+        req_includes.push("\"rust_future_completer.hpp\"".into());
+    }
+    if let Some(ref base) = class.base_class {
+        req_includes.push(format!("\"{}.hpp\"", base).into());
+    }
     let my_self_cpp = format!("\"{}\"", cpp_code::cpp_header_name(class));
     let my_self_c = format!("\"{}\"", cpp_code::c_header_name(class));
+    let consts_decl = generate_consts(ctx, class, &mut req_includes)?;
     req_includes.retain(|el| *el != my_self_cpp && *el != my_self_c);
-    do_generate(ctx, class, &req_includes, &m_sigs)?;
+    do_generate(
+        ctx,
+        class,
+        &req_includes,
+        &m_sigs,
+        &consts_decl,
+        &async_methods_code,
+        protocols,
+    )?;
+    Ok(())
+}
+
+/// Functions from `foreign_fn!` are generated as `inline` functions of namespace,
+/// they are placed into separate header together with declarations of C functions,
+/// `fns_holder` is used only to generate names of C functions
+pub(in crate::cpp) fn generate_module_fns(
+    ctx: &mut CppContext,
+    fns_holder: &ForeignClassInfo,
+) -> Result<()> {
+    use std::fmt::Write;
+
+    for callback in fns_holder.closure_callbacks() {
+        if ctx
+            .generated_closure_callbacks
+            .insert(callback.name.to_string().into())
+        {
+            finterface::generate_interface(ctx, &callback)?;
+        }
+    }
+    for completer in fns_holder.future_completers() {
+        if ctx
+            .generated_future_completers
+            .insert(completer.name.to_string().into())
+        {
+            finterface::generate_interface(ctx, &completer)?;
+        }
+    }
+    let async_fns: Vec<&ForeignMethod> = fns_holder
+        .methods
+        .iter()
+        .filter(|m| m.async_output().is_some())
+        .collect();
+    let class = &*fns_holder.with_async_methods(ctx.conv_map.async_spawner())?;
+
+    let mut m_sigs = find_suitable_foreign_types_for_methods(ctx, class)?;
+    check_overloads(class, &m_sigs)?;
+    let mut req_includes = cpp_code::cpp_list_required_includes(&mut m_sigs);
+    let async_fns_code = generate_cpp_async_methods(ctx, class, &async_fns, &m_sigs)?;
+    if !async_fns.is_empty() {
+        req_includes.push("<future>".into());
+        req_includes.push("<memory>".into());
+        req_includes.push("\"rust_future_completer.hpp\"".into());
+    }
+
+    let c_path = ctx.cfg.output_dir.join(cpp_code::MODULE_FNS_C_HEADER);
+    let mut c_include_f = FileWriteCache::new(&c_path, ctx.generated_foreign_files);
+    let cpp_path = ctx.cfg.output_dir.join(cpp_code::MODULE_FNS_CPP_HEADER);
+    let mut cpp_include_f = FileWriteCache::new(&cpp_path, ctx.generated_foreign_files);
+    let map_write_err = |path: &std::path::Path, err: std::io::Error| {
+        DiagnosticError::new(
+            class.src_id,
+            class.span(),
+            format!("write to {} failed: {}", path.display(), err),
+        )
+    };
+
+    generte_c_header_preamble(ctx, "", None, &mut c_include_f);
+    let mut decls = String::new();
+    // start functions of async functions are not part of API
+    let mut detail_decls = String::new();
+    let mut inline_impl = String::new();
+    for (method, f_method) in class.methods.iter().zip(&m_sigs) {
+        let parts = method_code_parts(class, method, f_method)?;
+        let mut doc_comments = cpp_code::doc_comments_to_c_comments(&method.doc_comments, false);
+        if !doc_comments.is_empty() {
+            doc_comments.push('\n');
+        }
+        writeln!(
+            c_include_f,
+            r#"
+{doc_comments}    {ret_type} {c_func_name}({args_with_types});"#,
+            doc_comments = doc_comments,
+            ret_type = f_method.output.as_ref().name,
+            c_func_name = parts.c_func_name,
+            args_with_types = parts.c_args_with_types,
+        )
+        .expect(WRITE_TO_MEM_FAILED_MSG);
+        let (decls, prefix) = match method.access {
+            MethodAccess::Public => (&mut decls, ""),
+            MethodAccess::Private | MethodAccess::Protected => (&mut detail_decls, "detail::"),
+        };
+        writeln!(
+            decls,
+            r#"
+{doc_comments}    {deprecated}inline {cpp_ret_type} {fn_name}({cpp_args_decl}) noexcept;"#,
+            doc_comments = doc_comments,
+            deprecated = cpp_code::cpp_deprecated_attr(method.deprecated.as_ref()),
+            cpp_ret_type = parts.cpp_ret_type,
+            fn_name = method.short_name(),
+            cpp_args_decl = parts.cpp_args_decl,
+        )
+        .expect(WRITE_TO_MEM_FAILED_MSG);
+        write!(
+            &mut inline_impl,
+            r#"
+    inline {cpp_ret_type} {prefix}{fn_name}({cpp_args_with_types}) noexcept
+    {{
+{conv_args_code}"#,
+            cpp_ret_type = parts.cpp_ret_type,
+            prefix = prefix,
+            fn_name = method.short_name(),
+            cpp_args_with_types = parts.cpp_args_with_types,
+            conv_args_code = parts.conv_args_code,
+        )
+        .expect(WRITE_TO_MEM_FAILED_MSG);
+        if f_method.output.as_ref().name != "void" {
+            writeln!(
+                &mut inline_impl,
+                r#"
+        {c_ret_type} {ret} = {c_func_name}({cpp_args_for_c});
+{convert_ret_for_cpp}
+    }}"#,
+                c_ret_type = f_method.output.as_ref().name,
+                ret = parts.ret_name,
+                c_func_name = parts.c_func_name,
+                cpp_args_for_c = parts.cpp_args_for_c,
+                convert_ret_for_cpp = parts.convert_ret_for_cpp,
+            )
+        } else {
+            writeln!(
+                &mut inline_impl,
+                r#"
+        {c_func_name}({cpp_args_for_c});{input_to_output}
+    }}"#,
+                c_func_name = parts.c_func_name,
+                cpp_args_for_c = parts.cpp_args_for_c,
+                input_to_output = parts.input_to_output_ret_code,
+            )
+        }
+        .expect(WRITE_TO_MEM_FAILED_MSG);
+        ctx.rust_code.append(&mut generate_static_method(
+            ctx.conv_map,
+            &parts.method_ctx(class, method, f_method),
+        )?);
+    }
+    writeln!(
+        c_include_f,
+        r#"
+#ifdef __cplusplus
+}}
+#endif
+"#
+    )
+    .expect(WRITE_TO_MEM_FAILED_MSG);
+
+    let mut includes = String::new();
+    for inc in &req_includes {
+        writeln!(&mut includes, "#include {}", inc).expect(WRITE_TO_MEM_FAILED_MSG);
+    }
+    let detail_decls = if !detail_decls.is_empty() {
+        format!(
+            "\nnamespace detail {{{}}} // namespace detail\n",
+            detail_decls
+        )
+    } else {
+        String::new()
+    };
+    writeln!(
+        cpp_include_f,
+        r#"// Automatically generated by flapigen
+#pragma once
+
+//for assert
+#include <cassert>
+//for std::abort
+#include <cstdlib>
+//for std::move
+#include <utility>
+
+{includes}
+#include "{c_header}"
+
+namespace {namespace} {{
+{decls}{detail_decls}{async_fns_code}
+{inline_impl}
+}} // namespace {namespace}"#,
+        includes = includes,
+        c_header = cpp_code::MODULE_FNS_C_HEADER,
+        namespace = ctx.cfg.namespace_name,
+        decls = decls,
+        detail_decls = detail_decls,
+        async_fns_code = async_fns_code,
+        inline_impl = inline_impl,
+    )
+    .expect(WRITE_TO_MEM_FAILED_MSG);
+
+    c_include_f
+        .update_file_if_necessary()
+        .map_err(|err| map_write_err(&c_path, err))?;
+    cpp_include_f
+        .update_file_if_necessary()
+        .map_err(|err| map_write_err(&cpp_path, err))?;
+    Ok(())
+}
+
+/// Abstract class has only pure virtual methods, derived classes
+/// implement them via calls of Rust code, so there is no C header and Rust code for it
+fn generate_abstract_class(ctx: &mut CppContext, class: &ForeignClassInfo) -> Result<()> {
+    use std::fmt::Write;
+
+    let mut m_sigs = find_suitable_foreign_types_for_methods(ctx, class)?;
+    check_overloads(class, &m_sigs)?;
+    let mut req_includes = cpp_code::cpp_list_required_includes(&mut m_sigs);
+    if let Some(ref base) = class.base_class {
+        req_includes.push(format!("\"{}.hpp\"", base).into());
+    }
+
+    let mut methods_decl = String::new();
+    let mut last_cpp_access = Some("public");
+    for (method, f_method) in class.methods.iter().zip(&m_sigs) {
+        // derived class is template with variant for const reference,
+        // it can implement only const methods
+        if method.variant != MethodVariant::Method(SelfTypeVariant::Rptr) {
+            return Err(DiagnosticError::new(
+                class.src_id,
+                method.span(),
+                format!(
+                    "method {} of abstract class {} should take `&self`, \
+                     because of it is implemented by const references to derived classes",
+                    method.short_name(),
+                    class.name
+                ),
+            ));
+        }
+        let method_access = match method.access {
+            MethodAccess::Private => "private",
+            MethodAccess::Public => "public",
+            MethodAccess::Protected => "protected",
+        };
+        if last_cpp_access
+            .map(|last| last != method_access)
+            .unwrap_or(true)
+        {
+            writeln!(methods_decl, "{}:", method_access).expect(WRITE_TO_MEM_FAILED_MSG);
+        }
+        last_cpp_access = Some(method_access);
+        let f_output_type = f_method
+            .input
+            .iter()
+            .find(|x| x.input_to_output)
+            .unwrap_or(&f_method.output);
+        let cpp_ret_type = match f_output_type.cpp_converter.as_ref() {
+            Some(cpp_converter) => cpp_converter.typename.as_str(),
+            None => f_output_type.as_ref().name.as_str(),
+        };
+        writeln!(
+            methods_decl,
+            r#"{doc_comments}
+    virtual {cpp_ret_type} {method_name}({cpp_args_decl}) const noexcept = 0;"#,
+            doc_comments = cpp_code::doc_comments_to_c_comments(&method.doc_comments, false),
+            cpp_ret_type = cpp_ret_type.replace("struct", ""),
+            method_name = method.short_name(),
+            cpp_args_decl =
+                cpp_code::cpp_generate_args_with_defaults(f_method, method, class.src_id)?,
+        )
+        .expect(WRITE_TO_MEM_FAILED_MSG);
+    }
+
+    let mut includes = String::new();
+    for inc in &req_includes {
+        writeln!(&mut includes, "#include {}", inc).expect(WRITE_TO_MEM_FAILED_MSG);
+    }
+    let cpp_path = ctx.cfg.output_dir.join(cpp_code::cpp_header_name(class));
+    let mut cpp_include_f = FileWriteCache::new(&cpp_path, ctx.generated_foreign_files);
+    write!(
+        cpp_include_f,
+        r#"// Automatically generated by flapigen
+#pragma once
+
+{includes}
+namespace {namespace} {{
+
+{doc_comments}
+class {deprecated}{class_name}{extends} {{
+public:
+    virtual ~{class_name}() noexcept = default;
+{methods_decl}"#,
+        deprecated = cpp_code::cpp_deprecated_attr(class.deprecated.as_ref()),
+        includes = includes,
+        namespace = ctx.cfg.namespace_name,
+        doc_comments = cpp_code::doc_comments_to_c_comments(&class.doc_comments, true),
+        class_name = class.name,
+        extends = cpp_extends(class),
+        methods_decl = methods_decl,
+    )
+    .expect(WRITE_TO_MEM_FAILED_MSG);
+    if !class.foreign_code.is_empty() {
+        writeln!(cpp_include_f, "\n{}", class.foreign_code).expect(WRITE_TO_MEM_FAILED_MSG);
+    }
+    writeln!(
+        cpp_include_f,
+        r#"}};
+
+}} // namespace {namespace}"#,
+        namespace = ctx.cfg.namespace_name
+    )
+    .expect(WRITE_TO_MEM_FAILED_MSG);
+    cpp_include_f
+        .update_file_if_necessary()
+        .map_err(DiagnosticError::map_any_err_to_our_err)?;
     Ok(())
 }
 
+fn cpp_extends(class: &ForeignClassInfo) -> String {
+    match class.base_class {
+        Some(ref base) => format!(" : public {}", base),
+        None => String::new(),
+    }
+}
+
+fn generate_consts(
+    ctx: &mut CppContext,
+    class: &ForeignClassInfo,
+    req_includes: &mut Vec<SmolStr>,
+) -> Result<String> {
+    let mut consts_decl = String::new();
+    for fconst in &class.consts {
+        fconst.check_type()?;
+        let value_check = fconst.rust_code_to_check_value();
+        if !value_check.is_empty() {
+            ctx.rust_code.push(value_check);
+        }
+        let rty = ctx
+            .conv_map
+            .find_or_alloc_rust_type(&fconst.ty, fconst.src_id);
+        let fti = map_type(
+            ctx,
+            &rty,
+            Direction::Outgoing,
+            (fconst.src_id, fconst.ty.span()),
+        )?;
+        for inc in &fti.provides_by_module {
+            if !req_includes.contains(inc) {
+                req_includes.push(inc.clone());
+            }
+        }
+        let cpp_type = fti.cpp_type_name();
+        let value = cpp_const_value(&fconst.value, &cpp_type)
+            .map_err(|err| DiagnosticError::new(fconst.src_id, fconst.span(), err))?;
+        let mut doc_comments = cpp_code::doc_comments_to_c_comments(&fconst.doc_comments, false);
+        if !doc_comments.is_empty() {
+            doc_comments.push('\n');
+        }
+        writeln!(
+            &mut consts_decl,
+            "{doc_comments}    static constexpr {cpp_type} {name} = {value};",
+            doc_comments = doc_comments,
+            cpp_type = cpp_type,
+            name = fconst.name,
+            value = value,
+        )
+        .expect(WRITE_TO_MEM_FAILED_MSG);
+    }
+    Ok(consts_decl)
+}
+
+/// C++ literal for value of constant with given type
+fn cpp_const_value(
+    value: &ForeignConstValue,
+    cpp_type: &str,
+) -> std::result::Result<String, String> {
+    match (value, cpp_type) {
+        (
+            ForeignConstValue::Int(x),
+            "int8_t" | "int16_t" | "int32_t" | "int64_t" | "intptr_t" | "uint8_t" | "uint16_t"
+            | "uint32_t" | "uint64_t" | "uintptr_t",
+        ) => {
+            if *x >= i128::from(i32::MIN) && *x <= i128::from(i32::MAX) {
+                Ok(x.to_string())
+            } else if *x == i128::from(i64::MIN) {
+                // -9223372036854775808LL is not valid literal
+                Ok(format!("({}LL - 1)", x + 1))
+            } else if *x < 0 {
+                Ok(format!("{}LL", x))
+            } else {
+                Ok(format!("{}ULL", x))
+            }
+        }
+        (ForeignConstValue::Float(x), "float") => Ok(format!("{}f", x)),
+        (ForeignConstValue::Float(x), "double") => Ok(x.clone()),
+        (ForeignConstValue::Bool(x), "bool") => Ok(x.to_string()),
+        (ForeignConstValue::Str(x), "std::string_view" | "boost::string_view") => {
+            Ok(c_like_string_literal(x))
+        }
+        _ => Err(format!(
+            "C++ type {} is not supported for constant",
+            cpp_type
+        )),
+    }
+}
+
 fn do_generate(
     ctx: &mut CppContext,
     class: &ForeignClassInfo,
     req_includes: &[SmolStr],
     methods_sign: &[CppForeignMethodSignature],
+    consts_decl: &str,
+    async_methods_code: &str,
+    protocols: DerivedProtocols,
 ) -> Result<()> {
     use std::fmt::Write;
 
@@ -97,7 +557,21 @@ fn do_generate(
     let c_class_type = cpp_code::c_class_type(class);
     let class_doc_comments = cpp_code::doc_comments_to_c_comments(&class.doc_comments, true);
 
-    generte_c_header_preamble(ctx, &class_doc_comments, &c_class_type, &mut c_include_f);
+    generte_c_header_preamble(
+        ctx,
+        &class_doc_comments,
+        Some(&c_class_type),
+        &mut c_include_f,
+    );
+    let repr_c_fields = if need_repr_c(class) {
+        let (c_struct, fields_c_types) = generate_repr_c_struct(ctx, class)?;
+        c_include_f
+            .write_all(c_struct.as_bytes())
+            .expect(WRITE_TO_MEM_FAILED_MSG);
+        Some(fields_c_types)
+    } else {
+        None
+    };
     let plain_class = need_plain_class(class);
     let class_name = if !plain_class {
         format!("{}Wrapper", class.name)
@@ -121,6 +595,9 @@ fn do_generate(
         &mut c_include_f,
         &mut cpp_include_f,
     )?;
+    cpp_include_f
+        .write_all(consts_decl.as_bytes())
+        .expect(WRITE_TO_MEM_FAILED_MSG);
 
     let mut last_cpp_access = Some("public");
 
@@ -184,6 +661,8 @@ May be you need to use `private constructor = empty;` syntax?",
             .expect(WRITE_TO_MEM_FAILED_MSG);
 
         let method_access = match method.access {
+            // internal class is used only by friends from generated code
+            _ if class.is_internal => "private",
             MethodAccess::Private => "private",
             MethodAccess::Public => "public",
             MethodAccess::Protected => "protected",
@@ -198,9 +677,31 @@ May be you need to use `private constructor = empty;` syntax?",
         cpp_include_f
             .write_all(cpp_code::doc_comments_to_c_comments(&method.doc_comments, false).as_bytes())
             .expect(WRITE_TO_MEM_FAILED_MSG);
-        let c_func_name = c_func_name(class, method);
-        let c_args_with_types =
-            cpp_code::c_generate_args_with_types(f_method, method.arg_names_without_self(), false);
+        if let Some(ref repr_c_fields) = repr_c_fields {
+            let field = method.property.as_ref().and_then(|p| {
+                repr_c_fields
+                    .iter()
+                    .find(|(f, _)| f.name == p.name.as_str())
+            });
+            if let Some((field, c_type)) = field {
+                write_repr_c_field_accessor(&mut cpp_include_f, class, method, field, c_type);
+                continue;
+            }
+        }
+        let parts = method_code_parts(class, method, f_method)?;
+        let MethodCodeParts {
+            ref c_func_name,
+            ref c_args_with_types,
+            ref cpp_args_with_types,
+            ref cpp_args_decl,
+            ref conv_args_code,
+            ref cpp_args_for_c,
+            ref ret_name,
+            ref cpp_ret_type,
+            ref convert_ret_for_cpp,
+            ref input_to_output_ret_code,
+            ..
+        } = parts;
         let comma_c_args_with_types = if c_args_with_types.is_empty() {
             String::new()
         } else {
@@ -211,147 +712,8 @@ May be you need to use `private constructor = empty;` syntax?",
         } else {
             !method.fn_decl.inputs.is_empty()
         };
-
-        let mut known_names: FxHashSet<SmolStr> =
-            method.arg_names_without_self().map(|x| x.into()).collect();
-        if let MethodVariant::Method(_) = method.variant {
-            if known_names.contains("this") {
-                return Err(DiagnosticError::new(
-                    class.src_id,
-                    method.rust_id.span(),
-                    "Invalid argument name 'this' reserved for generate code purposes",
-                ));
-            }
-            known_names.insert("this".into());
-        }
-        let ret_name = new_unique_name(&known_names, "ret");
-        known_names.insert(ret_name.clone());
-        let conv_ret = new_unique_name(&known_names, "conv_ret");
-        known_names.insert(conv_ret.clone());
-
-        let cpp_args_with_types =
-            cpp_code::cpp_generate_args_with_types(f_method, method.arg_names_without_self());
-
-        let (conv_args_code, cpp_args_for_c) =
-            cpp_code::convert_args(f_method, &mut known_names, method.arg_names_without_self())?;
-
-        let real_output_typename: Cow<str> = match method.fn_decl.output {
-            syn::ReturnType::Default => Cow::Borrowed("()"),
-            syn::ReturnType::Type(_, ref t) => {
-                let mut ty: syn::Type = (**t).clone();
-                strip_lifetimes(&mut ty);
-                ty.into_token_stream().to_string().into()
-            }
-        };
-
-        let mut rust_args_with_types = String::new();
-        let mut input_to_output_arg: Option<(&CppForeignTypeInfo, &str)> = None;
-        for (f_type_info, arg_name) in f_method.input.iter().zip(method.arg_names_without_self()) {
-            if f_type_info.input_to_output {
-                if method.variant == MethodVariant::Constructor {
-                    return Err(DiagnosticError::new(
-                        class.src_id,
-                        method.rust_id.span(),
-                        "constructor has argument with 'intput_to_output' tag, but constructor can not return",
-                    ));
-                }
-                if input_to_output_arg.is_some() {
-                    return Err(DiagnosticError::new(
-                        class.src_id,
-                        method.rust_id.span(),
-                        "method has two arguments with 'intput_to_output' tag",
-                    ));
-                }
-                if method.fn_decl.output != syn::ReturnType::Default {
-                    return Err(DiagnosticError::new(
-                        class.src_id,
-                        method.rust_id.span(),
-                        "method has argument with 'intput_to_output' tag, but return type not '()'",
-                    ));
-                }
-                input_to_output_arg = Some((f_type_info, arg_name));
-            }
-            write!(
-                &mut rust_args_with_types,
-                "{}: {}, ",
-                arg_name,
-                f_type_info.as_ref().correspoding_rust_type.typename(),
-            )
-            .expect(WRITE_TO_MEM_FAILED_MSG);
-        }
-
-        let method_ctx = MethodContext {
-            class,
-            method,
-            f_method,
-            c_func_name: &c_func_name,
-            decl_func_args: &rust_args_with_types,
-            real_output_typename: &real_output_typename,
-            ret_name: &ret_name,
-        };
-
+        let method_ctx = parts.method_ctx(class, method, f_method);
         let method_name = method.short_name().as_str().to_string();
-        let f_output_type = if let Some((ref input_to_output_arg, _)) = input_to_output_arg {
-            input_to_output_arg
-        } else {
-            &f_method.output
-        };
-        let (cpp_ret_type, convert_ret_for_cpp) = match f_output_type.cpp_converter.as_ref() {
-            Some(cpp_converter) => {
-                let cpp_ret_type = cpp_converter.typename.clone();
-                if input_to_output_arg.is_none() {
-                    let conv_code =
-                        cpp_converter
-                            .converter
-                            .generate_code_with_subst_func(|param_name| match param_name {
-                                TypeConvCodeSubstParam::Name(name) => {
-                                    if name == FROM_VAR_TEMPLATE {
-                                        Some(Cow::Borrowed(&ret_name))
-                                    } else if name == TO_VAR_TYPE_TEMPLATE {
-                                        Some(format!("{} {}", cpp_ret_type, conv_ret).into())
-                                    } else if name == TO_VAR_TEMPLATE {
-                                        Some(Cow::Borrowed(&conv_ret))
-                                    } else {
-                                        None
-                                    }
-                                }
-                                TypeConvCodeSubstParam::Tmp(name_template) => {
-                                    let tmp_name = new_unique_name(&known_names, name_template);
-                                    let tmp_name_ret = tmp_name.to_string().into();
-                                    known_names.insert(tmp_name);
-                                    Some(tmp_name_ret)
-                                }
-                            })?;
-                    if cpp_converter.converter.has_param(TO_VAR_TYPE_TEMPLATE) {
-                        (
-                            cpp_ret_type,
-                            format!("{}\n        return {};", conv_code, conv_ret),
-                        )
-                    } else {
-                        (cpp_ret_type, format!("        return {};", conv_code))
-                    }
-                } else {
-                    (cpp_ret_type, format!("        return {};", ret_name))
-                }
-            }
-            None => (
-                f_output_type.as_ref().name.clone(),
-                format!("        return {};", ret_name),
-            ),
-        };
-        //rename types like "struct Foo" to "Foo" to make VC++ compiler happy
-        let cpp_ret_type = cpp_ret_type.as_str().replace("struct", "");
-
-        let input_to_output_ret_code = if let Some((_, ref arg_name)) = input_to_output_arg {
-            format!(
-                r#"
-        return {};
-"#,
-                arg_name
-            )
-        } else {
-            String::new()
-        };
 
         match method.variant {
             MethodVariant::StaticMethod => {
@@ -368,13 +730,13 @@ May be you need to use `private constructor = empty;` syntax?",
                 writeln!(
                     cpp_include_f,
                     r#"
-    static {cpp_ret_type} {method_name}({cpp_args_with_types}) noexcept;"#,
+    {deprecated}static {cpp_ret_type} {method_name}({cpp_args_decl}) noexcept;"#,
+                    deprecated = cpp_code::cpp_deprecated_attr(method.deprecated.as_ref()),
                     method_name = method_name,
                     cpp_ret_type = cpp_ret_type,
-                    cpp_args_with_types = cpp_args_with_types,
+                    cpp_args_decl = cpp_args_decl,
                 )
                 .expect(WRITE_TO_MEM_FAILED_MSG);
-
                 if !plain_class {
                     write!(
                         &mut inline_impl,
@@ -455,10 +817,11 @@ May be you need to use `private constructor = empty;` syntax?",
                 writeln!(
                     cpp_include_f,
                     r#"
-    {cpp_ret_type} {method_name}({cpp_args_with_types}) {const_if_readonly}noexcept;"#,
+    {deprecated}{cpp_ret_type} {method_name}({cpp_args_decl}) {const_if_readonly}noexcept;"#,
+                    deprecated = cpp_code::cpp_deprecated_attr(method.deprecated.as_ref()),
                     method_name = method_name,
                     cpp_ret_type = cpp_ret_type,
-                    cpp_args_with_types = cpp_args_with_types,
+                    cpp_args_decl = cpp_args_decl,
                     const_if_readonly = const_if_readonly,
                 )
                 .expect(WRITE_TO_MEM_FAILED_MSG);
@@ -558,7 +921,7 @@ May be you need to use `private constructor = empty;` syntax?",
                     writeln!(
                         cpp_include_f,
                         r#"
-    {class_name}({cpp_args_with_types}) noexcept
+    {deprecated}{class_name}({cpp_args_decl}) noexcept
     {{
 {conv_args_code}
         this->self_ = {c_func_name}({cpp_args_for_c});
@@ -566,8 +929,9 @@ May be you need to use `private constructor = empty;` syntax?",
             std::abort();
         }}
     }}"#,
+                        deprecated = cpp_code::cpp_deprecated_attr(method.deprecated.as_ref()),
                         c_func_name = c_func_name,
-                        cpp_args_with_types = cpp_args_with_types,
+                        cpp_args_decl = cpp_args_decl,
                         class_name = class_name,
                         cpp_args_for_c = cpp_args_for_c,
                         conv_args_code = conv_args_code,
@@ -676,6 +1040,28 @@ private:
     )
     .expect(WRITE_TO_MEM_FAILED_MSG);
 
+    if !protocols.is_empty() {
+        cpp_include_f
+            .write_all(generate_cpp_operators(&class_name, plain_class, protocols).as_bytes())
+            .expect(WRITE_TO_MEM_FAILED_MSG);
+    }
+    if class.iterator_item().is_some() {
+        write!(
+            cpp_include_f,
+            r#"
+public:
+    using iterator = RustForeignIterator<{class_name}>;
+    friend iterator;
+    iterator begin() {{ return iterator{{ this }}; }}
+    iterator end() noexcept {{ return iterator{{}}; }}
+"#,
+            class_name = class_name,
+        )
+        .expect(WRITE_TO_MEM_FAILED_MSG);
+    }
+    cpp_include_f
+        .write_all(async_methods_code.as_bytes())
+        .expect(WRITE_TO_MEM_FAILED_MSG);
     if !class.foreign_code.is_empty() {
         writeln!(cpp_include_f, "\n{}", class.foreign_code).expect(WRITE_TO_MEM_FAILED_MSG);
     }
@@ -729,6 +1115,30 @@ namespace {namespace} {{"#,
         write_methods_impls(&mut cpp_include_f, &ctx.cfg.namespace_name, &inline_impl)
             .map_err(map_write_err!(cpp_path))?;
     }
+    if protocols.hash {
+        let class_type = if !plain_class {
+            format!("{}::{}<OWN_DATA>", ctx.cfg.namespace_name, class_name)
+        } else {
+            format!("{}::{}", ctx.cfg.namespace_name, class_name)
+        };
+        writeln!(
+            cpp_include_f,
+            r#"
+namespace std {{
+template<{template_params}>
+struct hash<{class_type}> {{
+    std::size_t operator()(const {class_type} &o) const noexcept
+    {{
+        return static_cast<std::size_t>(o.{hash}());
+    }}
+}};
+}} // namespace std"#,
+            template_params = if !plain_class { "bool OWN_DATA" } else { "" },
+            class_type = class_type,
+            hash = DERIVED_HASH_METHOD,
+        )
+        .expect(WRITE_TO_MEM_FAILED_MSG);
+    }
 
     if !plain_class {
         writeln!(
@@ -739,9 +1149,10 @@ namespace {namespace} {{"#,
 namespace {namespace} {{
 template<bool>
 class {base_class_name};
-using {class_name} = {base_class_name}<true>;
-using {class_name}Ref = {base_class_name}<false>;
+using {class_name} {deprecated}= {base_class_name}<true>;
+using {class_name}Ref {deprecated}= {base_class_name}<false>;
 }} // namespace {namespace}"#,
+            deprecated = cpp_code::cpp_deprecated_attr(class.deprecated.as_ref()),
             namespace = ctx.cfg.namespace_name,
             class_name = class.name,
             base_class_name = class_name
@@ -753,8 +1164,9 @@ using {class_name}Ref = {base_class_name}<false>;
 #pragma once
 
 namespace {namespace} {{
-class {class_name};
+class {deprecated}{class_name};
 }} // namespace {namespace}"#,
+            deprecated = cpp_code::cpp_deprecated_attr(class.deprecated.as_ref()),
             namespace = ctx.cfg.namespace_name,
             class_name = class.name,
         )
@@ -784,6 +1196,300 @@ class {class_name};
     Ok(())
 }
 
+/// Parts of C and C++ code of method, common for all variants of method
+struct MethodCodeParts<'a> {
+    c_func_name: String,
+    c_args_with_types: String,
+    cpp_args_with_types: String,
+    cpp_args_decl: String,
+    conv_args_code: String,
+    cpp_args_for_c: String,
+    rust_args_with_types: String,
+    real_output_typename: Cow<'a, str>,
+    ret_name: SmolStr,
+    cpp_ret_type: String,
+    convert_ret_for_cpp: String,
+    input_to_output_ret_code: String,
+}
+
+impl<'a> MethodCodeParts<'a> {
+    fn method_ctx(
+        &'a self,
+        class: &'a ForeignClassInfo,
+        method: &'a ForeignMethod,
+        f_method: &'a CppForeignMethodSignature,
+    ) -> MethodContext<'a> {
+        MethodContext {
+            class,
+            method,
+            f_method,
+            c_func_name: &self.c_func_name,
+            decl_func_args: &self.rust_args_with_types,
+            real_output_typename: &self.real_output_typename,
+            ret_name: &self.ret_name,
+        }
+    }
+}
+
+fn method_code_parts<'a>(
+    class: &ForeignClassInfo,
+    method: &'a ForeignMethod,
+    f_method: &CppForeignMethodSignature,
+) -> Result<MethodCodeParts<'a>> {
+    let c_func_name = c_func_name(class, method);
+    let c_args_with_types =
+        cpp_code::c_generate_args_with_types(f_method, method.arg_names_without_self(), false);
+
+    let mut known_names: FxHashSet<SmolStr> =
+        method.arg_names_without_self().map(|x| x.into()).collect();
+    if let MethodVariant::Method(_) = method.variant {
+        if known_names.contains("this") {
+            return Err(DiagnosticError::new(
+                class.src_id,
+                method.rust_id.span(),
+                "Invalid argument name 'this' reserved for generate code purposes",
+            ));
+        }
+        known_names.insert("this".into());
+    }
+    let ret_name = new_unique_name(&known_names, "ret");
+    known_names.insert(ret_name.clone());
+    let conv_ret = new_unique_name(&known_names, "conv_ret");
+    known_names.insert(conv_ret.clone());
+
+    let cpp_args_with_types =
+        cpp_code::cpp_generate_args_with_types(f_method, method.arg_names_without_self());
+    let cpp_args_decl = cpp_code::cpp_generate_args_with_defaults(f_method, method, class.src_id)?;
+
+    let (conv_args_code, cpp_args_for_c) =
+        cpp_code::convert_args(f_method, &mut known_names, method.arg_names_without_self())?;
+
+    let real_output_typename: Cow<str> = match method.fn_decl.output {
+        syn::ReturnType::Default => Cow::Borrowed("()"),
+        syn::ReturnType::Type(_, ref t) => {
+            let mut ty: syn::Type = (**t).clone();
+            strip_lifetimes(&mut ty);
+            ty.into_token_stream().to_string().into()
+        }
+    };
+
+    let mut rust_args_with_types = String::new();
+    let mut input_to_output_arg: Option<(&CppForeignTypeInfo, &str)> = None;
+    for (f_type_info, arg_name) in f_method.input.iter().zip(method.arg_names_without_self()) {
+        if f_type_info.input_to_output {
+            if method.variant == MethodVariant::Constructor {
+                return Err(DiagnosticError::new(
+                    class.src_id,
+                    method.rust_id.span(),
+                    "constructor has argument with 'intput_to_output' tag, but constructor can not return",
+                ));
+            }
+            if input_to_output_arg.is_some() {
+                return Err(DiagnosticError::new(
+                    class.src_id,
+                    method.rust_id.span(),
+                    "method has two arguments with 'intput_to_output' tag",
+                ));
+            }
+            if method.fn_decl.output != syn::ReturnType::Default {
+                return Err(DiagnosticError::new(
+                    class.src_id,
+                    method.rust_id.span(),
+                    "method has argument with 'intput_to_output' tag, but return type not '()'",
+                ));
+            }
+            input_to_output_arg = Some((f_type_info, arg_name));
+        }
+        write!(
+            &mut rust_args_with_types,
+            "{}: {}, ",
+            arg_name,
+            f_type_info.as_ref().correspoding_rust_type.typename(),
+        )
+        .expect(WRITE_TO_MEM_FAILED_MSG);
+    }
+
+    let f_output_type = if let Some((ref input_to_output_arg, _)) = input_to_output_arg {
+        input_to_output_arg
+    } else {
+        &f_method.output
+    };
+    let (cpp_ret_type, convert_ret_for_cpp) = match f_output_type.cpp_converter.as_ref() {
+        Some(cpp_converter) => {
+            let cpp_ret_type = cpp_converter.typename.clone();
+            if input_to_output_arg.is_none() {
+                let conv_code =
+                    cpp_converter
+                        .converter
+                        .generate_code_with_subst_func(|param_name| match param_name {
+                            TypeConvCodeSubstParam::Name(name) => {
+                                if name == FROM_VAR_TEMPLATE {
+                                    Some(Cow::Borrowed(&ret_name))
+                                } else if name == TO_VAR_TYPE_TEMPLATE {
+                                    Some(format!("{} {}", cpp_ret_type, conv_ret).into())
+                                } else if name == TO_VAR_TEMPLATE {
+                                    Some(Cow::Borrowed(&conv_ret))
+                                } else {
+                                    None
+                                }
+                            }
+                            TypeConvCodeSubstParam::Tmp(name_template) => {
+                                let tmp_name = new_unique_name(&known_names, name_template);
+                                let tmp_name_ret = tmp_name.to_string().into();
+                                known_names.insert(tmp_name);
+                                Some(tmp_name_ret)
+                            }
+                        })?;
+                if cpp_converter.converter.has_param(TO_VAR_TYPE_TEMPLATE) {
+                    (
+                        cpp_ret_type,
+                        format!("{}\n        return {};", conv_code, conv_ret),
+                    )
+                } else {
+                    (cpp_ret_type, format!("        return {};", conv_code))
+                }
+            } else {
+                (cpp_ret_type, format!("        return {};", ret_name))
+            }
+        }
+        None => (
+            f_output_type.as_ref().name.clone(),
+            format!("        return {};", ret_name),
+        ),
+    };
+    //rename types like "struct Foo" to "Foo" to make VC++ compiler happy
+    let cpp_ret_type = cpp_ret_type.as_str().replace("struct", "");
+
+    let input_to_output_ret_code = if let Some((_, ref arg_name)) = input_to_output_arg {
+        format!(
+            r#"
+        return {};
+"#,
+            arg_name
+        )
+    } else {
+        String::new()
+    };
+
+    Ok(MethodCodeParts {
+        c_func_name,
+        c_args_with_types,
+        cpp_args_with_types,
+        cpp_args_decl,
+        conv_args_code,
+        cpp_args_for_c,
+        rust_args_with_types,
+        real_output_typename,
+        ret_name,
+        cpp_ret_type,
+        convert_ret_for_cpp,
+        input_to_output_ret_code,
+    })
+}
+
+/// Public methods that return `std::future` for async methods,
+/// they call private methods generated by `ForeignClassInfo::with_async_methods`
+fn generate_cpp_async_methods(
+    ctx: &mut CppContext,
+    class: &ForeignClassInfo,
+    async_methods: &[&ForeignMethod],
+    methods_sign: &[CppForeignMethodSignature],
+) -> Result<String> {
+    let mut code = String::new();
+    if async_methods.is_empty() {
+        return Ok(code);
+    }
+    let mut map_outgoing = |ty: &Type| -> Result<String> {
+        let rty = ctx.conv_map.find_or_alloc_rust_type(ty, class.src_id);
+        let fti = map_type(ctx, &rty, Direction::Outgoing, (class.src_id, ty.span()))?;
+        Ok(cpp_code::cpp_arg_type(&fti)
+            .replace("struct", "")
+            .trim()
+            .to_string())
+    };
+    let error_type = map_outgoing(&parse_type!(String))?;
+    let start_idx = class.methods.len() - async_methods.len();
+    // functions from `foreign_fn!` are placed into namespace,
+    // see `generate_module_fns`
+    let module_fns = class.is_module_items_holder;
+    let mut last_cpp_access = None;
+    for (i, method) in async_methods.iter().enumerate() {
+        let start_method = &class.methods[start_idx + i];
+        let f_method = &methods_sign[start_idx + i];
+        let value = method.async_output().and_then(future_value_type);
+        let value_type = match value {
+            Some(value) => map_outgoing(value)?,
+            None => "void".to_string(),
+        };
+        let method_access = match method.access {
+            MethodAccess::Private => "private",
+            MethodAccess::Public => "public",
+            MethodAccess::Protected => "protected",
+        };
+        if !module_fns && last_cpp_access != Some(method_access) {
+            writeln!(code, "{}:", method_access).expect(WRITE_TO_MEM_FAILED_MSG);
+        }
+        last_cpp_access = Some(method_access);
+        let (static_if_static, const_if_readonly, call_prefix) = match method.variant {
+            MethodVariant::StaticMethod if module_fns => ("inline ", "", "detail::"),
+            MethodVariant::StaticMethod => ("static ", "", ""),
+            MethodVariant::Method(self_variant) => (
+                "",
+                if self_variant.is_read_only() {
+                    " const"
+                } else {
+                    ""
+                },
+                "this->",
+            ),
+            MethodVariant::Constructor => ("", "", ""),
+        };
+        let mut known_names: FxHashSet<SmolStr> =
+            method.arg_names_without_self().map(|x| x.into()).collect();
+        let completer = new_unique_name(&known_names, "completer");
+        known_names.insert(completer.clone());
+        let future = new_unique_name(&known_names, "future");
+        let args = method
+            .arg_names_without_self()
+            .fold(String::new(), |mut acc, x| {
+                acc.push_str(x);
+                acc.push_str(", ");
+                acc
+            });
+        code.push_str(&cpp_code::doc_comments_to_c_comments(
+            &method.doc_comments,
+            false,
+        ));
+        writeln!(
+            code,
+            r#"
+    {static_if_static}std::future<{value_type}> {method_name}({args_with_types}){const_if_readonly}
+    {{
+        using Completer = RustFutureCompleter<{completer_class}, {value_type}, {error_type}>;
+        std::unique_ptr<Completer> {completer}{{new Completer()}};
+        auto {future} = {completer}->get_future();
+        {call_prefix}{start_method}({args}std::move({completer}));
+        return {future};
+    }}"#,
+            static_if_static = static_if_static,
+            value_type = value_type,
+            method_name = method.short_name(),
+            args_with_types =
+                cpp_code::cpp_generate_args_with_defaults(f_method, method, class.src_id)?,
+            const_if_readonly = const_if_readonly,
+            completer = completer,
+            completer_class = future_completer_name(value, method.span()),
+            error_type = error_type,
+            future = future,
+            call_prefix = call_prefix,
+            start_method = start_method.short_name(),
+            args = args,
+        )
+        .expect(WRITE_TO_MEM_FAILED_MSG);
+    }
+    Ok(code)
+}
+
 fn generate_static_method(conv_map: &mut TypeMap, mc: &MethodContext) -> Result<Vec<TokenStream>> {
     let c_ret_type = mc
         .f_method
@@ -977,6 +1683,91 @@ pub extern "C" fn {func_name}({decl_func_args}) -> *const ::std::os::raw::c_void
     Ok(gen_code)
 }
 
+/// Operators for derived traits, they use private methods,
+/// see `ForeignClassInfo::with_derived_protocol_methods`.
+/// Comparison of object with itself is handled without call of Rust code,
+/// because of `self_type` like `Arc<Mutex<T>>` it would be deadlock.
+fn generate_cpp_operators(
+    class_name: &str,
+    plain_class: bool,
+    protocols: DerivedProtocols,
+) -> String {
+    let (template, other_type, other) = if !plain_class {
+        (
+            "\n    template<bool OTHER_OWN_DATA>",
+            format!("{}<OTHER_OWN_DATA>", class_name),
+            "o.as_cref()",
+        )
+    } else {
+        ("", class_name.to_string(), "o")
+    };
+    let mut code = "\npublic:".to_string();
+    if protocols.eq {
+        write!(
+            &mut code,
+            r#"{template}
+    bool operator==(const {other_type} &o) const noexcept
+    {{
+        return this->self_ == o.self_ || {eq}({other});
+    }}{template}
+    bool operator!=(const {other_type} &o) const noexcept {{ return !(*this == o); }}"#,
+            template = template,
+            other_type = other_type,
+            other = other,
+            eq = DERIVED_EQ_METHOD,
+        )
+        .expect(WRITE_TO_MEM_FAILED_MSG);
+    }
+    if protocols.cmp.is_some() {
+        // result of comparison is 2 if values are not comparable
+        for (op, same, cond) in &[
+            ("<", "false", "c == -1"),
+            ("<=", "true", "c == -1 || c == 0"),
+            (">", "false", "c == 1"),
+            (">=", "true", "c == 1 || c == 0"),
+        ] {
+            write!(
+                &mut code,
+                r#"{template}
+    bool operator{op}(const {other_type} &o) const noexcept
+    {{
+        if (this->self_ == o.self_) {{
+            return {same};
+        }}
+        const int32_t c = {compare}({other});
+        return {cond};
+    }}"#,
+                template = template,
+                op = op,
+                other_type = other_type,
+                same = same,
+                compare = DERIVED_COMPARE_METHOD,
+                other = other,
+                cond = cond,
+            )
+            .expect(WRITE_TO_MEM_FAILED_MSG);
+        }
+    }
+    if protocols.to_string.is_some() {
+        write!(
+            &mut code,
+            r#"
+    friend std::ostream &operator<<(std::ostream &os, const {class_name} &o)
+    {{
+        return os << o.{to_string}().to_std_string();
+    }}"#,
+            class_name = class_name,
+            to_string = DERIVED_TO_STRING_METHOD,
+        )
+        .expect(WRITE_TO_MEM_FAILED_MSG);
+    }
+    if protocols.hash {
+        code.push_str("\n    template<typename> friend struct std::hash;");
+    }
+    code.push('\n');
+    code
+}
+
 fn write_methods_impls(
     file: &mut FileWriteCache,
     namespace_name: &str,
@@ -992,6 +1783,80 @@ fn write_methods_impls(
     )
 }
 
+/// Methods of derived class that override methods of abstract class
+/// should take `&self`, because of abstract class declares only const methods,
+/// and non-const method with the same name hides them instead of overriding
+pub(in crate::cpp) fn check_overrides(items: &[ItemToExpand]) -> Result<()> {
+    let classes = items.iter().filter_map(|item| match item {
+        ItemToExpand::Class(ref x) => Some(x),
+        _ => None,
+    });
+    for class in classes.clone() {
+        let base = match class.base_class {
+            Some(ref base_name) => classes.clone().find(|x| x.name == *base_name),
+            None => None,
+        };
+        let base = match base {
+            Some(base) => base,
+            None => continue,
+        };
+        for base_method in &base.methods {
+            let overridden = class.methods.iter().find(|x| {
+                x.short_name() == base_method.short_name()
+                    && x.fn_decl.inputs.len() == base_method.fn_decl.inputs.len()
+            });
+            if let Some(method) = overridden {
+                if method.variant != MethodVariant::Method(SelfTypeVariant::Rptr) {
+                    return Err(DiagnosticError::new(
+                        class.src_id,
+                        method.span(),
+                        format!(
+                            "method {} of class {} overrides method of abstract class {}, \
+                             so it should take `&self`, otherwise in C++ it hides \
+                             const method instead of overriding it",
+                            method.short_name(),
+                            class.name,
+                            base.name
+                        ),
+                    )
+                    .add_span_note((base.src_id, base_method.span()), "overridden method here"));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Overloaded methods should differ by C++ types of arguments,
+/// taking into account that trailing arguments with default values can be omitted
+fn check_overloads(class: &ForeignClassInfo, m_sigs: &[CppForeignMethodSignature]) -> Result<()> {
+    let mut signatures = Vec::with_capacity(class.methods.len());
+    for (method, f_method) in class.methods.iter().zip(m_sigs) {
+        if method.is_dummy_constructor() {
+            continue;
+        }
+        let name = match method.variant {
+            MethodVariant::Constructor => class.name.to_string(),
+            MethodVariant::Method(_) | MethodVariant::StaticMethod => method.short_name(),
+        };
+        let mut args = Vec::with_capacity(f_method.input.len());
+        for (f_type_info, default) in f_method
+            .input
+            .iter()
+            .zip(method.arg_defaults_without_self())
+            .filter(|(f_type_info, _)| !f_type_info.input_to_output)
+        {
+            if default.is_some() {
+                signatures.push((method, name.clone(), args.clone()));
+            }
+            let type_name = cpp_code::cpp_arg_type(f_type_info);
+            args.push(type_name.split_whitespace().collect::<Vec<_>>().join(" "));
+        }
+        signatures.push((method, name, args));
+    }
+    check_overloads_distinguishable(class, "C++", signatures.into_iter())
+}
+
 fn find_suitable_foreign_types_for_methods(
     ctx: &mut CppContext,
     class: &ForeignClassInfo,
@@ -1051,10 +1916,11 @@ fn find_suitable_foreign_types_for_methods(
     Ok(ret)
 }
 
+/// `c_class_type` is `None` for header without class, like header of module functions
 fn generte_c_header_preamble(
     ctx: &CppContext,
     class_doc_comments: &str,
-    c_class_type: &str,
+    c_class_type: Option<&str>,
     c_include_f: &mut FileWriteCache,
 ) {
     writeln!(
@@ -1071,14 +1937,19 @@ static_assert(sizeof(uintptr_t) == sizeof(uint8_t) * {sizeof_usize},
    "our conversation usize <-> uintptr_t is wrong");
 extern "C" {{
 #endif
-
-    typedef struct {c_class_type} {c_class_type};
 "##,
         doc_comments = class_doc_comments,
-        c_class_type = c_class_type,
         sizeof_usize = ctx.target_pointer_width / 8,
     )
     .expect(WRITE_TO_MEM_FAILED_MSG);
+    if let Some(c_class_type) = c_class_type {
+        writeln!(
+            c_include_f,
+            "    typedef struct {c_class_type} {c_class_type};\n",
+            c_class_type = c_class_type,
+        )
+        .expect(WRITE_TO_MEM_FAILED_MSG);
+    }
 }
 
 fn generate_cpp_header_preamble(
@@ -1120,17 +1991,19 @@ namespace {namespace} {{
 
 template<bool>
 class {class_name};
-using {class_dot_name} = {class_name}<true>;
-using {class_dot_name}Ref = {class_name}<false>;
+using {class_dot_name} {deprecated}= {class_name}<true>;
+using {class_dot_name}Ref {deprecated}= {class_name}<false>;
 
 {doc_comments}
 template<bool OWN_DATA>
-class {class_name} {{
+class {class_name}{extends} {{
 public:
     using value_type = {class_name}<true>;
     friend class {class_name}<true>;
     friend class {class_name}<false>;"#,
+            deprecated = cpp_code::cpp_deprecated_attr(class.deprecated.as_ref()),
             includes = includes,
+            extends = cpp_extends(class),
             class_name = tmp_class_name,
             class_dot_name = class.name,
             namespace = ctx.cfg.namespace_name,
@@ -1155,9 +2028,11 @@ public:
 namespace {namespace} {{
 
 {doc_comments}
-class {class_name} {{
+class {deprecated}{class_name}{extends} {{
 public:"#,
+            deprecated = cpp_code::cpp_deprecated_attr(class.deprecated.as_ref()),
             includes = includes,
+            extends = cpp_extends(class),
             class_name = class.name,
             namespace = ctx.cfg.namespace_name,
             doc_comments = class_doc_comments,
@@ -1407,3 +2282,157 @@ fn genearte_copy_stuff(
 pub(in crate::cpp) fn need_plain_class(class: &ForeignClassInfo) -> bool {
     class.derive_list.iter().any(|x| *x == PLAIN_CLASS)
 }
+
+#[inline]
+fn need_repr_c(class: &ForeignClassInfo) -> bool {
+    class.derive_list.iter().any(|x| *x == REPR_C)
+}
+
+fn repr_c_struct_name(class: &ForeignClassInfo) -> String {
+    format!("{}ReprC", class.name)
+}
+
+/// For `#[derive(ReprC)]`: C struct with the same layout as Rust `self_type`,
+/// described by fields of class, plus Rust code to check layout at compile time.
+/// Returns declaration of struct and C types of fields.
+fn generate_repr_c_struct<'a>(
+    ctx: &mut CppContext,
+    class: &'a ForeignClassInfo,
+) -> Result<(String, Vec<(&'a ForeignField, SmolStr)>)> {
+    let self_desc = class.self_desc.as_ref().ok_or_else(|| {
+        DiagnosticError::new(
+            class.src_id,
+            class.span(),
+            format!("{} for class without self_type", REPR_C),
+        )
+    })?;
+    if normalize_type(&self_desc.self_type) != normalize_type(&self_desc.constructor_ret_type) {
+        return Err(DiagnosticError::new(
+            class.src_id,
+            self_desc.constructor_ret_type.span(),
+            format!(
+                "{} requires constructor that returns self_type, \
+                 so C++ code can access Rust object directly",
+                REPR_C
+            ),
+        ));
+    }
+    if class.fields.is_empty() {
+        return Err(DiagnosticError::new(
+            class.src_id,
+            class.span(),
+            format!("{} for class without fields", REPR_C),
+        ));
+    }
+    let mut c_struct = String::new();
+    let mut includes = FxHashSet::<SmolStr>::default();
+    let mut fields_c_types = Vec::with_capacity(class.fields.len());
+    for field in &class.fields {
+        let rty = ctx
+            .conv_map
+            .find_or_alloc_rust_type(&field.ty, class.src_id);
+        let fti = map_repr_c_type(ctx, &rty, (class.src_id, field.ty.span()))?;
+        includes.extend(fti.provides_by_module.iter().cloned());
+        writeln!(&mut c_struct, "        {} {};", fti.base.name, field.name)
+            .expect(WRITE_TO_MEM_FAILED_MSG);
+        fields_c_types.push((field, fti.base.name.clone()));
+    }
+    let mut includes: Vec<_> = includes.into_iter().collect();
+    includes.sort();
+    let mut code = String::new();
+    for inc in &includes {
+        writeln!(&mut code, "#include {}", inc).expect(WRITE_TO_MEM_FAILED_MSG);
+    }
+    write!(
+        &mut code,
+        r#"
+    /// the same layout as Rust type {rust_ty}
+    struct {name} {{
+{fields}    }};
+"#,
+        rust_ty = DisplayToTokens(&self_desc.self_type),
+        name = repr_c_struct_name(class),
+        fields = c_struct,
+    )
+    .expect(WRITE_TO_MEM_FAILED_MSG);
+
+    let self_type = &self_desc.self_type;
+    let layout_err = format!(
+        "layout of {} does not match fields of class {}, is it #[repr(C)] struct?",
+        DisplayToTokens(self_type),
+        class.name
+    );
+    let fields_asserts = class.fields.iter().map(|f| {
+        let name = &f.name;
+        quote! {
+            assert!(
+                ::std::mem::offset_of!(MyReprC, #name) == ::std::mem::offset_of!(#self_type, #name),
+                #layout_err
+            );
+        }
+    });
+    let fields_decl = class.fields.iter().map(|f| {
+        let name = &f.name;
+        let ty = &f.ty;
+        quote!(#name: #ty)
+    });
+    ctx.rust_code.push(quote! {
+        const _: () = {
+            #[repr(C)]
+            #[allow(dead_code)]
+            struct MyReprC {
+                #(#fields_decl),*
+            }
+            assert!(
+                ::std::mem::size_of::<MyReprC>() == ::std::mem::size_of::<#self_type>(),
+                #layout_err
+            );
+            assert!(
+                ::std::mem::align_of::<MyReprC>() == ::std::mem::align_of::<#self_type>(),
+                #layout_err
+            );
+            #(#fields_asserts)*
+        };
+    });
+    Ok((code, fields_c_types))
+}
+
+/// Accessor of field of `#[derive(ReprC)]` class, it works with memory
+/// of Rust object directly, without call of Rust code,
+/// this is valid because `generate_repr_c_struct` checks layout at compile time
+fn write_repr_c_field_accessor(
+    out: &mut FileWriteCache,
+    class: &ForeignClassInfo,
+    method: &ForeignMethod,
+    field: &ForeignField,
+    c_type: &str,
+) {
+    if method.fn_decl.inputs.len() == 1 {
+        writeln!(
+            out,
+            r#"
+    {c_type} {method_name}() const noexcept
+    {{
+        return reinterpret_cast<const {c_struct} *>(this->self_)->{field};
+    }}"#,
+            c_type = c_type,
+            method_name = method.short_name(),
+            c_struct = repr_c_struct_name(class),
+            field = field.name,
+        )
+    } else {
+        writeln!(
+            out,
+            r#"
+    void {method_name}({c_type} {field}) noexcept
+    {{
+        reinterpret_cast<{c_struct} *>(this->self_)->{field} = {field};
+    }}"#,
+            c_type = c_type,
+            method_name = method.short_name(),
+            c_struct = repr_c_struct_name(class),
+            field = field.name,
+        )
+    }
+    .expect(WRITE_TO_MEM_FAILED_MSG);
+}
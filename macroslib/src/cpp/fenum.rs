@@ -1,23 +1,28 @@
 use log::trace;
+use petgraph::Direction;
 use quote::quote;
+use smol_str::SmolStr;
 use std::{io::Write, rc::Rc};
-use syn::Type;
+use syn::{spanned::Spanned, Type};
 
 use crate::{
-    cpp::{cpp_code, CppContext},
+    cpp::{cpp_code, fclass, map_type::map_type, CppContext},
     error::{invalid_src_id_span, DiagnosticError, Result},
     extension::extend_foreign_enum,
     file_cache::FileWriteCache,
     typemap::{
         ast::{parse_ty_with_given_span, TypeName},
         ty::{ForeignConversationIntermediate, ForeignConversationRule, ForeignTypeS},
-        TypeConvCode, FROM_VAR_TEMPLATE,
+        RustTypeIdx, TypeConvCode, TypeConvEdge, FROM_VAR_TEMPLATE, TO_VAR_TEMPLATE,
     },
-    types::ForeignEnumInfo,
-    WRITE_TO_MEM_FAILED_MSG,
+    types::{ForeignEnumInfo, ENUM_RAW_FREE, ENUM_RAW_TAG},
+    CppVariant, WRITE_TO_MEM_FAILED_MSG,
 };
 
 pub(in crate::cpp) fn generate_enum(ctx: &mut CppContext, fenum: &ForeignEnumInfo) -> Result<()> {
+    if !fenum.is_c_like() {
+        return generate_data_enum(ctx, fenum);
+    }
     if (fenum.items.len() as u64) >= u64::from(u32::max_value()) {
         return Err(DiagnosticError::new(
             fenum.src_id,
@@ -26,23 +31,36 @@ pub(in crate::cpp) fn generate_enum(ctx: &mut CppContext, fenum: &ForeignEnumInf
         ));
     }
 
+    let values = fenum.items_values_as_u32()?;
+
     trace!("enum_ti: {}", fenum.name);
     let enum_name = &fenum.name;
     let enum_ti: Type = parse_ty_with_given_span(&enum_name.to_string(), fenum.name.span())
         .map_err(|err| DiagnosticError::from_syn_err(fenum.src_id, err))?;
-    let enum_rty = ctx.conv_map.find_or_alloc_rust_type_that_implements(
-        &enum_ti,
-        &["SwigForeignEnum"],
-        fenum.src_id,
-    );
-
-    generate_c_code_for_enum(ctx, fenum)
-        .map_err(|err| DiagnosticError::new(fenum.src_id, fenum.span(), err))?;
-    generate_rust_trait_for_enum(ctx, fenum)?;
-
     let u32_rty = ctx
         .conv_map
         .find_or_alloc_rust_type_no_src_id(&parse_type! { u32 });
+    let enum_rty = if fenum.is_flags() {
+        let enum_rty = ctx.conv_map.find_or_alloc_rust_type(&enum_ti, fenum.src_id);
+        add_conversation_rules_for_flags(ctx, fenum, enum_rty.to_idx(), u32_rty.to_idx());
+        enum_rty
+    } else {
+        ctx.conv_map.find_or_alloc_rust_type_that_implements(
+            &enum_ti,
+            &["SwigForeignEnum"],
+            fenum.src_id,
+        )
+    };
+
+    generate_c_code_for_enum(ctx, fenum, &values)
+        .map_err(|err| DiagnosticError::new(fenum.src_id, fenum.span(), err))?;
+    if !fenum.is_flags() {
+        generate_rust_trait_for_enum(ctx, fenum, &values)?;
+    }
+    let values_check = fenum.rust_code_to_check_items_values();
+    if !values_check.is_empty() {
+        ctx.rust_code.push(values_check);
+    }
 
     let enum_ftype = ForeignTypeS {
         name: TypeName::new(fenum.name.to_string(), (fenum.src_id, fenum.name.span())),
@@ -81,9 +99,372 @@ pub(in crate::cpp) fn generate_enum(ctx: &mut CppContext, fenum: &ForeignEnumInf
     Ok(())
 }
 
+/// `bitflags` type is passed as `u32` bits
+fn add_conversation_rules_for_flags(
+    ctx: &mut CppContext,
+    fenum: &ForeignEnumInfo,
+    enum_rty: RustTypeIdx,
+    u32_rty: RustTypeIdx,
+) {
+    ctx.conv_map.add_conversation_rule(
+        enum_rty,
+        u32_rty,
+        TypeConvEdge::new(
+            TypeConvCode::new2(
+                format!(
+                    "let mut {to}: u32 = {from}.bits() as u32;",
+                    to = TO_VAR_TEMPLATE,
+                    from = FROM_VAR_TEMPLATE,
+                ),
+                invalid_src_id_span(),
+            ),
+            None,
+        ),
+    );
+    ctx.conv_map.add_conversation_rule(
+        u32_rty,
+        enum_rty,
+        TypeConvEdge::new(
+            TypeConvCode::new2(
+                format!(
+                    "let mut {to}: {enum_name} = {enum_name}::from_bits_truncate({from} as _);",
+                    to = TO_VAR_TEMPLATE,
+                    from = FROM_VAR_TEMPLATE,
+                    enum_name = fenum.name,
+                ),
+                invalid_src_id_span(),
+            ),
+            None,
+        ),
+    );
+}
+
+/// Enum with data in variants is mapped to `std::variant` (or `boost::variant`)
+/// of structs, one struct per variant. Values are passed as pointers to boxed Rust enum,
+/// see `ForeignEnumInfo::raw_class`
+fn generate_data_enum(ctx: &mut CppContext, fenum: &ForeignEnumInfo) -> Result<()> {
+    let mut raw_class = fenum.raw_class();
+    // methods of internal class are private, only conversion code can use them
+    raw_class.foreign_code = format!("    friend struct {};", raw_access_struct_name(fenum));
+    fclass::generate(ctx, &raw_class)?;
+
+    let enum_ti: Type = parse_ty_with_given_span(&fenum.name.to_string(), fenum.name.span())
+        .map_err(|err| DiagnosticError::from_syn_err(fenum.src_id, err))?;
+    let enum_rty = ctx.conv_map.find_or_alloc_rust_type(&enum_ti, fenum.src_id);
+    let i64_rty = ctx
+        .conv_map
+        .find_or_alloc_rust_type_no_src_id(&parse_type! { i64 });
+    ctx.conv_map.add_conversation_rule(
+        enum_rty.to_idx(),
+        i64_rty.to_idx(),
+        TypeConvEdge::new(
+            TypeConvCode::new2(
+                format!(
+                    "let mut {to}: i64 = Box::into_raw(Box::new({from})) as i64;",
+                    to = TO_VAR_TEMPLATE,
+                    from = FROM_VAR_TEMPLATE,
+                ),
+                invalid_src_id_span(),
+            ),
+            None,
+        ),
+    );
+    ctx.conv_map.add_conversation_rule(
+        i64_rty.to_idx(),
+        enum_rty.to_idx(),
+        TypeConvEdge::new(
+            TypeConvCode::new2(
+                format!(
+                    "let mut {to}: {enum_name} = *unsafe {{ Box::from_raw({from} as *mut {enum_name}) }};",
+                    to = TO_VAR_TEMPLATE,
+                    from = FROM_VAR_TEMPLATE,
+                    enum_name = fenum.name,
+                ),
+                invalid_src_id_span(),
+            ),
+            None,
+        ),
+    );
+
+    let mut fields = Vec::with_capacity(fenum.items.len());
+    for item in &fenum.items {
+        let mut item_fields = vec![];
+        for (name, ty) in item.fields() {
+            let rty = ctx.conv_map.find_or_alloc_rust_type(ty, fenum.src_id);
+            let ty_span = (fenum.src_id, ty.span());
+            let out_ti = map_type(ctx, &rty, Direction::Outgoing, ty_span)?;
+            let in_ti = map_type(ctx, &rty, Direction::Incoming, ty_span)?;
+            let out_name = out_ti.cpp_type_name();
+            let in_name = in_ti.cpp_type_name();
+            // fields have input type, so user can create value to pass it to Rust,
+            // but string view does not own data, so string is used instead of it
+            let (cpp_type, getter_suffix, arg) = if out_name == in_name {
+                (in_name, "", format!("std::move(v.{})", name))
+            } else if out_name == "RustString" {
+                (
+                    "std::string".into(),
+                    ".to_std_string()",
+                    format!("v.{}", name),
+                )
+            } else {
+                return Err(DiagnosticError::new2(
+                    ty_span,
+                    format!(
+                        "C++ type of field for input ({}) and for output ({}) are different, \
+                         so it can not be field of enum",
+                        in_name, out_name
+                    ),
+                ));
+            };
+            item_fields.push(DataEnumField {
+                name: name.to_string().into(),
+                cpp_type,
+                getter_suffix,
+                arg,
+            });
+        }
+        fields.push(item_fields);
+    }
+    generate_cpp_code_for_data_enum(ctx, fenum, &fields)?;
+
+    let enum_ftype = ForeignTypeS {
+        name: TypeName::new(fenum.name.to_string(), (fenum.src_id, fenum.name.span())),
+        provides_by_module: vec![
+            format!("\"{}\"", cpp_code::cpp_header_name_for_enum(fenum)).into()
+        ],
+        into_from_rust: Some(ForeignConversationRule {
+            rust_ty: enum_rty.to_idx(),
+            intermediate: Some(ForeignConversationIntermediate {
+                input_to_output: false,
+                intermediate_ty: i64_rty.to_idx(),
+                conv_code: Rc::new(TypeConvCode::new(
+                    format!(
+                        "{}::from_raw({})",
+                        raw_access_struct_name(fenum),
+                        FROM_VAR_TEMPLATE
+                    ),
+                    invalid_src_id_span(),
+                )),
+            }),
+        }),
+        from_into_rust: Some(ForeignConversationRule {
+            rust_ty: enum_rty.to_idx(),
+            intermediate: Some(ForeignConversationIntermediate {
+                input_to_output: false,
+                intermediate_ty: i64_rty.to_idx(),
+                conv_code: Rc::new(TypeConvCode::new(
+                    format!(
+                        "{}::to_raw(std::move({}))",
+                        raw_access_struct_name(fenum),
+                        FROM_VAR_TEMPLATE
+                    ),
+                    invalid_src_id_span(),
+                )),
+            }),
+        }),
+        name_prefix: None,
+    };
+    ctx.conv_map.alloc_foreign_type(enum_ftype)?;
+    Ok(())
+}
+
+struct DataEnumField {
+    name: SmolStr,
+    cpp_type: SmolStr,
+    /// added to result of getter of raw class to get value of field
+    getter_suffix: &'static str,
+    /// expression to pass field of `v` to constructor of Rust enum
+    arg: String,
+}
+
+/// Struct with conversion of C++ value to pointer to Rust enum and back,
+/// it is friend of raw class
+fn raw_access_struct_name(fenum: &ForeignEnumInfo) -> String {
+    format!("{}RawAccess", fenum.name)
+}
+
+fn generate_cpp_code_for_data_enum(
+    ctx: &mut CppContext,
+    fenum: &ForeignEnumInfo,
+    fields: &[Vec<DataEnumField>],
+) -> Result<()> {
+    let path = ctx
+        .cfg
+        .output_dir
+        .join(cpp_code::cpp_header_name_for_enum(fenum));
+    let mut file = FileWriteCache::new(&path, ctx.generated_foreign_files);
+    let (variant_include, variant_type, get_func, index_method) = match ctx.cfg.cpp_variant {
+        CppVariant::Std17 => ("<variant>", "std::variant", "std::get", "index"),
+        CppVariant::Boost => (
+            "<boost/variant.hpp>",
+            "boost::variant",
+            "boost::get",
+            "which",
+        ),
+    };
+    let raw_class = fenum.raw_class_name();
+    writeln!(
+        file,
+        r#"// Automatically generated by flapigen
+#pragma once
+
+#include <cstdint>
+#include <cstdlib>
+#include <string>
+#include <utility>
+#include {variant_include}
+
+#include "{raw_class}.hpp"
+
+namespace {namespace} {{"#,
+        variant_include = variant_include,
+        raw_class = raw_class,
+        namespace = ctx.cfg.namespace_name,
+    )
+    .expect(WRITE_TO_MEM_FAILED_MSG);
+
+    let struct_name = |item_name| format!("{}_{}", fenum.name, item_name);
+    for (item, item_fields) in fenum.items.iter().zip(fields) {
+        let mut doc_comments = cpp_code::doc_comments_to_c_comments(&item.doc_comments, true);
+        if !doc_comments.is_empty() && !doc_comments.ends_with('\n') {
+            doc_comments.push('\n');
+        }
+        writeln!(
+            file,
+            "{doc_comments}struct {name} {{",
+            doc_comments = doc_comments,
+            name = struct_name(&item.name),
+        )
+        .expect(WRITE_TO_MEM_FAILED_MSG);
+        for f in item_fields {
+            writeln!(file, "    {} {};", f.cpp_type, f.name).expect(WRITE_TO_MEM_FAILED_MSG);
+        }
+        writeln!(file, "}};\n").expect(WRITE_TO_MEM_FAILED_MSG);
+    }
+
+    let mut doc_comments = cpp_code::doc_comments_to_c_comments(&fenum.doc_comments, true);
+    if !doc_comments.is_empty() && !doc_comments.ends_with('\n') {
+        doc_comments.push('\n');
+    }
+    let variants = fenum
+        .items
+        .iter()
+        .map(|item| struct_name(&item.name))
+        .collect::<Vec<_>>()
+        .join(", ");
+    writeln!(
+        file,
+        r#"{doc_comments}using {enum_name} = {variant_type}<{variants}>;
+
+/// Conversion of `{enum_name}` to and from pointer to Rust enum, for generated code only
+struct {raw_access} {{
+    static {enum_name} from_raw(int64_t raw) noexcept
+    {{
+        switch ({raw_class}::{tag}(raw)) {{"#,
+        doc_comments = doc_comments,
+        enum_name = fenum.name,
+        variant_type = variant_type,
+        variants = variants,
+        raw_access = raw_access_struct_name(fenum),
+        raw_class = raw_class,
+        tag = ENUM_RAW_TAG,
+    )
+    .expect(WRITE_TO_MEM_FAILED_MSG);
+    for (i, (item, item_fields)) in fenum.items.iter().zip(fields).enumerate() {
+        let args = item_fields
+            .iter()
+            .map(|f| {
+                format!(
+                    " {}::{}(raw){}",
+                    raw_class,
+                    item.raw_getter_name(&f.name),
+                    f.getter_suffix
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+        writeln!(
+            file,
+            r#"        case {index}: {{
+            {struct_name} ret{{{args}{space}}};
+            {raw_class}::{free}(raw);
+            return ret;
+        }}"#,
+            index = i,
+            struct_name = struct_name(&item.name),
+            args = args,
+            space = if item_fields.is_empty() { "" } else { " " },
+            raw_class = raw_class,
+            free = ENUM_RAW_FREE,
+        )
+        .expect(WRITE_TO_MEM_FAILED_MSG);
+    }
+    writeln!(
+        file,
+        r#"        default:
+            std::abort();
+        }}
+    }}
+
+    static int64_t to_raw({enum_name} x) noexcept
+    {{
+        switch (x.{index_method}()) {{"#,
+        enum_name = fenum.name,
+        index_method = index_method,
+    )
+    .expect(WRITE_TO_MEM_FAILED_MSG);
+    for (i, (item, item_fields)) in fenum.items.iter().zip(fields).enumerate() {
+        let args = item_fields
+            .iter()
+            .map(|f| f.arg.as_str())
+            .collect::<Vec<_>>()
+            .join(", ");
+        let get_value = if item_fields.is_empty() {
+            String::new()
+        } else {
+            format!(
+                "            auto &v = {get_func}<{struct_name}>(x);\n",
+                get_func = get_func,
+                struct_name = struct_name(&item.name)
+            )
+        };
+        writeln!(
+            file,
+            r#"        case {index}: {{
+{get_value}            return {raw_class}::{constructor}({args});
+        }}"#,
+            index = i,
+            get_value = get_value,
+            raw_class = raw_class,
+            constructor = item.raw_constructor_name(),
+            args = args,
+        )
+        .expect(WRITE_TO_MEM_FAILED_MSG);
+    }
+    writeln!(
+        file,
+        r#"        default:
+            std::abort();
+        }}
+    }}
+}};
+}} // namespace {namespace}"#,
+        namespace = ctx.cfg.namespace_name,
+    )
+    .expect(WRITE_TO_MEM_FAILED_MSG);
+
+    let mut cnt = file.take_content();
+    extend_foreign_enum(fenum, &mut cnt, ctx.enum_ext_handlers)?;
+    file.replace_content(cnt);
+    file.update_file_if_necessary()
+        .map_err(DiagnosticError::map_any_err_to_our_err)?;
+    Ok(())
+}
+
 fn generate_c_code_for_enum(
     ctx: &mut CppContext,
     enum_info: &ForeignEnumInfo,
+    values: &[u32],
 ) -> std::result::Result<(), DiagnosticError> {
     let c_path = ctx
         .cfg
@@ -92,20 +473,31 @@ fn generate_c_code_for_enum(
     let mut file = FileWriteCache::new(&c_path, ctx.generated_foreign_files);
     let enum_doc_comments = cpp_code::doc_comments_to_c_comments(&enum_info.doc_comments, true);
 
+    let is_flags = enum_info.is_flags();
     writeln!(
         file,
         r#"// Automatically generated by flapigen
 #pragma once
-
+{includes}
 namespace {namespace} {{
 {doc_comments}
-enum {enum_name} {{"#,
-        enum_name = enum_info.name,
+{enum_decl} {{"#,
+        includes = if is_flags {
+            "\n#include <cstdint>\n"
+        } else {
+            ""
+        },
+        enum_decl = if is_flags {
+            format!("enum class {} : uint32_t", enum_info.name)
+        } else {
+            format!("enum {}", enum_info.name)
+        },
         doc_comments = enum_doc_comments,
         namespace = ctx.cfg.namespace_name,
     )
     .expect(WRITE_TO_MEM_FAILED_MSG);
 
+    let signed_values = enum_info.items_values();
     for (i, item) in enum_info.items.iter().enumerate() {
         //Enums are aligned left, so we pass true to get left aligned comments.
         let mut doc_comments = cpp_code::doc_comments_to_c_comments(&item.doc_comments, true);
@@ -114,9 +506,14 @@ enum {enum_name} {{"#,
         }
         writeln!(
             file,
-            "{doc_comments}{item_name} = {index}{separator}",
+            "{doc_comments}{item_name} {deprecated}= {value}{separator}",
             item_name = item.name,
-            index = i,
+            deprecated = cpp_code::cpp_deprecated_attr(item.deprecated.as_ref()),
+            value = if is_flags {
+                values[i].to_string()
+            } else {
+                signed_values[i].to_string()
+            },
             doc_comments = doc_comments,
             separator = if i == enum_info.items.len() - 1 {
                 "\n"
@@ -127,10 +524,40 @@ enum {enum_name} {{"#,
         .expect(WRITE_TO_MEM_FAILED_MSG);
     }
 
+    writeln!(file, "}};").expect(WRITE_TO_MEM_FAILED_MSG);
+    if is_flags {
+        for op in &["|", "&", "^"] {
+            writeln!(
+                file,
+                r#"
+inline {enum_name} operator{op}({enum_name} a, {enum_name} b)
+{{
+    return static_cast<{enum_name}>(static_cast<uint32_t>(a) {op} static_cast<uint32_t>(b));
+}}
+inline {enum_name} &operator{op}=({enum_name} &a, {enum_name} b)
+{{
+    a = a {op} b;
+    return a;
+}}"#,
+                enum_name = enum_info.name,
+                op = op,
+            )
+            .expect(WRITE_TO_MEM_FAILED_MSG);
+        }
+        writeln!(
+            file,
+            r#"
+inline {enum_name} operator~({enum_name} a)
+{{
+    return static_cast<{enum_name}>(~static_cast<uint32_t>(a));
+}}"#,
+            enum_name = enum_info.name,
+        )
+        .expect(WRITE_TO_MEM_FAILED_MSG);
+    }
     writeln!(
         file,
-        r#"}};
-}} // namespace {namespace}
+        r#"}} // namespace {namespace}
 "#,
         namespace = ctx.cfg.namespace_name
     )
@@ -143,15 +570,17 @@ enum {enum_name} {{"#,
     Ok(())
 }
 
-fn generate_rust_trait_for_enum(ctx: &mut CppContext, enum_info: &ForeignEnumInfo) -> Result<()> {
+fn generate_rust_trait_for_enum(
+    ctx: &mut CppContext,
+    enum_info: &ForeignEnumInfo,
+    values: &[u32],
+) -> Result<()> {
     let mut arms_to_u32 = Vec::with_capacity(enum_info.items.len());
     let mut arms_from_u32 = Vec::with_capacity(enum_info.items.len());
-    assert!((enum_info.items.len() as u64) <= u64::from(u32::max_value()));
-    for (i, item) in enum_info.items.iter().enumerate() {
+    for (item, value) in enum_info.items.iter().zip(values) {
         let item_name = &item.rust_name;
-        let idx = i as u32;
-        arms_to_u32.push(quote! { #item_name => #idx });
-        arms_from_u32.push(quote! { #idx => #item_name });
+        arms_to_u32.push(quote! { #item_name => #value });
+        arms_from_u32.push(quote! { #value => #item_name });
     }
 
     let rust_enum_name = &enum_info.name;
@@ -13,6 +13,7 @@ use crate::{
     error::{invalid_src_id_span, panic_on_syn_error, DiagnosticError, Result},
     file_cache::FileWriteCache,
     namegen::new_unique_name,
+    source_registry::SourceId,
     typemap::{
         ast::{parse_ty_with_given_span, DisplayToTokens, TypeName},
         ty::{ForeignConversationIntermediate, ForeignConversationRule, ForeignTypeS, RustType},
@@ -27,10 +28,27 @@ pub(in crate::cpp) fn generate_interface(
     ctx: &mut CppContext,
     interface: &ForeignInterface,
 ) -> Result<()> {
+    if let Some((trait_code, conv_rules)) = interface.closure_glue() {
+        ctx.rust_code.push(trait_code);
+        ctx.conv_map
+            .merge(SourceId::none(), &conv_rules, ctx.target_pointer_width)?;
+    }
     let mut f_methods = find_suitable_ftypes_for_interace_methods(ctx, interface)?;
-    let req_includes = cpp_code::cpp_list_required_includes(&mut f_methods);
-    cpp_code_generate_interface(ctx, interface, &req_includes, &f_methods)
-        .map_err(|err| DiagnosticError::new(interface.src_id, interface.span(), err))?;
+    let mut req_includes = cpp_code::cpp_list_required_includes(&mut f_methods);
+    let std_function = if interface.closure.is_some() {
+        req_includes.push("<functional>".into());
+        Some(cpp_std_function_type(&f_methods[0]))
+    } else {
+        None
+    };
+    cpp_code_generate_interface(
+        ctx,
+        interface,
+        &req_includes,
+        &f_methods,
+        std_function.as_deref(),
+    )
+    .map_err(|err| DiagnosticError::new(interface.src_id, interface.span(), err))?;
     rust_code_generate_interface(ctx, interface, &f_methods)?;
 
     let c_struct_name = format!("C_{}", interface.name);
@@ -122,9 +140,75 @@ pub(in crate::cpp) fn generate_interface(
         .into(),
     );
 
+    if let (Some(sig), Some(std_function)) = (interface.closure.as_ref(), std_function) {
+        let closure_rust_ty: Type = parse_ty_with_given_span(
+            &format!("Box<dyn Fn{} + Send>", DisplayToTokens(&**sig)),
+            interface.name.span(),
+        )
+        .map_err(|err| DiagnosticError::from_syn_err(interface.src_id, err))?;
+        let closure_rust_ty = ctx
+            .conv_map
+            .find_or_alloc_rust_type(&closure_rust_ty, interface.src_id);
+        let cpp_abs_class_header: SmolStr =
+            format!("\"{}\"", cpp_interface_header(interface)).into();
+
+        let tmp_name = "$tmp".into();
+        let conv_code = format!(
+            r#"
+        {c_struct} {tmp_name} = {interface}::to_c_interface({interface}::from_function(std::move({var})));
+        {to} = &{tmp_name};
+"#,
+            var = FROM_VAR_TEMPLATE,
+            interface = interface.name,
+            to = TO_VAR_TYPE_TEMPLATE,
+            tmp_name = tmp_name,
+            c_struct = c_struct_name,
+        );
+        let params = vec![
+            FROM_VAR_TEMPLATE.into(),
+            TO_VAR_TYPE_TEMPLATE.into(),
+            tmp_name,
+        ];
+        let conv_code = TypeConvCode::with_params(conv_code, invalid_src_id_span(), params);
+
+        ctx.conv_map.alloc_foreign_type(ForeignTypeS {
+            name: TypeName::new(std_function, interface.src_id_span()),
+            provides_by_module: vec![
+                cpp_abs_class_header,
+                "<functional>".into(),
+                "<utility>".into(),
+            ],
+            into_from_rust: None,
+            from_into_rust: Some(ForeignConversationRule {
+                rust_ty: closure_rust_ty.to_idx(),
+                intermediate: Some(ForeignConversationIntermediate {
+                    input_to_output: false,
+                    intermediate_ty: rust_ty.to_idx(),
+                    conv_code: Rc::new(conv_code),
+                }),
+            }),
+            name_prefix: None,
+        })?;
+    }
+
     Ok(())
 }
 
+/// `std::function<R(A, B)>` for the only method of closure's callback
+fn cpp_std_function_type(f_method: &CppForeignMethodSignature) -> String {
+    let ret_type = match f_method.output.cpp_converter.as_ref() {
+        Some(out_conv) => out_conv.typename.as_str(),
+        None => f_method.output.base.name.as_str(),
+    };
+    let args = f_method
+        .input
+        .iter()
+        .map(cpp_code::cpp_arg_type)
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!("std::function<{}({})>", ret_type, args)
+}
+
 fn rust_code_generate_interface(
     ctx: &mut CppContext,
     interface: &ForeignInterface,
@@ -365,6 +449,7 @@ fn cpp_code_generate_interface(
     interface: &ForeignInterface,
     req_includes: &[SmolStr],
     f_methods: &[CppForeignMethodSignature],
+    std_function: Option<&str>,
 ) -> std::result::Result<(), DiagnosticError> {
     use std::fmt::Write;
 
@@ -426,7 +511,7 @@ struct C_{interface_name} {{
                     .replace(FROM_VAR_TEMPLATE, &ret_name);
                 (out_conv.typename.clone(), conv_code)
             } else {
-                (c_ret_type.clone(), String::new())
+                (c_ret_type.clone(), ret_name.to_string())
             };
         writeln!(
             file_c,
@@ -446,7 +531,8 @@ struct C_{interface_name} {{
         writeln!(
             &mut cpp_virtual_methods,
             r#"{doc_comments}
-    virtual {cpp_ret_type} {method_name}({single_args_with_types}) noexcept = 0;"#,
+    {deprecated}virtual {cpp_ret_type} {method_name}({single_args_with_types}) noexcept = 0;"#,
+            deprecated = cpp_code::cpp_deprecated_attr(method.deprecated.as_ref()),
             method_name = method.name,
             doc_comments = cpp_code::doc_comments_to_c_comments(&method.doc_comments, false),
             single_args_with_types =
@@ -457,6 +543,9 @@ struct C_{interface_name} {{
 
         let (conv_args_code, call_input_args) =
             cpp_code::convert_args(f_method, &mut known_names, method.arg_names_without_self())?;
+        if method.deprecated.is_some() {
+            cpp_static_reroute_methods.push_str(cpp_code::CPP_IGNORE_DEPRECATED_BEGIN);
+        }
 
         write!(
             &mut cpp_static_reroute_methods,
@@ -506,6 +595,9 @@ struct C_{interface_name} {{
             )
             .expect(WRITE_TO_MEM_FAILED_MSG);
         }
+        if method.deprecated.is_some() {
+            cpp_static_reroute_methods.push_str(cpp_code::CPP_IGNORE_DEPRECATED_END);
+        }
         writeln!(
             &mut cpp_fill_c_interface_struct,
             "        ret.{method_name} = c_{method_name};",
@@ -521,6 +613,50 @@ struct C_{interface_name} {{
         )
         .expect(WRITE_TO_MEM_FAILED_MSG);
 
+    let mut from_function = String::new();
+    if let (Some(std_function), Some(method), Some(f_method)) =
+        (std_function, interface.items.first(), f_methods.first())
+    {
+        let call_args = method
+            .arg_names_without_self()
+            .map(|name| format!("std::move({})", name))
+            .collect::<Vec<_>>()
+            .join(", ");
+        write!(
+            &mut from_function,
+            r#"
+    //! wrap any callable object, like lambda, into {interface_name}
+    static std::unique_ptr<{interface_name}> from_function({std_function} f)
+    {{
+        class Impl final : public {interface_name} {{
+        public:
+            explicit Impl({std_function} f) noexcept : f_(std::move(f)) {{}}
+            {cpp_ret_type} {method_name}({args_with_types}) noexcept override
+            {{
+                return f_({call_args});
+            }}
+        private:
+            {std_function} f_;
+        }};
+        return std::unique_ptr<{interface_name}>(new Impl(std::move(f)));
+    }}
+"#,
+            interface_name = interface.name,
+            std_function = std_function,
+            cpp_ret_type = f_method
+                .output
+                .cpp_converter
+                .as_ref()
+                .map(|x| x.typename.as_str())
+                .unwrap_or_else(|| f_method.output.base.name.as_str()),
+            method_name = method.name,
+            args_with_types =
+                cpp_code::cpp_generate_args_with_types(f_method, method.arg_names_without_self()),
+            call_args = call_args,
+        )
+        .expect(WRITE_TO_MEM_FAILED_MSG);
+    }
+
     let mut includes = String::new();
     for inc in req_includes {
         writeln!(&mut includes, r#"#include {}"#, inc).expect(WRITE_TO_MEM_FAILED_MSG);
@@ -543,7 +679,7 @@ class {interface_name} {{
 public:
     virtual ~{interface_name}() noexcept {{}}
 {virtual_methods}
-
+{from_function}
     static C_{interface_name} to_c_interface(std::unique_ptr<{interface_name}> p)
     {{
         assert(p != nullptr);
@@ -561,6 +697,7 @@ private:
         doc_comments = interface_comments,
         c_interface_struct_header = c_interface_struct_header,
         virtual_methods = cpp_virtual_methods,
+        from_function = from_function,
         static_reroute_methods = cpp_static_reroute_methods,
         cpp_fill_c_interface_struct = cpp_fill_c_interface_struct,
         namespace_name = ctx.cfg.namespace_name,
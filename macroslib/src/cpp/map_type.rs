@@ -118,25 +118,32 @@ fn do_map_type(
         return Ok(ftype);
     }
 
-    match direction {
-        Direction::Outgoing => Err(DiagnosticError::new2(
+    let mut err = match direction {
+        Direction::Outgoing => DiagnosticError::new2(
             arg_ty_span,
             format!(
                 "Do not know conversation from \
                  such rust type '{}' to C++ type",
                 arg_ty
             ),
-        )),
+        ),
 
-        Direction::Incoming => Err(DiagnosticError::new2(
+        Direction::Incoming => DiagnosticError::new2(
             arg_ty_span,
             format!(
                 "Do not know conversation from C++ type \
                  to such rust type '{}'",
                 arg_ty
             ),
-        )),
+        ),
+    };
+    if let Some(explanation) = ctx
+        .conv_map
+        .explain_conv_failure(arg_ty.to_idx(), direction)
+    {
+        err.span_note(arg_ty_span, explanation);
     }
+    Err(err)
 }
 
 struct CppContextForArg<'a, 'b> {
@@ -47,7 +47,7 @@ use syn::spanned::Spanned;
 
 use crate::{
     cpp::{map_class_self_type::register_typemap_for_self_type, map_type::map_type},
-    error::{invalid_src_id_span, DiagnosticError, Result},
+    error::{emit_warning, invalid_src_id_span, DiagnosticError, Result},
     extension::{ClassExtHandlers, EnumExtHandlers, ExtHandlers, MethodExtHandlers},
     file_cache::FileWriteCache,
     source_registry::SourceId,
@@ -60,9 +60,12 @@ use crate::{
         },
         CItem, CItems, ForeignTypeInfo, TypeConvCode, TypeMapConvRuleInfo,
     },
-    types::{ForeignClassInfo, ForeignMethod, ItemToExpand, MethodAccess, MethodVariant},
-    CppConfig, CppOptional, CppStrView, CppVariant, LanguageGenerator, SourceCode, TypeMap,
-    SMART_PTR_COPY_TRAIT, WRITE_TO_MEM_FAILED_MSG,
+    types::{
+        check_module_items_holder_name, ForeignClassInfo, ForeignMethod, ItemToExpand,
+        MethodAccess, MethodVariant,
+    },
+    CppConfig, CppOptional, CppStrView, CppVariant, GeneratedRustCode, LanguageGenerator,
+    SourceCode, TypeMap, SMART_PTR_COPY_TRAIT, WRITE_TO_MEM_FAILED_MSG,
 };
 
 #[derive(Debug)]
@@ -182,6 +185,13 @@ impl CppForeignTypeInfo {
             cpp_converter,
         })
     }
+    /// Type in C++ code, after conversion from C type if it is required
+    pub(in crate::cpp) fn cpp_type_name(&self) -> SmolStr {
+        match self.cpp_converter {
+            Some(ref conv) => conv.typename.clone(),
+            None => self.base.name.clone(),
+        }
+    }
 }
 
 impl AsRef<ForeignTypeInfo> for CppForeignTypeInfo {
@@ -260,9 +270,12 @@ impl CppConfig {
 
             if class.smart_ptr_copy_derived() {
                 if class.copy_derived() {
-                    println!(
-                        "cargo:warning=class {} marked as Copy and {}, ignore Copy",
-                        class.name, SMART_PTR_COPY_TRAIT
+                    emit_warning(
+                        (class.src_id, class.span()),
+                        format!(
+                            "class {} marked as Copy and {}, ignore Copy",
+                            class.name, SMART_PTR_COPY_TRAIT
+                        ),
                     );
                 }
                 if check_if_smart_pointer_return_inner_type(&this_type, "Rc").is_none()
@@ -310,6 +323,10 @@ struct CppContext<'a> {
     rust_code: &'a mut Vec<TokenStream>,
     common_files: &'a mut FxHashMap<SmolStr, FileWriteCache>,
     generated_foreign_files: &'a mut FxHashSet<PathBuf>,
+    /// callbacks for results of async methods that are already generated
+    generated_future_completers: FxHashSet<SmolStr>,
+    /// callbacks for closures that are already generated
+    generated_closure_callbacks: FxHashSet<SmolStr>,
     class_ext_handlers: &'a ClassExtHandlers,
     method_ext_handlers: &'a MethodExtHandlers,
     enum_ext_handlers: &'a EnumExtHandlers,
@@ -324,8 +341,9 @@ impl LanguageGenerator for CppConfig {
         items: Vec<ItemToExpand>,
         remove_not_generated_files: bool,
         ext_handlers: ExtHandlers,
-    ) -> Result<Vec<TokenStream>> {
+    ) -> Result<GeneratedRustCode> {
         let mut ret = Vec::with_capacity(items.len());
+        let mut items_code = Vec::with_capacity(items.len());
         let mut files = FxHashMap::<SmolStr, FileWriteCache>::default();
         let mut generated_foreign_files = FxHashSet::default();
         {
@@ -336,24 +354,51 @@ impl LanguageGenerator for CppConfig {
                 rust_code: &mut ret,
                 common_files: &mut files,
                 generated_foreign_files: &mut generated_foreign_files,
+                generated_future_completers: FxHashSet::default(),
+                generated_closure_callbacks: FxHashSet::default(),
                 class_ext_handlers: ext_handlers.class_ext_handlers,
                 method_ext_handlers: ext_handlers.method_ext_handlers,
                 enum_ext_handlers: ext_handlers.enum_ext_handlers,
             };
             init(&mut ctx, code)?;
+            check_module_items_holder_name(&items, &self.module_items_class)?;
+            fclass::check_overrides(&items)?;
             for item in &items {
                 if let ItemToExpand::Class(ref fclass) = item {
                     self.register_class(ctx.conv_map, fclass)?;
                 }
             }
-            for item in items {
+            let mut module_consts = vec![];
+            let mut module_fns = vec![];
+            for (idx, item) in items.into_iter().enumerate() {
+                let code_start = ctx.rust_code.len();
                 match item {
                     ItemToExpand::Class(fclass) => fclass::generate(&mut ctx, &fclass)?,
+                    ItemToExpand::Const(fconst) => module_consts.push(*fconst),
+                    ItemToExpand::Fn(ffn) => module_fns.push(*ffn),
                     ItemToExpand::Enum(fenum) => fenum::generate_enum(&mut ctx, &fenum)?,
                     ItemToExpand::Interface(finterface) => {
                         finterface::generate_interface(&mut ctx, &finterface)?
                     }
                 }
+                items_code.push((idx, code_start..ctx.rust_code.len()));
+            }
+            // constants need class, functions are placed into namespace
+            if !module_consts.is_empty() {
+                let globals = ForeignClassInfo::module_items_holder(
+                    &self.module_items_class,
+                    module_consts,
+                    vec![],
+                );
+                fclass::generate(&mut ctx, &globals)?;
+            }
+            if !module_fns.is_empty() {
+                let fns_holder = ForeignClassInfo::module_items_holder(
+                    &self.module_items_class,
+                    vec![],
+                    module_fns,
+                );
+                fclass::generate_module_fns(&mut ctx, &fns_holder)?;
             }
         }
 
@@ -380,12 +425,53 @@ impl LanguageGenerator for CppConfig {
             .map_err(DiagnosticError::map_any_err_to_our_err)?;
         }
 
-        Ok(ret)
+        Ok(GeneratedRustCode {
+            code: ret,
+            items_code,
+        })
     }
 }
 
 fn c_func_name(class: &ForeignClassInfo, method: &ForeignMethod) -> String {
-    do_c_func_name(class, method.access, &method.short_name())
+    let short_name = method.short_name();
+    let overloads = class
+        .methods
+        .iter()
+        .filter(|m| m.access == method.access && m.short_name() == short_name)
+        .collect::<Vec<_>>();
+    if overloads.len() > 1 {
+        // C has no overloading, so use Rust name to make C function name unique
+        let rust_name = |m: &ForeignMethod| {
+            m.rust_id
+                .segments
+                .last()
+                .map(|x| x.ident.to_string())
+                .unwrap_or_default()
+        };
+        let method_rust_name = rust_name(method);
+        let mut func_name = format!("{}__{}", short_name, method_rust_name);
+        // Rust names can be the same too (`Foo::new` and `Boo::new`),
+        // or mangled name can clash with other method, so add index
+        if overloads
+            .iter()
+            .filter(|m| rust_name(m) == method_rust_name)
+            .nth(1)
+            .is_some()
+            || class
+                .methods
+                .iter()
+                .any(|m| m.access == method.access && m.short_name() == func_name)
+        {
+            let idx = overloads
+                .iter()
+                .position(|m| std::ptr::eq(*m, method))
+                .expect("method should be member of class");
+            func_name = format!("{}__{}", func_name, idx);
+        }
+        do_c_func_name(class, method.access, &func_name)
+    } else {
+        do_c_func_name(class, method.access, &short_name)
+    }
 }
 
 fn do_c_func_name(
@@ -473,6 +559,15 @@ fn register_c_type(
     Ok(something_defined)
 }
 
+/// All options that can be used in `cfg_option` of `foreign_typemap!`
+pub(crate) fn typemap_cfg_options() -> FxHashSet<&'static str> {
+    let mut opts = FxHashSet::<&'static str>::default();
+    opts.extend(CppOptional::iter().map(|x| -> &'static str { x.into() }));
+    opts.extend(CppVariant::iter().map(|x| -> &'static str { x.into() }));
+    opts.extend(CppStrView::iter().map(|x| -> &'static str { x.into() }));
+    opts
+}
+
 fn merge_rule(ctx: &mut CppContext, mut rule: TypeMapConvRuleInfo) -> Result<()> {
     debug!("merge_rule begin {:?}", rule);
     if rule.is_empty() {
@@ -482,15 +577,7 @@ fn merge_rule(ctx: &mut CppContext, mut rule: TypeMapConvRuleInfo) -> Result<()>
             format!("rule {:?} is empty", rule),
         ));
     }
-    let all_options = {
-        let mut opts = FxHashSet::<&'static str>::default();
-        opts.extend(CppOptional::iter().map(|x| -> &'static str { x.into() }));
-        opts.extend(CppVariant::iter().map(|x| -> &'static str { x.into() }));
-        opts.extend(CppStrView::iter().map(|x| -> &'static str { x.into() }));
-        opts
-    };
-
-    validate_cfg_options(&rule, &all_options)?;
+    validate_cfg_options(&rule, &typemap_cfg_options())?;
     let options = {
         let mut opts = FxHashSet::<&'static str>::default();
         opts.insert(ctx.cfg.cpp_variant.into());
@@ -0,0 +1,68 @@
+#pragma once
+
+#include <cstddef> //ptrdiff_t
+#include <iterator>
+#include <memory>
+#include <utility>
+
+/**
+ * Input iterator over class that holds Rust iterator,
+ * current element is fetched from Rust when iterator is created or incremented,
+ * so it can be dereferenced several times. Copies of iterator share
+ * the same Rust iterator, so only one of them can be incremented.
+ */
+template <typename Range>
+class RustForeignIterator final {
+public:
+    using iterator_category = std::input_iterator_tag;
+    using value_type = decltype(std::declval<Range &>().rustNext());
+    using difference_type = ptrdiff_t;
+    using pointer = value_type *;
+    using reference = value_type &;
+
+    RustForeignIterator() noexcept
+        : range(nullptr)
+    {
+    }
+    explicit RustForeignIterator(Range *r)
+        : range(r)
+    {
+        fetch();
+    }
+
+    reference operator*() const noexcept { return *this->current; }
+    pointer operator->() const noexcept { return this->current.get(); }
+
+    RustForeignIterator &operator++()
+    {
+        fetch();
+        return *this;
+    }
+    RustForeignIterator operator++(int)
+    {
+        RustForeignIterator prev = *this;
+        fetch();
+        return prev;
+    }
+
+    bool operator==(const RustForeignIterator &o) const noexcept
+    {
+        return this->at_end() == o.at_end();
+    }
+    bool operator!=(const RustForeignIterator &o) const noexcept { return !operator==(o); }
+
+private:
+    void fetch()
+    {
+        // shared, because element can be move only type, but iterator should be copyable
+        if (this->range != nullptr && this->range->rustHasNext()) {
+            this->current = std::make_shared<value_type>(this->range->rustNext());
+        } else {
+            this->current.reset();
+        }
+    }
+    bool at_end() const noexcept { return this->current == nullptr; }
+
+    Range *range;
+    std::shared_ptr<value_type> current;
+};
@@ -0,0 +1,45 @@
+#pragma once
+
+#include <exception>
+#include <future>
+#include <stdexcept>
+#include <utility>
+
+/**
+ * Implementation of callback that receives result of Rust future,
+ * result is passed to `std::future` returned by `get_future`.
+ * Rust reports error as string, it is converted to `std::runtime_error`
+ */
+template <typename Completer, typename T, typename Error>
+class RustFutureCompleter final : public Completer {
+public:
+    std::future<T> get_future() { return this->promise.get_future(); }
+
+    void complete(T value) noexcept override { this->promise.set_value(std::move(value)); }
+
+    void fail(Error error) noexcept override
+    {
+        this->promise.set_exception(
+            std::make_exception_ptr(std::runtime_error(error.to_std_string())));
+    }
+
+private:
+    std::promise<T> promise;
+};
+
+template <typename Completer, typename Error>
+class RustFutureCompleter<Completer, void, Error> final : public Completer {
+public:
+    std::future<void> get_future() { return this->promise.get_future(); }
+
+    void complete() noexcept override { this->promise.set_value(); }
+
+    void fail(Error error) noexcept override
+    {
+        this->promise.set_exception(
+            std::make_exception_ptr(std::runtime_error(error.to_std_string())));
+    }
+
+private:
+    std::promise<void> promise;
+};
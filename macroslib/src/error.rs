@@ -1,6 +1,10 @@
-use std::fmt::{Display, Write};
+use std::{
+    cell::RefCell,
+    fmt::{Display, Write},
+};
 
 use crate::{
+    json::JsonValue,
     source_registry::{SourceId, SourceRegistry},
     SourceCode, WRITE_TO_MEM_FAILED_MSG,
 };
@@ -61,6 +65,104 @@ impl Display for DiagnosticError {
 
 pub(crate) type Result<T> = std::result::Result<T, DiagnosticError>;
 
+/// Line and column inside source file, line is 1-indexed,
+/// column is 0-indexed (in UTF-8 characters)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineColumn {
+    pub line: usize,
+    pub column: usize,
+}
+
+/// Part of source file to which diagnostic message is related
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceSpan {
+    /// Name of source, the same as used in error messages,
+    /// for example "crate_name: path/to/file.rs.in"
+    pub file_name: String,
+    pub start: LineColumn,
+    pub end: LineColumn,
+}
+
+/// One message of error, with location information if it is available
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiagnosticMessage {
+    pub span: Option<SourceSpan>,
+    pub message: String,
+}
+
+impl Display for DiagnosticMessage {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::result::Result<(), core::fmt::Error> {
+        match self.span {
+            Some(ref sp) => write!(
+                f,
+                "{}:{}:{}: {}",
+                sp.file_name, sp.start.line, sp.start.column, self.message
+            ),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+/// Error returned by `Generator::try_expand` and `Generator::try_expand_many`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error {
+    /// The main error message
+    pub message: DiagnosticMessage,
+    /// Additional messages that point to related places in source code
+    pub notes: Vec<DiagnosticMessage>,
+}
+
+impl Error {
+    pub(crate) fn new(src_reg: &SourceRegistry, err: DiagnosticError) -> Self {
+        let mut messages = err
+            .data
+            .into_iter()
+            .flat_map(|(src_id, err)| err.into_iter().map(move |e| (src_id, e)))
+            .map(|(src_id, err)| {
+                let span = if src_id.is_none() {
+                    None
+                } else {
+                    let (start, end) = (err.span().start(), err.span().end());
+                    Some(SourceSpan {
+                        file_name: src_reg.src_with_id(src_id).id_of_code.clone(),
+                        start: LineColumn {
+                            line: start.line,
+                            column: start.column,
+                        },
+                        end: LineColumn {
+                            line: end.line,
+                            column: end.column,
+                        },
+                    })
+                };
+                DiagnosticMessage {
+                    span,
+                    message: err.to_string(),
+                }
+            });
+        let message = messages.next().unwrap_or_else(|| DiagnosticMessage {
+            span: None,
+            message: "unknown error".into(),
+        });
+        Error {
+            message,
+            notes: messages.collect(),
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::result::Result<(), core::fmt::Error> {
+        write!(f, "error: {}", self.message)?;
+        for note in &self.notes {
+            write!(f, "\nnote: {}", note)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for Error {}
+
 pub(crate) fn panic_on_syn_error(id_of_code: &str, code: String, err: syn::Error) -> ! {
     let mut src_reg = SourceRegistry::default();
     let src_id = src_reg.register(SourceCode {
@@ -83,12 +185,12 @@ pub(crate) fn panic_on_parse_error(src_reg: &SourceRegistry, main_err: &Diagnost
             eprintln!("error in {}", src.id_of_code);
         }
         prev_err_src_id = Some(*src_id);
-        eprint_error_location(err, src);
+        eprintln!("{}", error_location_to_string("error", err, src));
     }
     panic!();
 }
 
-fn eprint_error_location(err: &syn::Error, src: &SourceCode) {
+fn error_location_to_string(level: &str, err: &syn::Error, src: &SourceCode) -> String {
     let span = err.span();
     let start = span.start();
     let end = span.end();
@@ -125,12 +227,194 @@ fn eprint_error_location(err: &syn::Error, src: &SourceCode) {
         }
     }
 
-    eprintln!(
-        "parsing of {name} failed\nerror: {err}\n{code_problem}\nAt {name}:{line_s}:{col_s}",
+    let header = if level == "error" {
+        format!("parsing of {} failed\n", src.id_of_code)
+    } else {
+        String::new()
+    };
+    format!(
+        "{header}{level}: {err}\n{code_problem}\nAt {name}:{line_s}:{col_s}",
+        header = header,
+        level = level,
         name = src.id_of_code,
         err = err,
         code_problem = code_problem,
         line_s = start.line,
         col_s = start.column,
-    );
+    )
+}
+
+struct Warnings {
+    /// source that parsed at the moment, to report warnings from `syn::parse::Parse`
+    /// implementations, where we have no `SourceId`
+    cur_src_id: SourceId,
+    data: Vec<DiagnosticError>,
+}
+
+thread_local! {
+    static WARNINGS: RefCell<Warnings> = const {
+        RefCell::new(Warnings {
+            cur_src_id: SourceId::none(),
+            data: vec![],
+        })
+    };
+}
+
+/// Print warning for cargo and remember it for machine readable diagnostics
+pub(crate) fn emit_warning<T: Display>((src_id, sp): SourceIdSpan, msg: T) {
+    println!("cargo:warning={}", msg);
+    WARNINGS.with(|w| {
+        w.borrow_mut()
+            .data
+            .push(DiagnosticError::new(src_id, sp, msg))
+    });
+}
+
+/// Print warning with notes for cargo, with location of each part,
+/// and remember it for machine readable diagnostics
+pub(crate) fn emit_warning_with_notes(src_reg: &SourceRegistry, warn: DiagnosticError) {
+    for (idx, (src_id, err)) in warn.data.iter().enumerate() {
+        let prefix = if idx == 0 { "" } else { "note: " };
+        if src_id.is_none() {
+            println!("cargo:warning={}{}", prefix, err);
+        } else {
+            let start = err.span().start();
+            println!(
+                "cargo:warning={}{}:{}:{}: {}",
+                prefix,
+                src_reg.src_with_id(*src_id).id_of_code,
+                start.line,
+                start.column,
+                err
+            );
+        }
+    }
+    WARNINGS.with(|w| w.borrow_mut().data.push(warn));
+}
+
+/// The same as `emit_warning`, but for source that parsed at the moment
+pub(crate) fn emit_parse_warning<T: Display>(sp: Span, msg: T) {
+    let src_id = WARNINGS.with(|w| w.borrow().cur_src_id);
+    emit_warning((src_id, sp), msg);
+}
+
+pub(crate) fn emit_warning_without_src_info<T: Display>(msg: T) {
+    emit_warning(invalid_src_id_span(), msg);
+}
+
+pub(crate) fn set_parsed_src_id(src_id: SourceId) {
+    WARNINGS.with(|w| w.borrow_mut().cur_src_id = src_id);
+}
+
+pub(crate) fn take_warnings() -> Vec<DiagnosticError> {
+    WARNINGS.with(|w| {
+        let mut w = w.borrow_mut();
+        w.cur_src_id = SourceId::none();
+        std::mem::take(&mut w.data)
+    })
+}
+
+/// Convert warnings and error to JSON in format similar to `rustc --error-format=json`,
+/// one diagnostic per line
+pub(crate) fn diagnostics_to_json(
+    src_reg: &SourceRegistry,
+    warnings: &[DiagnosticError],
+    err: Option<&DiagnosticError>,
+) -> String {
+    let mut ret = String::new();
+    for w in warnings {
+        if let Some((main, notes)) = w.data.split_first() {
+            let diag = diagnostic_to_json(src_reg, "warning", main, notes);
+            writeln!(&mut ret, "{}", diag).expect(WRITE_TO_MEM_FAILED_MSG);
+        }
+    }
+    if let Some(err) = err {
+        if let Some((main, notes)) = err.data.split_first() {
+            let diag = diagnostic_to_json(src_reg, "error", main, notes);
+            writeln!(&mut ret, "{}", diag).expect(WRITE_TO_MEM_FAILED_MSG);
+        }
+    }
+    ret
+}
+
+fn diagnostic_to_json(
+    src_reg: &SourceRegistry,
+    level: &str,
+    (src_id, err): &(SourceId, syn::Error),
+    notes: &[(SourceId, syn::Error)],
+) -> JsonValue {
+    let (spans, rendered) = if src_id.is_none() {
+        (vec![], format!("{}: {}", level, err))
+    } else {
+        let src = src_reg.src_with_id(*src_id);
+        (
+            vec![span_to_json(src, err.span())],
+            error_location_to_string(level, err, src),
+        )
+    };
+    let children: Vec<JsonValue> = notes
+        .iter()
+        .map(|note| diagnostic_to_json(src_reg, "note", note, &[]))
+        .collect();
+    JsonValue::object()
+        .field("message", err.to_string())
+        .field("code", JsonValue::Null)
+        .field("level", level)
+        .field("spans", spans)
+        .field("children", children)
+        .field("rendered", rendered)
+}
+
+fn span_to_json(src: &SourceCode, sp: Span) -> JsonValue {
+    let (start, end) = (sp.start(), sp.end());
+    let byte_offset = |pos: proc_macro2::LineColumn| -> usize {
+        let mut offset = 0;
+        for (i, line) in src.code.split('\n').enumerate() {
+            if i + 1 == pos.line {
+                return offset
+                    + line
+                        .char_indices()
+                        .nth(pos.column)
+                        .map(|(idx, _)| idx)
+                        .unwrap_or_else(|| line.len());
+            }
+            offset += line.len() + 1;
+        }
+        src.code.len()
+    };
+    let text: Vec<JsonValue> = src
+        .code
+        .lines()
+        .enumerate()
+        .skip(start.line.saturating_sub(1))
+        .take(end.line + 1 - start.line)
+        .map(|(i, line)| {
+            let line_no = i + 1;
+            let highlight_start = if line_no == start.line {
+                start.column + 1
+            } else {
+                1
+            };
+            let highlight_end = if line_no == end.line {
+                end.column + 1
+            } else {
+                line.chars().count() + 1
+            };
+            JsonValue::object()
+                .field("text", line)
+                .field("highlight_start", highlight_start)
+                .field("highlight_end", highlight_end)
+        })
+        .collect();
+    JsonValue::object()
+        .field("file_name", src.id_of_code.as_str())
+        .field("byte_start", byte_offset(start))
+        .field("byte_end", byte_offset(end))
+        .field("line_start", start.line)
+        .field("line_end", end.line)
+        .field("column_start", start.column + 1)
+        .field("column_end", end.column + 1)
+        .field("is_primary", true)
+        .field("text", text)
+        .field("label", JsonValue::Null)
 }
@@ -1,7 +1,7 @@
 use crate::{
     error::{DiagnosticError, Result},
     types::{ForeignClassInfo, ForeignEnumInfo},
-    MethodVariant,
+    MethodVariant, FLAGS_DERIVE,
 };
 use rustc_hash::FxHashMap;
 
@@ -79,6 +79,9 @@ pub(crate) fn extend_foreign_enum(
     ext_enum_handlers: &EnumExtHandlers,
 ) -> Result<()> {
     for derive in &enum_info.derive_list {
+        if derive == FLAGS_DERIVE {
+            continue;
+        }
         if let Some(cb) = ext_enum_handlers.get(derive) {
             cb(cnt, &enum_info.name.to_string());
         } else {
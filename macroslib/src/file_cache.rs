@@ -65,6 +65,11 @@ impl FileWriteCache {
         self.cnt = bytes;
     }
 
+    #[allow(dead_code)]
+    pub fn content_len(&self) -> usize {
+        self.cnt.len()
+    }
+
     #[allow(dead_code)]
     pub fn define_item<S: Into<String>>(&mut self, item: S) {
         self.already_defined_items.insert(item.into());
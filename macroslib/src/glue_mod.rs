@@ -0,0 +1,231 @@
+//! Placement of Rust code generated for items declared inside `mod` of glue file.
+//!
+//! Code for such items is placed into child module of glue module
+//! (one per language), so it can use everything visible in glue module.
+//! Named items of this code are re-exported up to the top level,
+//! because code generated for other items may refer to them.
+
+use std::fmt::Write;
+
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::error::{DiagnosticError, Result};
+
+/// `mod` found in glue file
+pub(crate) struct GlueMod {
+    pub ident: syn::Ident,
+    /// index of parent module, `None` if module is declared at top level
+    pub parent: Option<usize>,
+    /// `#[cfg]` attributes of module
+    pub cfg_attrs: Vec<syn::Attribute>,
+    /// position in output of glue files where code of module should be inserted,
+    /// parent always has bigger position than its children
+    pub code_pos: usize,
+}
+
+/// Code generated for one language
+pub(crate) struct LangGlueModsCode {
+    /// code to insert into each glue module, empty if module has no code
+    pub mods: Vec<String>,
+    /// code to place at the top level of language code
+    pub top: String,
+}
+
+/// `items_code` is code generated for items of each glue module,
+/// `lang_mod_name` is name of module with language code,
+/// `utils_macros` are `macro_rules!` from "types maps",
+/// they are visible in glue modules only in case of several languages
+pub(crate) fn generate_glue_mods_code(
+    glue_mods: &[GlueMod],
+    items_code: Vec<Vec<TokenStream>>,
+    lang_mod_name: &str,
+    multi_lang: bool,
+    utils_macros: &[syn::Item],
+) -> Result<LangGlueModsCode> {
+    debug_assert_eq!(glue_mods.len(), items_code.len());
+    // child module is required if module or its descendant has code,
+    // parent always has smaller index than its children
+    let mut need_child: Vec<bool> = items_code.iter().map(|x| !x.is_empty()).collect();
+    for idx in (0..glue_mods.len()).rev() {
+        if let (true, Some(parent)) = (need_child[idx], glue_mods[idx].parent) {
+            need_child[parent] = true;
+        }
+    }
+    let lang_mod: syn::Ident = syn::parse_str(lang_mod_name).map_err(|err| {
+        DiagnosticError::new_without_src_info(format!(
+            "Can not parse `{}` as name of module for language code: {}",
+            lang_mod_name, err
+        ))
+    })?;
+    let re_export = |idx: usize, mod_path: TokenStream| -> TokenStream {
+        let GlueMod {
+            ref ident,
+            ref cfg_attrs,
+            ..
+        } = glue_mods[idx];
+        quote! {
+            #(#cfg_attrs)*
+            #[allow(unused_imports)]
+            pub(crate) use #mod_path::#ident::#lang_mod::*;
+        }
+    };
+
+    let mut ret = LangGlueModsCode {
+        mods: vec![String::new(); glue_mods.len()],
+        top: String::new(),
+    };
+    for (idx, code) in items_code.into_iter().enumerate() {
+        if !need_child[idx] {
+            continue;
+        }
+        let mut depth = 1;
+        let mut parent = glue_mods[idx].parent;
+        while let Some(p) = parent {
+            depth += 1;
+            parent = glue_mods[p].parent;
+        }
+        let supers = (0..=depth).map(|_| quote!(super));
+        let top_path = quote!(#(#supers)::*);
+        let mut uses = vec![quote! {
+            #[allow(unused_imports)]
+            use super::*;
+            #[allow(unused_imports)]
+            use #top_path::*;
+        }];
+        if multi_lang {
+            uses.push(quote! {
+                #[allow(unused_imports)]
+                use #top_path::#lang_mod::*;
+                #(#utils_macros)*
+            });
+        }
+        for (child_idx, child) in glue_mods.iter().enumerate() {
+            if child.parent == Some(idx) && need_child[child_idx] {
+                uses.push(re_export(child_idx, quote!(super)));
+            }
+        }
+        let code = code
+            .into_iter()
+            .map(make_items_visible_in_crate)
+            .collect::<Result<Vec<_>>>()?;
+        write!(
+            &mut ret.mods[idx],
+            "{}",
+            quote! {
+                #[allow(unknown_lints, private_interfaces, private_bounds, unused_macros)]
+                pub(crate) mod #lang_mod {
+                    #(#uses)*
+                    #(#code)*
+                }
+            }
+        )
+        .expect("write to String failed, no free mem?");
+        ret.mods[idx].push('\n');
+    }
+    // in case of several languages top level is module of language
+    let top_path = if multi_lang {
+        quote!(super)
+    } else {
+        quote!(self)
+    };
+    for (idx, glue_mod) in glue_mods.iter().enumerate() {
+        if glue_mod.parent.is_none() && need_child[idx] {
+            writeln!(&mut ret.top, "{}", re_export(idx, top_path.clone()))
+                .expect("write to String failed, no free mem?");
+        }
+    }
+    Ok(ret)
+}
+
+/// Insert code of glue modules into output of glue files at positions
+/// recorded during parsing, `code` should start with this output
+pub(crate) fn insert_glue_mods_code(
+    mut code: Vec<u8>,
+    glue_mods: &[GlueMod],
+    mods_code: &[String],
+) -> Vec<u8> {
+    debug_assert_eq!(glue_mods.len(), mods_code.len());
+    let mut mods: Vec<(usize, &String)> = glue_mods
+        .iter()
+        .map(|x| x.code_pos)
+        .zip(mods_code)
+        .collect();
+    // insert from the end, so positions of the rest stay valid
+    mods.sort_by_key(|(pos, _)| std::cmp::Reverse(*pos));
+    for (pos, mod_code) in mods {
+        code.splice(pos..pos, mod_code.bytes());
+    }
+    code
+}
+
+/// Make private named items of `code` and their fields and methods
+/// visible in whole crate, so they can be re-exported and used by other items
+pub(crate) fn make_items_visible_in_crate(code: TokenStream) -> Result<TokenStream> {
+    let mut file: syn::File = syn::parse2(code).map_err(generated_code_err)?;
+    for item in &mut file.items {
+        if let syn::Item::Impl(syn::ItemImpl {
+            trait_: None,
+            items,
+            ..
+        }) = item
+        {
+            for impl_item in items {
+                match impl_item {
+                    syn::ImplItem::Const(syn::ImplItemConst { vis, .. })
+                    | syn::ImplItem::Method(syn::ImplItemMethod { vis, .. })
+                    | syn::ImplItem::Type(syn::ImplItemType { vis, .. }) => {
+                        make_visible_in_crate(vis)
+                    }
+                    _ => {}
+                }
+            }
+            continue;
+        }
+        let vis = match item {
+            syn::Item::Const(syn::ItemConst { ident, vis, .. }) if ident != "_" => vis,
+            syn::Item::Enum(syn::ItemEnum { vis, .. })
+            | syn::Item::Fn(syn::ItemFn { vis, .. })
+            | syn::Item::Mod(syn::ItemMod { vis, .. })
+            | syn::Item::Static(syn::ItemStatic { vis, .. })
+            | syn::Item::Struct(syn::ItemStruct { vis, .. })
+            | syn::Item::Trait(syn::ItemTrait { vis, .. })
+            | syn::Item::Type(syn::ItemType { vis, .. })
+            | syn::Item::Union(syn::ItemUnion { vis, .. }) => vis,
+            _ => continue,
+        };
+        make_visible_in_crate(vis);
+        // helper structs can be constructed by code of other items
+        let fields = match item {
+            syn::Item::Struct(syn::ItemStruct { fields, .. }) => fields.iter_mut().collect(),
+            syn::Item::Union(syn::ItemUnion { fields, .. }) => fields.named.iter_mut().collect(),
+            _ => Vec::new(),
+        };
+        for field in fields {
+            make_visible_in_crate(&mut field.vis);
+        }
+    }
+    Ok(quote!(#file))
+}
+
+fn make_visible_in_crate(vis: &mut syn::Visibility) {
+    if let syn::Visibility::Inherited = vis {
+        *vis = syn::parse_quote!(pub(crate));
+    }
+}
+
+/// The same as `make_items_visible_in_crate`, but for code as text
+pub(crate) fn make_code_visible_in_crate(code: Vec<u8>) -> Result<Vec<u8>> {
+    let code = String::from_utf8(code).map_err(generated_code_err)?;
+    let tokens: TokenStream = syn::parse_str(&code).map_err(generated_code_err)?;
+    Ok(make_items_visible_in_crate(tokens)?
+        .to_string()
+        .into_bytes())
+}
+
+fn generated_code_err<E: std::fmt::Display>(err: E) -> DiagnosticError {
+    DiagnosticError::new_without_src_info(format!(
+        "Can not parse generated code to place it into glue module: {}",
+        err
+    ))
+}
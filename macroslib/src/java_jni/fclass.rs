@@ -1,14 +1,14 @@
 use log::{debug, trace};
 use petgraph::Direction;
 use proc_macro2::{Ident, Span, TokenStream};
-use quote::quote;
+use quote::{quote, ToTokens};
 use rustc_hash::{FxHashMap, FxHashSet};
 use smol_str::SmolStr;
 use std::io::Write;
 use syn::{spanned::Spanned, Type};
 
 use super::{
-    calc_this_type_for_method, java_class_full_name, java_class_name_to_jni, java_code,
+    calc_this_type_for_method, finterface, java_class_full_name, java_class_name_to_jni, java_code,
     map_type::map_type, method_name, rust_code, JavaContext, JavaConverter, JavaForeignTypeInfo,
     JniForeignMethodSignature, INTERNAL_PTR_MARKER, JAVA_RUST_SELF_NAME, REACHABILITY_FENCE_CLASS,
 };
@@ -18,18 +18,25 @@ use crate::{
     file_cache::FileWriteCache,
     namegen::new_unique_name,
     typemap::{
-        ast::{if_result_return_ok_err_types, list_lifetimes, normalize_type},
+        ast::{if_result_return_ok_err_types, list_lifetimes, strip_lifetimes},
         ty::RustType,
         utils::{
-            convert_to_heap_pointer, create_suitable_types_for_constructor_and_self,
+            check_overloads_distinguishable, convert_to_heap_pointer,
+            create_suitable_types_for_constructor_and_self,
             foreign_from_rust_convert_method_output, foreign_to_rust_convert_method_inputs,
             unpack_from_heap_pointer,
         },
         ForeignTypeInfo, FROM_VAR_TEMPLATE, TO_VAR_TEMPLATE, TO_VAR_TYPE_TEMPLATE,
     },
-    types::{ForeignClassInfo, ForeignMethod, MethodAccess, MethodVariant, SelfTypeVariant},
-    JavaConfig, JavaReachabilityFence, CLONE_TRAIT, COPY_TRAIT, SMART_PTR_COPY_TRAIT,
-    WRITE_TO_MEM_FAILED_MSG,
+    types::{
+        c_like_string_literal, future_completer_name, future_value_type, DerivedProtocols,
+        ForeignClassInfo, ForeignConstValue, ForeignMethod, MethodAccess, MethodVariant,
+        SelfTypeVariant, DERIVED_COMPARE_METHOD, DERIVED_EQ_METHOD, DERIVED_HASH_METHOD,
+        DERIVED_TO_STRING_METHOD, FUTURE_COMPLETE_METHOD, FUTURE_FAIL_METHOD,
+        ITERATOR_HAS_NEXT_METHOD, ITERATOR_NEXT_METHOD,
+    },
+    JavaConfig, JavaReachabilityFence, CLONE_TRAIT, COPY_TRAIT, ORD_TRAIT, PROTOCOL_DERIVES,
+    SMART_PTR_COPY_TRAIT, WRITE_TO_MEM_FAILED_MSG,
 };
 
 pub(in crate::java_jni) fn generate(ctx: &mut JavaContext, class: &ForeignClassInfo) -> Result<()> {
@@ -37,11 +44,51 @@ pub(in crate::java_jni) fn generate(ctx: &mut JavaContext, class: &ForeignClassI
         "generate: begin for {}, this_type_for_method {:?}",
         class.name, class.self_desc
     );
+    // Java has no default values of arguments, so emulate them via overloads
+    let class = class.with_default_args_overloads();
+    // closures are passed as objects of functional interfaces
+    for callback in class.closure_callbacks() {
+        if ctx
+            .generated_closure_callbacks
+            .insert(callback.name.to_string().into())
+        {
+            finterface::generate_interface(ctx, &callback)?;
+        }
+    }
+    if class.is_abstract {
+        let f_methods_sign = find_suitable_foreign_types_for_methods(ctx, &class)?;
+        check_overloads(&class, &f_methods_sign)?;
+        return generate_java_abstract_class(
+            ctx,
+            &class,
+            &f_methods_sign,
+            ctx.cfg.null_annotation_package.as_deref(),
+        );
+    }
+    // protocols of derived traits (`equals`, `hashCode`, ...) use private Rust methods
+    let class = &*class.with_derived_protocol_methods()?;
+    // async methods pass result to Java callback, that completes `CompletableFuture`
+    for completer in class.future_completers() {
+        if ctx
+            .generated_future_completers
+            .insert(completer.name.to_string().into())
+        {
+            finterface::generate_interface(ctx, &completer)?;
+        }
+    }
+    let async_methods: Vec<&ForeignMethod> = class
+        .methods
+        .iter()
+        .filter(|m| m.async_output().is_some())
+        .collect();
+    let class = &*class.with_async_methods(ctx.conv_map.async_spawner())?;
 
     let f_methods_sign = find_suitable_foreign_types_for_methods(ctx, class)?;
+    check_overloads(class, &f_methods_sign)?;
     generate_java_code(
         ctx,
         class,
+        &async_methods,
         &f_methods_sign,
         ctx.cfg.null_annotation_package.as_deref(),
     )?;
@@ -70,6 +117,110 @@ pub(in crate::java_jni) fn generate(ctx: &mut JavaContext, class: &ForeignClassI
     Ok(())
 }
 
+/// Overloaded methods should differ by Java types of arguments after erasure
+/// of generic parameters, the same is true for native methods that they call
+fn check_overloads(
+    class: &ForeignClassInfo,
+    f_methods: &[JniForeignMethodSignature],
+) -> Result<()> {
+    let methods = || {
+        class
+            .methods
+            .iter()
+            .zip(f_methods)
+            .filter(|(m, _)| !m.is_dummy_constructor())
+    };
+    check_overloads_distinguishable(
+        class,
+        "Java",
+        methods().map(|(method, f_method)| {
+            let name = match method.variant {
+                MethodVariant::Constructor => class.name.to_string(),
+                MethodVariant::Method(_) | MethodVariant::StaticMethod => method.short_name(),
+            };
+            let args = f_method
+                .input
+                .iter()
+                .map(|arg| java_erased_type(&arg.as_ref().name))
+                .collect();
+            (method, name, args)
+        }),
+    )?;
+    check_overloads_distinguishable(
+        class,
+        "Java",
+        methods().map(|(method, f_method)| {
+            let mut args = Vec::with_capacity(f_method.input.len() + 1);
+            if let MethodVariant::Method(_) = method.variant {
+                args.push("long".to_string());
+            }
+            args.extend(f_method.input.iter().map(|arg| {
+                let type_name = arg
+                    .java_converter
+                    .as_ref()
+                    .map(|x| x.java_transition_type.as_str())
+                    .unwrap_or_else(|| arg.as_ref().name.as_str());
+                java_erased_type(type_name)
+            }));
+            (method, method_name(method, f_method), args)
+        }),
+    )
+}
+
+/// Java type as it is seen by overload resolution: without annotations and generic parameters
+fn java_erased_type(type_name: &str) -> String {
+    let mut ret = String::with_capacity(type_name.len());
+    let mut generic_level = 0;
+    for ch in java_code::filter_null_annotation(type_name).chars() {
+        match ch {
+            '<' => generic_level += 1,
+            '>' => generic_level -= 1,
+            _ if generic_level == 0 && !ch.is_whitespace() => ret.push(ch),
+            _ => {}
+        }
+    }
+    ret
+}
+
+/// Java literal for value of constant with given type
+fn java_const_value(
+    value: &ForeignConstValue,
+    java_type: &str,
+) -> std::result::Result<String, String> {
+    let int_range = |min: i128, max: i128, x: i128, suffix: &str| {
+        if x < min || x > max {
+            Err(format!(
+                "value {} does not fit into Java's {}",
+                x, java_type
+            ))
+        } else {
+            Ok(format!("{}{}", x, suffix))
+        }
+    };
+    match (value, java_type) {
+        (ForeignConstValue::Int(x), "byte") => {
+            int_range(i128::from(i8::MIN), i128::from(i8::MAX), *x, "")
+        }
+        (ForeignConstValue::Int(x), "short") => {
+            int_range(i128::from(i16::MIN), i128::from(i16::MAX), *x, "")
+        }
+        (ForeignConstValue::Int(x), "int") => {
+            int_range(i128::from(i32::MIN), i128::from(i32::MAX), *x, "")
+        }
+        (ForeignConstValue::Int(x), "long") => {
+            int_range(i128::from(i64::MIN), i128::from(i64::MAX), *x, "L")
+        }
+        (ForeignConstValue::Float(x), "float") => Ok(format!("{}f", x)),
+        (ForeignConstValue::Float(x), "double") => Ok(x.clone()),
+        (ForeignConstValue::Bool(x), "boolean") => Ok(x.to_string()),
+        (ForeignConstValue::Str(x), "String") => Ok(c_like_string_literal(x)),
+        _ => Err(format!(
+            "Java type {} is not supported for constant",
+            java_type
+        )),
+    }
+}
+
 struct MethodContext<'a> {
     class: &'a ForeignClassInfo,
     method: &'a ForeignMethod,
@@ -83,6 +234,7 @@ struct MethodContext<'a> {
 fn generate_java_code(
     ctx: &mut JavaContext,
     class: &ForeignClassInfo,
+    async_methods: &[&ForeignMethod],
     methods_sign: &[JniForeignMethodSignature],
     null_annotation_package: Option<&str>,
 ) -> Result<()> {
@@ -91,21 +243,91 @@ fn generate_java_code(
 
     let imports = java_code::get_null_annotation_imports(null_annotation_package, methods_sign);
 
-    let class_doc_comments = java_code::doc_comments_to_java_comments(&class.doc_comments, true);
+    let class_doc_comments = java_code::doc_comments_with_deprecation(
+        &class.doc_comments,
+        class.deprecated.as_ref(),
+        true,
+    );
+    let protocols = class.derived_protocols()?;
+    let iter_item_type = if class.iterator_item().is_some() {
+        class
+            .methods
+            .iter()
+            .zip(methods_sign)
+            .find(|(m, _)| m.short_name() == ITERATOR_NEXT_METHOD)
+            .map(|(_, f_method)| {
+                let item_type = java_code::filter_null_annotation(&f_method.output.base.name);
+                java_code::boxed_primitive_type(item_type.trim()).to_string()
+            })
+    } else {
+        None
+    };
+    let mut interfaces = vec![];
+    if protocols.cmp == Some(ORD_TRAIT) {
+        interfaces.push(format!("Comparable<{}>", class.name));
+    }
+    if let Some(ref item_type) = iter_item_type {
+        interfaces.push(format!("java.util.Iterator<{}>", item_type));
+        interfaces.push(format!("Iterable<{}>", item_type));
+    }
+    let implements = if !interfaces.is_empty() {
+        format!(" implements {}", interfaces.join(", "))
+    } else {
+        String::new()
+    };
     writeln!(
         file,
         r#"// Automatically generated by flapigen
 package {package_name};
 {imports}
 {doc_comments}
-public final class {class_name} {{"#,
+{class_access}final class {class_name}{extends}{implements} {{"#,
         package_name = ctx.cfg.package_name,
         imports = imports,
+        // internal class is visible only for generated code in the same package
+        class_access = if class.is_internal { "" } else { "public " },
         class_name = class.name,
+        extends = java_extends(class),
+        implements = implements,
         doc_comments = class_doc_comments,
     )
     .expect(WRITE_TO_MEM_FAILED_MSG);
 
+    for fconst in &class.consts {
+        fconst.check_type()?;
+        let value_check = fconst.rust_code_to_check_value();
+        if !value_check.is_empty() {
+            ctx.rust_code.push(value_check);
+        }
+        let rty = ctx
+            .conv_map
+            .find_or_alloc_rust_type(&fconst.ty, fconst.src_id);
+        let fti = map_type(
+            ctx,
+            &rty,
+            Direction::Outgoing,
+            (fconst.src_id, fconst.ty.span()),
+        )?;
+        let java_type = java_code::filter_null_annotation(&fti.base.name);
+        let java_type = java_type.trim();
+        let value = java_const_value(&fconst.value, java_type)
+            .map_err(|err| DiagnosticError::new(fconst.src_id, fconst.span(), err))?;
+        let mut doc_comments =
+            java_code::doc_comments_to_java_comments(&fconst.doc_comments, false);
+        if !doc_comments.is_empty() {
+            doc_comments.push('\n');
+        }
+        writeln!(
+            file,
+            "{doc_comments}    public static final {java_type} {name} = {value};",
+            doc_comments = doc_comments,
+            java_type = java_type,
+            name = fconst.name,
+            value = value,
+        )
+        .expect(WRITE_TO_MEM_FAILED_MSG);
+    }
+
     let mut have_methods = false;
     let mut have_constructor = false;
 
@@ -113,7 +335,11 @@ public final class {class_name} {{"#,
         write!(
             &mut file,
             "{doc_comments}",
-            doc_comments = java_code::doc_comments_to_java_comments(&method.doc_comments, false)
+            doc_comments = java_code::doc_comments_with_deprecation(
+                &method.doc_comments,
+                method.deprecated.as_ref(),
+                false
+            )
         )
         .expect(WRITE_TO_MEM_FAILED_MSG);
 
@@ -438,6 +664,19 @@ May be you need to use `private constructor = empty;` syntax?",
             ),
         ));
     }
+    generate_java_protocols(&mut file, class, protocols);
+    if let Some(ref item_type) = iter_item_type {
+        generate_java_iterator(&mut file, item_type);
+    }
+    generate_java_async_methods(
+        ctx,
+        &mut file,
+        class,
+        async_methods,
+        methods_sign,
+        null_annotation_package.is_some(),
+    )?;
+
     if have_constructor {
         writeln!(
             file,
@@ -490,7 +729,11 @@ May be you need to use `private constructor = empty;` syntax?",
     extend_foreign_class(
         class,
         &mut cnt,
-        &[CLONE_TRAIT, COPY_TRAIT, SMART_PTR_COPY_TRAIT],
+        &[CLONE_TRAIT, COPY_TRAIT, SMART_PTR_COPY_TRAIT]
+            .iter()
+            .chain(PROTOCOL_DERIVES.iter())
+            .copied()
+            .collect::<Vec<_>>(),
         ctx.class_ext_handlers,
         ctx.method_ext_handlers,
     )?;
@@ -501,6 +744,329 @@ May be you need to use `private constructor = empty;` syntax?",
     Ok(())
 }
 
+fn java_extends(class: &ForeignClassInfo) -> String {
+    match class.base_class {
+        Some(ref base) => format!(" extends {}", base),
+        None => String::new(),
+    }
+}
+
+/// Abstract class has only abstract methods, derived classes
+/// implement them via calls of Rust code, so there is no Rust code for it
+fn generate_java_abstract_class(
+    ctx: &mut JavaContext,
+    class: &ForeignClassInfo,
+    methods_sign: &[JniForeignMethodSignature],
+    null_annotation_package: Option<&str>,
+) -> Result<()> {
+    let path = ctx.cfg.output_dir.join(format!("{}.java", class.name));
+    let mut file = FileWriteCache::new(&path, ctx.generated_foreign_files);
+
+    let imports = java_code::get_null_annotation_imports(null_annotation_package, methods_sign);
+    let class_doc_comments = java_code::doc_comments_with_deprecation(
+        &class.doc_comments,
+        class.deprecated.as_ref(),
+        true,
+    );
+    writeln!(
+        file,
+        r#"// Automatically generated by flapigen
+package {package_name};
+{imports}
+{doc_comments}
+public abstract class {class_name}{extends} {{"#,
+        package_name = ctx.cfg.package_name,
+        imports = imports,
+        class_name = class.name,
+        extends = java_extends(class),
+        doc_comments = class_doc_comments,
+    )
+    .expect(WRITE_TO_MEM_FAILED_MSG);
+
+    for (method, f_method) in class.methods.iter().zip(methods_sign) {
+        write!(
+            &mut file,
+            "{doc_comments}",
+            doc_comments = java_code::doc_comments_with_deprecation(
+                &method.doc_comments,
+                method.deprecated.as_ref(),
+                false
+            )
+        )
+        .expect(WRITE_TO_MEM_FAILED_MSG);
+        let may_return_error = match method.fn_decl.output {
+            syn::ReturnType::Default => false,
+            syn::ReturnType::Type(_, ref ptype) => {
+                let ret_rust_ty = ctx.conv_map.find_or_alloc_rust_type(ptype, class.src_id);
+                if_result_return_ok_err_types(&ret_rust_ty).is_some()
+            }
+        };
+        writeln!(
+            file,
+            r#"
+    {method_access} abstract {ret_type} {method_name}({args_with_types}){exception_spec};"#,
+            method_access = match method.access {
+                MethodAccess::Private => "private",
+                MethodAccess::Public => "public",
+                MethodAccess::Protected => "protected",
+            },
+            ret_type = f_method.output.base.name,
+            method_name = method.short_name(),
+            args_with_types = java_code::args_with_java_types(
+                f_method,
+                method.arg_names_without_self(),
+                java_code::ArgsFormatFlags::EXTERNAL,
+                null_annotation_package.is_some(),
+            ),
+            exception_spec = if may_return_error {
+                " throws Exception"
+            } else {
+                ""
+            },
+        )
+        .expect(WRITE_TO_MEM_FAILED_MSG);
+    }
+
+    file.write_all(class.foreign_code.as_bytes())
+        .expect(WRITE_TO_MEM_FAILED_MSG);
+    write!(file, "}}").expect(WRITE_TO_MEM_FAILED_MSG);
+    file.update_file_if_necessary()
+        .map_err(DiagnosticError::map_any_err_to_our_err)?;
+    Ok(())
+}
+
+/// `equals`, `hashCode`, `toString` and `compareTo` via private methods,
+/// see `ForeignClassInfo::with_derived_protocol_methods`.
+/// Comparison of object with itself is handled without call of Rust code,
+/// because of `self_type` like `Arc<Mutex<T>>` it would be deadlock.
+fn generate_java_protocols(
+    file: &mut FileWriteCache,
+    class: &ForeignClassInfo,
+    protocols: DerivedProtocols,
+) {
+    if protocols.eq {
+        writeln!(
+            file,
+            r#"
+    @Override
+    public boolean equals(Object obj) {{
+        if (this == obj)
+            return true;
+        if (!(obj instanceof {class_name}))
+            return false;
+        {class_name} other = ({class_name}) obj;
+        if ({rust_self_name} == other.{rust_self_name})
+            return true;
+        return {eq}(other);
+    }}"#,
+            class_name = class.name,
+            rust_self_name = JAVA_RUST_SELF_NAME,
+            eq = DERIVED_EQ_METHOD,
+        )
+        .expect(WRITE_TO_MEM_FAILED_MSG);
+    }
+    if protocols.hash {
+        writeln!(
+            file,
+            r#"
+    @Override
+    public int hashCode() {{
+        long h = {hash}();
+        return (int) (h ^ (h >>> 32));
+    }}"#,
+            hash = DERIVED_HASH_METHOD,
+        )
+        .expect(WRITE_TO_MEM_FAILED_MSG);
+    }
+    if protocols.to_string.is_some() {
+        writeln!(
+            file,
+            r#"
+    @Override
+    public String toString() {{
+        return {to_string}();
+    }}"#,
+            to_string = DERIVED_TO_STRING_METHOD,
+        )
+        .expect(WRITE_TO_MEM_FAILED_MSG);
+    }
+    // `Comparable` requires total order
+    if protocols.cmp == Some(ORD_TRAIT) {
+        writeln!(
+            file,
+            r#"
+    @Override
+    public int compareTo({class_name} other) {{
+        if ({rust_self_name} == other.{rust_self_name})
+            return 0;
+        return {compare}(other);
+    }}"#,
+            class_name = class.name,
+            rust_self_name = JAVA_RUST_SELF_NAME,
+            compare = DERIVED_COMPARE_METHOD,
+        )
+        .expect(WRITE_TO_MEM_FAILED_MSG);
+    }
+}
+
+/// `Iterator` and `Iterable` (for usage in for-each loop) for class that holds
+/// Rust iterator, see `ForeignClassInfo::iterator_classes`.
+/// Rust iterator can not be restarted, so `Iterable` is single-pass
+fn generate_java_iterator(file: &mut FileWriteCache, item_type: &str) {
+    writeln!(
+        file,
+        r#"
+    private boolean iteratorTaken;
+
+    @Override
+    public boolean hasNext() {{
+        return {has_next}();
+    }}
+
+    @Override
+    public {item_type} next() {{
+        if (!{has_next}())
+            throw new java.util.NoSuchElementException();
+        return {next}();
+    }}
+
+    /**
+     * Elements can be traversed only once, so only one call is allowed
+     * @throws IllegalStateException if iterator was already requested
+     */
+    @Override
+    public java.util.Iterator<{item_type}> iterator() {{
+        if (iteratorTaken)
+            throw new IllegalStateException("elements of Rust iterator can be traversed only once");
+        iteratorTaken = true;
+        return this;
+    }}"#,
+        item_type = item_type,
+        has_next = ITERATOR_HAS_NEXT_METHOD,
+        next = ITERATOR_NEXT_METHOD,
+    )
+    .expect(WRITE_TO_MEM_FAILED_MSG);
+}
+
+/// Public methods that return `CompletableFuture` for async methods,
+/// they call private methods generated by `ForeignClassInfo::with_async_methods`
+fn generate_java_async_methods(
+    ctx: &mut JavaContext,
+    file: &mut FileWriteCache,
+    class: &ForeignClassInfo,
+    async_methods: &[&ForeignMethod],
+    methods_sign: &[JniForeignMethodSignature],
+    use_null_annotation: bool,
+) -> Result<()> {
+    let start_idx = class.methods.len() - async_methods.len();
+    for (i, method) in async_methods.iter().enumerate() {
+        let start_method = &class.methods[start_idx + i];
+        let f_method = &methods_sign[start_idx + i];
+        let value = method.async_output().and_then(future_value_type);
+        let (value_type, complete_args, complete_value) = match value {
+            Some(value) => {
+                let value_rty = ctx.conv_map.find_or_alloc_rust_type(value, class.src_id);
+                let value_fti = map_type(
+                    ctx,
+                    &value_rty,
+                    Direction::Outgoing,
+                    (class.src_id, value.span()),
+                )?;
+                let value_type = java_code::filter_null_annotation(&value_fti.base.name);
+                let value_type = java_code::boxed_primitive_type(value_type.trim()).to_string();
+                let complete_sign = JniForeignMethodSignature {
+                    output: ForeignTypeInfo {
+                        name: "void".into(),
+                        correspoding_rust_type: ctx
+                            .conv_map
+                            .find_or_alloc_rust_type_no_src_id(&parse_type! { () }),
+                    }
+                    .into(),
+                    input: vec![value_fti],
+                };
+                let complete_args = java_code::args_with_java_types(
+                    &complete_sign,
+                    ["value"].iter().copied(),
+                    java_code::ArgsFormatFlags::EXTERNAL,
+                    use_null_annotation,
+                );
+                (value_type, complete_args, "value")
+            }
+            None => ("Void".to_string(), String::new(), "null"),
+        };
+        let known_names: FxHashSet<SmolStr> =
+            method.arg_names_without_self().map(|x| x.into()).collect();
+        let future_name = new_unique_name(&known_names, "future");
+        let method_access = match method.access {
+            MethodAccess::Private => "private",
+            MethodAccess::Public => "public",
+            MethodAccess::Protected => "protected",
+        };
+        let method_kind = match method.variant {
+            MethodVariant::StaticMethod => "static",
+            MethodVariant::Method(_) | MethodVariant::Constructor => "final",
+        };
+        let args_with_types = java_code::args_with_java_types(
+            f_method,
+            method.arg_names_without_self(),
+            java_code::ArgsFormatFlags::EXTERNAL,
+            use_null_annotation,
+        );
+        let args = method
+            .arg_names_without_self()
+            .fold(String::new(), |mut acc, x| {
+                acc.push_str(x);
+                acc.push_str(", ");
+                acc
+            });
+        let non_null = if use_null_annotation { "@NonNull " } else { "" };
+        write!(
+            file,
+            "{doc_comments}",
+            doc_comments = java_code::doc_comments_with_deprecation(
+                &method.doc_comments,
+                method.deprecated.as_ref(),
+                false
+            )
+        )
+        .expect(WRITE_TO_MEM_FAILED_MSG);
+        writeln!(
+            file,
+            r#"
+    {method_access} {method_kind} {non_null}java.util.concurrent.CompletableFuture<{value_type}> {method_name}({args_with_types}) {{
+        java.util.concurrent.CompletableFuture<{value_type}> {future} = new java.util.concurrent.CompletableFuture<{value_type}>();
+        {start_method}({args}new {completer}() {{
+            @Override
+            public void {complete}({complete_args}) {{
+                {future}.complete({complete_value});
+            }}
+            @Override
+            public void {fail}({non_null}String error) {{
+                {future}.completeExceptionally(new Exception(error));
+            }}
+        }});
+        return {future};
+    }}"#,
+            method_access = method_access,
+            method_kind = method_kind,
+            non_null = non_null,
+            value_type = value_type,
+            method_name = method.short_name(),
+            args_with_types = args_with_types.trim_end_matches(", "),
+            future = future_name,
+            start_method = start_method.short_name(),
+            args = args,
+            completer = future_completer_name(value, method.span()),
+            complete = FUTURE_COMPLETE_METHOD,
+            complete_args = complete_args,
+            complete_value = complete_value,
+            fail = FUTURE_FAIL_METHOD,
+        )
+        .expect(WRITE_TO_MEM_FAILED_MSG);
+    }
+    Ok(())
+}
+
 fn generate_rust_code(
     ctx: &mut JavaContext,
     class: &ForeignClassInfo,
@@ -649,9 +1215,14 @@ May be you need to use `private constructor = empty;` syntax?",
             buf
         };
 
+        // not `normalize_type`, because of it strips `dyn`
         let real_output_typename = match method.fn_decl.output {
-            syn::ReturnType::Default => "()",
-            syn::ReturnType::Type(_, ref ty) => normalize_type(&*ty),
+            syn::ReturnType::Default => "()".to_string(),
+            syn::ReturnType::Type(_, ref ty) => {
+                let mut ty: Type = (**ty).clone();
+                strip_lifetimes(&mut ty);
+                ty.into_token_stream().to_string()
+            }
         };
 
         let method_ctx = MethodContext {
@@ -987,7 +1558,7 @@ fn generate_method(
     let from_ty = ctx
         .conv_map
         .find_or_alloc_rust_type(&from_ty, mc.class.src_id);
-    let this_type_ref = from_ty.normalized_name.as_str();
+    let this_type_ref = from_ty.typename();
     let to_ty = ctx
         .conv_map
         .find_or_alloc_rust_type(&to_ty, mc.class.src_id);
@@ -1,11 +1,15 @@
 use log::trace;
+use petgraph::Direction;
 use proc_macro2::{Span, TokenStream};
 use quote::quote;
-use std::{io::Write, rc::Rc};
-use syn::{Ident, Type};
+use smol_str::SmolStr;
+use std::{fmt::Write as FmtWrite, io::Write, rc::Rc};
+use syn::{spanned::Spanned, Ident, Type};
 
 use super::{
-    java_class_full_name, java_class_name_to_jni, java_code::doc_comments_to_java_comments,
+    fclass, java_class_full_name, java_class_name_to_jni,
+    java_code::{doc_comments_to_java_comments, doc_comments_with_deprecation},
+    map_type::map_type,
     map_write_err, JavaContext,
 };
 use crate::{
@@ -16,7 +20,7 @@ use crate::{
         ty::{ForeignConversationIntermediate, ForeignConversationRule, ForeignTypeS},
         RustTypeIdx, TypeConvCode, TypeConvEdge, FROM_VAR_TEMPLATE, TO_VAR_TEMPLATE,
     },
-    types::ForeignEnumInfo,
+    types::{ForeignEnumInfo, ENUM_RAW_FREE, ENUM_RAW_TAG},
     WRITE_TO_MEM_FAILED_MSG,
 };
 
@@ -28,6 +32,9 @@ pub(in crate::java_jni) fn generate_enum(
 ) -> Result<()> {
     let enum_name = &fenum.name;
     trace!("generate_enum: enum {}", enum_name);
+    if !fenum.is_c_like() {
+        return generate_data_enum(ctx, fenum);
+    }
     if (fenum.items.len() as u64) >= (i32::max_value() as u64) {
         return Err(DiagnosticError::new(
             fenum.src_id,
@@ -35,6 +42,17 @@ pub(in crate::java_jni) fn generate_enum(
             "Too many items in enum",
         ));
     }
+    let values = fenum.items_values_as_u32()?;
+    if fenum.is_flags() {
+        return generate_flags_enum(ctx, fenum, &values);
+    }
+    if let Some(idx) = values.iter().position(|x| *x as i32 == -1) {
+        return Err(DiagnosticError::new(
+            fenum.src_id,
+            fenum.items[idx].name.span(),
+            "value -1 is reserved to pass None of Option<enum>",
+        ));
+    }
     let enum_ti: Type = parse_ty_with_given_span(&enum_name.to_string(), fenum.name.span())
         .map_err(|err| DiagnosticError::from_syn_err(fenum.src_id, err))?;
     let enum_rty = ctx.conv_map.find_or_alloc_rust_type_that_implements(
@@ -43,9 +61,9 @@ pub(in crate::java_jni) fn generate_enum(
         fenum.src_id,
     );
 
-    generate_java_code_for_enum(ctx, fenum)
+    generate_java_code_for_enum(ctx, fenum, &values)
         .map_err(|err| DiagnosticError::new(fenum.src_id, fenum.span(), &err))?;
-    generate_rust_code_for_enum(ctx, fenum)?;
+    generate_rust_code_for_enum(ctx, fenum, &values)?;
 
     let jint_rty = ctx.conv_map.ty_to_rust_type(&parse_type! { jint });
 
@@ -97,9 +115,347 @@ pub(in crate::java_jni) fn generate_enum(
     Ok(())
 }
 
+/// Flags are mapped to Java enum plus conversation of bits to/from `EnumSet`,
+/// so in Java code value of flags type is `EnumSet` of items
+fn generate_flags_enum(
+    ctx: &mut JavaContext,
+    fenum: &ForeignEnumInfo,
+    values: &[u32],
+) -> Result<()> {
+    let enum_ti: Type = parse_ty_with_given_span(&fenum.name.to_string(), fenum.name.span())
+        .map_err(|err| DiagnosticError::from_syn_err(fenum.src_id, err))?;
+    let enum_rty = ctx.conv_map.find_or_alloc_rust_type(&enum_ti, fenum.src_id);
+    let jint_rty = ctx
+        .conv_map
+        .find_or_alloc_rust_type_no_src_id(&parse_type! { jint });
+    ctx.conv_map.add_conversation_rule(
+        enum_rty.to_idx(),
+        jint_rty.to_idx(),
+        TypeConvEdge::new(
+            TypeConvCode::new2(
+                format!(
+                    "let mut {to}: jint = {from}.bits() as jint;",
+                    to = TO_VAR_TEMPLATE,
+                    from = FROM_VAR_TEMPLATE,
+                ),
+                invalid_src_id_span(),
+            ),
+            None,
+        ),
+    );
+    ctx.conv_map.add_conversation_rule(
+        jint_rty.to_idx(),
+        enum_rty.to_idx(),
+        TypeConvEdge::new(
+            TypeConvCode::new2(
+                format!(
+                    "let mut {to}: {enum_name} = {enum_name}::from_bits_truncate({from} as _);",
+                    to = TO_VAR_TEMPLATE,
+                    from = FROM_VAR_TEMPLATE,
+                    enum_name = fenum.name,
+                ),
+                invalid_src_id_span(),
+            ),
+            None,
+        ),
+    );
+    generate_java_code_for_enum(ctx, fenum, values)
+        .map_err(|err| DiagnosticError::new(fenum.src_id, fenum.span(), &err))?;
+    let values_check = fenum.rust_code_to_check_items_values();
+    if !values_check.is_empty() {
+        ctx.rust_code.push(values_check);
+    }
+
+    let set_type = format!("java.util.EnumSet<{}>", fenum.name);
+    let enum_ftype = ForeignTypeS {
+        name: TypeName::new(set_type.clone(), (fenum.src_id, fenum.name.span())),
+        provides_by_module: vec![],
+        into_from_rust: Some(ForeignConversationRule {
+            rust_ty: enum_rty.to_idx(),
+            intermediate: Some(ForeignConversationIntermediate {
+                input_to_output: false,
+                intermediate_ty: jint_rty.to_idx(),
+                conv_code: Rc::new(TypeConvCode::new(
+                    format!(
+                        "        {set_type} {out} = {enum_name}.fromBits({var});",
+                        set_type = set_type,
+                        out = TO_VAR_TEMPLATE,
+                        enum_name = fenum.name,
+                        var = FROM_VAR_TEMPLATE
+                    ),
+                    invalid_src_id_span(),
+                )),
+            }),
+        }),
+        from_into_rust: Some(ForeignConversationRule {
+            rust_ty: enum_rty.to_idx(),
+            intermediate: Some(ForeignConversationIntermediate {
+                input_to_output: false,
+                intermediate_ty: jint_rty.to_idx(),
+                conv_code: Rc::new(TypeConvCode::new(
+                    format!(
+                        "        int {out} = {enum_name}.toBits({var});",
+                        out = TO_VAR_TEMPLATE,
+                        enum_name = fenum.name,
+                        var = FROM_VAR_TEMPLATE
+                    ),
+                    invalid_src_id_span(),
+                )),
+            }),
+        }),
+        name_prefix: None,
+    };
+    ctx.conv_map.alloc_foreign_type(enum_ftype)?;
+    Ok(())
+}
+
+/// Enum with data in variants is mapped to abstract class with nested
+/// final class per variant. Values are passed as pointers to boxed Rust enum,
+/// see `ForeignEnumInfo::raw_class`
+fn generate_data_enum(ctx: &mut JavaContext, fenum: &ForeignEnumInfo) -> Result<()> {
+    let raw_class = fenum.raw_class();
+    fclass::generate(ctx, &raw_class)?;
+
+    let enum_ti: Type = parse_ty_with_given_span(&fenum.name.to_string(), fenum.name.span())
+        .map_err(|err| DiagnosticError::from_syn_err(fenum.src_id, err))?;
+    let enum_rty = ctx.conv_map.find_or_alloc_rust_type(&enum_ti, fenum.src_id);
+    let jlong_rty = ctx
+        .conv_map
+        .find_or_alloc_rust_type_no_src_id(&parse_type! { jlong });
+    ctx.conv_map.add_conversation_rule(
+        enum_rty.to_idx(),
+        jlong_rty.to_idx(),
+        TypeConvEdge::new(
+            TypeConvCode::new2(
+                format!(
+                    "let mut {to}: jlong = Box::into_raw(Box::new({from})) as jlong;",
+                    to = TO_VAR_TEMPLATE,
+                    from = FROM_VAR_TEMPLATE,
+                ),
+                invalid_src_id_span(),
+            ),
+            None,
+        ),
+    );
+    ctx.conv_map.add_conversation_rule(
+        jlong_rty.to_idx(),
+        enum_rty.to_idx(),
+        TypeConvEdge::new(
+            TypeConvCode::new2(
+                format!(
+                    "let mut {to}: {enum_name} = *unsafe {{ Box::from_raw({from} as *mut {enum_name}) }};",
+                    to = TO_VAR_TEMPLATE,
+                    from = FROM_VAR_TEMPLATE,
+                    enum_name = fenum.name,
+                ),
+                invalid_src_id_span(),
+            ),
+            None,
+        ),
+    );
+
+    let mut fields_types = Vec::with_capacity(fenum.items.len());
+    for item in &fenum.items {
+        let mut item_fields = vec![];
+        for (name, ty) in item.fields() {
+            let rty = ctx.conv_map.find_or_alloc_rust_type(ty, fenum.src_id);
+            let fti = map_type(ctx, &rty, Direction::Outgoing, (fenum.src_id, ty.span()))?;
+            item_fields.push((name, fti.base.name));
+        }
+        fields_types.push(item_fields);
+    }
+    generate_java_code_for_data_enum(ctx, fenum, &fields_types)
+        .map_err(|err| DiagnosticError::new(fenum.src_id, fenum.span(), &err))?;
+
+    let enum_ftype = ForeignTypeS {
+        name: TypeName::new(fenum.name.to_string(), (fenum.src_id, fenum.name.span())),
+        provides_by_module: vec![],
+        into_from_rust: Some(ForeignConversationRule {
+            rust_ty: enum_rty.to_idx(),
+            intermediate: Some(ForeignConversationIntermediate {
+                input_to_output: false,
+                intermediate_ty: jlong_rty.to_idx(),
+                conv_code: Rc::new(TypeConvCode::new(
+                    format!(
+                        "        {enum_name} {out} = {enum_name}.fromRaw({var});",
+                        out = TO_VAR_TEMPLATE,
+                        enum_name = fenum.name,
+                        var = FROM_VAR_TEMPLATE
+                    ),
+                    invalid_src_id_span(),
+                )),
+            }),
+        }),
+        from_into_rust: Some(ForeignConversationRule {
+            rust_ty: enum_rty.to_idx(),
+            intermediate: Some(ForeignConversationIntermediate {
+                input_to_output: false,
+                intermediate_ty: jlong_rty.to_idx(),
+                conv_code: Rc::new(TypeConvCode::new(
+                    format!(
+                        "        long {out} = {var}.toRaw();",
+                        out = TO_VAR_TEMPLATE,
+                        var = FROM_VAR_TEMPLATE
+                    ),
+                    invalid_src_id_span(),
+                )),
+            }),
+        }),
+        name_prefix: None,
+    };
+    ctx.conv_map.alloc_foreign_type(enum_ftype)?;
+    Ok(())
+}
+
+fn generate_java_code_for_data_enum(
+    ctx: &mut JavaContext,
+    fenum: &ForeignEnumInfo,
+    fields_types: &[Vec<(Ident, SmolStr)>],
+) -> std::result::Result<(), String> {
+    let path = ctx.cfg.output_dir.join(format!("{}.java", fenum.name));
+    let mut file = FileWriteCache::new(&path, ctx.generated_foreign_files);
+    let enum_doc_comments = doc_comments_to_java_comments(&fenum.doc_comments, true);
+    let raw_class = fenum.raw_class_name();
+    let mut imports = String::new();
+    if let Some(null_annotation_package) = ctx.cfg.null_annotation_package.as_ref() {
+        for annotation in &["NonNull", "Nullable"] {
+            let used = fields_types
+                .iter()
+                .flatten()
+                .any(|(_, ty)| ty.contains(&format!("@{}", annotation)));
+            if used {
+                writeln!(
+                    &mut imports,
+                    "import {}.{};",
+                    null_annotation_package, annotation
+                )
+                .expect(WRITE_TO_MEM_FAILED_MSG);
+            }
+        }
+    }
+    write!(
+        file,
+        r#"// Automatically generated by flapigen
+package {package_name};
+{imports}
+{doc_comments}
+public abstract class {enum_name} {{
+    private {enum_name}() {{}}
+
+    /*package*/ abstract long toRaw();
+
+    /*package*/ static {enum_name} fromRaw(long raw) {{
+        try {{
+            switch ({raw_class}.{tag}(raw)) {{"#,
+        package_name = ctx.cfg.package_name,
+        imports = imports,
+        enum_name = fenum.name,
+        doc_comments = enum_doc_comments,
+        raw_class = raw_class,
+        tag = ENUM_RAW_TAG,
+    )
+    .expect(WRITE_TO_MEM_FAILED_MSG);
+
+    for (i, (item, fields)) in fenum.items.iter().zip(fields_types).enumerate() {
+        let args = fields
+            .iter()
+            .map(|(name, _)| format!("{}.{}(raw)", raw_class, item.raw_getter_name(name)))
+            .collect::<Vec<_>>()
+            .join(", ");
+        write!(
+            file,
+            r#"
+            case {index}: return new {item_name}({args});"#,
+            index = i,
+            item_name = item.name,
+            args = args,
+        )
+        .expect(WRITE_TO_MEM_FAILED_MSG);
+    }
+    write!(
+        file,
+        r#"
+            default: throw new Error("Invalid tag for enum {enum_name}");
+            }}
+        }} finally {{
+            {raw_class}.{free}(raw);
+        }}
+    }}
+"#,
+        enum_name = fenum.name,
+        raw_class = raw_class,
+        free = ENUM_RAW_FREE,
+    )
+    .expect(WRITE_TO_MEM_FAILED_MSG);
+
+    for (item, fields) in fenum.items.iter().zip(fields_types) {
+        let mut doc_comments =
+            doc_comments_with_deprecation(&item.doc_comments, item.deprecated.as_ref(), false);
+        if !doc_comments.is_empty() {
+            if !doc_comments.ends_with('\n') {
+                doc_comments.push('\n');
+            }
+            doc_comments.push_str("    ");
+        }
+        let params = fields
+            .iter()
+            .map(|(name, ty)| format!("{} {}", ty, name))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let args = fields
+            .iter()
+            .map(|(name, _)| name.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        write!(
+            file,
+            r#"
+    {doc_comments}public static final class {item_name} extends {enum_name} {{
+        public {item_name}({params}) {{"#,
+            doc_comments = doc_comments,
+            item_name = item.name,
+            enum_name = fenum.name,
+            params = params,
+        )
+        .expect(WRITE_TO_MEM_FAILED_MSG);
+        for (name, _) in fields {
+            write!(
+                file,
+                r#"
+            this.{name} = {name};"#,
+                name = name
+            )
+            .expect(WRITE_TO_MEM_FAILED_MSG);
+        }
+        write!(
+            file,
+            r#"
+        }}
+        @Override
+        /*package*/ long toRaw() {{
+            return {raw_class}.{constructor}({args});
+        }}
+"#,
+            raw_class = raw_class,
+            constructor = item.raw_constructor_name(),
+            args = args,
+        )
+        .expect(WRITE_TO_MEM_FAILED_MSG);
+        for (name, ty) in fields {
+            writeln!(file, "        public final {} {};", ty, name).expect(WRITE_TO_MEM_FAILED_MSG);
+        }
+        writeln!(file, "    }}").expect(WRITE_TO_MEM_FAILED_MSG);
+    }
+    writeln!(file, "}}").expect(WRITE_TO_MEM_FAILED_MSG);
+
+    file.update_file_if_necessary().map_err(&map_write_err)?;
+    Ok(())
+}
+
 fn generate_java_code_for_enum(
     ctx: &mut JavaContext,
     fenum: &ForeignEnumInfo,
+    values: &[u32],
 ) -> std::result::Result<(), String> {
     let path = ctx.cfg.output_dir.join(format!("{}.java", fenum.name));
     let mut file = FileWriteCache::new(&path, ctx.generated_foreign_files);
@@ -118,7 +474,8 @@ public enum {enum_name} {{"#,
     .expect(WRITE_TO_MEM_FAILED_MSG);
 
     for (i, item) in fenum.items.iter().enumerate() {
-        let mut doc_comments = doc_comments_to_java_comments(&item.doc_comments, false);
+        let mut doc_comments =
+            doc_comments_with_deprecation(&item.doc_comments, item.deprecated.as_ref(), false);
         if !doc_comments.is_empty() {
             if !doc_comments.ends_with('\n') {
                 doc_comments.push('\n');
@@ -127,9 +484,9 @@ public enum {enum_name} {{"#,
         }
         writeln!(
             file,
-            "    {doc_comments}{item_name}({index}){separator}",
+            "    {doc_comments}{item_name}({value}){separator}",
             item_name = item.name,
-            index = i,
+            value = values[i] as i32,
             doc_comments = doc_comments,
             separator = if i == fenum.items.len() - 1 { ';' } else { ',' },
         )
@@ -143,19 +500,54 @@ public enum {enum_name} {{"#,
     {enum_name}(int value) {{
         this.value = value;
     }}
-    public final int getValue() {{ return value; }}
+    public final int getValue() {{ return value; }}"#,
+        enum_name = fenum.name
+    )
+    .expect(WRITE_TO_MEM_FAILED_MSG);
+
+    if fenum.is_flags() {
+        writeln!(
+            file,
+            r#"
+    public static java.util.EnumSet<{enum_name}> fromBits(int bits) {{
+        java.util.EnumSet<{enum_name}> ret = java.util.EnumSet.noneOf({enum_name}.class);
+        for ({enum_name} x : values()) {{
+            if (x.value != 0 && (bits & x.value) == x.value) {{
+                ret.add(x);
+            }}
+        }}
+        return ret;
+    }}
+    public static int toBits(java.util.Set<{enum_name}> set) {{
+        int bits = 0;
+        for ({enum_name} x : set) {{
+            bits |= x.value;
+        }}
+        return bits;
+    }}
+}}"#,
+            enum_name = fenum.name
+        )
+        .expect(WRITE_TO_MEM_FAILED_MSG);
+        file.update_file_if_necessary().map_err(&map_write_err)?;
+        return Ok(());
+    }
+
+    write!(
+        file,
+        r#"
     /*package*/ static {enum_name} fromInt(int x) {{
         switch (x) {{"#,
         enum_name = fenum.name
     )
     .expect(WRITE_TO_MEM_FAILED_MSG);
 
-    for (i, item) in fenum.items.iter().enumerate() {
+    for (item, value) in fenum.items.iter().zip(values) {
         write!(
             file,
             r#"
-            case {index}: return {item_name};"#,
-            index = i,
+            case {value}: return {item_name};"#,
+            value = *value as i32,
             item_name = item.name
         )
         .expect(WRITE_TO_MEM_FAILED_MSG);
@@ -176,15 +568,18 @@ public enum {enum_name} {{"#,
     Ok(())
 }
 
-fn generate_rust_code_for_enum(ctx: &mut JavaContext, fenum: &ForeignEnumInfo) -> Result<()> {
+fn generate_rust_code_for_enum(
+    ctx: &mut JavaContext,
+    fenum: &ForeignEnumInfo,
+    values: &[u32],
+) -> Result<()> {
     let mut arms_to_jint = Vec::with_capacity(fenum.items.len());
     let mut arms_from_jint = Vec::with_capacity(fenum.items.len());
-    assert!((fenum.items.len() as u64) <= u64::from(i32::max_value() as u32));
-    for (i, item) in fenum.items.iter().enumerate() {
+    for (item, value) in fenum.items.iter().zip(values) {
         let item_name = &item.rust_name;
-        let idx = i as i32;
-        arms_to_jint.push(quote! { #item_name => #idx });
-        arms_from_jint.push(quote! { #idx => #item_name });
+        let value = *value as i32;
+        arms_to_jint.push(quote! { #item_name => #value });
+        arms_from_jint.push(quote! { #value => #item_name });
     }
 
     let rust_enum_name = &fenum.name;
@@ -206,6 +601,10 @@ fn generate_rust_code_for_enum(ctx: &mut JavaContext, fenum: &ForeignEnumInfo) -
             }
         }
     });
+    let values_check = fenum.rust_code_to_check_items_values();
+    if !values_check.is_empty() {
+        ctx.rust_code.push(values_check);
+    }
 
     Ok(())
 }
@@ -28,6 +28,11 @@ pub(in crate::java_jni) fn generate_interface(
     ctx: &mut JavaContext,
     interface: &ForeignInterface,
 ) -> Result<()> {
+    if let Some((trait_code, conv_rules)) = interface.closure_glue() {
+        ctx.rust_code.push(trait_code);
+        ctx.conv_map
+            .merge(SourceId::none(), &conv_rules, ctx.pointer_target_width)?;
+    }
     let f_methods = find_suitable_ftypes_for_interace_methods(ctx, interface)?;
     generate_java_code_for_interface(
         ctx,
@@ -150,8 +155,13 @@ fn generate_java_code_for_interface(
 package {package_name};
 {imports}
 {doc_comments}
-public interface {interface_name} {{"#,
+{functional}public interface {interface_name} {{"#,
         package_name = ctx.cfg.package_name,
+        functional = if interface.closure.is_some() {
+            "@FunctionalInterface\n"
+        } else {
+            ""
+        },
         interface_name = interface.name,
         doc_comments = interface_comments,
         imports = imports,
@@ -165,7 +175,11 @@ public interface {interface_name} {{"#,
 {doc_comments}
     {output_type} {method_name}({single_args_with_types});"#,
             method_name = method.name,
-            doc_comments = java_code::doc_comments_to_java_comments(&method.doc_comments, false),
+            doc_comments = java_code::doc_comments_with_deprecation(
+                &method.doc_comments,
+                method.deprecated.as_ref(),
+                false
+            ),
             single_args_with_types = java_code::args_with_java_types(
                 f_method,
                 method.arg_names_without_self(),
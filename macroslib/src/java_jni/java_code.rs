@@ -1,7 +1,7 @@
 use bitflags::bitflags;
 
 use super::{JniForeignMethodSignature, NullAnnotation};
-use crate::WRITE_TO_MEM_FAILED_MSG;
+use crate::{types::Deprecation, WRITE_TO_MEM_FAILED_MSG};
 
 bitflags! {
     pub(in crate::java_jni) struct ArgsFormatFlags: u8 {
@@ -61,6 +61,34 @@ pub(in crate::java_jni) fn args_with_java_types<'a, NI: Iterator<Item = &'a str>
     res
 }
 
+/// Doc comments of item, for deprecated item with `@deprecated` tag
+/// and `@Deprecated` annotation after them
+pub(in crate::java_jni) fn doc_comments_with_deprecation(
+    doc_comments: &[String],
+    deprecated: Option<&Deprecation>,
+    class_comments: bool,
+) -> String {
+    let deprecated = match deprecated {
+        Some(x) => x,
+        None => return doc_comments_to_java_comments(doc_comments, class_comments),
+    };
+    let mut doc_comments = doc_comments.to_vec();
+    let message = deprecated.message();
+    // Javadoc's `@deprecated` tag without description is useless
+    if !message.is_empty() {
+        doc_comments.push(format!("@deprecated {}", message));
+    }
+    let mut comments = doc_comments_to_java_comments(&doc_comments, class_comments);
+    if !comments.is_empty() {
+        comments.push('\n');
+    }
+    if !class_comments {
+        comments.push_str("    ");
+    }
+    comments.push_str("@Deprecated");
+    comments
+}
+
 pub(in crate::java_jni) fn doc_comments_to_java_comments(
     doc_comments: &[String],
     class_comments: bool,
@@ -147,6 +175,20 @@ pub(in crate::java_jni) fn filter_null_annotation(type_name: &str) -> String {
     type_name.replace("@NonNull", "").replace("@Nullable", "")
 }
 
+/// Class for primitive type, to use it as generic parameter
+pub(in crate::java_jni) fn boxed_primitive_type(type_name: &str) -> &str {
+    match type_name {
+        "boolean" => "Boolean",
+        "byte" => "Byte",
+        "short" => "Short",
+        "int" => "Integer",
+        "long" => "Long",
+        "float" => "Float",
+        "double" => "Double",
+        _ => type_name,
+    }
+}
+
 pub(in crate::java_jni) fn is_primitive_type(type_name: &str) -> bool {
     match type_name {
         "void" | "boolean" | "byte" | "short" | "int" | "long" | "float" | "double" => true,
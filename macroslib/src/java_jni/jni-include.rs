@@ -174,6 +174,20 @@ pub trait SwigForeignCLikeEnum {
     fn from_jint(_: jint) -> Self;
 }
 
+/// Receives result of Rust future returned by async method
+#[allow(dead_code)]
+pub trait SwigFutureCompleter<T>: Send {
+    fn complete(&self, value: T);
+    fn fail(&self, error: String);
+}
+
+/// Receives notification about completion of Rust future without value
+#[allow(dead_code)]
+pub trait SwigVoidFutureCompleter: Send {
+    fn complete(&self);
+    fn fail(&self, error: String);
+}
+
 #[allow(dead_code)]
 pub struct JavaString {
     string: jstring,
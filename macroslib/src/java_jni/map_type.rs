@@ -7,7 +7,9 @@ use super::{
     JavaForeignTypeInfo, NullAnnotation,
 };
 use crate::{
-    error::{invalid_src_id_span, DiagnosticError, Result, SourceIdSpan},
+    error::{
+        emit_warning_without_src_info, invalid_src_id_span, DiagnosticError, Result, SourceIdSpan,
+    },
     typemap::{
         ast::{if_option_return_some_type, DisplayToTokens, TyParamsSubstList},
         ty::{ForeignType, RustType, TraitNamesSet},
@@ -200,25 +202,32 @@ fn do_map_type(
         return Ok(ftype);
     }
 
-    match direction {
-        Direction::Outgoing => Err(DiagnosticError::new2(
+    let mut err = match direction {
+        Direction::Outgoing => DiagnosticError::new2(
             arg_ty_span,
             format!(
                 "Do not know conversation from \
                  such rust type '{}' to Java type",
                 arg_ty
             ),
-        )),
+        ),
 
-        Direction::Incoming => Err(DiagnosticError::new2(
+        Direction::Incoming => DiagnosticError::new2(
             arg_ty_span,
             format!(
                 "Do not know conversation from Java type \
                  to such rust type '{}'",
                 arg_ty
             ),
-        )),
+        ),
+    };
+    if let Some(explanation) = ctx
+        .conv_map
+        .explain_conv_failure(arg_ty.to_idx(), direction)
+    {
+        err.span_note(arg_ty_span, explanation);
     }
+    Err(err)
 }
 
 fn is_ty_implement_traits(tmap: &TypeMap, ty: &syn::Type, traits: &TraitNamesSet) -> bool {
@@ -230,10 +239,10 @@ fn is_ty_implement_traits(tmap: &TypeMap, ty: &syn::Type, traits: &TraitNamesSet
         }
         true
     } else {
-        println!(
-            "cargo:warning=mapping types: type {} unknown",
+        emit_warning_without_src_info(format!(
+            "mapping types: type {} unknown",
             DisplayToTokens(ty)
-        );
+        ));
         false
     }
 }
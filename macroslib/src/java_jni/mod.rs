@@ -16,7 +16,7 @@ use std::{fmt, io::Write, path::PathBuf};
 use syn::{spanned::Spanned, Type};
 
 use crate::{
-    error::{invalid_src_id_span, DiagnosticError, Result},
+    error::{emit_warning, invalid_src_id_span, DiagnosticError, Result},
     extension::{ClassExtHandlers, ExtHandlers, MethodExtHandlers},
     file_cache::FileWriteCache,
     typemap::{
@@ -31,8 +31,11 @@ use crate::{
         },
         ForeignTypeInfo, TypeMapConvRuleInfo,
     },
-    types::{ForeignClassInfo, ForeignMethod, ItemToExpand, MethodVariant},
-    JavaConfig, JavaReachabilityFence, LanguageGenerator, SourceCode, TypeMap,
+    types::{
+        check_module_items_holder_name, ForeignClassInfo, ForeignMethod, ItemToExpand,
+        MethodVariant,
+    },
+    GeneratedRustCode, JavaConfig, JavaReachabilityFence, LanguageGenerator, SourceCode, TypeMap,
     SMART_PTR_COPY_TRAIT, WRITE_TO_MEM_FAILED_MSG,
 };
 use map_class_self_type::register_typemap_for_self_type;
@@ -48,6 +51,10 @@ struct JavaContext<'a> {
     rust_code: &'a mut Vec<TokenStream>,
     generated_foreign_files: &'a mut FxHashSet<PathBuf>,
     java_type_to_jni_sig_map: FxHashMap<SmolStr, SmolStr>,
+    /// callbacks for results of async methods that are already generated
+    generated_future_completers: FxHashSet<SmolStr>,
+    /// callbacks for closures that are already generated
+    generated_closure_callbacks: FxHashSet<SmolStr>,
     class_ext_handlers: &'a ClassExtHandlers,
     method_ext_handlers: &'a MethodExtHandlers,
 }
@@ -146,9 +153,12 @@ impl JavaConfig {
             );
             if class.smart_ptr_copy_derived() {
                 if class.copy_derived() {
-                    println!(
-                        "cargo:warning=class {} marked as Copy and {}, ignore Copy",
-                        class.name, SMART_PTR_COPY_TRAIT
+                    emit_warning(
+                        (class.src_id, class.span()),
+                        format!(
+                            "class {} marked as Copy and {}, ignore Copy",
+                            class.name, SMART_PTR_COPY_TRAIT
+                        ),
                     );
                 }
                 if check_if_smart_pointer_return_inner_type(&this_type, "Rc").is_none()
@@ -184,7 +194,7 @@ impl LanguageGenerator for JavaConfig {
         items: Vec<ItemToExpand>,
         remove_not_generated_files: bool,
         ext_handlers: ExtHandlers,
-    ) -> Result<Vec<TokenStream>> {
+    ) -> Result<GeneratedRustCode> {
         let mut ret = Vec::with_capacity(items.len());
         let mut generated_foreign_files = FxHashSet::default();
         let mut ctx = JavaContext {
@@ -194,20 +204,29 @@ impl LanguageGenerator for JavaConfig {
             rust_code: &mut ret,
             generated_foreign_files: &mut generated_foreign_files,
             java_type_to_jni_sig_map: rust_code::predefined_java_type_to_jni_sig(),
+            generated_future_completers: FxHashSet::default(),
+            generated_closure_callbacks: FxHashSet::default(),
             class_ext_handlers: ext_handlers.class_ext_handlers,
             method_ext_handlers: ext_handlers.method_ext_handlers,
         };
         init(&mut ctx, code)?;
+        check_module_items_holder_name(&items, &self.module_items_class)?;
         for item in &items {
             if let ItemToExpand::Class(ref fclass) = item {
                 self.register_class(&mut ctx, fclass)?;
             }
         }
-        for item in items {
+        let mut module_consts = vec![];
+        let mut module_fns = vec![];
+        let mut items_code = Vec::with_capacity(items.len());
+        for (idx, item) in items.into_iter().enumerate() {
+            let code_start = ctx.rust_code.len();
             match item {
                 ItemToExpand::Class(fclass) => {
                     fclass::generate(&mut ctx, &fclass)?;
                 }
+                ItemToExpand::Const(fconst) => module_consts.push(*fconst),
+                ItemToExpand::Fn(ffn) => module_fns.push(*ffn),
                 ItemToExpand::Enum(fenum) => {
                     fenum::generate_enum(&mut ctx, &fenum)?;
                 }
@@ -215,6 +234,16 @@ impl LanguageGenerator for JavaConfig {
                     finterface::generate_interface(&mut ctx, &finterface)?;
                 }
             }
+            items_code.push((idx, code_start..ctx.rust_code.len()));
+        }
+
+        if !module_consts.is_empty() || !module_fns.is_empty() {
+            let globals = ForeignClassInfo::module_items_holder(
+                &self.module_items_class,
+                module_consts,
+                module_fns,
+            );
+            fclass::generate(&mut ctx, &globals)?;
         }
 
         if remove_not_generated_files {
@@ -229,7 +258,10 @@ impl LanguageGenerator for JavaConfig {
             .map_err(DiagnosticError::map_any_err_to_our_err)?;
         }
 
-        Ok(ret)
+        Ok(GeneratedRustCode {
+            code: ret,
+            items_code,
+        })
     }
     fn post_proccess_code(
         &self,
@@ -295,6 +327,14 @@ fn calc_this_type_for_method(tm: &TypeMap, class: &ForeignClassInfo) -> Option<T
     }
 }
 
+/// All options that can be used in `cfg_option` of `foreign_typemap!`
+pub(crate) fn typemap_cfg_options() -> FxHashSet<&'static str> {
+    let mut opts = FxHashSet::<&'static str>::default();
+    opts.insert("NullAnnotations");
+    opts.insert("NoNullAnnotations");
+    opts
+}
+
 fn merge_rule(ctx: &mut JavaContext, mut rule: TypeMapConvRuleInfo) -> Result<()> {
     debug!("merge_rule begin {:?}", rule);
     if rule.is_empty() {
@@ -304,13 +344,7 @@ fn merge_rule(ctx: &mut JavaContext, mut rule: TypeMapConvRuleInfo) -> Result<()
             format!("rule {:?} is empty", rule),
         ));
     }
-    let all_options = {
-        let mut opts = FxHashSet::<&'static str>::default();
-        opts.insert("NullAnnotations");
-        opts.insert("NoNullAnnotations");
-        opts
-    };
-    validate_cfg_options(&rule, &all_options)?;
+    validate_cfg_options(&rule, &typemap_cfg_options())?;
     let options = {
         let mut opts = FxHashSet::<&'static str>::default();
         if ctx.cfg.null_annotation_package.is_some() {
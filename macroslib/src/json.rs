@@ -0,0 +1,351 @@
+//! Minimal JSON writer and reader, enough to produce and read back
+//! machine readable output without extra dependencies
+
+use std::fmt;
+
+use smol_str::SmolStr;
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum JsonValue {
+    Null,
+    Bool(bool),
+    Number(i64),
+    String(String),
+    Array(Vec<JsonValue>),
+    /// keys order is preserved
+    Object(Vec<(SmolStr, JsonValue)>),
+}
+
+impl JsonValue {
+    pub(crate) fn object() -> Self {
+        JsonValue::Object(vec![])
+    }
+    /// # Panics
+    /// If `self` is not object
+    pub(crate) fn field<K: Into<SmolStr>, V: Into<JsonValue>>(mut self, key: K, val: V) -> Self {
+        match self {
+            JsonValue::Object(ref mut fields) => fields.push((key.into(), val.into())),
+            _ => panic!("Internal error: JsonValue::field called for not object"),
+        }
+        self
+    }
+    pub(crate) fn get(&self, key: &str) -> Option<&JsonValue> {
+        match self {
+            JsonValue::Object(fields) => fields.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+    pub(crate) fn as_str(&self) -> Option<&str> {
+        match self {
+            JsonValue::String(x) => Some(x.as_str()),
+            _ => None,
+        }
+    }
+    pub(crate) fn as_i64(&self) -> Option<i64> {
+        match self {
+            JsonValue::Number(x) => Some(*x),
+            _ => None,
+        }
+    }
+    pub(crate) fn as_array(&self) -> Option<&[JsonValue]> {
+        match self {
+            JsonValue::Array(x) => Some(x.as_slice()),
+            _ => None,
+        }
+    }
+    /// Parse JSON, only integer numbers are supported
+    pub(crate) fn parse(text: &str) -> Result<JsonValue, String> {
+        let mut parser = Parser {
+            text: text.as_bytes(),
+            pos: 0,
+        };
+        let ret = parser.parse_value()?;
+        parser.skip_ws();
+        if parser.pos != parser.text.len() {
+            return Err(parser.error("unexpected data after JSON value"));
+        }
+        Ok(ret)
+    }
+}
+
+struct Parser<'a> {
+    text: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, msg: &str) -> String {
+        format!("invalid JSON at byte {}: {}", self.pos, msg)
+    }
+
+    fn skip_ws(&mut self) {
+        while self.pos < self.text.len() && self.text[self.pos].is_ascii_whitespace() {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&mut self) -> Option<u8> {
+        self.skip_ws();
+        self.text.get(self.pos).cloned()
+    }
+
+    fn expect(&mut self, ch: u8) -> Result<(), String> {
+        if self.peek() == Some(ch) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(self.error(&format!("expect '{}'", ch as char)))
+        }
+    }
+
+    fn parse_keyword(&mut self, keyword: &str, val: JsonValue) -> Result<JsonValue, String> {
+        if self.text[self.pos..].starts_with(keyword.as_bytes()) {
+            self.pos += keyword.len();
+            Ok(val)
+        } else {
+            Err(self.error("unknown keyword"))
+        }
+    }
+
+    fn parse_value(&mut self) -> Result<JsonValue, String> {
+        match self.peek() {
+            Some(b'n') => self.parse_keyword("null", JsonValue::Null),
+            Some(b't') => self.parse_keyword("true", JsonValue::Bool(true)),
+            Some(b'f') => self.parse_keyword("false", JsonValue::Bool(false)),
+            Some(b'"') => Ok(JsonValue::String(self.parse_string()?)),
+            Some(b'[') => {
+                self.pos += 1;
+                let mut items = vec![];
+                if self.peek() == Some(b']') {
+                    self.pos += 1;
+                    return Ok(JsonValue::Array(items));
+                }
+                loop {
+                    items.push(self.parse_value()?);
+                    if self.peek() == Some(b',') {
+                        self.pos += 1;
+                    } else {
+                        self.expect(b']')?;
+                        return Ok(JsonValue::Array(items));
+                    }
+                }
+            }
+            Some(b'{') => {
+                self.pos += 1;
+                let mut fields = vec![];
+                if self.peek() == Some(b'}') {
+                    self.pos += 1;
+                    return Ok(JsonValue::Object(fields));
+                }
+                loop {
+                    if self.peek() != Some(b'"') {
+                        return Err(self.error("expect key"));
+                    }
+                    let key = self.parse_string()?;
+                    self.expect(b':')?;
+                    fields.push((key.into(), self.parse_value()?));
+                    if self.peek() == Some(b',') {
+                        self.pos += 1;
+                    } else {
+                        self.expect(b'}')?;
+                        return Ok(JsonValue::Object(fields));
+                    }
+                }
+            }
+            Some(b'-') | Some(b'0'..=b'9') => {
+                let start = self.pos;
+                self.pos += 1;
+                while self.pos < self.text.len() && self.text[self.pos].is_ascii_digit() {
+                    self.pos += 1;
+                }
+                std::str::from_utf8(&self.text[start..self.pos])
+                    .ok()
+                    .and_then(|x| x.parse::<i64>().ok())
+                    .map(JsonValue::Number)
+                    .ok_or_else(|| self.error("invalid number"))
+            }
+            _ => Err(self.error("expect JSON value")),
+        }
+    }
+
+    fn parse_string(&mut self) -> Result<String, String> {
+        // skip '"'
+        self.pos += 1;
+        let mut ret = Vec::new();
+        loop {
+            let ch = *self
+                .text
+                .get(self.pos)
+                .ok_or_else(|| self.error("unterminated string"))?;
+            self.pos += 1;
+            match ch {
+                b'"' => break,
+                b'\\' => {
+                    let ch = *self
+                        .text
+                        .get(self.pos)
+                        .ok_or_else(|| self.error("unterminated string"))?;
+                    self.pos += 1;
+                    match ch {
+                        b'"' | b'\\' | b'/' => ret.push(ch),
+                        b'n' => ret.push(b'\n'),
+                        b'r' => ret.push(b'\r'),
+                        b't' => ret.push(b'\t'),
+                        b'b' => ret.push(0x08),
+                        b'f' => ret.push(0x0c),
+                        b'u' => {
+                            let code = self
+                                .text
+                                .get(self.pos..self.pos + 4)
+                                .and_then(|x| std::str::from_utf8(x).ok())
+                                .and_then(|x| u32::from_str_radix(x, 16).ok())
+                                .and_then(std::char::from_u32)
+                                .ok_or_else(|| self.error("invalid unicode escape"))?;
+                            self.pos += 4;
+                            let mut buf = [0_u8; 4];
+                            ret.extend_from_slice(code.encode_utf8(&mut buf).as_bytes());
+                        }
+                        _ => return Err(self.error("invalid escape")),
+                    }
+                }
+                ch => ret.push(ch),
+            }
+        }
+        String::from_utf8(ret).map_err(|_| self.error("invalid UTF-8"))
+    }
+}
+
+impl From<bool> for JsonValue {
+    fn from(x: bool) -> Self {
+        JsonValue::Bool(x)
+    }
+}
+
+impl From<usize> for JsonValue {
+    fn from(x: usize) -> Self {
+        JsonValue::Number(x as i64)
+    }
+}
+
+impl From<i64> for JsonValue {
+    fn from(x: i64) -> Self {
+        JsonValue::Number(x)
+    }
+}
+
+impl From<&str> for JsonValue {
+    fn from(x: &str) -> Self {
+        JsonValue::String(x.into())
+    }
+}
+
+impl From<String> for JsonValue {
+    fn from(x: String) -> Self {
+        JsonValue::String(x)
+    }
+}
+
+impl<T: Into<JsonValue>> From<Option<T>> for JsonValue {
+    fn from(x: Option<T>) -> Self {
+        match x {
+            Some(x) => x.into(),
+            None => JsonValue::Null,
+        }
+    }
+}
+
+impl<T: Into<JsonValue>> From<Vec<T>> for JsonValue {
+    fn from(x: Vec<T>) -> Self {
+        JsonValue::Array(x.into_iter().map(Into::into).collect())
+    }
+}
+
+fn write_json_str(f: &mut fmt::Formatter, s: &str) -> fmt::Result {
+    f.write_str("\"")?;
+    for ch in s.chars() {
+        match ch {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            ch if (ch as u32) < 0x20 => write!(f, "\\u{:04x}", ch as u32)?,
+            ch => write!(f, "{}", ch)?,
+        }
+    }
+    f.write_str("\"")
+}
+
+impl fmt::Display for JsonValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            JsonValue::Null => f.write_str("null"),
+            JsonValue::Bool(x) => write!(f, "{}", x),
+            JsonValue::Number(x) => write!(f, "{}", x),
+            JsonValue::String(x) => write_json_str(f, x),
+            JsonValue::Array(items) => {
+                f.write_str("[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i != 0 {
+                        f.write_str(",")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                f.write_str("]")
+            }
+            JsonValue::Object(fields) => {
+                f.write_str("{")?;
+                for (i, (key, val)) in fields.iter().enumerate() {
+                    if i != 0 {
+                        f.write_str(",")?;
+                    }
+                    write_json_str(f, key)?;
+                    write!(f, ":{}", val)?;
+                }
+                f.write_str("}")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_value_to_string() {
+        let val = JsonValue::object()
+            .field("a", "x\"y\n")
+            .field("b", vec![JsonValue::Null, 5_usize.into(), true.into()])
+            .field("c", JsonValue::object())
+            .field("d", Option::<String>::None);
+        assert_eq!(
+            r#"{"a":"x\"y\n","b":[null,5,true],"c":{},"d":null}"#,
+            val.to_string()
+        );
+    }
+
+    #[test]
+    fn test_json_value_parse() {
+        let val = JsonValue::object()
+            .field("a", "x\"y\n\u{1}ю")
+            .field("b", vec![JsonValue::Null, (-5_i64).into(), false.into()])
+            .field("c", JsonValue::object())
+            .field("d", Vec::<JsonValue>::new());
+        assert_eq!(val, JsonValue::parse(&val.to_string()).unwrap());
+        assert_eq!(
+            val,
+            JsonValue::parse(
+                r#" { "a" : "x\"y\n\u0001ю", "b": [ null, -5, false ], "c": { }, "d": [] } "#
+            )
+            .unwrap()
+        );
+        assert_eq!(
+            Some(-5),
+            val.get("b").unwrap().as_array().unwrap()[1].as_i64()
+        );
+        assert!(JsonValue::parse("[1,").is_err());
+        assert!(JsonValue::parse("{} x").is_err());
+        assert!(JsonValue::parse("1.5").is_err());
+    }
+}
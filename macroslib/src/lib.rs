@@ -30,12 +30,18 @@ macro_rules! parse_type_spanned_checked {
     }}
 }
 
+mod annotations;
+pub mod api_check;
+pub mod backend;
 mod code_parse;
 mod cpp;
 mod error;
 mod extension;
 pub mod file_cache;
+mod glue_mod;
 mod java_jni;
+mod json;
+mod model;
 mod namegen;
 mod python;
 mod source_registry;
@@ -47,6 +53,7 @@ use std::{
     env, io,
     io::Write,
     mem,
+    ops::Range,
     path::{Path, PathBuf},
     process::{Command, Stdio},
     str,
@@ -60,10 +67,21 @@ use strum::EnumIter;
 use syn::spanned::Spanned;
 
 use crate::{
-    error::{panic_on_parse_error, DiagnosticError, Result},
+    backend::LanguageBackend,
+    error::{
+        diagnostics_to_json, emit_warning, emit_warning_with_notes, emit_warning_without_src_info,
+        invalid_src_id_span, panic_on_parse_error, set_parsed_src_id, take_warnings,
+        DiagnosticError, Result, SourceIdSpan,
+    },
     source_registry::{SourceId, SourceRegistry},
-    typemap::{ast::DisplayToTokens, TypeMap},
-    types::ItemToExpand,
+    typemap::{
+        ast::{normalize_type, DisplayToTokens},
+        TypeMap,
+    },
+    types::{
+        check_abstract_classes_usage, closure_callback_name, ForeignClassInfo, ForeignMethod,
+        ItemToExpand, MODULE_ITEMS_CLASS,
+    },
 };
 
 pub(crate) static WRITE_TO_MEM_FAILED_MSG: &str = "Write to memory buffer failed, no free mem?";
@@ -72,18 +90,48 @@ pub(crate) static COPY_TRAIT: &str = "Copy";
 pub(crate) static CLONE_TRAIT: &str = "Clone";
 pub(crate) static PLAIN_CLASS: &str = "PlainClass";
 pub(crate) static CAMEL_CASE_ALIASES: &str = "camelCaseAliases";
-pub(crate) static KNOWN_CLASS_DERIVES: [&str; 5] = [
+pub(crate) static REPR_C: &str = "ReprC";
+pub(crate) static PROPERTY_ATTR: &str = "property";
+pub(crate) static FLAGS_DERIVE: &str = "Flags";
+pub(crate) static PARTIAL_EQ_TRAIT: &str = "PartialEq";
+pub(crate) static EQ_TRAIT: &str = "Eq";
+pub(crate) static HASH_TRAIT: &str = "Hash";
+pub(crate) static DISPLAY_TRAIT: &str = "Display";
+pub(crate) static DEBUG_TRAIT: &str = "Debug";
+pub(crate) static PARTIAL_ORD_TRAIT: &str = "PartialOrd";
+pub(crate) static ORD_TRAIT: &str = "Ord";
+/// Traits of `self_type` mapped to protocols of foreign language
+pub(crate) static PROTOCOL_DERIVES: [&str; 7] = [
+    PARTIAL_EQ_TRAIT,
+    EQ_TRAIT,
+    HASH_TRAIT,
+    DISPLAY_TRAIT,
+    DEBUG_TRAIT,
+    PARTIAL_ORD_TRAIT,
+    ORD_TRAIT,
+];
+pub(crate) static KNOWN_CLASS_DERIVES: [&str; 13] = [
     CLONE_TRAIT,
     COPY_TRAIT,
     SMART_PTR_COPY_TRAIT,
     PLAIN_CLASS,
     CAMEL_CASE_ALIASES,
+    REPR_C,
+    PARTIAL_EQ_TRAIT,
+    EQ_TRAIT,
+    HASH_TRAIT,
+    DISPLAY_TRAIT,
+    DEBUG_TRAIT,
+    PARTIAL_ORD_TRAIT,
+    ORD_TRAIT,
 ];
 
+pub use api_check::ApiCheckMode;
+pub use error::{DiagnosticMessage, Error, LineColumn, SourceSpan};
 pub use extension::MethodInfo;
 use extension::{ClassExtHandlers, EnumExtHandlers, ExtHandlers, MethodExtHandlers};
-use rustc_hash::FxHashMap;
-pub use types::MethodVariant;
+use rustc_hash::{FxHashMap, FxHashSet};
+pub use types::{ForeignConstValue, MethodAccess, MethodVariant, SelfTypeVariant};
 
 /// Calculate target pointer width from environment variable
 /// that `cargo` inserts
@@ -101,6 +149,39 @@ pub enum LanguageConfig {
     JavaConfig(JavaConfig),
     CppConfig(CppConfig),
     PythonConfig(PythonConfig),
+    /// Language supported by backend implemented outside of `flapigen`
+    Custom(Box<dyn LanguageBackend>),
+}
+
+impl LanguageConfig {
+    fn name(&self) -> String {
+        match self {
+            LanguageConfig::JavaConfig(_) => "java".into(),
+            LanguageConfig::CppConfig(_) => "cpp".into(),
+            LanguageConfig::PythonConfig(_) => "python".into(),
+            LanguageConfig::Custom(ref backend) => backend.name().into(),
+        }
+    }
+    /// name of class with items declared at module level
+    fn module_items_class(&self) -> &str {
+        match self {
+            LanguageConfig::JavaConfig(ref cfg) => &cfg.module_items_class,
+            LanguageConfig::CppConfig(ref cfg) => &cfg.module_items_class,
+            LanguageConfig::PythonConfig(_) | LanguageConfig::Custom(_) => MODULE_ITEMS_CLASS,
+        }
+    }
+    /// name of module for generated Rust code in case of several languages
+    fn rust_module_name(&self) -> String {
+        format!("flapigen_{}", self.name())
+    }
+    /// options that language supports in `cfg_option` of `foreign_typemap!`
+    fn typemap_cfg_options(&self) -> FxHashSet<&'static str> {
+        match self {
+            LanguageConfig::JavaConfig(_) => java_jni::typemap_cfg_options(),
+            LanguageConfig::CppConfig(_) => cpp::typemap_cfg_options(),
+            LanguageConfig::PythonConfig(_) | LanguageConfig::Custom(_) => FxHashSet::default(),
+        }
+    }
 }
 
 /// Configuration for Java binding generation
@@ -111,6 +192,7 @@ pub struct JavaConfig {
     null_annotation_package: Option<String>,
     optional_package: String,
     reachability_fence: JavaReachabilityFence,
+    module_items_class: String,
 }
 
 impl JavaConfig {
@@ -125,6 +207,7 @@ impl JavaConfig {
             null_annotation_package: None,
             optional_package: "java.util".to_string(),
             reachability_fence: JavaReachabilityFence::GenerateFence(8),
+            module_items_class: MODULE_ITEMS_CLASS.into(),
         }
     }
     /// Use @NonNull for types where appropriate
@@ -172,6 +255,12 @@ impl JavaConfig {
         self.reachability_fence = reachability_fence;
         self
     }
+    /// Name of class with items declared via `foreign_fn!` and `foreign_const!`,
+    /// default value is "Globals"
+    pub fn module_items_class(mut self, name: String) -> JavaConfig {
+        self.module_items_class = name;
+        self
+    }
 }
 
 /// What reachability fence to use
@@ -198,6 +287,7 @@ pub struct CppConfig {
     /// Create separate *_impl.hpp files with methods implementations.
     /// Can be necessary for the project with circular dependencies between classes.
     separate_impl_headers: bool,
+    module_items_class: String,
 }
 
 /// To which `C++` type map `std::option::Option`
@@ -267,6 +357,7 @@ impl CppConfig {
             cpp_variant: CppVariant::Std17,
             cpp_str_view: CppStrView::Std17,
             separate_impl_headers: false,
+            module_items_class: MODULE_ITEMS_CLASS.into(),
         }
     }
     pub fn cpp_optional(self, cpp_optional: CppOptional) -> CppConfig {
@@ -305,6 +396,25 @@ impl CppConfig {
             ..self
         }
     }
+    /// Name of class with items declared via `foreign_fn!` and `foreign_const!`,
+    /// default value is "Globals"
+    pub fn module_items_class(self, module_items_class: String) -> CppConfig {
+        CppConfig {
+            module_items_class,
+            ..self
+        }
+    }
+}
+
+/// Where to write diagnostics (errors and warnings) in JSON format,
+/// similar to `rustc --error-format=json`, one JSON object per line
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum JsonDiagnosticsOutput {
+    /// Print to standard error stream
+    Stderr,
+    /// Write to file near output file, with ".diagnostics.json" suffix,
+    /// for example "src/java_glue.rs.diagnostics.json"
+    FileNextToOutput,
 }
 
 /// Configuration for Java binding generation
@@ -325,17 +435,23 @@ impl PythonConfig {
 /// It designed to use inside `build.rs`.
 pub struct Generator {
     init_done: bool,
-    config: LanguageConfig,
-    conv_map: TypeMap,
-    conv_map_source: Vec<SourceId>,
-    foreign_lang_helpers: Vec<SourceCode>,
+    /// the first one from `new`, others from `add_language`
+    langs: Vec<TargetLanguage>,
     pointer_target_width: usize,
     src_reg: SourceRegistry,
     rustfmt_bindings: bool,
     remove_not_generated_files: bool,
+    model_output: Option<PathBuf>,
+    conv_graph_dot_dir: Option<PathBuf>,
+    explain_conv_failures: bool,
+    lint_type_maps: bool,
+    api_baseline: Option<(PathBuf, ApiCheckMode)>,
+    annotated_srcs: Vec<PathBuf>,
     class_ext_handlers: ClassExtHandlers,
     method_ext_handlers: MethodExtHandlers,
     enum_ext_handlers: EnumExtHandlers,
+    json_diagnostics: Option<JsonDiagnosticsOutput>,
+    async_spawner: Option<String>,
 }
 
 struct SourceCode {
@@ -343,11 +459,21 @@ struct SourceCode {
     code: String,
 }
 
+/// Language specific part of `Generator`
+struct TargetLanguage {
+    config: LanguageConfig,
+    conv_map: TypeMap,
+    conv_map_source: Vec<SourceId>,
+    foreign_lang_helpers: Vec<SourceCode>,
+}
+
 static FOREIGNER_CLASS_DEPRECATED: &str = "foreigner_class";
 static FOREIGN_CLASS: &str = "foreign_class";
 static FOREIGN_ENUM: &str = "foreign_enum";
 static FOREIGN_INTERFACE_DEPRECATED: &str = "foreign_interface";
 static FOREIGN_CALLBACK: &str = "foreign_callback";
+static FOREIGN_CONST: &str = "foreign_const";
+static FOREIGN_FN: &str = "foreign_fn";
 static FOREIGNER_CODE_DEPRECATED: &str = "foreigner_code";
 static FOREIGN_CODE: &str = "foreign_code";
 static FOREIGN_TYPEMAP: &str = "foreign_typemap";
@@ -355,76 +481,53 @@ static FOREIGN_TYPEMAP: &str = "foreign_typemap";
 impl Generator {
     pub fn new(config: LanguageConfig) -> Generator {
         let pointer_target_width = target_pointer_width_from_env();
-        let mut conv_map_source = Vec::new();
-        let mut foreign_lang_helpers = Vec::new();
         let mut src_reg = SourceRegistry::default();
-        match config {
-            LanguageConfig::JavaConfig(ref java_cfg) => {
-                conv_map_source.push(
-                    src_reg.register(SourceCode {
-                        id_of_code: "jni-include.rs".into(),
-                        code: include_str!("java_jni/jni-include.rs")
-                            .replace(
-                                "java.util.Optional",
-                                &format!("{}.Optional", java_cfg.optional_package),
-                            )
-                            .replace(
-                                "java/util/Optional",
-                                &format!(
-                                    "{}/Optional",
-                                    java_cfg.optional_package.replace('.', "/")
-                                ),
-                            ),
-                    }),
-                );
-            }
-            LanguageConfig::CppConfig(..) => {
-                conv_map_source.push(src_reg.register(SourceCode {
-                    id_of_code: "cpp-include.rs".into(),
-                    code: include_str!("cpp/cpp-include.rs").into(),
-                }));
-                foreign_lang_helpers.push(SourceCode {
-                    id_of_code: "rust_vec_impl.hpp".into(),
-                    code: include_str!("cpp/rust_vec_impl.hpp").into(),
-                });
-                foreign_lang_helpers.push(SourceCode {
-                    id_of_code: "rust_foreign_vec_impl.hpp".into(),
-                    code: include_str!("cpp/rust_foreign_vec_impl.hpp").into(),
-                });
-                foreign_lang_helpers.push(SourceCode {
-                    id_of_code: "rust_foreign_slice_iter.hpp".into(),
-                    code: include_str!("cpp/rust_foreign_slice_iter.hpp").into(),
-                });
-                foreign_lang_helpers.push(SourceCode {
-                    id_of_code: "rust_foreign_slice_impl.hpp".into(),
-                    code: include_str!("cpp/rust_foreign_slice_impl.hpp").into(),
-                });
-                foreign_lang_helpers.push(SourceCode {
-                    id_of_code: "rust_slice_tmpl.hpp".into(),
-                    code: include_str!("cpp/rust_slice_tmpl.hpp").into(),
-                });
-            }
-            LanguageConfig::PythonConfig(..) => {
-                conv_map_source.push(src_reg.register(SourceCode {
-                    id_of_code: "python-include.rs".into(),
-                    code: include_str!("python/python-include.rs").into(),
-                }));
-            }
-        }
+        let lang = TargetLanguage::new(config, &mut src_reg);
         Generator {
             init_done: false,
-            config,
-            conv_map: TypeMap::default(),
-            conv_map_source,
-            foreign_lang_helpers,
+            langs: vec![lang],
             pointer_target_width: pointer_target_width.unwrap_or(0),
             src_reg,
             rustfmt_bindings: false,
             remove_not_generated_files: false,
+            model_output: None,
+            conv_graph_dot_dir: None,
+            explain_conv_failures: false,
+            lint_type_maps: false,
+            api_baseline: None,
+            annotated_srcs: Vec::new(),
             class_ext_handlers: FxHashMap::default(),
             method_ext_handlers: FxHashMap::default(),
             enum_ext_handlers: FxHashMap::default(),
+            json_diagnostics: None,
+            async_spawner: None,
+        }
+    }
+
+    /// Generate bindings for one more language in the same pass.
+    /// Source code is parsed only once, and Rust code for all languages is
+    /// placed into the same output file, each language into its own module:
+    /// `flapigen_java`, `flapigen_cpp`, `flapigen_python` or `flapigen_<name>` for custom backend.
+    /// `foreign_class!`, `foreign_typemap!` and other macroses from parsed sources
+    /// are used for all languages, `#[cfg(flapigen_lang = "java")]` restricts macro
+    /// to one language, `merge_type_map` after this call adds rules only for the new language.
+    ///
+    /// # Panics
+    /// Panics if bindings for such language already requested
+    pub fn add_language(mut self, config: LanguageConfig) -> Self {
+        if self
+            .langs
+            .iter()
+            .any(|x| x.config.rust_module_name() == config.rust_module_name())
+        {
+            panic!(
+                "Generator already has configuration for {}",
+                config.rust_module_name()
+            );
         }
+        let lang = TargetLanguage::new(config, &mut self.src_reg);
+        self.langs.push(lang);
+        self
     }
 
     /// By default we get pointer_target_width via cargo (more exactly CARGO_CFG_TARGET_POINTER_WIDTH),
@@ -448,12 +551,101 @@ impl Generator {
         self
     }
 
-    /// Add new foreign langauge type <-> Rust mapping
+    /// Report errors and warnings also in JSON format, suitable for IDE and CI tools.
+    /// Warnings are still printed for cargo via "cargo:warning=" lines.
+    pub fn json_diagnostics(mut self, output: JsonDiagnosticsOutput) -> Self {
+        self.json_diagnostics = Some(output);
+        self
+    }
+
+    /// Write description of all parsed classes, enums and callbacks in JSON format
+    /// into `path`. Format is versioned via "format_version" field, and for each
+    /// argument and return value contains Rust type and foreign type for each language.
+    pub fn export_model<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.model_output = Some(path.into());
+        self
+    }
+
+    /// Write graph of Rust types conversations for each language
+    /// in Graphviz DOT format into `dir`, as `<language>-conv-graph.dot`.
+    /// Generic rules are shown as dashed templates. File is written even if
+    /// generation failed, to help find out why conversation is not possible.
+    pub fn conv_graph_dot_output<P: Into<PathBuf>>(mut self, dir: P) -> Self {
+        self.conv_graph_dot_dir = Some(dir.into());
+        self
+    }
+
+    /// If true, errors about types that can not be converted to/from foreign language
+    /// contain explanation: generic rules that were tried, why they did not match
+    /// (unsatisfied trait bound, wrong number of generic arguments and so on),
+    /// and the closest foreign types in conversation graph
+    pub fn explain_conversion_failures(mut self, doit: bool) -> Self {
+        self.explain_conv_failures = doit;
+        self
+    }
+
+    /// Rust expression used to run futures returned by async methods,
+    /// like `fn fetch(&self) -> impl Future<Output = T>;` in `foreign_class!`.
+    /// It is called with one argument of type `impl Future<Output = ()> + Send + 'static`,
+    /// for example `"tokio::spawn"` or `"crate::RUNTIME.spawn"`.
+    pub fn async_spawner(mut self, spawner: &str) -> Self {
+        self.async_spawner = Some(spawner.into());
+        self
+    }
+
+    /// If true (default is false), after code generation check "types maps" and warn about:
+    /// rules that override builtin rules, generic rules that can never match
+    /// any type, Rust types with two equally short conversation paths
+    /// to the same foreign type and rules gated by `cfg_option` that
+    /// no configuration enables.
+    pub fn lint_type_maps(mut self, doit: bool) -> Self {
+        self.lint_type_maps = doit;
+        self
+    }
+
+    /// Compare API of generated bindings with `baseline`, file written
+    /// by `export_model` for previous version, and report breaking changes,
+    /// like removed methods, changed signatures or renumbered enum items.
+    pub fn check_api_compatibility<P: Into<PathBuf>>(
+        mut self,
+        baseline: P,
+        mode: ApiCheckMode,
+    ) -> Self {
+        self.api_baseline = Some((baseline.into(), mode));
+        self
+    }
+
+    /// Also generate bindings for items of ordinary Rust code in `path`
+    /// (file or directory, that scanned recursively for ".rs" files),
+    /// marked with attributes instead of `foreign_class!` and friends:
+    /// `#[flapigen::export] impl Foo`, `#[flapigen::export] enum`
+    /// and `#[flapigen::callback] trait`. Public methods of `impl` are exported,
+    /// except marked with `#[flapigen::skip]`, method without `self` that returns
+    /// `Self` or marked with `#[flapigen::constructor]` is constructor.
+    /// Rust compiler does not know these attributes, so they should be wrapped
+    /// into `cfg_attr`, for example `#[cfg_attr(flapigen, flapigen::export)]`,
+    /// or provided by no-op attribute macros.
+    /// Functions and methods are called via path of module relative to `path`,
+    /// like `a::b::Foo::new` for `path/a/b.rs` (inline `mod` are taken into account too),
+    /// so generated code should be included into module that corresponds to `path`,
+    /// and exported types should be visible there, for example via `use`.
+    pub fn scan_annotated_sources<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.annotated_srcs.push(path.into());
+        self
+    }
+
+    /// Add new foreign langauge type <-> Rust mapping,
+    /// in case of several languages it is added for the last one
     pub fn merge_type_map(mut self, id_of_code: &str, code: &str) -> Self {
-        self.conv_map_source.push(self.src_reg.register(SourceCode {
+        let src_id = self.src_reg.register(SourceCode {
             id_of_code: id_of_code.into(),
             code: code.into(),
-        }));
+        });
+        self.langs
+            .last_mut()
+            .expect("Internal error: Generator without language")
+            .conv_map_source
+            .push(src_id);
         self
     }
 
@@ -481,10 +673,17 @@ impl Generator {
     /// Register callback to extend/modify enum, if `foreign_enum` has #[derive(attr_name)]
     /// then after foreign code generation `cb` would be called, with full code of module,
     /// plus enum name
+    ///
+    /// # Panics
+    /// Panics if callback for such name already registered, or if `attr_name` is "Flags",
+    /// this name is reserved for `foreign_enum` backed by `bitflags`
     pub fn register_enum_attribute_callback<F>(mut self, attr_name: &str, cb: F) -> Self
     where
         F: Fn(&mut Vec<u8>, &str) + 'static,
     {
+        if attr_name == FLAGS_DERIVE {
+            panic!("This '{}' attribute name is reserved", attr_name);
+        }
         if self.enum_ext_handlers.contains_key(attr_name) {
             panic!(
                 "enum attribute callback for name '{}' already registered",
@@ -518,27 +717,12 @@ impl Generator {
     ///
     /// # Panics
     /// Panics on error
-    pub fn expand<S, D>(mut self, crate_name: &str, src: S, dst: D)
+    pub fn expand<S, D>(self, crate_name: &str, src: S, dst: D)
     where
         S: AsRef<Path>,
         D: AsRef<Path>,
     {
-        let src_cnt = std::fs::read_to_string(src.as_ref()).unwrap_or_else(|err| {
-            panic!(
-                "Error during read for file {}: {}",
-                src.as_ref().display(),
-                err
-            )
-        });
-
-        let src_id = self.src_reg.register(SourceCode {
-            id_of_code: format!("{}: {}", crate_name, src.as_ref().display()),
-            code: src_cnt,
-        });
-
-        if let Err(err) = self.expand_str(&[src_id], dst) {
-            panic_on_parse_error(&self.src_reg, &err);
-        }
+        self.expand_many(crate_name, &[src], dst);
     }
 
     /// process `srcs` and save result of macro expansion to `dst`
@@ -550,187 +734,690 @@ impl Generator {
         S: AsRef<Path>,
         D: AsRef<Path>,
     {
-        let mut src_ids = Vec::with_capacity(srcs.len());
-        for src in srcs {
-            let src_cnt = std::fs::read_to_string(src.as_ref()).unwrap_or_else(|err| {
-                panic!(
-                    "Error during read for file {}: {}",
-                    src.as_ref().display(),
-                    err
-                )
-            });
+        let ret = self.expand_files(crate_name, srcs, dst.as_ref());
+        self.report_json_diagnostics(dst.as_ref(), ret.as_ref().err());
+        if let Err(err) = ret {
+            panic_on_parse_error(&self.src_reg, &err);
+        }
+    }
+
+    /// process `src` and save result of macro expansion to `dst`,
+    /// the same as `expand`, but returns error instead of panic
+    pub fn try_expand<S, D>(
+        self,
+        crate_name: &str,
+        src: S,
+        dst: D,
+    ) -> std::result::Result<(), Error>
+    where
+        S: AsRef<Path>,
+        D: AsRef<Path>,
+    {
+        self.try_expand_many(crate_name, &[src], dst)
+    }
 
-            let src_id = self.src_reg.register(SourceCode {
-                id_of_code: format!("{}: {}", crate_name, src.as_ref().display()),
-                code: src_cnt,
-            });
-            src_ids.push(src_id);
+    /// process `srcs` and save result of macro expansion to `dst`,
+    /// the same as `expand_many`, but returns error instead of panic
+    pub fn try_expand_many<S, D>(
+        mut self,
+        crate_name: &str,
+        srcs: &[S],
+        dst: D,
+    ) -> std::result::Result<(), Error>
+    where
+        S: AsRef<Path>,
+        D: AsRef<Path>,
+    {
+        let ret = self.expand_files(crate_name, srcs, dst.as_ref());
+        self.report_json_diagnostics(dst.as_ref(), ret.as_ref().err());
+        ret.map_err(|err| Error::new(&self.src_reg, err))
+    }
+
+    fn expand_files<S, D>(&mut self, crate_name: &str, srcs: &[S], dst: D) -> Result<()>
+    where
+        S: AsRef<Path>,
+        D: AsRef<Path>,
+    {
+        // drop warnings of previous `Generator` in the same thread
+        take_warnings();
+        let mut glue_files = Vec::with_capacity(srcs.len());
+        for src in srcs {
+            let path = src.as_ref().to_path_buf();
+            let src_id = self.read_glue_file(crate_name, &path, invalid_src_id_span())?;
+            glue_files.push(GlueFile { src_id, path });
+        }
+        let mut annotated_files = Vec::new();
+        for root in &self.annotated_srcs {
+            let mut files = Vec::new();
+            collect_rust_files(root, &mut files)?;
+            for path in files {
+                let mod_path = module_path_of_file(root, &path)?;
+                annotated_files.push((path, mod_path));
+            }
+        }
+        let mut annotated_srcs = Vec::with_capacity(annotated_files.len());
+        for (path, mod_path) in annotated_files {
+            let src_id = self.read_glue_file(crate_name, &path, invalid_src_id_span())?;
+            annotated_srcs.push(AnnotatedFile { src_id, mod_path });
         }
 
-        if let Err(err) = self.expand_str(&src_ids, dst) {
-            panic_on_parse_error(&self.src_reg, &err);
+        self.expand_str(crate_name, glue_files, &annotated_srcs, dst)
+    }
+
+    fn report_json_diagnostics(&self, dst: &Path, err: Option<&DiagnosticError>) {
+        let warnings = take_warnings();
+        let output = match self.json_diagnostics {
+            Some(x) => x,
+            None => return,
+        };
+        let json = diagnostics_to_json(&self.src_reg, &warnings, err);
+        match output {
+            JsonDiagnosticsOutput::Stderr => eprint!("{}", json),
+            JsonDiagnosticsOutput::FileNextToOutput => {
+                let mut path = dst.as_os_str().to_os_string();
+                path.push(".diagnostics.json");
+                let path = PathBuf::from(path);
+                if let Err(err) = std::fs::write(&path, json) {
+                    println!(
+                        "cargo:warning=Can not write diagnostics to {}: {}",
+                        path.display(),
+                        err
+                    );
+                }
+            }
         }
     }
 
     /// process `src` and save result of macro expansion to `dst`
-    ///
-    /// # Panics
-    /// Panics on I/O errors
-    fn expand_str<D>(&mut self, src_ids: &[SourceId], dst: D) -> Result<()>
+    fn expand_str<D>(
+        &mut self,
+        crate_name: &str,
+        glue_files: Vec<GlueFile>,
+        annotated_srcs: &[AnnotatedFile],
+        dst: D,
+    ) -> Result<()>
     where
         D: AsRef<Path>,
     {
         if self.pointer_target_width == 0 {
-            panic!(
+            return Err(DiagnosticError::new_without_src_info(
                 r#"pointer target width unknown,
  set env CARGO_CFG_TARGET_POINTER_WIDTH environment variable,
  or use `with_pointer_target_width` function
-"#
-            );
+"#,
+            ));
         }
-        let items = self.init_types_map(self.pointer_target_width)?;
+        for lang in &self.langs {
+            if let LanguageConfig::Custom(ref backend) = lang.config {
+                let mod_name = lang.config.rust_module_name();
+                if syn::parse_str::<syn::Ident>(&mod_name).is_err() {
+                    return Err(DiagnosticError::new_without_src_info(format!(
+                        "name `{}` of language backend is invalid, it is used as part of Rust module name `{}`, \
+                         so it should contain only ASCII letters, digits and `_`",
+                        backend.name(),
+                        mod_name
+                    )));
+                }
+            }
+        }
+        let async_spawner = self
+            .async_spawner
+            .as_ref()
+            .map(|code| {
+                syn::parse_str::<syn::Expr>(code).map_err(|err| {
+                    DiagnosticError::new_without_src_info(format!(
+                        "Can not parse `{}` passed to `async_spawner`: {}",
+                        code, err
+                    ))
+                })
+            })
+            .transpose()?;
+        let utils_code = self.init_types_map(self.pointer_target_width)?;
+        let multi_lang = self.langs.len() > 1;
 
         let mut file =
             file_cache::FileWriteCache::new(dst.as_ref(), &mut file_cache::NoNeedFsOpsRegistration);
 
-        for item in items {
-            write!(&mut file, "{}", DisplayToTokens(&item)).expect(WRITE_TO_MEM_FAILED_MSG);
-        }
-
-        let mut items_to_expand = Vec::with_capacity(1000);
-
-        for src_id in src_ids {
-            let syn_file = syn::parse_file(self.src_reg.src(*src_id))
-                .map_err(|err| DiagnosticError::from_syn_err(*src_id, err))?;
-
-            for item in syn_file.items {
-                if let syn::Item::Macro(mut item_macro) = item {
-                    let is_our_macro = [
-                        FOREIGNER_CLASS_DEPRECATED,
-                        FOREIGN_CLASS,
-                        FOREIGN_ENUM,
-                        FOREIGN_INTERFACE_DEPRECATED,
-                        FOREIGN_CALLBACK,
-                        FOREIGN_TYPEMAP,
-                    ]
-                    .iter()
-                    .any(|x| item_macro.mac.path.is_ident(x));
-                    if !is_our_macro {
-                        writeln!(&mut file, "{}", DisplayToTokens(&item_macro))
-                            .expect("mem I/O failed");
-                        continue;
-                    }
-                    debug!("Found {}", DisplayToTokens(&item_macro.mac.path));
-                    if item_macro.mac.tokens.is_empty() {
-                        return Err(DiagnosticError::new(
-                            *src_id,
-                            item_macro.span(),
-                            format!(
-                                "missing tokens in call of macro '{}'",
-                                DisplayToTokens(&item_macro.mac.path)
-                            ),
-                        ));
-                    }
-                    let mut tts = TokenStream::new();
-                    mem::swap(&mut tts, &mut item_macro.mac.tokens);
-                    if item_macro.mac.path.is_ident(FOREIGNER_CLASS_DEPRECATED)
-                        || item_macro.mac.path.is_ident(FOREIGN_CLASS)
-                    {
-                        if item_macro.mac.path.is_ident(FOREIGNER_CLASS_DEPRECATED) {
-                            println!(
-                                "cargo:warning={} is deprecated, use {} instead",
-                                FOREIGNER_CLASS_DEPRECATED, FOREIGN_CLASS
-                            );
-                        }
-                        let fclass = code_parse::parse_foreigner_class(*src_id, &self.config, tts)?;
-                        debug!("expand_foreigner_class: self_desc {:?}", fclass.self_desc);
-                        self.conv_map.register_foreigner_class(&fclass);
-                        items_to_expand.push(ItemToExpand::Class(Box::new(fclass)));
-                    } else if item_macro.mac.path.is_ident(FOREIGN_ENUM) {
-                        let fenum = code_parse::parse_foreign_enum(*src_id, tts)?;
-                        items_to_expand.push(ItemToExpand::Enum(fenum));
-                    } else if item_macro.mac.path.is_ident(FOREIGN_INTERFACE_DEPRECATED)
-                        || item_macro.mac.path.is_ident(FOREIGN_CALLBACK)
-                    {
-                        if item_macro.mac.path.is_ident(FOREIGN_INTERFACE_DEPRECATED) {
-                            println!(
-                                "cargo:warning={} is deprecated, use {} instead",
-                                FOREIGN_INTERFACE_DEPRECATED, FOREIGN_CALLBACK
-                            );
-                        }
-                        let finterface = code_parse::parse_foreign_interface(*src_id, tts)?;
-                        items_to_expand.push(ItemToExpand::Interface(finterface));
-                    } else if item_macro.mac.path.is_ident(FOREIGN_TYPEMAP) {
-                        self.conv_map.parse_foreign_typemap_macro(*src_id, tts)?;
-                    } else {
-                        unreachable!();
+        if !multi_lang {
+            for item in &utils_code[0] {
+                write!(&mut file, "{}", DisplayToTokens(item)).expect(WRITE_TO_MEM_FAILED_MSG);
+            }
+        }
+
+        let mut langs_items: Vec<Vec<ItemToExpand>> = self
+            .langs
+            .iter()
+            .map(|_| Vec::with_capacity(1000))
+            .collect();
+
+        let mut langs_items_glue_mod: Vec<Vec<Option<usize>>> =
+            self.langs.iter().map(|_| Vec::new()).collect();
+        let glue_mods = {
+            let mut ctx = GlueParseCtx {
+                crate_name,
+                out: &mut file,
+                items_to_expand: &mut langs_items,
+                items_glue_mod: &mut langs_items_glue_mod,
+                glue_mods: Vec::new(),
+                cur_glue_mod: None,
+            };
+            for glue in glue_files {
+                let mod_dir = file_dir(&glue.path).to_path_buf();
+                self.parse_glue_file(&mut ctx, glue, &mod_dir)?;
+            }
+            ctx.glue_mods
+        };
+        let mut annotated_items = Vec::new();
+        for annotated in annotated_srcs {
+            let src_id = annotated.src_id;
+            set_parsed_src_id(src_id);
+            let syn_file = syn::parse_file(self.src_reg.src(src_id))
+                .map_err(|err| DiagnosticError::from_syn_err(src_id, err))?;
+            annotated_items.extend(annotations::parse_annotated_items(
+                src_id,
+                &annotated.mod_path,
+                &syn_file,
+            )?);
+        }
+        // order of files can not be controlled by user, so enums go first,
+        // as in glue files they are usually defined before usage
+        annotated_items.sort_by_key(|item| match item {
+            ItemToExpand::Enum(_) => 0,
+            _ => 1,
+        });
+        for item in annotated_items {
+            for (lang, items) in self.langs.iter_mut().zip(langs_items.iter_mut()) {
+                match item.clone() {
+                    ItemToExpand::Class(fclass) => {
+                        add_foreign_class(&mut lang.conv_map, items, *fclass)?
                     }
-                } else {
-                    writeln!(&mut file, "{}", DisplayToTokens(&item)).expect("mem I/O failed");
+                    item => items.push(item),
+                }
+            }
+        }
+        set_parsed_src_id(SourceId::none());
+        for items in &langs_items {
+            check_closure_callbacks_names(items)?;
+            check_abstract_classes_usage(items)?;
+        }
+        let mut langs_types = Vec::with_capacity(self.langs.len());
+        let mut glue_mods_code = vec![String::new(); glue_mods.len()];
+        for (((lang, utils), items_to_expand), items_glue_mod) in self
+            .langs
+            .iter_mut()
+            .zip(utils_code)
+            .zip(&langs_items)
+            .zip(&mut langs_items_glue_mod)
+        {
+            // annotated items are declared outside of glue modules
+            items_glue_mod.resize(items_to_expand.len(), None);
+            let generator = Generator::language_generator(&lang.config);
+            // in case of several languages each one has its own module,
+            // so the same names from different "types maps" do not collide
+            let utils_macros = utils
+                .iter()
+                .filter(|item| match item {
+                    syn::Item::Macro(item_macro) => item_macro.mac.path.is_ident("macro_rules"),
+                    _ => false,
+                })
+                .cloned()
+                .collect::<Vec<_>>();
+            let mut lang_code = if multi_lang {
+                let mut lang_code = Vec::new();
+                for item in utils {
+                    write!(&mut lang_code, "{}", DisplayToTokens(&item))
+                        .expect(WRITE_TO_MEM_FAILED_MSG);
+                }
+                lang_code
+            } else {
+                file.take_content()
+            };
+            lang.conv_map
+                .set_explain_conv_failures(self.explain_conv_failures);
+            lang.conv_map.set_async_spawner(async_spawner.clone());
+            let code = generator.expand_items(
+                &mut lang.conv_map,
+                self.pointer_target_width,
+                &lang.foreign_lang_helpers,
+                items_to_expand.clone(),
+                self.remove_not_generated_files,
+                ExtHandlers {
+                    class_ext_handlers: &self.class_ext_handlers,
+                    method_ext_handlers: &self.method_ext_handlers,
+                    enum_ext_handlers: &self.enum_ext_handlers,
+                },
+            );
+            if let Some(ref dir) = self.conv_graph_dot_dir {
+                match (write_conv_graph_dot(dir, lang), &code) {
+                    (Ok(()), _) => {}
+                    (Err(err), Ok(_)) => return Err(err),
+                    // graph is needed to investigate this error, so do not replace it
+                    (Err(err), Err(_)) => emit_warning_without_src_info(err.to_string()),
+                }
+            }
+            let code = code?;
+            let mut code_glue_mod = vec![None; code.code.len()];
+            for (item_idx, range) in code.items_code {
+                for x in &mut code_glue_mod[range] {
+                    *x = items_glue_mod[item_idx];
+                }
+            }
+            let mut mods_items_code = vec![Vec::new(); glue_mods.len()];
+            for (elem, glue_mod) in code.code.into_iter().zip(code_glue_mod) {
+                match glue_mod {
+                    Some(idx) => mods_items_code[idx].push(elem),
+                    None => writeln!(&mut lang_code, "{}", elem).expect(WRITE_TO_MEM_FAILED_MSG),
+                }
+            }
+            let mods_code = glue_mod::generate_glue_mods_code(
+                &glue_mods,
+                mods_items_code,
+                &lang.config.rust_module_name(),
+                multi_lang,
+                &utils_macros,
+            )?;
+            lang_code.extend_from_slice(mods_code.top.as_bytes());
+            let lang_code = if multi_lang {
+                // code of glue modules should be seen by post processing,
+                // it only appends code, so modules code can be cut back
+                let lang_code_len = lang_code.len();
+                for mod_code in &mods_code.mods {
+                    lang_code.extend_from_slice(mod_code.as_bytes());
+                }
+                let mods_code_len = lang_code.len() - lang_code_len;
+                let mut lang_code = generator.post_proccess_code(
+                    &mut lang.conv_map,
+                    self.pointer_target_width,
+                    lang_code,
+                )?;
+                lang_code.drain(lang_code_len..lang_code_len + mods_code_len);
+                if mods_code.mods.iter().any(|x| !x.is_empty()) {
+                    // items of language module are used by code in glue modules
+                    lang_code = glue_mod::make_code_visible_in_crate(lang_code)?;
+                }
+                for (all_code, mod_code) in glue_mods_code.iter_mut().zip(mods_code.mods) {
+                    all_code.push_str(&mod_code);
+                }
+                lang_code
+            } else {
+                let lang_code =
+                    glue_mod::insert_glue_mods_code(lang_code, &glue_mods, &mods_code.mods);
+                generator.post_proccess_code(
+                    &mut lang.conv_map,
+                    self.pointer_target_width,
+                    lang_code,
+                )?
+            };
+            if self.lint_type_maps {
+                // the first "types map" source is always builtin one, see `TargetLanguage::new`
+                let builtin = &lang.conv_map_source[..1];
+                for warn in lang
+                    .conv_map
+                    .lint(builtin, &lang.config.typemap_cfg_options())
+                {
+                    emit_warning_with_notes(&self.src_reg, warn);
                 }
             }
+            if self.model_output.is_some() || self.api_baseline.is_some() {
+                langs_types.push(model::LangForeignTypes::collect(
+                    lang.config.name(),
+                    &mut lang.conv_map,
+                    items_to_expand,
+                ));
+            }
+            if multi_lang {
+                writeln!(
+                    &mut file,
+                    "pub mod {} {{\n#[allow(unused_imports)]\nuse super::*;",
+                    lang.config.rust_module_name()
+                )
+                .expect(WRITE_TO_MEM_FAILED_MSG);
+                file.write_all(&lang_code).expect(WRITE_TO_MEM_FAILED_MSG);
+                writeln!(&mut file, "}}").expect(WRITE_TO_MEM_FAILED_MSG);
+            } else {
+                file.replace_content(lang_code);
+            }
+        }
+
+        if multi_lang {
+            let code =
+                glue_mod::insert_glue_mods_code(file.take_content(), &glue_mods, &glue_mods_code);
+            file.replace_content(code);
+        }
+
+        let model_json = if self.model_output.is_some() || self.api_baseline.is_some() {
+            // items are described as they are seen by the first language
+            model::model_to_json(&langs_items[0], &langs_types)
+        } else {
+            String::new()
+        };
+        if let Some((ref baseline_path, mode)) = self.api_baseline {
+            check_api_compatibility(baseline_path, mode, &model_json)?;
+        }
+        if let Some(ref model_output) = self.model_output {
+            std::fs::write(model_output, &model_json).map_err(|err| {
+                DiagnosticError::new_without_src_info(format!(
+                    "Error during write to file {}: {}",
+                    model_output.display(),
+                    err
+                ))
+            })?;
         }
-        let generator = Generator::language_generator(&self.config);
-        let code = generator.expand_items(
-            &mut self.conv_map,
-            self.pointer_target_width,
-            &self.foreign_lang_helpers,
-            items_to_expand,
-            self.remove_not_generated_files,
-            ExtHandlers {
-                class_ext_handlers: &self.class_ext_handlers,
-                method_ext_handlers: &self.method_ext_handlers,
-                enum_ext_handlers: &self.enum_ext_handlers,
-            },
-        )?;
-        for elem in code {
-            writeln!(&mut file, "{}", elem).expect(WRITE_TO_MEM_FAILED_MSG);
-        }
-
-        let source_bytes = file.take_content();
-        let source_bytes = generator.post_proccess_code(
-            &mut self.conv_map,
-            self.pointer_target_width,
-            source_bytes,
-        )?;
-        file.replace_content(source_bytes);
 
         if self.rustfmt_bindings {
             let source_bytes = file.take_content();
-            let new_cnt =
-                rustfmt_cnt(source_bytes, RustEdition::Edition2018).unwrap_or_else(|err| {
-                    panic!("Error during running of rustfmt: {}", err);
-                });
+            let new_cnt = rustfmt_cnt(source_bytes, RustEdition::Edition2018).map_err(|err| {
+                DiagnosticError::new_without_src_info(format!(
+                    "Error during running of rustfmt: {}",
+                    err
+                ))
+            })?;
             file.replace_content(new_cnt);
         }
 
-        file.update_file_if_necessary().unwrap_or_else(|err| {
-            panic!(
+        file.update_file_if_necessary().map_err(|err| {
+            DiagnosticError::new_without_src_info(format!(
                 "Error during write to file {}: {}",
                 dst.as_ref().display(),
                 err
-            );
+            ))
+        })?;
+        Ok(())
+    }
+
+    /// read file with glue code and register it in `SourceRegistry`,
+    /// `sp` is place from where file is referenced
+    fn read_glue_file(
+        &mut self,
+        crate_name: &str,
+        path: &Path,
+        sp: SourceIdSpan,
+    ) -> Result<SourceId> {
+        let src_cnt = std::fs::read_to_string(path).map_err(|err| {
+            DiagnosticError::new2(
+                sp,
+                format!("Error during read for file {}: {}", path.display(), err),
+            )
+        })?;
+        println!("cargo:rerun-if-changed={}", path.display());
+        // the same file can be referenced via different paths,
+        // like `sub/../glue.rs.in` and `glue.rs.in`
+        let canonical_path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        let id_of_code = format!("{}: {}", crate_name, canonical_path.display());
+        if self.src_reg.is_registered(&id_of_code) {
+            return Err(DiagnosticError::new2(
+                sp,
+                format!("File {} already included", path.display()),
+            ));
+        }
+        Ok(self.src_reg.register(SourceCode {
+            id_of_code,
+            code: src_cnt,
+        }))
+    }
+
+    /// `mod_dir` is directory for files of child modules
+    fn parse_glue_file(
+        &mut self,
+        ctx: &mut GlueParseCtx,
+        glue: GlueFile,
+        mod_dir: &Path,
+    ) -> Result<()> {
+        set_parsed_src_id(glue.src_id);
+        let syn_file = syn::parse_file(self.src_reg.src(glue.src_id))
+            .map_err(|err| DiagnosticError::from_syn_err(glue.src_id, err))?;
+        self.parse_glue_items(ctx, &glue, mod_dir, syn_file.items)
+    }
+
+    fn parse_glue_mod(
+        &mut self,
+        ctx: &mut GlueParseCtx,
+        glue: &GlueFile,
+        mod_dir: &Path,
+        item_mod: syn::ItemMod,
+    ) -> Result<()> {
+        let mut path_attr = None;
+        let mut attrs = Vec::with_capacity(item_mod.attrs.len());
+        for attr in item_mod.attrs {
+            if attr.path.is_ident("path") {
+                match attr.parse_meta() {
+                    Ok(syn::Meta::NameValue(syn::MetaNameValue {
+                        lit: syn::Lit::Str(lit_str),
+                        ..
+                    })) => path_attr = Some(lit_str),
+                    _ => {
+                        return Err(DiagnosticError::new(
+                            glue.src_id,
+                            attr.span(),
+                            "expect #[path = \"file\"]",
+                        ))
+                    }
+                }
+            } else {
+                attrs.push(attr);
+            }
+        }
+        let vis = item_mod.vis;
+        let ident = item_mod.ident;
+        writeln!(ctx.out, "{} {{", quote::quote!(#(#attrs)* #vis mod #ident))
+            .expect(WRITE_TO_MEM_FAILED_MSG);
+        let glue_mod_idx = ctx.glue_mods.len();
+        ctx.glue_mods.push(glue_mod::GlueMod {
+            ident: ident.clone(),
+            parent: ctx.cur_glue_mod,
+            cfg_attrs: attrs
+                .into_iter()
+                .filter(|attr| attr.path.is_ident("cfg"))
+                .collect(),
+            code_pos: 0,
         });
+        let parent_glue_mod = ctx.cur_glue_mod.replace(glue_mod_idx);
+        let child_mod_dir = mod_dir.join(ident.to_string());
+        match item_mod.content {
+            Some((_, items)) => self.parse_glue_items(ctx, glue, &child_mod_dir, items)?,
+            None => {
+                let (path, child_mod_dir, sp) = if let Some(path_attr) = path_attr {
+                    let path = file_dir(&glue.path).join(path_attr.value());
+                    let child_mod_dir = file_dir(&path).to_path_buf();
+                    (path, child_mod_dir, path_attr.span())
+                } else {
+                    let file_name = format!("{}.rs", ident);
+                    let path = mod_dir.join(&file_name);
+                    let path = if path.exists() {
+                        path
+                    } else {
+                        child_mod_dir.join("mod.rs")
+                    };
+                    (path, child_mod_dir, ident.span())
+                };
+                let src_id = self.read_glue_file(ctx.crate_name, &path, (glue.src_id, sp))?;
+                self.parse_glue_file(ctx, GlueFile { src_id, path }, &child_mod_dir)?;
+                set_parsed_src_id(glue.src_id);
+            }
+        }
+        // code generated for items of module is placed here
+        ctx.glue_mods[glue_mod_idx].code_pos = ctx.out.content_len();
+        ctx.cur_glue_mod = parent_glue_mod;
+        writeln!(ctx.out, "}}").expect(WRITE_TO_MEM_FAILED_MSG);
+        Ok(())
+    }
+
+    /// Parse flapigen's macroses and copy other items into output,
+    /// `include!` and `mod` are resolved relative to `glue` file
+    fn parse_glue_items(
+        &mut self,
+        ctx: &mut GlueParseCtx,
+        glue: &GlueFile,
+        mod_dir: &Path,
+        items: Vec<syn::Item>,
+    ) -> Result<()> {
+        for item in items {
+            if let syn::Item::Macro(ref item_macro) = item {
+                if item_macro.mac.path.is_ident("include") {
+                    if let Ok(include_path) =
+                        syn::parse2::<syn::LitStr>(item_macro.mac.tokens.clone())
+                    {
+                        let path = file_dir(&glue.path).join(include_path.value());
+                        let src_id = self.read_glue_file(
+                            ctx.crate_name,
+                            &path,
+                            (glue.src_id, include_path.span()),
+                        )?;
+                        self.parse_glue_file(ctx, GlueFile { src_id, path }, mod_dir)?;
+                        set_parsed_src_id(glue.src_id);
+                        continue;
+                    }
+                }
+            }
+            if let syn::Item::Mod(item_mod) = item {
+                self.parse_glue_mod(ctx, glue, mod_dir, item_mod)?;
+                continue;
+            }
+            if let syn::Item::Macro(mut item_macro) = item {
+                let is_our_macro = [
+                    FOREIGNER_CLASS_DEPRECATED,
+                    FOREIGN_CLASS,
+                    FOREIGN_ENUM,
+                    FOREIGN_INTERFACE_DEPRECATED,
+                    FOREIGN_CALLBACK,
+                    FOREIGN_CONST,
+                    FOREIGN_FN,
+                    FOREIGN_TYPEMAP,
+                ]
+                .iter()
+                .any(|x| item_macro.mac.path.is_ident(x));
+                if !is_our_macro {
+                    writeln!(ctx.out, "{}", DisplayToTokens(&item_macro)).expect("mem I/O failed");
+                    continue;
+                }
+                // items disabled by `#[cfg]` are omitted from Rust and foreign code,
+                // with help of `flapigen_lang` item can be enabled only for some languages
+                let mut enabled_langs = Vec::with_capacity(self.langs.len());
+                for (idx, lang) in self.langs.iter().enumerate() {
+                    if code_parse::is_enabled_by_cfg_for_lang(
+                        &item_macro.attrs,
+                        &lang.config.name(),
+                    )
+                    .map_err(|err| DiagnosticError::from_syn_err(glue.src_id, err))?
+                    {
+                        enabled_langs.push(idx);
+                    }
+                }
+                if enabled_langs.is_empty() {
+                    continue;
+                }
+                debug!("Found {}", DisplayToTokens(&item_macro.mac.path));
+                if item_macro.mac.tokens.is_empty() {
+                    return Err(DiagnosticError::new(
+                        glue.src_id,
+                        item_macro.span(),
+                        format!(
+                            "missing tokens in call of macro '{}'",
+                            DisplayToTokens(&item_macro.mac.path)
+                        ),
+                    ));
+                }
+                let mut tts = TokenStream::new();
+                mem::swap(&mut tts, &mut item_macro.mac.tokens);
+                if item_macro.mac.path.is_ident(FOREIGNER_CLASS_DEPRECATED)
+                    || item_macro.mac.path.is_ident(FOREIGN_CLASS)
+                {
+                    if item_macro.mac.path.is_ident(FOREIGNER_CLASS_DEPRECATED) {
+                        emit_warning(
+                            (glue.src_id, item_macro.mac.path.span()),
+                            format!(
+                                "{} is deprecated, use {} instead",
+                                FOREIGNER_CLASS_DEPRECATED, FOREIGN_CLASS
+                            ),
+                        );
+                    }
+                    // syntax of class depends on language, so parse it for each one
+                    for idx in &enabled_langs {
+                        let lang = &mut self.langs[*idx];
+                        let fclass = code_parse::parse_foreigner_class(
+                            glue.src_id,
+                            &lang.config,
+                            tts.clone(),
+                        )?;
+                        debug!("expand_foreigner_class: self_desc {:?}", fclass.self_desc);
+                        add_foreign_class(
+                            &mut lang.conv_map,
+                            &mut ctx.items_to_expand[*idx],
+                            fclass,
+                        )?;
+                    }
+                } else if item_macro.mac.path.is_ident(FOREIGN_ENUM) {
+                    let fenum = code_parse::parse_foreign_enum(glue.src_id, tts)?;
+                    ctx.push_item(&enabled_langs, ItemToExpand::Enum(fenum));
+                } else if item_macro.mac.path.is_ident(FOREIGN_INTERFACE_DEPRECATED)
+                    || item_macro.mac.path.is_ident(FOREIGN_CALLBACK)
+                {
+                    if item_macro.mac.path.is_ident(FOREIGN_INTERFACE_DEPRECATED) {
+                        emit_warning(
+                            (glue.src_id, item_macro.mac.path.span()),
+                            format!(
+                                "{} is deprecated, use {} instead",
+                                FOREIGN_INTERFACE_DEPRECATED, FOREIGN_CALLBACK
+                            ),
+                        );
+                    }
+                    let finterface = code_parse::parse_foreign_interface(glue.src_id, tts)?;
+                    ctx.push_item(&enabled_langs, ItemToExpand::Interface(finterface));
+                } else if item_macro.mac.path.is_ident(FOREIGN_CONST) {
+                    let consts = code_parse::parse_foreign_consts(glue.src_id, tts)?;
+                    for fconst in consts {
+                        ctx.push_item(&enabled_langs, ItemToExpand::Const(Box::new(fconst)));
+                    }
+                } else if item_macro.mac.path.is_ident(FOREIGN_FN) {
+                    for idx in &enabled_langs {
+                        let fns = code_parse::parse_foreign_fns(
+                            glue.src_id,
+                            &self.langs[*idx].config,
+                            tts.clone(),
+                        )?;
+                        ctx.items_to_expand[*idx]
+                            .extend(fns.into_iter().map(|x| ItemToExpand::Fn(Box::new(x))));
+                    }
+                } else if item_macro.mac.path.is_ident(FOREIGN_TYPEMAP) {
+                    for idx in &enabled_langs {
+                        self.langs[*idx]
+                            .conv_map
+                            .parse_foreign_typemap_macro(glue.src_id, tts.clone())?;
+                    }
+                } else {
+                    unreachable!();
+                }
+                ctx.update_items_glue_mod();
+            } else {
+                writeln!(ctx.out, "{}", DisplayToTokens(&item)).expect("mem I/O failed");
+            }
+        }
         Ok(())
     }
 
-    fn init_types_map(&mut self, target_pointer_width: usize) -> Result<Vec<syn::Item>> {
+    /// returns utils code for each language
+    fn init_types_map(&mut self, target_pointer_width: usize) -> Result<Vec<Vec<syn::Item>>> {
         if self.init_done {
-            return Ok(vec![]);
+            return Ok(self.langs.iter().map(|_| vec![]).collect());
         }
         self.init_done = true;
-        for code_id in &self.conv_map_source {
-            let code = self.src_reg.src(*code_id);
-            self.conv_map.merge(*code_id, code, target_pointer_width)?;
-        }
+        let mut ret = Vec::with_capacity(self.langs.len());
+        for lang in &mut self.langs {
+            for code_id in &lang.conv_map_source {
+                let code = self.src_reg.src(*code_id);
+                set_parsed_src_id(*code_id);
+                lang.conv_map.merge(*code_id, code, target_pointer_width)?;
+            }
+            set_parsed_src_id(SourceId::none());
 
-        if self.conv_map.is_empty() {
-            return Err(DiagnosticError::new_without_src_info(
-                "After merge all \"types maps\" have no convertion code",
-            ));
+            if lang.conv_map.is_empty() {
+                return Err(DiagnosticError::new_without_src_info(
+                    "After merge all \"types maps\" have no convertion code",
+                ));
+            }
+            ret.push(lang.conv_map.take_utils_code());
         }
-
-        Ok(self.conv_map.take_utils_code())
+        Ok(ret)
     }
 
     fn language_generator(cfg: &LanguageConfig) -> &dyn LanguageGenerator {
@@ -738,10 +1425,391 @@ impl Generator {
             LanguageConfig::JavaConfig(ref java_cfg) => java_cfg,
             LanguageConfig::CppConfig(ref cpp_cfg) => cpp_cfg,
             LanguageConfig::PythonConfig(ref python_cfg) => python_cfg,
+            LanguageConfig::Custom(ref backend) => backend,
+        }
+    }
+}
+
+/// File with `foreign_class!` and other macroses
+struct GlueFile {
+    src_id: SourceId,
+    path: PathBuf,
+}
+
+/// File with items marked by `#[flapigen::export]` and friends
+struct AnnotatedFile {
+    src_id: SourceId,
+    /// path of file's module relative to the scanned directory
+    mod_path: Vec<syn::Ident>,
+}
+
+struct GlueParseCtx<'a> {
+    crate_name: &'a str,
+    out: &'a mut file_cache::FileWriteCache,
+    /// items for each language, in the same order as `Generator::langs`
+    items_to_expand: &'a mut [Vec<ItemToExpand>],
+    /// index in `glue_mods` of module where item was declared,
+    /// for each item in `items_to_expand`
+    items_glue_mod: &'a mut [Vec<Option<usize>>],
+    glue_mods: Vec<glue_mod::GlueMod>,
+    /// module which items are parsed now
+    cur_glue_mod: Option<usize>,
+}
+
+impl GlueParseCtx<'_> {
+    fn push_item(&mut self, langs: &[usize], item: ItemToExpand) {
+        for idx in langs {
+            self.items_to_expand[*idx].push(item.clone());
+        }
+    }
+
+    /// Remember current module for just added items
+    fn update_items_glue_mod(&mut self) {
+        for (items, glue_mods) in self
+            .items_to_expand
+            .iter()
+            .zip(self.items_glue_mod.iter_mut())
+        {
+            glue_mods.resize(items.len(), self.cur_glue_mod);
+        }
+    }
+}
+
+/// Register class in "types map" of language and add it to items to expand,
+/// classes for iterators returned by its methods are added before it,
+/// methods of base class are added to class
+fn add_foreign_class(
+    conv_map: &mut TypeMap,
+    items: &mut Vec<ItemToExpand>,
+    mut fclass: ForeignClassInfo,
+) -> Result<()> {
+    if let Some(base_name) = fclass.base_class.clone() {
+        let base = items.iter().find_map(|item| match item {
+            ItemToExpand::Class(ref x) if x.name == base_name => Some(x),
+            _ => None,
+        });
+        match base {
+            Some(base) if base.is_abstract => fclass.inherit_methods(base),
+            Some(base) => {
+                return Err(DiagnosticError::new(
+                    fclass.src_id,
+                    base_name.span(),
+                    format!(
+                        "class {} is not abstract, so it can not be base class of {}",
+                        base_name, fclass.name
+                    ),
+                )
+                .add_span_note((base.src_id, base.span()), "declared here"));
+            }
+            None => {
+                return Err(DiagnosticError::new(
+                    fclass.src_id,
+                    base_name.span(),
+                    format!(
+                        "base class {} of {} not found, it should be declared before",
+                        base_name, fclass.name
+                    ),
+                ));
+            }
+        }
+    }
+    for iter_class in fclass.iterator_classes() {
+        let prev = items.iter().find_map(|item| match item {
+            ItemToExpand::Class(ref x) if x.name == iter_class.name => Some(x),
+            _ => None,
+        });
+        if let Some(prev) = prev {
+            if normalize_type(&prev.self_type_as_ty())
+                != normalize_type(&iter_class.self_type_as_ty())
+            {
+                return Err(DiagnosticError::new(
+                    fclass.src_id,
+                    iter_class.span(),
+                    format!(
+                        "class {} for iterator conflicts with class with the same name",
+                        iter_class.name
+                    ),
+                )
+                .add_span_note((prev.src_id, prev.span()), "previous definition here"));
+            }
+            continue;
+        }
+        conv_map.register_foreigner_class(&iter_class);
+        items.push(ItemToExpand::Class(Box::new(iter_class)));
+    }
+    conv_map.register_foreigner_class(&fclass);
+    items.push(ItemToExpand::Class(Box::new(fclass)));
+    Ok(())
+}
+
+/// Callbacks generated for closures are named after types of closure's arguments,
+/// so check that the names are unique and do not conflict with names of other items
+fn check_closure_callbacks_names(items: &[ItemToExpand]) -> Result<()> {
+    let mut names = FxHashMap::<String, SourceIdSpan>::default();
+    for item in items {
+        let (name, sp) = match item {
+            ItemToExpand::Class(ref x) => (&x.name, (x.src_id, x.span())),
+            ItemToExpand::Interface(ref x) => (&x.name, x.src_id_span()),
+            ItemToExpand::Enum(ref x) => (&x.name, (x.src_id, x.span())),
+            ItemToExpand::Const(_) | ItemToExpand::Fn(_) => continue,
+        };
+        names.insert(name.to_string(), sp);
+    }
+    let mut callbacks = FxHashMap::<String, (String, SourceIdSpan)>::default();
+    let methods = items
+        .iter()
+        .flat_map(|item| -> Vec<(SourceId, &ForeignMethod)> {
+            match item {
+                ItemToExpand::Class(ref x) => x.methods.iter().map(|m| (x.src_id, m)).collect(),
+                ItemToExpand::Fn(ref x) => vec![(x.src_id, &x.method)],
+                _ => vec![],
+            }
+        });
+    for (src_id, method) in methods {
+        for sig in method.closure_signatures() {
+            let sp = (src_id, sig.span());
+            let name = closure_callback_name(sig, sig.span()).to_string();
+            let sig = format!("Fn{}", DisplayToTokens(sig));
+            if let Some(prev) = names.get(&name) {
+                return Err(DiagnosticError::new2(
+                    sp,
+                    format!(
+                        "callback {} generated for closure `{}` conflicts with item with the same name",
+                        name, sig
+                    ),
+                )
+                .add_span_note(*prev, "declared here"));
+            }
+            if let Some((prev_sig, prev_sp)) = callbacks.get(&name) {
+                if *prev_sig != sig {
+                    return Err(DiagnosticError::new2(
+                        sp,
+                        format!(
+                            "callback {} generated for closure `{}` conflicts with callback for closure `{}`",
+                            name, sig, prev_sig
+                        ),
+                    )
+                    .add_span_note(*prev_sp, "previous closure here"));
+                }
+                continue;
+            }
+            callbacks.insert(name, (sig, sp));
+        }
+    }
+    Ok(())
+}
+
+fn file_dir(path: &Path) -> &Path {
+    path.parent().unwrap_or_else(|| Path::new(""))
+}
+
+/// `path` is file or directory, in last case find ".rs" files recursively
+fn collect_rust_files(path: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    if !path.is_dir() {
+        files.push(path.into());
+        return Ok(());
+    }
+    // to notice new files
+    println!("cargo:rerun-if-changed={}", path.display());
+    let read_dir_err = |err| {
+        DiagnosticError::new_without_src_info(format!(
+            "Can not read directory {}: {}",
+            path.display(),
+            err
+        ))
+    };
+    let mut entries = Vec::new();
+    for entry in std::fs::read_dir(path).map_err(read_dir_err)? {
+        entries.push(entry.map_err(read_dir_err)?.path());
+    }
+    // to make order of generated code independent from file system
+    entries.sort();
+    for entry in entries {
+        if entry.is_dir() || entry.extension().is_some_and(|ext| ext == "rs") {
+            collect_rust_files(&entry, files)?;
+        }
+    }
+    Ok(())
+}
+
+/// Module of file found in `root` during scan of annotated sources,
+/// like `a::b` for `root/a/b.rs` or `root/a/b/mod.rs`
+fn module_path_of_file(root: &Path, path: &Path) -> Result<Vec<syn::Ident>> {
+    let rel_path = path.strip_prefix(root).unwrap_or_else(|_| Path::new(""));
+    let mut names: Vec<String> = rel_path
+        .parent()
+        .into_iter()
+        .flat_map(|x| x.components())
+        .map(|x| x.as_os_str().to_string_lossy().into_owned())
+        .collect();
+    if let Some(stem) = rel_path.file_stem() {
+        let stem = stem.to_string_lossy();
+        let is_root_of_module =
+            stem == "mod" || (names.is_empty() && (stem == "lib" || stem == "main"));
+        if !is_root_of_module {
+            names.push(stem.into_owned());
+        }
+    }
+    names
+        .iter()
+        .map(|name| {
+            syn::parse_str::<syn::Ident>(name).map_err(|_| {
+                DiagnosticError::new_without_src_info(format!(
+                    "Can not get name of module for {}: `{}` is not valid identifier",
+                    path.display(),
+                    name
+                ))
+            })
+        })
+        .collect()
+}
+
+fn check_api_compatibility(baseline_path: &Path, mode: ApiCheckMode, model: &str) -> Result<()> {
+    let baseline = match std::fs::read_to_string(baseline_path) {
+        Ok(x) => x,
+        Err(err) => {
+            emit_warning_without_src_info(format!(
+                "Can not read API baseline {}: {}, check skipped",
+                baseline_path.display(),
+                err
+            ));
+            return Ok(());
+        }
+    };
+    let changes = api_check::compare_models(&baseline, model).map_err(|err| {
+        DiagnosticError::new_without_src_info(format!(
+            "API check against {} failed: {}",
+            baseline_path.display(),
+            err
+        ))
+    })?;
+    let mut breaking = Vec::new();
+    for change in changes {
+        debug!("api check: {}", change);
+        if change.kind == api_check::ApiChangeKind::Breaking {
+            breaking.push(change.description);
+        }
+    }
+    if breaking.is_empty() {
+        return Ok(());
+    }
+    match mode {
+        ApiCheckMode::Warn => {
+            for change in breaking {
+                emit_warning_without_src_info(format!("breaking API change: {}", change));
+            }
+            Ok(())
+        }
+        ApiCheckMode::Error => Err(DiagnosticError::new_without_src_info(format!(
+            "API is not compatible with {}:\n{}",
+            baseline_path.display(),
+            breaking.join("\n")
+        ))),
+    }
+}
+
+fn write_conv_graph_dot(dir: &Path, lang: &TargetLanguage) -> Result<()> {
+    let path = dir.join(format!("{}-conv-graph.dot", lang.config.name()));
+    std::fs::create_dir_all(dir)
+        .and_then(|_| std::fs::write(&path, lang.conv_map.to_dot()))
+        .map_err(|err| {
+            DiagnosticError::new_without_src_info(format!(
+                "Error during write to file {}: {}",
+                path.display(),
+                err
+            ))
+        })
+}
+
+impl TargetLanguage {
+    fn new(config: LanguageConfig, src_reg: &mut SourceRegistry) -> TargetLanguage {
+        let mut conv_map_source = Vec::new();
+        let mut foreign_lang_helpers = Vec::new();
+        match config {
+            LanguageConfig::JavaConfig(ref java_cfg) => {
+                conv_map_source.push(
+                    src_reg.register(SourceCode {
+                        id_of_code: "jni-include.rs".into(),
+                        code: include_str!("java_jni/jni-include.rs")
+                            .replace(
+                                "java.util.Optional",
+                                &format!("{}.Optional", java_cfg.optional_package),
+                            )
+                            .replace(
+                                "java/util/Optional",
+                                &format!(
+                                    "{}/Optional",
+                                    java_cfg.optional_package.replace('.', "/")
+                                ),
+                            ),
+                    }),
+                );
+            }
+            LanguageConfig::CppConfig(..) => {
+                conv_map_source.push(src_reg.register(SourceCode {
+                    id_of_code: "cpp-include.rs".into(),
+                    code: include_str!("cpp/cpp-include.rs").into(),
+                }));
+                foreign_lang_helpers.push(SourceCode {
+                    id_of_code: "rust_vec_impl.hpp".into(),
+                    code: include_str!("cpp/rust_vec_impl.hpp").into(),
+                });
+                foreign_lang_helpers.push(SourceCode {
+                    id_of_code: "rust_foreign_vec_impl.hpp".into(),
+                    code: include_str!("cpp/rust_foreign_vec_impl.hpp").into(),
+                });
+                foreign_lang_helpers.push(SourceCode {
+                    id_of_code: "rust_foreign_slice_iter.hpp".into(),
+                    code: include_str!("cpp/rust_foreign_slice_iter.hpp").into(),
+                });
+                foreign_lang_helpers.push(SourceCode {
+                    id_of_code: "rust_foreign_iterator.hpp".into(),
+                    code: include_str!("cpp/rust_foreign_iterator.hpp").into(),
+                });
+                foreign_lang_helpers.push(SourceCode {
+                    id_of_code: "rust_future_completer.hpp".into(),
+                    code: include_str!("cpp/rust_future_completer.hpp").into(),
+                });
+                foreign_lang_helpers.push(SourceCode {
+                    id_of_code: "rust_foreign_slice_impl.hpp".into(),
+                    code: include_str!("cpp/rust_foreign_slice_impl.hpp").into(),
+                });
+                foreign_lang_helpers.push(SourceCode {
+                    id_of_code: "rust_slice_tmpl.hpp".into(),
+                    code: include_str!("cpp/rust_slice_tmpl.hpp").into(),
+                });
+            }
+            LanguageConfig::PythonConfig(..) => {
+                conv_map_source.push(src_reg.register(SourceCode {
+                    id_of_code: "python-include.rs".into(),
+                    code: include_str!("python/python-include.rs").into(),
+                }));
+            }
+            LanguageConfig::Custom(ref backend) => {
+                conv_map_source.push(src_reg.register(SourceCode {
+                    id_of_code: format!("{}-include.rs", backend.name()),
+                    code: backend.type_map_code(),
+                }));
+            }
+        }
+        TargetLanguage {
+            config,
+            conv_map: TypeMap::default(),
+            conv_map_source,
+            foreign_lang_helpers,
         }
     }
 }
 
+/// Rust code generated by language backend
+#[derive(Default)]
+struct GeneratedRustCode {
+    code: Vec<TokenStream>,
+    /// `code[range]` was generated for item with index `idx`
+    /// of items passed to `expand_items`, as `(idx, range)`
+    items_code: Vec<(usize, Range<usize>)>,
+}
+
 trait LanguageGenerator {
     fn expand_items(
         &self,
@@ -751,7 +1819,7 @@ trait LanguageGenerator {
         items: Vec<ItemToExpand>,
         remove_not_generated_files: bool,
         ext_handlers: ExtHandlers,
-    ) -> Result<Vec<TokenStream>>;
+    ) -> Result<GeneratedRustCode>;
 
     fn post_proccess_code(
         &self,
@@ -819,11 +1887,11 @@ pub fn rustfmt_cnt(source: Vec<u8>, edition: RustEdition) -> io::Result<Vec<u8>>
             "Rustfmt parsing errors.".to_string(),
         )),
         Some(3) => {
-            println!("cargo:warning=Rustfmt could not format some lines.");
+            emit_warning_without_src_info("Rustfmt could not format some lines.");
             Ok(src)
         }
         _ => {
-            println!("cargo:warning=Internal rustfmt error");
+            emit_warning_without_src_info("Internal rustfmt error");
             Ok(src)
         }
     }
@@ -0,0 +1,378 @@
+//! Description of parsed `foreign_class!`, `foreign_enum!` and `foreign_callback!`
+//! in JSON format, for tools that can not parse Rust macros
+
+use rustc_hash::FxHashMap;
+use smol_str::SmolStr;
+use syn::{spanned::Spanned, Type};
+
+use crate::{
+    backend::calc_this_type_for_method,
+    error::SourceIdSpan,
+    json::JsonValue,
+    typemap::{
+        ast::{normalize_type, DisplayToTokens},
+        MapToForeignFlag, TypeMap,
+    },
+    types::{
+        FnArg, ForeignClassInfo, ForeignConst, ForeignConstValue, ForeignEnumInfo,
+        ForeignInterface, ForeignMethod, ItemToExpand, MethodAccess, MethodVariant,
+        SelfTypeVariant,
+    },
+};
+
+/// Should be increased on any incompatible change of JSON structure
+pub(crate) const MODEL_FORMAT_VERSION: i64 = 1;
+
+/// Foreign types that one language uses for Rust types of parsed items
+pub(crate) struct LangForeignTypes {
+    lang: String,
+    /// key is normalized Rust type and direction (`true` for Rust to foreign)
+    types: FxHashMap<(&'static str, bool), Option<String>>,
+}
+
+impl LangForeignTypes {
+    /// Should be called after code generation for language,
+    /// so all types are already known to `conv_map`
+    pub(crate) fn collect(lang: String, conv_map: &mut TypeMap, items: &[ItemToExpand]) -> Self {
+        let mut ret = LangForeignTypes {
+            lang,
+            types: FxHashMap::default(),
+        };
+        for item in items {
+            match item {
+                ItemToExpand::Class(ref fclass) => {
+                    for fconst in &fclass.consts {
+                        ret.add(conv_map, &fconst.ty, true, (fclass.src_id, fconst.span()));
+                    }
+                    for method in &fclass.methods {
+                        let sp = (fclass.src_id, method.span());
+                        for ty in args_types(&method.fn_decl.inputs) {
+                            ret.add(conv_map, ty, false, sp);
+                        }
+                        if let Some(ty) = output_type(&method.fn_decl.output) {
+                            ret.add(conv_map, ty, true, sp);
+                        }
+                    }
+                }
+                ItemToExpand::Enum(ref fenum) => {
+                    for item in &fenum.items {
+                        for (_, ty) in item.fields() {
+                            ret.add(conv_map, ty, true, (fenum.src_id, ty.span()));
+                        }
+                    }
+                }
+                ItemToExpand::Const(ref fconst) => {
+                    ret.add(conv_map, &fconst.ty, true, (fconst.src_id, fconst.span()));
+                }
+                ItemToExpand::Fn(ref ffn) => {
+                    let sp = (ffn.src_id, ffn.method.span());
+                    for ty in args_types(&ffn.method.fn_decl.inputs) {
+                        ret.add(conv_map, ty, false, sp);
+                    }
+                    if let Some(ty) = output_type(&ffn.method.fn_decl.output) {
+                        ret.add(conv_map, ty, true, sp);
+                    }
+                }
+                ItemToExpand::Interface(ref finterface) => {
+                    let sp = finterface.src_id_span();
+                    for method in &finterface.items {
+                        for ty in args_types(&method.fn_decl.inputs) {
+                            ret.add(conv_map, ty, true, sp);
+                        }
+                        if let Some(ty) = output_type(&method.fn_decl.output) {
+                            ret.add(conv_map, ty, false, sp);
+                        }
+                    }
+                }
+            }
+        }
+        ret
+    }
+
+    fn add(&mut self, conv_map: &mut TypeMap, ty: &Type, to_foreign: bool, sp: SourceIdSpan) {
+        let key = (normalize_type(ty), to_foreign);
+        if self.types.contains_key(&key) {
+            return;
+        }
+        let rust_ty = conv_map.find_or_alloc_rust_type(ty, sp.0);
+        let direction = if to_foreign {
+            petgraph::Direction::Outgoing
+        } else {
+            petgraph::Direction::Incoming
+        };
+        let ftype = conv_map
+            .map_through_conversation_to_foreign(
+                rust_ty.to_idx(),
+                direction,
+                MapToForeignFlag::FullSearch,
+                sp,
+                calc_this_type_for_method,
+            )
+            .map(|ftype| conv_map[ftype].name.as_str().to_string());
+        self.types.insert(key, ftype);
+    }
+}
+
+/// Convert parsed items to JSON, `langs_types` is used to find
+/// foreign types for each Rust type of arguments and return values
+pub(crate) fn model_to_json(items: &[ItemToExpand], langs_types: &[LangForeignTypes]) -> String {
+    let mut json_items = Vec::with_capacity(items.len());
+    for item in items {
+        json_items.push(match item {
+            ItemToExpand::Class(ref fclass) => class_to_json(fclass, langs_types),
+            ItemToExpand::Enum(ref fenum) => enum_to_json(fenum, langs_types),
+            ItemToExpand::Interface(ref finterface) => callback_to_json(finterface, langs_types),
+            ItemToExpand::Const(ref fconst) => const_to_json(fconst, true, langs_types),
+            ItemToExpand::Fn(ref ffn) => method_to_json(&ffn.method, true, langs_types),
+        });
+    }
+    let model = JsonValue::object()
+        .field("format_version", MODEL_FORMAT_VERSION)
+        .field(
+            "languages",
+            langs_types
+                .iter()
+                .map(|x| x.lang.as_str())
+                .collect::<Vec<_>>(),
+        )
+        .field("items", json_items);
+    format!("{}\n", model)
+}
+
+fn class_to_json(fclass: &ForeignClassInfo, langs_types: &[LangForeignTypes]) -> JsonValue {
+    let self_desc = fclass.self_desc.as_ref();
+    JsonValue::object()
+        .field("kind", "class")
+        .field("name", fclass.name.to_string())
+        .field("self_type", self_desc.map(|x| normalize_type(&x.self_type)))
+        .field(
+            "constructor_ret_type",
+            self_desc.map(|x| normalize_type(&x.constructor_ret_type)),
+        )
+        .field("doc_comments", fclass.doc_comments.clone())
+        .field("derives", fclass.derive_list.clone())
+        .field(
+            "methods",
+            fclass
+                .methods
+                .iter()
+                .map(|m| method_to_json(m, false, langs_types))
+                .collect::<Vec<_>>(),
+        )
+        .field(
+            "consts",
+            fclass
+                .consts
+                .iter()
+                .map(|x| const_to_json(x, false, langs_types))
+                .collect::<Vec<_>>(),
+        )
+}
+
+/// `with_kind` for constant at module level
+fn const_to_json(
+    fconst: &ForeignConst,
+    with_kind: bool,
+    langs_types: &[LangForeignTypes],
+) -> JsonValue {
+    let value = match fconst.value {
+        ForeignConstValue::Int(x) if x >= i128::from(i64::MIN) && x <= i128::from(i64::MAX) => {
+            JsonValue::from(x as i64)
+        }
+        ForeignConstValue::Int(x) => JsonValue::from(x.to_string()),
+        ForeignConstValue::Float(ref x) => JsonValue::from(x.clone()),
+        ForeignConstValue::Bool(x) => JsonValue::from(x),
+        ForeignConstValue::Str(ref x) => JsonValue::from(x.clone()),
+    };
+    let json = if with_kind {
+        JsonValue::object().field("kind", "const")
+    } else {
+        JsonValue::object()
+    };
+    json.field("name", fconst.name.to_string())
+        .field("doc_comments", fconst.doc_comments.clone())
+        .field("type", type_to_json(&fconst.ty, true, langs_types))
+        .field("value", value)
+}
+
+/// `with_kind` for function at module level
+fn method_to_json(
+    method: &ForeignMethod,
+    with_kind: bool,
+    langs_types: &[LangForeignTypes],
+) -> JsonValue {
+    let (variant, self_variant) = match method.variant {
+        MethodVariant::Constructor => ("constructor", None),
+        MethodVariant::StaticMethod => ("static_method", None),
+        MethodVariant::Method(self_variant) => ("method", Some(self_variant_to_str(self_variant))),
+    };
+    let access = match method.access {
+        MethodAccess::Private => "private",
+        MethodAccess::Public => "public",
+        MethodAccess::Protected => "protected",
+    };
+    let json = if with_kind {
+        JsonValue::object().field("kind", "fn")
+    } else {
+        JsonValue::object()
+    };
+    json.field("name", method.short_name())
+        .field("rust_path", path_to_string(&method.rust_id))
+        .field("variant", variant)
+        .field("self_variant", self_variant)
+        .field("access", access)
+        .field("doc_comments", method.doc_comments.clone())
+        .field(
+            "args",
+            args_to_json(&method.fn_decl.inputs, false, langs_types),
+        )
+        .field(
+            "return",
+            output_type(&method.fn_decl.output).map(|ty| type_to_json(ty, true, langs_types)),
+        )
+}
+
+fn enum_to_json(fenum: &ForeignEnumInfo, langs_types: &[LangForeignTypes]) -> JsonValue {
+    let values = fenum.items_values();
+    JsonValue::object()
+        .field("kind", "enum")
+        .field("name", fenum.name.to_string())
+        .field("doc_comments", fenum.doc_comments.clone())
+        .field("derives", fenum.derive_list.clone())
+        .field(
+            "items",
+            fenum
+                .items
+                .iter()
+                .enumerate()
+                .map(|(idx, item)| {
+                    let item_json = JsonValue::object()
+                        .field("name", item.name.to_string())
+                        .field("rust_path", path_to_string(&item.rust_name))
+                        .field(
+                            "value",
+                            if fenum.is_c_like() {
+                                JsonValue::from(values[idx])
+                            } else {
+                                JsonValue::from(idx)
+                            },
+                        )
+                        .field("doc_comments", item.doc_comments.clone());
+                    if fenum.is_c_like() {
+                        item_json
+                    } else {
+                        item_json.field(
+                            "fields",
+                            item.fields()
+                                .into_iter()
+                                .map(|(name, ty)| {
+                                    JsonValue::object()
+                                        .field("name", name.to_string())
+                                        .field("type", type_to_json(ty, true, langs_types))
+                                })
+                                .collect::<Vec<_>>(),
+                        )
+                    }
+                })
+                .collect::<Vec<_>>(),
+        )
+}
+
+fn callback_to_json(finterface: &ForeignInterface, langs_types: &[LangForeignTypes]) -> JsonValue {
+    let self_type: Type = syn::TypeTraitObject::clone(&finterface.self_type).into();
+    JsonValue::object()
+        .field("kind", "callback")
+        .field("name", finterface.name.to_string())
+        .field("self_type", normalize_type(&self_type))
+        .field("doc_comments", finterface.doc_comments.clone())
+        .field(
+            "methods",
+            finterface
+                .items
+                .iter()
+                .map(|m| {
+                    JsonValue::object()
+                        .field("name", m.name.to_string())
+                        .field("rust_path", path_to_string(&m.rust_name))
+                        .field("doc_comments", m.doc_comments.clone())
+                        .field("args", args_to_json(&m.fn_decl.inputs, true, langs_types))
+                        .field(
+                            "return",
+                            output_type(&m.fn_decl.output)
+                                .map(|ty| type_to_json(ty, false, langs_types)),
+                        )
+                })
+                .collect::<Vec<_>>(),
+        )
+}
+
+fn args_to_json(
+    inputs: &[FnArg],
+    to_foreign: bool,
+    langs_types: &[LangForeignTypes],
+) -> Vec<JsonValue> {
+    inputs
+        .iter()
+        .filter_map(|arg| match arg {
+            FnArg::SelfArg(..) => None,
+            FnArg::Default(ref arg) => {
+                let json = JsonValue::object()
+                    .field("name", arg.name.as_str())
+                    .field("type", type_to_json(&arg.ty, to_foreign, langs_types));
+                Some(match arg.default {
+                    Some(ref default) => {
+                        json.field("default", DisplayToTokens(default).to_string())
+                    }
+                    None => json,
+                })
+            }
+        })
+        .collect()
+}
+
+fn type_to_json(ty: &Type, to_foreign: bool, langs_types: &[LangForeignTypes]) -> JsonValue {
+    let rust_ty = normalize_type(ty);
+    let mut foreign_types = JsonValue::object();
+    for lang_types in langs_types {
+        let ftype = lang_types
+            .types
+            .get(&(rust_ty, to_foreign))
+            .cloned()
+            .unwrap_or(None);
+        foreign_types = foreign_types.field(SmolStr::new(&lang_types.lang), ftype);
+    }
+    JsonValue::object()
+        .field("rust", rust_ty)
+        .field("foreign", foreign_types)
+}
+
+fn args_types(inputs: &[FnArg]) -> impl Iterator<Item = &Type> {
+    inputs.iter().filter_map(|arg| match arg {
+        FnArg::SelfArg(..) => None,
+        FnArg::Default(ref arg) => Some(&arg.ty),
+    })
+}
+
+fn output_type(output: &syn::ReturnType) -> Option<&Type> {
+    match output {
+        syn::ReturnType::Default => None,
+        syn::ReturnType::Type(_, ref ty) => Some(ty),
+    }
+}
+
+fn self_variant_to_str(self_variant: SelfTypeVariant) -> &'static str {
+    match self_variant {
+        SelfTypeVariant::RptrMut => "&mut self",
+        SelfTypeVariant::Rptr => "&self",
+        SelfTypeVariant::Mut => "mut self",
+        SelfTypeVariant::Default => "self",
+    }
+}
+
+fn path_to_string(path: &syn::Path) -> String {
+    path.segments
+        .iter()
+        .map(|s| s.ident.to_string())
+        .collect::<Vec<_>>()
+        .join("::")
+}
@@ -1,7 +1,8 @@
 use crate::typemap::ty::RustType;
 use crate::{
-    error::Result,
+    error::{Result, SourceIdSpan},
     extension::{ClassExtHandlers, MethodExtHandlers},
+    namegen::new_unique_name,
     source_registry::SourceId,
     typemap::{
         ast::{GenericTypeConv, TypeName},
@@ -9,21 +10,28 @@ use crate::{
         TypeConvCode,
     },
     types::{
-        ForeignClassInfo, ForeignEnumInfo, ForeignInterface, ForeignMethod, ItemToExpand,
-        MethodVariant, SelfTypeVariant,
+        closure_signature, ForeignClassInfo, ForeignConst, ForeignEnumInfo, ForeignFunction,
+        ForeignInterface, ForeignMethod, ItemToExpand, MethodProperty, MethodVariant,
+        PropertyAccessor, SelfTypeVariant,
     },
-    DiagnosticError, LanguageGenerator, PythonConfig, SourceCode, TypeMap,
+    DiagnosticError, GeneratedRustCode, LanguageGenerator, PythonConfig, SourceCode, TypeMap,
+    DEBUG_TRAIT, DISPLAY_TRAIT,
 };
 use crate::{extension::ExtHandlers, typemap::ast};
 use heck::SnakeCase;
+use petgraph::Direction;
 use proc_macro2::{Span, TokenStream};
 use quote::quote;
 use quote::ToTokens;
+use rustc_hash::{FxHashMap, FxHashSet};
 use std::ops::Deref;
 use syn::parse_quote;
-use syn::{Ident, Type};
+use syn::{spanned::Spanned, Ident, Type};
 
 const ENUM_TRAIT_NAME: &str = "SwigForeignEnum";
+/// Enums that are converted via `to_py_object`/`from_py_object`
+/// functions of wrapper module: enums with data and flags
+const OBJECT_ENUM_TRAIT_NAME: &str = "SwigForeignObjectEnum";
 
 impl LanguageGenerator for PythonConfig {
     fn expand_items(
@@ -34,15 +42,29 @@ impl LanguageGenerator for PythonConfig {
         items: Vec<ItemToExpand>,
         _remove_not_generated_files: bool,
         ext_handlers: ExtHandlers,
-    ) -> Result<Vec<TokenStream>> {
+    ) -> Result<GeneratedRustCode> {
         for item in &items {
             if let ItemToExpand::Class(ref fclass) = item {
                 self.register_class(conv_map, fclass)?;
             }
         }
+        // hidden class for functions should not clash with wrappers of other items
+        let items_names: FxHashSet<String> = items
+            .iter()
+            .filter_map(|item| match item {
+                ItemToExpand::Class(fclass) => Some(&fclass.name),
+                ItemToExpand::Enum(fenum) => Some(&fenum.name),
+                ItemToExpand::Interface(finterface) => Some(&finterface.name),
+                ItemToExpand::Const(_) | ItemToExpand::Fn(_) => None,
+            })
+            .map(|name| name.to_string().to_snake_case())
+            .collect();
+        let fns_holder_name = new_unique_name(&items_names, "module_fns");
         let mut code = Vec::with_capacity(items.len());
         let mut module_initialization = Vec::with_capacity(items.len());
-        for item in items {
+        let mut module_fns = vec![];
+        let mut items_code = Vec::with_capacity(items.len());
+        for (idx, item) in items.into_iter().enumerate() {
             let (class_code, initialization) = match item {
                 ItemToExpand::Class(fclass) => self.generate_class(
                     conv_map,
@@ -50,16 +72,28 @@ impl LanguageGenerator for PythonConfig {
                     ext_handlers.class_ext_handlers,
                     ext_handlers.method_ext_handlers,
                 )?,
+                ItemToExpand::Const(fconst) => generate_module_const(&fconst)?,
+                ItemToExpand::Fn(ffn) => {
+                    module_fns.push(*ffn);
+                    continue;
+                }
                 ItemToExpand::Enum(fenum) => self.generate_enum(conv_map, &fenum)?,
                 ItemToExpand::Interface(finterface) => {
                     self.generate_interface(conv_map, &finterface)?
                 }
             };
+            items_code.push((idx, code.len()..code.len() + 1));
             code.push(class_code);
             module_initialization.push(initialization);
         }
+        if !module_fns.is_empty() {
+            let (fns_code, initialization) =
+                self.generate_module_fns(conv_map, &fns_holder_name, module_fns)?;
+            code.push(fns_code);
+            module_initialization.push(initialization);
+        }
         code.push(self.generate_module_initialization(&module_initialization)?);
-        Ok(code)
+        Ok(GeneratedRustCode { code, items_code })
     }
 }
 
@@ -90,9 +124,19 @@ impl PythonConfig {
             ));
         }
 
+        if class.iterator_item().is_some() {
+            return generate_iterator_class(class, conv_map);
+        }
+        // methods of abstract class are added to derived classes,
+        // there is no need for Python class for it
+        if class.is_abstract {
+            return Ok((TokenStream::new(), TokenStream::new()));
+        }
+
         let class_name = &class.name;
         let wrapper_mod_name =
             parse::<Ident>(&py_wrapper_mod_name(&class_name.to_string()), class.src_id)?;
+        check_methods_names_are_unique(class)?;
         let (rust_instance_field, rust_instance_getter) =
             generate_rust_instance_field_and_methods(class, conv_map)?;
         let methods_code = class
@@ -100,6 +144,18 @@ impl PythonConfig {
             .iter()
             .map(|m| generate_method_code(class, m, conv_map))
             .collect::<Result<Vec<_>>>()?;
+        let consts_code = class
+            .consts
+            .iter()
+            .map(|fconst| {
+                fconst.check_type()?;
+                let name = &fconst.name;
+                let value = fconst.rust_value();
+                Ok(quote! { static #name = #value; })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let consts_checks = class.consts.iter().map(|x| x.rust_code_to_check_value());
+        let protocols_code = generate_derived_protocols_code(class, conv_map)?;
         let mut doc_comments = class.doc_comments.clone();
         if let Some(constructor) = class
             .methods
@@ -122,11 +178,17 @@ impl PythonConfig {
 
                     #rust_instance_field
 
+                    #( #consts_code )*
+
                     #( #methods_code )*
+
+                    #protocols_code
                 });
 
                 #rust_instance_getter
             }
+
+            #( #consts_checks )*
         };
 
         let module_initialization_code = quote! {
@@ -137,11 +199,45 @@ impl PythonConfig {
         Ok((class_code, module_initialization_code))
     }
 
+    /// Functions are generated as static methods of hidden class `holder_name`,
+    /// and added to module during its initialization
+    fn generate_module_fns(
+        &self,
+        conv_map: &mut TypeMap,
+        holder_name: &str,
+        fns: Vec<ForeignFunction>,
+    ) -> Result<(TokenStream, TokenStream)> {
+        let holder = ForeignClassInfo::module_items_holder(holder_name, vec![], fns);
+        let (class_code, _) = self.generate_class(
+            conv_map,
+            &holder,
+            &ClassExtHandlers::default(),
+            &MethodExtHandlers::default(),
+        )?;
+        let class_name = &holder.name;
+        let wrapper_mod_name =
+            parse::<Ident>(&py_wrapper_mod_name(&class_name.to_string()), holder.src_id)?;
+        let fn_names = holder.methods.iter().map(|m| m.short_name());
+        let module_initialization_code = quote! {
+            {
+                let fns_holder = py.get_type::<#wrapper_mod_name::#class_name>();
+                #( m.add(py, #fn_names, fns_holder.getattr(py, #fn_names)?)?; )*
+            }
+        };
+        Ok((class_code, module_initialization_code))
+    }
+
     fn generate_enum(
         &self,
         conv_map: &mut TypeMap,
         enum_info: &ForeignEnumInfo,
     ) -> Result<(TokenStream, TokenStream)> {
+        if !enum_info.is_c_like() {
+            return generate_data_enum(conv_map, enum_info);
+        }
+        if enum_info.is_flags() {
+            return generate_flags_enum(conv_map, enum_info);
+        }
         let enum_name = &enum_info.name;
         let wrapper_mod_name = parse::<Ident>(
             &py_wrapper_mod_name(&enum_name.to_string()),
@@ -232,6 +328,390 @@ impl PythonConfig {
     }
 }
 
+/// Special methods for derived traits of `self_type`:
+/// `__richcmp__` for `PartialEq`/`PartialOrd`, `__hash__`, `__str__` for `Display`
+/// and `__repr__` for `Debug`
+fn generate_derived_protocols_code(
+    class: &ForeignClassInfo,
+    conv_map: &mut TypeMap,
+) -> Result<TokenStream> {
+    let protocols = class.derived_protocols()?;
+    if protocols.is_empty() {
+        return Ok(TokenStream::new());
+    }
+    let src_id = class.src_id;
+    let span = class.span();
+    let self_type = class.self_type_as_ty();
+    let self_ref_type = conv_map.find_or_alloc_rust_type(&parse_type!(&#self_type), src_id);
+    let (_, this_conversion) =
+        generate_conversion_for_argument(&self_ref_type, span, src_id, conv_map, "self", true)?;
+    let mut code = TokenStream::new();
+
+    if protocols.eq || protocols.cmp.is_some() {
+        let (other_type, other_conversion) = generate_conversion_for_argument(
+            &self_ref_type,
+            span,
+            src_id,
+            conv_map,
+            "other",
+            false,
+        )?;
+        let mut same_arms = vec![];
+        let mut arms = vec![];
+        if protocols.eq {
+            same_arms.push(quote! {
+                cpython::CompareOp::Eq => Some(true),
+                cpython::CompareOp::Ne => Some(false),
+            });
+            arms.push(quote! {
+                cpython::CompareOp::Eq => Some(PartialEq::eq(this, o)),
+                cpython::CompareOp::Ne => Some(PartialEq::ne(this, o)),
+            });
+        }
+        if protocols.cmp.is_some() {
+            same_arms.push(quote! {
+                cpython::CompareOp::Lt | cpython::CompareOp::Gt => Some(false),
+                cpython::CompareOp::Le | cpython::CompareOp::Ge => Some(true),
+            });
+            arms.push(quote! {
+                cpython::CompareOp::Lt => Some(PartialOrd::lt(this, o)),
+                cpython::CompareOp::Le => Some(PartialOrd::le(this, o)),
+                cpython::CompareOp::Gt => Some(PartialOrd::gt(this, o)),
+                cpython::CompareOp::Ge => Some(PartialOrd::ge(this, o)),
+            });
+        }
+        // comparison of object with itself should not lock mutex twice
+        code.extend(quote! {
+            def __richcmp__(&self, other: #other_type, op: cpython::CompareOp) -> cpython::PyResult<cpython::PyObject> {
+                #[allow(unused)]
+                use super::*;
+                use cpython::{PythonObject, ToPyObject};
+                #[allow(unreachable_patterns)]
+                let ret: Option<bool> = if self.as_object() == other.as_object() {
+                    match op {
+                        #( #same_arms )*
+                        _ => None,
+                    }
+                } else {
+                    match (#this_conversion, #other_conversion) {
+                        (this, o) => match op {
+                            #( #arms )*
+                            _ => None,
+                        },
+                    }
+                };
+                Ok(match ret {
+                    Some(x) => x.to_py_object(py).into_object(),
+                    None => py.NotImplemented(),
+                })
+            }
+        });
+    }
+    if protocols.hash {
+        code.extend(quote! {
+            def __hash__(&self) -> cpython::PyResult<i64> {
+                #[allow(unused)]
+                use super::*;
+                use std::hash::{Hash, Hasher};
+                let mut hasher = std::collections::hash_map::DefaultHasher::new();
+                match #this_conversion {
+                    this => this.hash(&mut hasher),
+                }
+                // -1 is reserved for errors by Python
+                let h = hasher.finish() as i64;
+                Ok(if h == -1 { -2 } else { h })
+            }
+        });
+    }
+    if class.has_derive(DISPLAY_TRAIT) {
+        code.extend(quote! {
+            def __str__(&self) -> cpython::PyResult<String> {
+                #[allow(unused)]
+                use super::*;
+                Ok(match #this_conversion {
+                    this => format!("{}", this),
+                })
+            }
+        });
+    }
+    if class.has_derive(DEBUG_TRAIT) {
+        code.extend(quote! {
+            def __repr__(&self) -> cpython::PyResult<String> {
+                #[allow(unused)]
+                use super::*;
+                Ok(match #this_conversion {
+                    this => format!("{:?}", this),
+                })
+            }
+        });
+    }
+    Ok(code)
+}
+
+/// Class for `Box<dyn Iterator<Item = T>>` implements iterator protocol,
+/// so items are converted to Python objects one by one during iteration.
+/// Python objects can be passed to other threads, so iterator should be `Send`.
+fn generate_iterator_class(
+    class: &ForeignClassInfo,
+    conv_map: &mut TypeMap,
+) -> Result<(TokenStream, TokenStream)> {
+    let src_id = class.src_id;
+    let span = class.span();
+    let (iter_ty, item) = match (class.iterator_type(), class.iterator_item()) {
+        (Some(iter_ty), Some(item)) => (iter_ty, item),
+        _ => unreachable!("generate_iterator_class: not iterator class {}", class.name),
+    };
+    let item_rust_type = conv_map.find_or_alloc_rust_type(item, src_id);
+    let (item_py_type, item_conversion) =
+        generate_conversion_for_return(&item_rust_type, span, src_id, conv_map, quote! {item})?;
+    let class_name = &class.name;
+    let wrapper_mod_name = parse::<Ident>(&py_wrapper_mod_name(&class_name.to_string()), src_id)?;
+    let docstring = class.doc_comments.as_slice().join("\n");
+    let class_code = quote! {
+        mod #wrapper_mod_name {
+            use super::*;
+            #[allow(unused)]
+            py_class!(pub class #class_name |py| {
+                static __doc__  = #docstring;
+
+                data rust_instance: std::sync::Mutex<#iter_ty>;
+
+                def __iter__(&self) -> cpython::PyResult<#class_name> {
+                    use cpython::PyClone;
+                    Ok(self.clone_ref(py))
+                }
+
+                def __next__(&self) -> cpython::PyResult<Option<#item_py_type>> {
+                    #[allow(unused)]
+                    use super::*;
+                    let next = self.rust_instance(py).lock().unwrap().next();
+                    Ok(match next {
+                        Some(item) => Some(#item_conversion),
+                        None => None,
+                    })
+                }
+            });
+
+            pub fn create_instance(py: cpython::Python, instance: #iter_ty) -> cpython::PyResult<#class_name> {
+                #class_name::create_instance(py, std::sync::Mutex::new(instance))
+            }
+        }
+    };
+    let module_initialization_code = quote! {
+        {
+            m.add_class::<#wrapper_mod_name::#class_name>(py)?;
+        }
+    };
+    Ok((class_code, module_initialization_code))
+}
+
+/// Constant at module level is added as attribute of module
+fn generate_module_const(fconst: &ForeignConst) -> Result<(TokenStream, TokenStream)> {
+    fconst.check_type()?;
+    let name = fconst.name.to_string();
+    let value = fconst.rust_value();
+    let module_initialization_code = quote! {
+        {
+            m.add(py, #name, #value)?;
+        }
+    };
+    Ok((
+        fconst.rust_code_to_check_value(),
+        module_initialization_code,
+    ))
+}
+
+/// Enum with data in variants is mapped to set of classes, one class per variant,
+/// each of them holds Rust value, so conversion to Rust requires `Clone`
+fn generate_data_enum(
+    conv_map: &mut TypeMap,
+    enum_info: &ForeignEnumInfo,
+) -> Result<(TokenStream, TokenStream)> {
+    let src_id = enum_info.src_id;
+    let enum_name = &enum_info.name;
+    let wrapper_mod_name = parse::<Ident>(&py_wrapper_mod_name(&enum_name.to_string()), src_id)?;
+    let mut classes_code = Vec::with_capacity(enum_info.items.len());
+    let mut class_names = Vec::with_capacity(enum_info.items.len());
+    let mut patterns = Vec::with_capacity(enum_info.items.len());
+    for item in &enum_info.items {
+        let class_name = parse::<Ident>(&format!("{}_{}", enum_name, item.name), src_id)?;
+        let fields = item.fields();
+        let mut names = Vec::with_capacity(fields.len());
+        let mut new_args = vec![quote!(_cls)];
+        let mut new_convertions = Vec::with_capacity(fields.len());
+        let mut getters = Vec::with_capacity(fields.len());
+        for (i, (name, ty)) in fields.iter().enumerate() {
+            let rust_type = conv_map.find_or_alloc_rust_type(ty, src_id);
+            let (arg_type, arg_convertion) = generate_conversion_for_argument(
+                &rust_type,
+                ty.span(),
+                src_id,
+                conv_map,
+                &name.to_string(),
+                false,
+            )?;
+            let (return_type, return_convertion) = generate_conversion_for_return(
+                &rust_type,
+                ty.span(),
+                src_id,
+                conv_map,
+                quote!(Clone::clone(#name)),
+            )?;
+            let pattern = item.rust_pattern_with_binding(i, name);
+            getters.push(quote! {
+                @property def #name(&self) -> cpython::PyResult<#return_type> {
+                    Ok(match self.rust_value(py) {
+                        #pattern => #return_convertion,
+                        #[allow(unreachable_patterns)]
+                        _ => unreachable!(),
+                    })
+                }
+            });
+            new_args.push(quote!(#name: #arg_type));
+            new_convertions.push(arg_convertion);
+            names.push(name.clone());
+        }
+        let construct = item.rust_constructor();
+        let docstring = item.doc_comments.as_slice().join("\n");
+        classes_code.push(quote! {
+            py_class!(pub class #class_name |py| {
+                static __doc__  = #docstring;
+                data rust_value: super::#enum_name;
+
+                def __new__(#( #new_args ),*) -> cpython::PyResult<#class_name> {
+                    #( let #names = #new_convertions; )*
+                    #class_name::create_instance(py, super::#construct)
+                }
+
+                #( #getters )*
+            });
+        });
+        patterns.push(item.rust_pattern_without_bindings());
+        class_names.push(class_name);
+    }
+    let enum_name_str = enum_name.to_string();
+    let class_code = quote! {
+        mod #wrapper_mod_name {
+            #[allow(unused)]
+            use super::*;
+
+            #( #classes_code )*
+
+            pub fn to_py_object(
+                py: cpython::Python,
+                value: super::#enum_name,
+            ) -> cpython::PyResult<cpython::PyObject> {
+                Ok(match value {
+                    #( super::#patterns => #class_names::create_instance(py, value)?.into_object(), )*
+                })
+            }
+
+            pub fn from_py_object(
+                py: cpython::Python,
+                obj: &cpython::PyObject,
+            ) -> cpython::PyResult<super::#enum_name> {
+                #(
+                    if let Ok(x) = obj.cast_as::<#class_names>(py) {
+                        return Ok(Clone::clone(x.rust_value(py)));
+                    }
+                )*
+                Err(cpython::PyErr::new::<cpython::exc::TypeError, _>(
+                    py, format!("value of {} is expected", #enum_name_str)
+                ))
+            }
+        }
+    };
+    let enum_ti: Type = ast::parse_ty_with_given_span(&enum_name.to_string(), enum_name.span())
+        .map_err(|err| DiagnosticError::from_syn_err(src_id, err))?;
+    conv_map.find_or_alloc_rust_type_that_implements(&enum_ti, &[OBJECT_ENUM_TRAIT_NAME], src_id);
+    let enum_ftype = ForeignTypeS {
+        name: TypeName::new(enum_name.to_string(), (src_id, enum_name.span())),
+        provides_by_module: vec![],
+        into_from_rust: None,
+        from_into_rust: None,
+        name_prefix: None,
+    };
+    conv_map.alloc_foreign_type(enum_ftype)?;
+
+    let module_initialization_code = quote! {
+        {
+            #( m.add_class::<#wrapper_mod_name::#class_names>(py)?; )*
+        }
+    };
+    Ok((class_code, module_initialization_code))
+}
+
+/// Flags are mapped to `enum.IntFlag` subclass created during module initialization
+fn generate_flags_enum(
+    conv_map: &mut TypeMap,
+    enum_info: &ForeignEnumInfo,
+) -> Result<(TokenStream, TokenStream)> {
+    let src_id = enum_info.src_id;
+    let enum_name = &enum_info.name;
+    let wrapper_mod_name = parse::<Ident>(&py_wrapper_mod_name(&enum_name.to_string()), src_id)?;
+    let foreign_variants = enum_info.items.iter().map(|item| item.name.to_string());
+    let rust_variants = enum_info.items.iter().map(|item| &item.rust_name);
+    let enum_name_str = enum_name.to_string();
+    let docstring = enum_info.doc_comments.as_slice().join("\n");
+    let class_code = quote! {
+        mod #wrapper_mod_name {
+            static CLASS: std::sync::Mutex<Option<cpython::PyObject>> = std::sync::Mutex::new(None);
+
+            pub fn init(py: cpython::Python, m: &cpython::PyModule) -> cpython::PyResult<()> {
+                use cpython::ObjectProtocol;
+                let members = vec![#( (#foreign_variants, super::#rust_variants.bits() as u32) ),*];
+                let class = py
+                    .import("enum")?
+                    .call(py, "IntFlag", (#enum_name_str, members), None)?;
+                class.setattr(py, "__doc__", #docstring)?;
+                m.add(py, #enum_name_str, class.clone_ref(py))?;
+                *CLASS.lock().unwrap() = Some(class);
+                Ok(())
+            }
+
+            pub fn to_py_object(
+                py: cpython::Python,
+                value: super::#enum_name,
+            ) -> cpython::PyResult<cpython::PyObject> {
+                use cpython::ObjectProtocol;
+                let class = CLASS
+                    .lock()
+                    .unwrap()
+                    .as_ref()
+                    .expect(concat!(#enum_name_str, " is not initialized"))
+                    .clone_ref(py);
+                class.call(py, (value.bits() as u32,), None)
+            }
+
+            pub fn from_py_object(
+                py: cpython::Python,
+                obj: &cpython::PyObject,
+            ) -> cpython::PyResult<super::#enum_name> {
+                let bits: u32 = obj.extract(py)?;
+                Ok(super::#enum_name::from_bits_truncate(bits as _))
+            }
+        }
+    };
+    let enum_ti: Type = ast::parse_ty_with_given_span(&enum_name.to_string(), enum_name.span())
+        .map_err(|err| DiagnosticError::from_syn_err(src_id, err))?;
+    conv_map.find_or_alloc_rust_type_that_implements(&enum_ti, &[OBJECT_ENUM_TRAIT_NAME], src_id);
+    let enum_ftype = ForeignTypeS {
+        name: TypeName::new(enum_name.to_string(), (src_id, enum_name.span())),
+        provides_by_module: vec![],
+        into_from_rust: None,
+        from_into_rust: None,
+        name_prefix: None,
+    };
+    conv_map.alloc_foreign_type(enum_ftype)?;
+
+    let module_initialization_code = quote! {
+        {
+            #wrapper_mod_name::init(py, m)?;
+        }
+    };
+    Ok((class_code, module_initialization_code))
+}
+
 fn generate_rust_instance_field_and_methods(
     class: &ForeignClassInfo,
     conv_map: &mut TypeMap,
@@ -299,6 +779,10 @@ fn generate_method_code(
     } else {
         0
     };
+    let is_setter = method
+        .property
+        .as_ref()
+        .is_some_and(|p| p.accessor == PropertyAccessor::Setter);
     let (args_list, mut args_convertions): (Vec<_>, Vec<_>) = method
         .fn_decl
         .inputs
@@ -308,15 +792,60 @@ fn generate_method_code(
             let named_arg = a
                 .as_named_arg()
                 .map_err(|err| DiagnosticError::from_syn_err(class.src_id, err))?;
-            let (arg_type, arg_convertion) = generate_conversion_for_argument(
-                &conv_map.find_or_alloc_rust_type(&named_arg.ty, class.src_id),
+            let rust_type = conv_map.find_or_alloc_rust_type(&named_arg.ty, class.src_id);
+            let (mut arg_type, mut arg_convertion) = generate_conversion_for_argument(
+                &rust_type,
                 method.span(),
                 class.src_id,
                 conv_map,
                 &named_arg.name,
                 true,
             )?;
-            Ok(((&named_arg.name, arg_type), arg_convertion))
+            let mut default = named_arg.default.as_ref().map(ToTokens::into_token_stream);
+            if let Some(ref default_value) = named_arg.default {
+                let is_none = match default_value {
+                    syn::Expr::Path(syn::ExprPath { ref path, .. }) => path.is_ident("None"),
+                    _ => false,
+                };
+                if arg_type != rust_type.ty && !is_none {
+                    // Rust expression is not valid value of Python side type,
+                    // so omitted argument is `None`, and default is used after conversion
+                    if ast::if_option_return_some_type(&rust_type).is_some() {
+                        return Err(DiagnosticError::new(
+                            class.src_id,
+                            default_value.span(),
+                            format!(
+                                "default value `{}` of optional argument with conversion \
+                                 is not supported for Python, only `None` can be used",
+                                default_value.into_token_stream()
+                            ),
+                        ));
+                    }
+                    let arg_name: Ident = parse(&named_arg.name, class.src_id)?;
+                    arg_type = parse_type!(Option<#arg_type>);
+                    arg_convertion = quote! {
+                        match #arg_name {
+                            Some(#arg_name) => #arg_convertion,
+                            None => #default_value,
+                        }
+                    };
+                    default = Some(quote!(None));
+                }
+            }
+            if is_setter {
+                // rust-cpython passes `None` to setter on `del obj.prop`
+                let arg_name: Ident = parse(&named_arg.name, class.src_id)?;
+                arg_type = parse_type!(Option<#arg_type>);
+                arg_convertion = quote! {
+                    match #arg_name {
+                        Some(#arg_name) => #arg_convertion,
+                        None => return Err(cpython::PyErr::new::<cpython::exc::AttributeError, _>(
+                            py, "can't delete attribute"
+                        )),
+                    }
+                };
+            }
+            Ok(((&named_arg.name, arg_type, default), arg_convertion))
         })
         .collect::<Result<Vec<_>>>()?
         .into_iter()
@@ -326,11 +855,13 @@ fn generate_method_code(
     }
     let mut args_list_tokens = args_list
         .into_iter()
-        .map(|(name, t)| {
-            parse(
-                &format!("{}: {}", name, t.into_token_stream().to_string()),
-                class.src_id,
-            )
+        .map(|(name, t, default)| {
+            let mut arg = format!("{}: {}", name, t.into_token_stream());
+            if let Some(default) = default {
+                // keyword argument with default value, see `py_argparse!`
+                arg.push_str(&format!(" = {}", default));
+            }
+            parse(&arg, class.src_id)
         })
         .collect::<std::result::Result<Vec<TokenStream>, _>>()?;
     if let MethodVariant::Method(_) = method.variant {
@@ -338,20 +869,119 @@ fn generate_method_code(
     } else if method.variant == MethodVariant::Constructor {
         args_list_tokens.insert(0, parse("_cls", class.src_id)?);
     }
-    let attribute = if method.variant == MethodVariant::StaticMethod {
-        parse("@staticmethod", class.src_id)?
+    let attribute = match method.property {
+        Some(MethodProperty {
+            accessor: PropertyAccessor::Getter,
+            ..
+        }) => quote!(@property),
+        Some(MethodProperty {
+            ref name,
+            accessor: PropertyAccessor::Setter,
+        }) => {
+            let getter = class
+                .methods
+                .iter()
+                .find(|m| {
+                    m.property
+                        .as_ref()
+                        .is_some_and(|p| p.name == *name && p.accessor == PropertyAccessor::Getter)
+                })
+                .ok_or_else(|| {
+                    DiagnosticError::new(class.src_id, method.span(), "setter without getter")
+                })?;
+            let getter_name = standard_method_name(getter, class.src_id)?;
+            quote!(@#getter_name.setter)
+        }
+        None if method.variant == MethodVariant::StaticMethod => {
+            parse("@staticmethod", class.src_id)?
+        }
+        None => TokenStream::new(),
+    };
+    let rust_call = if let Some(ref inline_block) = method.inline_block {
+        // `match` instead of `let` to keep temporaries, like lock guards, alive
+        let mut args_names = method
+            .arg_names_without_self()
+            .map(|name| parse::<Ident>(name, class.src_id))
+            .collect::<Result<Vec<_>>>()?;
+        if let MethodVariant::Method(_) = method.variant {
+            args_names.insert(0, parse("this", class.src_id)?);
+        }
+        quote! {
+            match (#( #args_convertions, )*) {
+                (#( #args_names, )*) => #inline_block
+            }
+        }
     } else {
-        TokenStream::new()
+        quote! {
+            #method_rust_path(#( #args_convertions ),*)
+        }
     };
+    let warn_deprecated = deprecation_warning_code(class, method);
+    if let Some(output) = method.async_output() {
+        let (_, value_conversion) = generate_conversion_for_return(
+            &conv_map.find_or_alloc_rust_type(output, class.src_id),
+            method.span(),
+            class.src_id,
+            conv_map,
+            quote!(value),
+        )?;
+        let spawner = conv_map.async_spawner().ok_or_else(|| {
+            DiagnosticError::new(
+                class.src_id,
+                method.span(),
+                "method returns future, but there is no executor to run it,\n\
+                 you should set it via `Generator::async_spawner`",
+            )
+        })?;
+        let docstring = parse::<TokenStream>(
+            &("/// ".to_owned() + &method.doc_comments.as_slice().join("\n/// ")),
+            class.src_id,
+        )?;
+        // result of future is passed to `asyncio.Future` in thread of event loop
+        return Ok(quote! {
+            #docstring #attribute def #method_name(
+                #( #args_list_tokens ),*
+            ) -> cpython::PyResult<cpython::PyObject> {
+                #[allow(unused)]
+                use super::*;
+                use cpython::{ObjectProtocol, PythonObject, ToPyObject};
+                #warn_deprecated
+                let future = #rust_call;
+                let event_loop = py
+                    .import("asyncio")?
+                    .call(py, "get_event_loop", cpython::NoArgs, None)?;
+                let py_future = event_loop.call_method(py, "create_future", cpython::NoArgs, None)?;
+                let py_future_ref = py_future.clone_ref(py);
+                #spawner(async move {
+                    let value = future.await;
+                    let gil = cpython::Python::acquire_gil();
+                    let py = gil.python();
+                    let result = (|| -> cpython::PyResult<cpython::PyObject> {
+                        let value = #value_conversion;
+                        Ok(value.into_py_object(py).into_object())
+                    })();
+                    let (setter, arg) = match result {
+                        Ok(value) => ("set_result", value),
+                        Err(mut err) => ("set_exception", err.instance(py)),
+                    };
+                    let scheduled = py_future_ref.getattr(py, setter).and_then(|setter| {
+                        event_loop.call_method(py, "call_soon_threadsafe", (setter, arg), None)
+                    });
+                    if let Err(err) = scheduled {
+                        err.print(py);
+                    }
+                });
+                Ok(py_future)
+            }
+        });
+    }
     let (return_type, rust_call_with_return_conversion) = generate_conversion_for_return(
         &conv_map
             .find_or_alloc_rust_type(&extract_return_type(&method.fn_decl.output), class.src_id),
         method.span(),
         class.src_id,
         conv_map,
-        quote! {
-            #method_rust_path(#( #args_convertions ),*)
-        },
+        rust_call,
     )?;
     let docstring = if !method_name.to_string().starts_with("__") {
         parse::<TokenStream>(
@@ -368,11 +998,42 @@ fn generate_method_code(
         ) -> cpython::PyResult<#return_type> {
             #[allow(unused)]
             use super::*;
+            #warn_deprecated
             Ok(#rust_call_with_return_conversion)
         }
     })
 }
 
+/// Emit `DeprecationWarning` on call of deprecated method,
+/// or on construction of object of deprecated class
+fn deprecation_warning_code(class: &ForeignClassInfo, method: &ForeignMethod) -> TokenStream {
+    let deprecated = match method.variant {
+        MethodVariant::Constructor => method.deprecated.as_ref().or(class.deprecated.as_ref()),
+        _ => method.deprecated.as_ref(),
+    };
+    let deprecated = match deprecated {
+        Some(x) => x,
+        None => return TokenStream::new(),
+    };
+    let item_name = if method.variant == MethodVariant::Constructor {
+        class.name.to_string()
+    } else if class.is_module_items_holder {
+        method.short_name()
+    } else {
+        format!("{}.{}", class.name, method.short_name())
+    };
+    let mut msg = format!("{} is deprecated", item_name);
+    let details = deprecated.message();
+    if !details.is_empty() {
+        msg.push_str(": ");
+        msg.push_str(&details);
+    }
+    quote! {
+        let category = py.import("builtins")?.get(py, "DeprecationWarning")?;
+        cpython::PyErr::warn(py, &category, #msg, 1)?;
+    }
+}
+
 fn standard_method_name(method: &ForeignMethod, src_id: SourceId) -> Result<syn::Ident> {
     Ok(method
         .name_alias
@@ -395,6 +1056,39 @@ fn method_name(method: &ForeignMethod, src_id: SourceId) -> Result<syn::Ident> {
     }
 }
 
+/// Python has no overloading, so methods with the same name
+/// would override each other (or not compile at all)
+fn check_methods_names_are_unique(class: &ForeignClassInfo) -> Result<()> {
+    let mut names = FxHashMap::<String, &ForeignMethod>::default();
+    for method in &class.methods {
+        let is_setter = method
+            .property
+            .as_ref()
+            .is_some_and(|p| p.accessor == PropertyAccessor::Setter);
+        if method.is_dummy_constructor() || is_setter {
+            continue;
+        }
+        let name = method_name(method, class.src_id)?.to_string();
+        if let Some(prev) = names.insert(name.clone(), method) {
+            let mut err = DiagnosticError::new(
+                class.src_id,
+                method.span(),
+                format!(
+                    "class {}: Python has no overloading, several methods \
+                     have the same name `{}`, use different aliases for them",
+                    class.name, name
+                ),
+            );
+            err.span_note(
+                (class.src_id, prev.span()),
+                "previous method with the same name",
+            );
+            return Err(err);
+        }
+    }
+    Ok(())
+}
+
 fn self_type_conversion(
     class: &ForeignClassInfo,
     method: &ForeignMethod,
@@ -452,7 +1146,17 @@ fn generate_conversion_for_argument(
     reference_allowed: bool,
 ) -> Result<(Type, TokenStream)> {
     let arg_name_ident: TokenStream = parse(arg_name, src_id)?;
-    if is_cpython_supported_type(rust_type) {
+    if let Some(sig) = closure_signature(&rust_type.ty) {
+        let conversion = generate_closure_argument_conversion(
+            rust_type,
+            sig,
+            &arg_name_ident,
+            method_span,
+            src_id,
+            conv_map,
+        )?;
+        Ok((parse_type!(cpython::PyObject), conversion))
+    } else if is_cpython_supported_type(rust_type) {
         Ok((rust_type.ty.clone(), arg_name_ident))
     } else if let Some((ty, conversion)) = if_exported_class_generate_argument_conversion(
         rust_type,
@@ -474,6 +1178,17 @@ fn generate_conversion_for_argument(
                 super::#enum_py_mod::from_u32(py, #arg_name_ident)?
             },
         ))
+    } else if rust_type
+        .implements
+        .contains_path(&parse(OBJECT_ENUM_TRAIT_NAME, src_id)?)
+    {
+        let enum_py_mod: Ident = parse(&py_wrapper_mod_name(&rust_type.normalized_name), src_id)?;
+        Ok((
+            parse_type!(cpython::PyObject),
+            quote! {
+                super::#enum_py_mod::from_py_object(py, &#arg_name_ident)?
+            },
+        ))
     } else if let Some(inner) = ast::if_option_return_some_type(&rust_type) {
         let (inner_py_type, inner_conversion) = generate_conversion_for_argument(
             &conv_map.find_or_alloc_rust_type(&inner, src_id),
@@ -545,14 +1260,110 @@ fn generate_conversion_for_argument(
             },
         ))
     } else {
-        Err(DiagnosticError::new(
-            src_id,
-            method_span,
+        Err(unsupported_type_error(
+            conv_map,
+            rust_type,
+            (src_id, method_span),
+            Direction::Incoming,
             format!("Unsupported argument type: {}", rust_type),
         ))
     }
 }
 
+/// Wrap Python callable into Rust closure,
+/// exception inside callable is printed, and if closure should return value also causes panic.
+/// Closure is `Send`, so the panic may happen in thread spawned by Rust,
+/// where it terminates only this thread, see "Closures" in the book
+fn generate_closure_argument_conversion(
+    rust_type: &RustType,
+    sig: &syn::ParenthesizedGenericArguments,
+    arg_name_ident: &TokenStream,
+    method_span: Span,
+    src_id: SourceId,
+    conv_map: &mut TypeMap,
+) -> Result<TokenStream> {
+    let args: Vec<Ident> = (0..sig.inputs.len())
+        .map(|i| Ident::new(&format!("a{}", i), Span::call_site()))
+        .collect();
+    let arg_types = sig.inputs.iter();
+    let py_args = sig
+        .inputs
+        .iter()
+        .zip(args.iter())
+        .map(|(ty, arg)| {
+            generate_conversion_for_return(
+                &conv_map.find_or_alloc_rust_type(ty, src_id),
+                method_span,
+                src_id,
+                conv_map,
+                quote! {#arg},
+            )
+            .map(|(_, conversion)| conversion)
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let py_args = if py_args.is_empty() {
+        quote! { cpython::NoArgs }
+    } else {
+        quote! { (#( #py_args, )*) }
+    };
+    let call = quote! {
+        cpython::ObjectProtocol::call(&callable, py, #py_args, None)?
+    };
+    let output = &sig.output;
+    let (ret_ty, call_and_convert, on_error) = match sig.output {
+        syn::ReturnType::Default => (
+            parse_type!(()),
+            quote! {
+                #call;
+                Ok(())
+            },
+            quote! { err.print(py); },
+        ),
+        syn::ReturnType::Type(_, ref ret_ty) => {
+            let (py_ret_ty, ret_conversion) = generate_conversion_for_argument(
+                &conv_map.find_or_alloc_rust_type(ret_ty, src_id),
+                method_span,
+                src_id,
+                conv_map,
+                "ret",
+                false,
+            )?;
+            (
+                (**ret_ty).clone(),
+                quote! {
+                    let ret: #py_ret_ty = #call.extract(py)?;
+                    Ok(#ret_conversion)
+                },
+                quote! {
+                    err.print(py);
+                    panic!("Python callable raised exception");
+                },
+            )
+        }
+    };
+    let closure = quote! {
+        {
+            let callable = #arg_name_ident;
+            move |#( #args: #arg_types ),*| #output {
+                let gil = cpython::Python::acquire_gil();
+                let py = gil.python();
+                let ret: cpython::PyResult<#ret_ty> = (|| { #call_and_convert })();
+                match ret {
+                    Ok(ret) => ret,
+                    Err(err) => {
+                        #on_error
+                    }
+                }
+            }
+        }
+    };
+    let closure_ty = &rust_type.ty;
+    Ok(match rust_type.ty {
+        Type::Reference(_) => quote! { &(#closure) as #closure_ty },
+        _ => quote! { Box::new(#closure) as #closure_ty },
+    })
+}
+
 fn generate_conversion_for_return(
     rust_type: &RustType,
     method_span: Span,
@@ -587,6 +1398,17 @@ fn generate_conversion_for_return(
                 #rust_call as u32
             },
         ))
+    } else if rust_type
+        .implements
+        .contains_path(&parse(OBJECT_ENUM_TRAIT_NAME, src_id)?)
+    {
+        let enum_py_mod: Ident = parse(&py_wrapper_mod_name(&rust_type.normalized_name), src_id)?;
+        Ok((
+            parse_type!(cpython::PyObject),
+            quote! {
+                super::#enum_py_mod::to_py_object(py, #rust_call)?
+            },
+        ))
     } else if let Some(inner) = ast::if_option_return_some_type(&rust_type) {
         let (inner_py_type, inner_conversion) = generate_conversion_for_return(
             &conv_map.find_or_alloc_rust_type(&inner, src_id),
@@ -693,14 +1515,30 @@ fn generate_conversion_for_return(
             },
         ))
     } else {
-        Err(DiagnosticError::new(
-            src_id,
-            method_span,
+        Err(unsupported_type_error(
+            conv_map,
+            rust_type,
+            (src_id, method_span),
+            Direction::Outgoing,
             format!("Unsupported return type: {}", rust_type),
         ))
     }
 }
 
+fn unsupported_type_error(
+    conv_map: &TypeMap,
+    rust_type: &RustType,
+    sp: SourceIdSpan,
+    direction: Direction,
+    msg: String,
+) -> DiagnosticError {
+    let mut err = DiagnosticError::new2(sp, msg);
+    if let Some(explanation) = conv_map.explain_conv_failure(rust_type.to_idx(), direction) {
+        err.span_note(sp, explanation);
+    }
+    err
+}
+
 fn is_cpython_supported_type(rust_type: &RustType) -> bool {
     let primitive_types = [
         "bool", "i8", "i16", "i32", "i64", "isize", "u8", "u16", "u32", "u64", "usize", "f32",
@@ -836,6 +1674,20 @@ fn if_exported_class_generate_return_conversion(
     method_span: Span,
     src_id: SourceId,
 ) -> Result<Option<(Type, TokenStream)>> {
+    if let Some(class) = conv_map
+        .find_foreigner_class_with_such_this_type(&rust_type.ty, |_, fc| {
+            fc.iterator_type().cloned()
+        })
+    {
+        let class_name = &class.name;
+        let py_mod: Ident = parse(&py_wrapper_mod_name(&class_name.to_string()), src_id)?;
+        return Ok(Some((
+            parse_type!(super::#py_mod::#class_name),
+            quote! {
+                super::#py_mod::create_instance(py, #rust_call)?
+            },
+        )));
+    }
     let (reference_type, rust_type_unref) =
         get_reference_info_and_inner_type(rust_type, conv_map, src_id);
     let smart_pointer_info = smart_pointer(&rust_type_unref, conv_map, src_id);
@@ -18,6 +18,9 @@ impl SourceRegistry {
         id
     }
 
+    pub(crate) fn is_registered(&self, id_of_code: &str) -> bool {
+        self.data.iter().any(|x| x.id_of_code == id_of_code)
+    }
     pub(crate) fn src(&self, src_id: SourceId) -> &str {
         &self.data[src_id.0.expect("Internal Error: Invalid source id")].code
     }
@@ -1,4 +1,6 @@
 pub mod ast;
+mod explain;
+mod lint;
 mod merge;
 mod parse;
 pub mod ty;
@@ -19,7 +21,9 @@ use smol_str::SmolStr;
 use syn::{parse_quote, Ident, Type};
 
 use crate::{
-    error::{invalid_src_id_span, DiagnosticError, Result, SourceIdSpan},
+    error::{
+        emit_warning_without_src_info, invalid_src_id_span, DiagnosticError, Result, SourceIdSpan,
+    },
     source_registry::SourceId,
     typemap::{
         ast::{get_trait_bounds, normalize_type, DisplayToTokens, GenericTypeConv, TypeName},
@@ -31,6 +35,7 @@ use crate::{
     types::ForeignClassInfo,
 };
 use ast::ConversationResult;
+use lint::RuleOverride;
 
 pub(crate) use typemap_macro::{
     CItem, CItems, ExpandedFType, TypeMapConvRuleInfo, TypeMapConvRuleInfoExpanderHelper,
@@ -242,6 +247,11 @@ pub(crate) struct TypeMap {
     /// because of it is possible only in langauge backend
     not_merged_data: Vec<TypeMapConvRuleInfo>,
     generic_rules: Vec<Rc<TypeMapConvRuleInfo>>,
+    /// attach explanation to "Do not know conversation" errors
+    explain_conv_failures: bool,
+    overridden_rules: Vec<RuleOverride>,
+    /// expression to spawn futures of async methods, see `Generator::async_spawner`
+    async_spawner: Option<syn::Expr>,
 }
 
 impl Default for TypeMap {
@@ -306,6 +316,9 @@ impl Default for TypeMap {
             ftypes_storage: ForeignTypesStorage::default(),
             not_merged_data: vec![],
             generic_rules: vec![],
+            explain_conv_failures: false,
+            overridden_rules: Vec::new(),
+            async_spawner: None,
         }
     }
 }
@@ -480,6 +493,14 @@ impl TypeMap {
         ret
     }
 
+    pub(crate) fn set_async_spawner(&mut self, spawner: Option<syn::Expr>) {
+        self.async_spawner = spawner;
+    }
+
+    pub(crate) fn async_spawner(&self) -> Option<&syn::Expr> {
+        self.async_spawner.as_ref()
+    }
+
     pub(crate) fn add_foreign(
         &mut self,
         correspoding_rty: RustType,
@@ -764,10 +785,10 @@ impl TypeMap {
                                     TypeName::new(foreign_name, (class.src_id, class.name.span())),
                                 ));
                             } else {
-                                println!(
-                                    "cargo:warning=No foreign_class for type '{}'",
+                                emit_warning_without_src_info(format!(
+                                    "No foreign_class for type '{}'",
                                     rust_ty.normalized_name
-                                );
+                                ));
                             }
                         }
                     }
@@ -871,11 +892,11 @@ impl TypeMap {
                     addon_path_len = match addon_path {
                         Ok(addon_path) => addon_path.len(),
                         Err(_err) => {
-                            println!(
-                                "cargo:warning=can not build path between foreign type
+                            emit_warning_without_src_info(format!(
+                                "can not build path between foreign type
  '{}' / '{}' and it's intermidiate '{}'",
                                 self[*ftype_idx].name, self[*other], self[*inter_ty]
-                            );
+                            ));
                             0_usize
                         }
                     };
@@ -927,6 +948,25 @@ impl TypeMap {
     }
 
     pub(crate) fn register_foreigner_class(&mut self, class: &ForeignClassInfo) {
+        if let Some(iter_ty) = class.iterator_type() {
+            // methods return `Box<dyn Iterator>`, but class holds `Peekable` of it
+            let boxed_iter = self.find_or_alloc_rust_type(iter_ty, class.src_id);
+            let peekable = self.find_or_alloc_rust_type(&class.self_type_as_ty(), class.src_id);
+            self.add_conversation_rule(
+                boxed_iter.to_idx(),
+                peekable.to_idx(),
+                TypeConvCode::new2(
+                    format!(
+                        "let {to_var}: {ty} = {from_var}.peekable();",
+                        to_var = TO_VAR_TEMPLATE,
+                        from_var = FROM_VAR_TEMPLATE,
+                        ty = peekable,
+                    ),
+                    invalid_src_id_span(),
+                )
+                .into(),
+            );
+        }
         self.foreign_classes.push(class.clone());
     }
 
@@ -1280,6 +1320,13 @@ mod tests {
             foreign_code: String::new(),
             doc_comments: vec![],
             derive_list: vec![],
+            fields: vec![],
+            consts: vec![],
+            is_abstract: false,
+            base_class: None,
+            deprecated: None,
+            is_internal: false,
+            is_module_items_holder: false,
         });
 
         let rc_refcell_foo_ty = types_map
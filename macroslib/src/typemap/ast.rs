@@ -26,7 +26,7 @@ use syn::{
     Type,
 };
 
-pub(in crate) use self::subst_map::{TyParamsSubstItem, TyParamsSubstList, TyParamsSubstMap};
+pub(crate) use self::subst_map::{TyParamsSubstItem, TyParamsSubstList, TyParamsSubstMap};
 use super::typemap_macro::expand_macroses;
 use crate::{
     error::{DiagnosticError, SourceIdSpan},
@@ -244,6 +244,20 @@ impl GenericTypeConv {
         goal_ty: Option<&RustType>,
         others: OtherRustTypes,
     ) -> Option<ConversationResult>
+    where
+        OtherRustTypes: Fn(&str) -> Option<&'a RustType>,
+    {
+        self.check_conv_possible(ty, goal_ty, others).ok()
+    }
+
+    /// The same as `is_conv_possible`, but in case of failure
+    /// reports why this rule can not be used for `ty`
+    pub(crate) fn check_conv_possible<'a, OtherRustTypes>(
+        &self,
+        ty: &RustType,
+        goal_ty: Option<&RustType>,
+        others: OtherRustTypes,
+    ) -> Result<ConversationResult<'_>, ConvMismatch>
     where
         OtherRustTypes: Fn(&str) -> Option<&'a RustType>,
     {
@@ -258,7 +272,7 @@ impl GenericTypeConv {
             subst_map.insert(&ty_p.ident, None);
         }
         if !is_second_subst_of_first(&self.from_ty, &ty.ty, &mut subst_map) {
-            return None;
+            return Err(pattern_mismatch(&self.from_ty, &ty.ty));
         }
         trace!(
             "is_conv_possible: {} is subst of {}, check trait bounds",
@@ -275,10 +289,8 @@ impl GenericTypeConv {
                     trait_bounds
                 );
 
-                if !is_ty_satisfy_trait_bounds(subst_it.ident, val, &trait_bounds, &others) {
-                    trace!("is_conv_possible: trait bounds check failed");
-                    return None;
-                }
+                check_ty_trait_bounds(subst_it.ident, val, &trait_bounds, &others)
+                    .inspect_err(|_| trace!("is_conv_possible: trait bounds check failed"))?;
             } else {
                 has_unbinded = true;
             }
@@ -305,10 +317,8 @@ impl GenericTypeConv {
             }
             for subst_it in subst_map.as_slice() {
                 if let Some(ref val) = subst_it.ty {
-                    if !is_ty_satisfy_trait_bounds(subst_it.ident, val, &trait_bounds, &others) {
-                        trace!("is_conv_possible: trait bounds check failed");
-                        return None;
-                    }
+                    check_ty_trait_bounds(subst_it.ident, val, &trait_bounds, &others)
+                        .inspect_err(|_| trace!("is_conv_possible: trait bounds check failed"))?;
                 }
             }
         }
@@ -334,7 +344,7 @@ impl GenericTypeConv {
                     != RustTypeS::make_unique_typename(&clean_from_ty, &foreign_name)
                 {
                     trace!("is_conv_possible: check failed by from_foreigner_hint check");
-                    return None;
+                    return Err(ConvMismatch::ForeignHint(foreign_name));
                 }
             }
         }
@@ -360,7 +370,7 @@ impl GenericTypeConv {
         let normalized_name =
             RustTypeS::make_unique_typename_if_need(normalize_type(&to_ty).to_string(), to_suffix)
                 .into();
-        Some(ConversationResult {
+        Ok(ConversationResult {
             to_ty,
             to_ty_name: normalized_name,
             subst_map,
@@ -415,25 +425,122 @@ impl GenericTypeConv {
     }
 }
 
-fn is_ty_satisfy_trait_bounds<'a, OtherRustTypes>(
+pub(in crate::typemap) fn check_ty_trait_bounds<'a, OtherRustTypes>(
     subst_ident: &syn::Ident,
     val: &syn::Type,
     trait_bounds: &[GenericTraitBound],
     others: &OtherRustTypes,
-) -> bool
+) -> Result<(), ConvMismatch>
 where
     OtherRustTypes: Fn(&str) -> Option<&'a RustType>,
 {
-    let traits_bound_not_match = |idx: usize| {
-        let requires = &trait_bounds[idx].trait_names;
-        let val_name = normalize_type(val);
-
-        others(val_name).map_or(true, |rt| !rt.implements.contains_subset(requires))
-    };
-    !trait_bounds
+    let bound = match trait_bounds
         .iter()
-        .position(|it| it.ty_param.as_ref() == subst_ident)
-        .map_or(false, traits_bound_not_match)
+        .find(|it| it.ty_param.as_ref() == subst_ident)
+    {
+        Some(x) => x,
+        None => return Ok(()),
+    };
+    let val_name = normalize_type(val);
+    match others(val_name) {
+        Some(rt) if rt.implements.contains_subset(&bound.trait_names) => Ok(()),
+        rt => Err(ConvMismatch::TraitBound {
+            param: subst_ident.to_string(),
+            ty: val_name.to_string(),
+            traits: bound
+                .trait_names
+                .iter()
+                .map(|x| DisplayToTokens(x).to_string())
+                .collect::<Vec<_>>()
+                .join(" + "),
+            unknown_ty: rt.is_none(),
+        }),
+    }
+}
+
+/// Why `GenericTypeConv` can not be applied to type
+#[derive(Debug, PartialEq)]
+pub(crate) enum ConvMismatch {
+    /// type does not match the pattern, for example `Vec<T>` vs `Option<i32>`
+    Shape,
+    /// the same type, but with different number of generic arguments
+    Arity { expected: usize, found: usize },
+    /// type bound to type parameter does not implement required traits
+    TraitBound {
+        param: String,
+        ty: String,
+        traits: String,
+        unknown_ty: bool,
+    },
+    /// rule restricted to such foreign type via `from_foreigner_hint`
+    ForeignHint(String),
+    /// rule does not describe conversation in required direction
+    NoRuleForDirection,
+}
+
+impl Display for ConvMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ConvMismatch::Shape => write!(f, "type does not match rule's pattern"),
+            ConvMismatch::Arity { expected, found } => write!(
+                f,
+                "wrong number of generic arguments: expected {}, found {}",
+                expected, found
+            ),
+            ConvMismatch::TraitBound {
+                param,
+                ty,
+                traits,
+                unknown_ty,
+            } => {
+                write!(
+                    f,
+                    "trait bound `{}: {}` not satisfied for {} = `{}`",
+                    param, traits, param, ty
+                )?;
+                if *unknown_ty {
+                    write!(f, " (type `{}` is unknown to type map)", ty)?;
+                }
+                Ok(())
+            }
+            ConvMismatch::ForeignHint(name) => {
+                write!(f, "rule applicable only to `{}`", name)
+            }
+            ConvMismatch::NoRuleForDirection => {
+                write!(f, "rule has no conversation in this direction")
+            }
+        }
+    }
+}
+
+/// Find out why `ty` does not match `pattern`
+pub(crate) fn pattern_mismatch(pattern: &Type, ty: &Type) -> ConvMismatch {
+    fn generic_args_count(args: &syn::PathArguments) -> usize {
+        match args {
+            syn::PathArguments::AngleBracketed(ref x) => x.args.len(),
+            _ => 0,
+        }
+    }
+    match (pattern, ty) {
+        (Type::Path(ref p1), Type::Path(ref p2)) => {
+            match (p1.path.segments.last(), p2.path.segments.last()) {
+                (Some(s1), Some(s2)) if s1.ident == s2.ident => {
+                    let expected = generic_args_count(&s1.arguments);
+                    let found = generic_args_count(&s2.arguments);
+                    if expected != found {
+                        ConvMismatch::Arity { expected, found }
+                    } else {
+                        ConvMismatch::Shape
+                    }
+                }
+                _ => ConvMismatch::Shape,
+            }
+        }
+        (Type::Reference(ref r1), Type::Reference(ref r2)) if r1.mutability == r2.mutability => {
+            pattern_mismatch(&r1.elem, &r2.elem)
+        }
+        _ => ConvMismatch::Shape,
+    }
 }
 
 /// for example true for Result<T, E> Result<u8, u8>
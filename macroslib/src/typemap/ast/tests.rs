@@ -276,6 +276,53 @@ fn generic_type_conv_find() {
     );
 }
 
+#[test]
+fn test_check_conv_possible_mismatch() {
+    let _ = env_logger::try_init();
+    let generic = get_generic_params_from_code! {
+        impl<T: SwigForeignClass> SwigFrom<Vec<T>> for jobjectArray {
+            fn swig_from(x: Vec<T>, _: *mut JNIEnv) -> Self {
+                unimplemented!();
+            }
+        }
+    };
+    let conv = GenericTypeConv::new(
+        str_to_ty("Vec<T>"),
+        str_to_ty("jobjectArray"),
+        generic,
+        TypeConvCode::invalid(),
+    );
+    assert_eq!(
+        ConvMismatch::Shape,
+        conv.check_conv_possible(&str_to_rust_ty("Option<Foo>"), None, |_| None)
+            .unwrap_err()
+    );
+    assert_eq!(
+        ConvMismatch::Arity {
+            expected: 1,
+            found: 2
+        },
+        pattern_mismatch(&str_to_ty("Vec<T>"), &str_to_ty("Vec<Foo, Alloc>"))
+    );
+    let err = conv
+        .check_conv_possible(&str_to_rust_ty("Vec<Foo>"), None, |_| None)
+        .unwrap_err();
+    assert_eq!(
+        ConvMismatch::TraitBound {
+            param: "T".into(),
+            ty: "Foo".into(),
+            traits: "SwigForeignClass".into(),
+            unknown_ty: true,
+        },
+        err
+    );
+    assert_eq!(
+        "trait bound `T: SwigForeignClass` not satisfied for T = `Foo` \
+         (type `Foo` is unknown to type map)",
+        err.to_string()
+    );
+}
+
 #[test]
 fn test_get_trait_bounds() {
     let _ = env_logger::try_init();
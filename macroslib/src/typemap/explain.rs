@@ -0,0 +1,402 @@
+//! Debug helpers for types map: dump of conversation graph
+//! in Graphviz DOT format, and explanation why type can not be
+//! converted to foreign type
+
+use std::{collections::VecDeque, fmt::Write};
+
+use petgraph::Direction;
+use rustc_hash::FxHashSet;
+
+use crate::{
+    typemap::{
+        ast::{
+            check_ty_trait_bounds, get_trait_bounds, is_second_subst_of_first, normalize_type,
+            pattern_mismatch, ConvMismatch, DisplayToTokens, GenericTypeConv, TyParamsSubstMap,
+        },
+        ty::{ForeignTypeS, RustType},
+        typemap_macro::FTypeLeftRightPair,
+        RustTypeIdx, TypeMap, TypeMapConvRuleInfo,
+    },
+    WRITE_TO_MEM_FAILED_MSG,
+};
+
+/// How many foreign types to show in explanation
+const MAX_CLOSEST_FOREIGN_TYPES: usize = 5;
+
+impl TypeMap {
+    /// Dump of conversation graph in Graphviz DOT format.
+    /// Rust types are boxes, foreign types are ellipses,
+    /// generic rules are shown as dashed templates
+    pub(crate) fn to_dot(&self) -> String {
+        let mut out = String::new();
+        writeln!(&mut out, "digraph conv_graph {{").expect(WRITE_TO_MEM_FAILED_MSG);
+        writeln!(&mut out, "    node [shape=box];").expect(WRITE_TO_MEM_FAILED_MSG);
+        for node in self.conv_graph.node_indices() {
+            writeln!(
+                &mut out,
+                "    r{} [label=\"{}\"];",
+                node.index(),
+                dot_escape(&self.conv_graph[node].normalized_name)
+            )
+            .expect(WRITE_TO_MEM_FAILED_MSG);
+        }
+        for edge in self.conv_graph.edge_indices() {
+            if let Some((from, to)) = self.conv_graph.edge_endpoints(edge) {
+                writeln!(&mut out, "    r{} -> r{};", from.index(), to.index())
+                    .expect(WRITE_TO_MEM_FAILED_MSG);
+            }
+        }
+        for (idx, ftype) in self.ftypes_storage.iter().enumerate() {
+            writeln!(
+                &mut out,
+                "    f{} [label=\"{}\", shape=ellipse];",
+                idx,
+                dot_escape(ftype.name.as_str())
+            )
+            .expect(WRITE_TO_MEM_FAILED_MSG);
+            if let Some(ref rule) = ftype.into_from_rust {
+                writeln!(
+                    &mut out,
+                    "    r{} -> f{} [style=dotted];",
+                    rule.rust_ty.index(),
+                    idx
+                )
+                .expect(WRITE_TO_MEM_FAILED_MSG);
+            }
+            if let Some(ref rule) = ftype.from_into_rust {
+                writeln!(
+                    &mut out,
+                    "    f{} -> r{} [style=dotted];",
+                    idx,
+                    rule.rust_ty.index()
+                )
+                .expect(WRITE_TO_MEM_FAILED_MSG);
+            }
+        }
+        for (idx, edge) in self.generic_edges.iter().enumerate() {
+            writeln!(
+                &mut out,
+                "    g{}_from [label=\"{} {}\", style=dashed];",
+                idx,
+                dot_escape(&DisplayToTokens(&edge.generic_params).to_string()),
+                dot_escape(normalize_type(&edge.from_ty))
+            )
+            .expect(WRITE_TO_MEM_FAILED_MSG);
+            writeln!(
+                &mut out,
+                "    g{}_to [label=\"{}\", style=dashed];",
+                idx,
+                dot_escape(normalize_type(&edge.to_ty))
+            )
+            .expect(WRITE_TO_MEM_FAILED_MSG);
+            writeln!(&mut out, "    g{0}_from -> g{0}_to [style=dashed];", idx)
+                .expect(WRITE_TO_MEM_FAILED_MSG);
+        }
+        for (idx, rule) in self.generic_rules.iter().enumerate() {
+            write_generic_rule_as_dot(&mut out, idx, rule);
+        }
+        writeln!(&mut out, "}}").expect(WRITE_TO_MEM_FAILED_MSG);
+        out
+    }
+
+    /// Turn on/off attaching of `explain_conversation` report to errors
+    /// about types that can not be converted
+    pub(crate) fn set_explain_conv_failures(&mut self, doit: bool) {
+        self.explain_conv_failures = doit;
+    }
+
+    /// `explain_conversation` if it was turned on via `set_explain_conv_failures`
+    pub(crate) fn explain_conv_failure(
+        &self,
+        rust_ty: RustTypeIdx,
+        direction: Direction,
+    ) -> Option<String> {
+        if self.explain_conv_failures {
+            Some(self.explain_conversation(rust_ty, direction))
+        } else {
+            None
+        }
+    }
+
+    /// Report which generic rules may be used to convert `rust_ty`,
+    /// why they can not be used, and which foreign types are
+    /// the closest ones in conversation graph
+    pub(crate) fn explain_conversation(
+        &self,
+        rust_ty: RustTypeIdx,
+        direction: Direction,
+    ) -> String {
+        let ty = &self.conv_graph[rust_ty];
+        let mut out = String::new();
+        let direction_desc = match direction {
+            Direction::Outgoing => "from Rust to foreign",
+            Direction::Incoming => "from foreign to Rust",
+        };
+        writeln!(
+            &mut out,
+            "explanation of conversation {} for type `{}`",
+            direction_desc, ty
+        )
+        .expect(WRITE_TO_MEM_FAILED_MSG);
+
+        let mut not_matched_shape = 0;
+        let mut candidates = Vec::new();
+        for edge in &self.generic_edges {
+            let res = match direction {
+                Direction::Outgoing => edge
+                    .check_conv_possible(ty, None, |name| self.find_rust_type_by_name(name))
+                    .map(|x| format!("gives `{}`", x.to_ty_name)),
+                Direction::Incoming => self.check_generic_edge_result(edge, ty),
+            };
+            let rule_desc = format!(
+                "{} {} => {}",
+                DisplayToTokens(&edge.generic_params),
+                normalize_type(&edge.from_ty),
+                normalize_type(&edge.to_ty)
+            );
+            match res {
+                Err(ConvMismatch::Shape) | Err(ConvMismatch::NoRuleForDirection) => {
+                    not_matched_shape += 1
+                }
+                Err(reason) => candidates.push((rule_desc, reason.to_string())),
+                Ok(desc) => candidates.push((rule_desc, format!("matched, {}", desc))),
+            }
+        }
+        for rule in &self.generic_rules {
+            let res = rule.check_ty_subst_of_my_generic_rtype(&ty.ty, direction, |ty, traits| {
+                self.find_rust_type_by_name(normalize_type(ty))
+                    .is_some_and(|rty| traits.iter().all(|t| rty.implements.contains_path(t)))
+            });
+            let rule_desc = generic_rule_desc(rule);
+            match res {
+                Err(ConvMismatch::Shape) | Err(ConvMismatch::NoRuleForDirection) => {
+                    not_matched_shape += 1
+                }
+                Err(reason) => candidates.push((rule_desc, reason.to_string())),
+                Ok(_) => candidates.push((rule_desc, "matched".to_string())),
+            }
+        }
+        if candidates.is_empty() {
+            writeln!(&mut out, "no generic rules match type").expect(WRITE_TO_MEM_FAILED_MSG);
+        } else {
+            writeln!(&mut out, "candidate generic rules:").expect(WRITE_TO_MEM_FAILED_MSG);
+            for (rule_desc, reason) in &candidates {
+                writeln!(&mut out, "    `{}`: {}", rule_desc, reason)
+                    .expect(WRITE_TO_MEM_FAILED_MSG);
+            }
+        }
+        if not_matched_shape > 0 {
+            writeln!(
+                &mut out,
+                "{} other generic rule(s) do not match type's shape",
+                not_matched_shape
+            )
+            .expect(WRITE_TO_MEM_FAILED_MSG);
+        }
+
+        let closest = self.closest_foreign_types(rust_ty, direction);
+        if closest.is_empty() {
+            write!(
+                &mut out,
+                "type is not connected with any foreign type in conversation graph"
+            )
+            .expect(WRITE_TO_MEM_FAILED_MSG);
+        } else {
+            write!(&mut out, "closest foreign types:").expect(WRITE_TO_MEM_FAILED_MSG);
+            for (dist, reachable, ftype) in closest {
+                let rule = match direction {
+                    Direction::Outgoing => ftype.into_from_rust.as_ref(),
+                    Direction::Incoming => ftype.from_into_rust.as_ref(),
+                }
+                .expect("Internal error: closest_foreign_types return type without rule");
+                write!(
+                    &mut out,
+                    "\n    `{}` via `{}`, {} step(s){}",
+                    ftype.name,
+                    self.conv_graph[rule.rust_ty],
+                    dist,
+                    if reachable {
+                        ""
+                    } else {
+                        ", but path has edges in opposite direction"
+                    }
+                )
+                .expect(WRITE_TO_MEM_FAILED_MSG);
+            }
+        }
+        out
+    }
+
+    pub(in crate::typemap) fn find_rust_type_by_name(&self, name: &str) -> Option<&RustType> {
+        self.rust_names_map
+            .get(name)
+            .map(|idx| &self.conv_graph[*idx])
+    }
+
+    /// Check if result of generic edge can be `ty`
+    fn check_generic_edge_result(
+        &self,
+        edge: &GenericTypeConv,
+        ty: &RustType,
+    ) -> Result<String, ConvMismatch> {
+        let mut subst_map = TyParamsSubstMap::default();
+        for ty_p in edge.generic_params.type_params() {
+            subst_map.insert(&ty_p.ident, None);
+        }
+        if !is_second_subst_of_first(&edge.to_ty, &ty.ty, &mut subst_map) {
+            return Err(pattern_mismatch(&edge.to_ty, &ty.ty));
+        }
+        let trait_bounds = get_trait_bounds(&edge.generic_params);
+        for subst_it in subst_map.as_slice() {
+            if let Some(ref val) = subst_it.ty {
+                check_ty_trait_bounds(subst_it.ident, val, &trait_bounds, &|name| {
+                    self.find_rust_type_by_name(name)
+                })?;
+            }
+        }
+        Ok(format!("from `{}`", normalize_type(&edge.from_ty)))
+    }
+
+    /// Breadth-first search of foreign types, ignoring direction of edges,
+    /// returns distance, flag if foreign type reachable in right direction
+    /// and foreign type itself
+    fn closest_foreign_types(
+        &self,
+        rust_ty: RustTypeIdx,
+        direction: Direction,
+    ) -> Vec<(usize, bool, &ForeignTypeS)> {
+        let mut ret = Vec::new();
+        let mut visited = FxHashSet::default();
+        let mut queue = VecDeque::new();
+        visited.insert(rust_ty);
+        queue.push_back((rust_ty, 0, true));
+        while let Some((node, dist, reachable)) = queue.pop_front() {
+            for ftype in self.ftypes_storage.iter() {
+                let rule = match direction {
+                    Direction::Outgoing => ftype.into_from_rust.as_ref(),
+                    Direction::Incoming => ftype.from_into_rust.as_ref(),
+                };
+                if rule.is_some_and(|r| r.rust_ty == node) {
+                    ret.push((dist, reachable, ftype));
+                }
+            }
+            if ret.len() >= MAX_CLOSEST_FOREIGN_TYPES {
+                break;
+            }
+            let mut walk = |neighbors_dir: Direction, right_dir: bool| {
+                for next in self.conv_graph.neighbors_directed(node, neighbors_dir) {
+                    if visited.insert(next) {
+                        queue.push_back((next, dist + 1, reachable && right_dir));
+                    }
+                }
+            };
+            walk(direction, true);
+            walk(direction.opposite(), false);
+        }
+        ret.truncate(MAX_CLOSEST_FOREIGN_TYPES);
+        ret
+    }
+}
+
+fn write_generic_rule_as_dot(out: &mut String, idx: usize, rule: &TypeMapConvRuleInfo) {
+    let left_ty = match rule
+        .rtype_left_to_right
+        .as_ref()
+        .or(rule.rtype_right_to_left.as_ref())
+    {
+        Some(x) => &x.left_ty,
+        None => return,
+    };
+    let generics = rule
+        .rtype_generics
+        .as_ref()
+        .map(|x| DisplayToTokens(x).to_string())
+        .unwrap_or_default();
+    writeln!(
+        out,
+        "    t{} [label=\"{} {}\", style=dashed];",
+        idx,
+        dot_escape(&generics),
+        dot_escape(normalize_type(left_ty))
+    )
+    .expect(WRITE_TO_MEM_FAILED_MSG);
+    if let Some(right_ty) = rule
+        .rtype_left_to_right
+        .as_ref()
+        .and_then(|x| x.right_ty.as_ref())
+    {
+        writeln!(
+            out,
+            "    t{0}_r2l [label=\"{1}\", style=dashed];\n    t{0} -> t{0}_r2l [style=dashed];",
+            idx,
+            dot_escape(normalize_type(right_ty))
+        )
+        .expect(WRITE_TO_MEM_FAILED_MSG);
+    }
+    if let Some(right_ty) = rule
+        .rtype_right_to_left
+        .as_ref()
+        .and_then(|x| x.right_ty.as_ref())
+    {
+        writeln!(
+            out,
+            "    t{0}_l2r [label=\"{1}\", style=dashed];\n    t{0}_l2r -> t{0} [style=dashed];",
+            idx,
+            dot_escape(normalize_type(right_ty))
+        )
+        .expect(WRITE_TO_MEM_FAILED_MSG);
+    }
+    for (fidx, frule) in rule.ftype_left_to_right.iter().enumerate() {
+        {
+            let name = ftype_name(&frule.left_right_ty);
+            writeln!(
+                out,
+                "    t{0}_fo{1} [label=\"{2}\", shape=ellipse, style=dashed];\n    t{0} -> t{0}_fo{1} [style=dotted];",
+                idx,
+                fidx,
+                dot_escape(name)
+            )
+            .expect(WRITE_TO_MEM_FAILED_MSG);
+        }
+    }
+    for (fidx, frule) in rule.ftype_right_to_left.iter().enumerate() {
+        {
+            let name = ftype_name(&frule.left_right_ty);
+            writeln!(
+                out,
+                "    t{0}_fi{1} [label=\"{2}\", shape=ellipse, style=dashed];\n    t{0}_fi{1} -> t{0} [style=dotted];",
+                idx,
+                fidx,
+                dot_escape(name)
+            )
+            .expect(WRITE_TO_MEM_FAILED_MSG);
+        }
+    }
+}
+
+fn generic_rule_desc(rule: &TypeMapConvRuleInfo) -> String {
+    let generics = rule
+        .rtype_generics
+        .as_ref()
+        .map(|x| DisplayToTokens(x).to_string())
+        .unwrap_or_default();
+    match rule
+        .rtype_left_to_right
+        .as_ref()
+        .or(rule.rtype_right_to_left.as_ref())
+    {
+        Some(x) => format!("{} {}", generics, normalize_type(&x.left_ty)),
+        None => generics,
+    }
+}
+
+fn ftype_name(pair: &FTypeLeftRightPair) -> &str {
+    match pair {
+        FTypeLeftRightPair::OnlyRight(ref x) | FTypeLeftRightPair::Both(_, ref x) => &x.name,
+        FTypeLeftRightPair::OnlyLeft(ref x) => &x.name,
+    }
+}
+
+fn dot_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
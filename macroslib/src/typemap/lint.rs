@@ -0,0 +1,334 @@
+//! Validation of types map rules: overridden builtin rules,
+//! generic rules that never match, ambiguous conversations
+//! and rules gated by options that no configuration enables
+
+use std::collections::VecDeque;
+
+use petgraph::{visit::EdgeRef, Direction};
+use rustc_hash::{FxHashMap, FxHashSet};
+use syn::spanned::Spanned;
+
+use crate::{
+    error::{DiagnosticError, SourceIdSpan},
+    source_registry::SourceId,
+    typemap::{
+        ast::{normalize_type, DisplayToTokens, GenericTypeConv},
+        typemap_macro::FTypeConvRule,
+        RustTypeIdx, TypeMap, TypeMapConvRuleInfo,
+    },
+};
+
+/// Rule that replaced rule from other source during merge
+#[derive(Debug)]
+pub(in crate::typemap) struct RuleOverride {
+    new: SourceIdSpan,
+    old: SourceIdSpan,
+    what: String,
+}
+
+impl TypeMap {
+    pub(in crate::typemap) fn record_override(
+        &mut self,
+        new: SourceIdSpan,
+        old: SourceIdSpan,
+        what: String,
+    ) {
+        if new.0 != old.0 {
+            self.overridden_rules.push(RuleOverride { new, old, what });
+        }
+    }
+
+    /// Check rules for problems that do not prevent code generation,
+    /// but most likely are mistakes. `builtin` are sources of rules
+    /// that come with flapigen, `cfg_options` are all options that language
+    /// backend supports for `cfg_option` of rules.
+    pub(crate) fn lint(
+        &self,
+        builtin: &[SourceId],
+        cfg_options: &FxHashSet<&'static str>,
+    ) -> Vec<DiagnosticError> {
+        let is_builtin = |src_id: SourceId| src_id.is_none() || builtin.contains(&src_id);
+        let mut ret = Vec::new();
+        self.lint_overrides(&is_builtin, &mut ret);
+        self.lint_generic_edges(&is_builtin, &mut ret);
+        self.lint_generic_rules(&is_builtin, &mut ret);
+        self.lint_ambiguous_paths(&is_builtin, &mut ret);
+        self.lint_cfg_options(cfg_options, &mut ret);
+        ret
+    }
+
+    fn lint_overrides<F: Fn(SourceId) -> bool>(
+        &self,
+        is_builtin: &F,
+        out: &mut Vec<DiagnosticError>,
+    ) {
+        for o in &self.overridden_rules {
+            if is_builtin(o.new.0) {
+                continue;
+            }
+            let (msg, note) = if is_builtin(o.old.0) {
+                ("overrides builtin rule", "builtin rule defined here")
+            } else {
+                (
+                    "overrides rule from other source",
+                    "overridden rule defined here",
+                )
+            };
+            out.push(
+                DiagnosticError::new2(o.new, format!("rule for {} {}", o.what, msg))
+                    .add_span_note(o.old, note),
+            );
+        }
+    }
+
+    fn lint_generic_edges<F: Fn(SourceId) -> bool>(
+        &self,
+        is_builtin: &F,
+        out: &mut Vec<DiagnosticError>,
+    ) {
+        for (idx, edge) in self.generic_edges.iter().enumerate() {
+            if is_builtin(edge.src_id) {
+                continue;
+            }
+            let shadowed_by = self.generic_edges[..idx]
+                .iter()
+                .find(|prev| generic_edge_desc(prev) == generic_edge_desc(edge));
+            if let Some(prev) = shadowed_by {
+                out.push(
+                    DiagnosticError::new2(
+                        generic_edge_span(edge),
+                        format!(
+                            "generic rule `{}` is never used, because of it is shadowed by the same rule",
+                            generic_edge_desc(edge)
+                        ),
+                    )
+                    .add_span_note(generic_edge_span(prev), "previous rule defined here"),
+                );
+                continue;
+            }
+            let match_any = self
+                .conv_graph
+                .node_indices()
+                .map(|idx| &self.conv_graph[idx])
+                .any(|ty| {
+                    edge.check_conv_possible(ty, None, |name| self.find_rust_type_by_name(name))
+                        .is_ok()
+                });
+            if !match_any {
+                out.push(DiagnosticError::new2(
+                    generic_edge_span(edge),
+                    format!(
+                        "generic rule `{}` can never match any registered type",
+                        generic_edge_desc(edge)
+                    ),
+                ));
+            }
+        }
+    }
+
+    fn lint_generic_rules<F: Fn(SourceId) -> bool>(
+        &self,
+        is_builtin: &F,
+        out: &mut Vec<DiagnosticError>,
+    ) {
+        for rule in &self.generic_rules {
+            if is_builtin(rule.src_id) {
+                continue;
+            }
+            let match_any = self
+                .conv_graph
+                .node_indices()
+                .map(|idx| &self.conv_graph[idx])
+                .any(|ty| {
+                    [Direction::Outgoing, Direction::Incoming]
+                        .iter()
+                        .any(|dir| {
+                            rule.check_ty_subst_of_my_generic_rtype(&ty.ty, *dir, |ty, traits| {
+                                self.find_rust_type_by_name(normalize_type(ty))
+                                    .is_some_and(|rty| {
+                                        traits.iter().all(|t| rty.implements.contains_path(t))
+                                    })
+                            })
+                            .is_ok()
+                        })
+                });
+            if !match_any {
+                out.push(DiagnosticError::new(
+                    rule.src_id,
+                    rule.span,
+                    "generic rule can never match any registered type",
+                ));
+            }
+        }
+    }
+
+    /// Find Rust types that have two equally short conversation paths
+    /// to/from the same foreign type, so which rules would be used depends
+    /// on order of rules, and report rules where these paths diverge
+    fn lint_ambiguous_paths<F: Fn(SourceId) -> bool>(
+        &self,
+        is_builtin: &F,
+        out: &mut Vec<DiagnosticError>,
+    ) {
+        let mut reported = FxHashSet::default();
+        for ftype in self.ftypes_storage.iter() {
+            let rules = [
+                (ftype.into_from_rust.as_ref(), Direction::Outgoing),
+                (ftype.from_into_rust.as_ref(), Direction::Incoming),
+            ];
+            for (rule, direction) in rules.iter() {
+                let rule = match rule {
+                    Some(x) => x,
+                    None => continue,
+                };
+                // for conversation from Rust we go from foreign type's Rust type
+                // backwards, so `dist` is length of path to foreign type
+                let dist = self.distances_from(rule.rust_ty, direction.opposite());
+                let mut nodes: Vec<_> = dist.iter().collect();
+                nodes.sort();
+                for (node, node_dist) in nodes {
+                    if *node_dist == 0 {
+                        continue;
+                    }
+                    let shortest: Vec<_> = self
+                        .conv_graph
+                        .edges_directed(*node, *direction)
+                        .filter(|e| {
+                            let next = if *direction == Direction::Outgoing {
+                                e.target()
+                            } else {
+                                e.source()
+                            };
+                            dist.get(&next).is_some_and(|d| *d + 1 == *node_dist)
+                        })
+                        .collect();
+                    if shortest.len() < 2 {
+                        continue;
+                    }
+                    let user_edge = match shortest
+                        .iter()
+                        .find(|e| !is_builtin(e.weight().code.src_id()))
+                    {
+                        Some(x) => x,
+                        None => continue,
+                    };
+                    let other_edge = shortest
+                        .iter()
+                        .find(|e| e.id() != user_edge.id())
+                        .expect("Internal error: at least two edges");
+                    let key = (
+                        user_edge.id().min(other_edge.id()),
+                        user_edge.id().max(other_edge.id()),
+                    );
+                    if !reported.insert(key) {
+                        continue;
+                    }
+                    let (dir_from, dir_to) = if *direction == Direction::Outgoing {
+                        (
+                            format!("`{}`", self.conv_graph[*node]),
+                            format!("foreign type `{}`", ftype.name),
+                        )
+                    } else {
+                        (
+                            format!("foreign type `{}`", ftype.name),
+                            format!("`{}`", self.conv_graph[*node]),
+                        )
+                    };
+                    out.push(
+                        DiagnosticError::new2(
+                            user_edge.weight().code.full_span(),
+                            format!(
+                                "two equally short conversation paths from {} to {}, \
+                                 one of them uses this rule",
+                                dir_from, dir_to
+                            ),
+                        )
+                        .add_span_note(
+                            other_edge.weight().code.full_span(),
+                            "another path uses this rule",
+                        ),
+                    );
+                }
+            }
+        }
+    }
+
+    fn distances_from(
+        &self,
+        start: RustTypeIdx,
+        direction: Direction,
+    ) -> FxHashMap<RustTypeIdx, usize> {
+        let mut dist = FxHashMap::default();
+        let mut queue = VecDeque::new();
+        dist.insert(start, 0);
+        queue.push_back(start);
+        while let Some(node) = queue.pop_front() {
+            let node_dist = dist[&node];
+            for next in self.conv_graph.neighbors_directed(node, direction) {
+                dist.entry(next).or_insert_with(|| {
+                    queue.push_back(next);
+                    node_dist + 1
+                });
+            }
+        }
+        dist
+    }
+
+    fn lint_cfg_options(
+        &self,
+        cfg_options: &FxHashSet<&'static str>,
+        out: &mut Vec<DiagnosticError>,
+    ) {
+        let mut avaible_opts: Vec<_> = cfg_options.iter().collect();
+        avaible_opts.sort();
+        let mut check_opt =
+            |rule: &TypeMapConvRuleInfo, opt: &crate::typemap::ast::SpannedSmolStr| {
+                if !cfg_options.contains(opt.as_str()) {
+                    out.push(DiagnosticError::new(
+                    rule.src_id,
+                    opt.sp,
+                    format!(
+                        "rule is gated by option {} that no configuration enables, avaible options {:?}",
+                        opt.as_str(),
+                        avaible_opts
+                    ),
+                ));
+                }
+            };
+        for rule in self
+            .generic_rules
+            .iter()
+            .map(|x| &**x)
+            .chain(self.not_merged_data.iter())
+        {
+            let ftype_rules: Vec<&FTypeConvRule> = rule
+                .ftype_left_to_right
+                .iter()
+                .chain(rule.ftype_right_to_left.iter())
+                .collect();
+            for frule in ftype_rules {
+                if let Some(ref opt) = frule.cfg_option {
+                    check_opt(rule, opt);
+                }
+            }
+            for fcode in &rule.f_code {
+                if let Some(ref opt) = fcode.cfg_option {
+                    check_opt(rule, opt);
+                }
+            }
+        }
+    }
+}
+
+fn generic_edge_desc(edge: &GenericTypeConv) -> String {
+    format!(
+        "{} {} => {}",
+        DisplayToTokens(&edge.generic_params),
+        normalize_type(&edge.from_ty),
+        normalize_type(&edge.to_ty)
+    )
+}
+
+fn generic_edge_span(edge: &GenericTypeConv) -> SourceIdSpan {
+    (edge.src_id, edge.from_ty.span())
+}
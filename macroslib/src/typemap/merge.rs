@@ -2,19 +2,19 @@ use crate::typemap::ty::ForeignConversationRule;
 use std::{convert::TryInto, mem, rc::Rc};
 
 use log::{debug, error, info};
-use petgraph::graph::NodeIndex;
+use petgraph::{graph::NodeIndex, Direction};
 use rustc_hash::FxHashMap;
 use smol_str::SmolStr;
 use syn::spanned::Spanned;
 
 use crate::{
-    error::{DiagnosticError, Result},
+    error::{DiagnosticError, Result, SourceIdSpan},
     source_registry::SourceId,
     typemap::{
         ast::{SpannedSmolStr, TypeName},
         ty::{ForeignConversationIntermediate, ForeignTypeS, ForeignTypesStorage},
-        typemap_macro::{FTypeLeftRightPair, ModuleName, TypeMapConvRuleInfo},
-        TypeConvEdge, TypeMap,
+        typemap_macro::{FTypeLeftRightPair, FTypeName, ModuleName, TypeMapConvRuleInfo},
+        RustTypeIdx, TypeConvEdge, TypeMap,
     },
 };
 
@@ -121,6 +121,7 @@ impl TypeMap {
                 .graph_idx;
             let to_ty = self.find_or_alloc_rust_type(&right_ty, src_id).graph_idx;
 
+            self.record_edge_override(from_ty, to_ty, code.full_span());
             self.conv_graph
                 .update_edge(from_ty, to_ty, TypeConvEdge::new(code, None));
             rtype_left_to_right = Some((from_ty, to_ty));
@@ -144,6 +145,7 @@ impl TypeMap {
                 .find_or_alloc_rust_type(&rule.left_ty, src_id)
                 .graph_idx;
             let from_ty = self.find_or_alloc_rust_type(&right_ty, src_id).graph_idx;
+            self.record_edge_override(from_ty, to_ty, code.full_span());
             self.conv_graph
                 .update_edge(from_ty, to_ty, TypeConvEdge::new(code, None));
             rtype_right_to_left = Some((from_ty, to_ty));
@@ -289,6 +291,8 @@ impl TypeMap {
             }
         }
 
+        self.record_ftype_overrides(src_id, ft_into_from_rust.as_ref(), Direction::Outgoing);
+        self.record_ftype_overrides(src_id, ft_from_into_rust.as_ref(), Direction::Incoming);
         match (ft_into_from_rust, ft_from_into_rust) {
             (Some((ft1, into_from_rust)), Some((ft2, from_into_rust))) => {
                 if ft1 != ft2 {
@@ -337,6 +341,44 @@ impl TypeMap {
     }
 }
 
+impl TypeMap {
+    fn record_edge_override(&mut self, from: RustTypeIdx, to: RustTypeIdx, new: SourceIdSpan) {
+        if let Some(existing_edge) = self.conv_graph.find_edge(from, to) {
+            let old = self.conv_graph[existing_edge].code.full_span();
+            let what = format!(
+                "conversation `{}` -> `{}`",
+                self.conv_graph[from], self.conv_graph[to]
+            );
+            self.record_override(new, old, what);
+        }
+    }
+
+    fn record_ftype_overrides(
+        &mut self,
+        src_id: SourceId,
+        new: Option<&(FTypeName, ForeignConversationRule)>,
+        direction: Direction,
+    ) {
+        let (ft, new_rule) = match new {
+            Some(x) => x,
+            None => return,
+        };
+        let ftype = match self.ftypes_storage.find_ftype_by_name(&ft.name) {
+            Some(x) => &self.ftypes_storage[x],
+            None => return,
+        };
+        let (prev_rule, dir_desc) = match direction {
+            Direction::Outgoing => (ftype.into_from_rust.as_ref(), "to"),
+            Direction::Incoming => (ftype.from_into_rust.as_ref(), "from"),
+        };
+        if let Some(prev_rule) = prev_rule {
+            let old = ftype_rule_span(ftype.name.span, prev_rule);
+            let what = format!("conversation {} foreign type `{}`", dir_desc, ftype.name);
+            self.record_override(ftype_rule_span((src_id, ft.sp), new_rule), old, what);
+        }
+    }
+}
+
 fn add_new_nodes(
     new_data: &TypeMap,
     data: &mut TypeMap,
@@ -380,6 +422,15 @@ fn add_new_edges(
                     data.conv_graph[*our_idx],
                     data.conv_graph[our_target],
                 );
+                let what = format!(
+                    "conversation `{}` -> `{}`",
+                    data.conv_graph[*our_idx], data.conv_graph[our_target]
+                );
+                data.record_override(
+                    new_data.conv_graph[new_edge].code.full_span(),
+                    data.conv_graph[existing_edge].code.full_span(),
+                    what,
+                );
             }
             data.conv_graph.update_edge(
                 *our_idx,
@@ -402,6 +453,23 @@ fn add_new_ftypes(
             .find_ftype_by_name(new_ftype.name.as_str())
         {
             Some(ftype_idx) => {
+                let our = &data.ftypes_storage[ftype_idx];
+                let overrides = [
+                    (&our.into_from_rust, &new_ftype.into_from_rust, "to"),
+                    (&our.from_into_rust, &new_ftype.from_into_rust, "from"),
+                ]
+                .iter()
+                .filter_map(|(our_rule, new_rule, dir)| {
+                    Some((
+                        ftype_rule_span(new_ftype.name.span, new_rule.as_ref()?),
+                        ftype_rule_span(our.name.span, our_rule.as_ref()?),
+                        format!("conversation {} foreign type `{}`", dir, our.name),
+                    ))
+                })
+                .collect::<Vec<_>>();
+                for (new, old, what) in overrides {
+                    data.record_override(new, old, what);
+                }
                 ftype_merge(&mut data.ftypes_storage[ftype_idx], new_ftype);
             }
             None => {
@@ -447,6 +515,15 @@ fn ftype_merge(our: &mut ForeignTypeS, extrn_ft: ForeignTypeS) {
     }
 }
 
+/// Where rule was defined, code of conversation if it exists,
+/// otherwise place where foreign type mentioned
+fn ftype_rule_span(ftype_name_span: SourceIdSpan, rule: &ForeignConversationRule) -> SourceIdSpan {
+    rule.intermediate
+        .as_ref()
+        .map(|x| x.conv_code.full_span())
+        .unwrap_or(ftype_name_span)
+}
+
 fn convert_req_module_to_provides_by_module(v: Vec<ModuleName>) -> Vec<SmolStr> {
     let mut ret = Vec::with_capacity(v.len());
     for x in v {
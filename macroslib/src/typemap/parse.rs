@@ -67,6 +67,9 @@ pub(in crate::typemap) fn parse(
         ftypes_storage: ForeignTypesStorage::default(),
         not_merged_data: vec![],
         generic_rules: vec![],
+        explain_conv_failures: false,
+        overridden_rules: Vec::new(),
+        async_spawner: None,
     };
 
     macro_rules! handle_attrs {
@@ -15,12 +15,15 @@ use syn::{
 };
 
 use crate::{
-    error::{invalid_src_id_span, DiagnosticError, Result, SourceIdSpan},
+    error::{
+        emit_warning_without_src_info, invalid_src_id_span, DiagnosticError, Result, SourceIdSpan,
+    },
     source_registry::SourceId,
     typemap::{
         ast::{
             get_trait_bounds, is_second_subst_of_first, normalize_type, parse_ty_with_given_span,
-            DisplayToTokens, GenericTypeConv, SpannedSmolStr, TyParamsSubstMap,
+            pattern_mismatch, ConvMismatch, DisplayToTokens, GenericTypeConv, SpannedSmolStr,
+            TyParamsSubstMap,
         },
         ty::TraitNamesSet,
         TypeConvCode,
@@ -195,6 +198,21 @@ impl TypeMapConvRuleInfo {
         direction: Direction,
         impl_trait: TraitChecker,
     ) -> Option<TyParamsSubstMap>
+    where
+        TraitChecker: Fn(&Type, &TraitNamesSet) -> bool,
+    {
+        self.check_ty_subst_of_my_generic_rtype(ty, direction, impl_trait)
+            .ok()
+    }
+
+    /// The same as `is_ty_subst_of_my_generic_rtype`, but in case of failure
+    /// reports why rule can not be used for `ty`
+    pub(crate) fn check_ty_subst_of_my_generic_rtype<TraitChecker>(
+        &self,
+        ty: &Type,
+        direction: Direction,
+        impl_trait: TraitChecker,
+    ) -> std::result::Result<TyParamsSubstMap<'_>, ConvMismatch>
     where
         TraitChecker: Fn(&Type, &TraitNamesSet) -> bool,
     {
@@ -206,7 +224,7 @@ impl TypeMapConvRuleInfo {
                 Direction::Incoming => self.rtype_right_to_left.as_ref(),
                 Direction::Outgoing => self.rtype_left_to_right.as_ref(),
             };
-            let rule = rule?;
+            let rule = rule.ok_or(ConvMismatch::NoRuleForDirection)?;
             &rule.left_ty
         };
 
@@ -219,25 +237,35 @@ impl TypeMapConvRuleInfo {
             subst_map.insert(&ty_p.ident, None);
         }
         if !is_second_subst_of_first(generic_ty, ty, &mut subst_map) {
-            return None;
+            return Err(pattern_mismatch(generic_ty, ty));
         }
         let bounds = get_trait_bounds(generics);
         for b in &bounds {
             if let Some(Some(ty)) = subst_map.get(b.ty_param.as_ref()) {
                 if !impl_trait(ty, &b.trait_names) {
-                    return None;
+                    return Err(ConvMismatch::TraitBound {
+                        param: b.ty_param.as_ref().to_string(),
+                        ty: normalize_type(ty).to_string(),
+                        traits: b
+                            .trait_names
+                            .iter()
+                            .map(|x| DisplayToTokens(x).to_string())
+                            .collect::<Vec<_>>()
+                            .join(" + "),
+                        unknown_ty: false,
+                    });
                 }
             } else {
-                println!(
-                    "cargo:warning=invalid generic bounds({}) refer unknown parameter, subst. map {:?}",
+                emit_warning_without_src_info(format!(
+                    "invalid generic bounds({}) refer unknown parameter, subst. map {:?}",
                     b.ty_param.as_ref(),
                     subst_map
-                );
-                return None;
+                ));
+                return Err(ConvMismatch::Shape);
             }
         }
 
-        Some(subst_map)
+        Ok(subst_map)
     }
 
     pub(crate) fn subst_generic_params_to_c_items(
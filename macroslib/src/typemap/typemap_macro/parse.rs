@@ -11,6 +11,7 @@ use super::{
     GENERIC_ALIAS, SWIG_CONCAT_IDENTS, SWIG_F_TYPE, SWIG_I_TYPE,
 };
 use crate::{
+    error::emit_parse_warning,
     source_registry::SourceId,
     str_replace::replace_first_and_other,
     typemap::{
@@ -141,9 +142,12 @@ impl syn::parse::Parse for TypeMapConvRuleInfo {
                     || mac.path.is_ident(FOREIGNER_CODE_DEPRECATED)
                 {
                     if mac.path.is_ident(FOREIGNER_CODE_DEPRECATED) {
-                        println!(
-                            "cargo:warning={} is deprecated, use {} instead",
-                            FOREIGNER_CODE_DEPRECATED, FOREIGN_CODE
+                        emit_parse_warning(
+                            mac.path.span(),
+                            format!(
+                                "{} is deprecated, use {} instead",
+                                FOREIGNER_CODE_DEPRECATED, FOREIGN_CODE
+                            ),
                         );
                     }
                     let fc_elem = syn::parse2::<ForeignCode>(mac.tokens)?;
@@ -1,6 +1,6 @@
 use proc_macro2::{Span, TokenStream};
 use quote::quote;
-use rustc_hash::FxHashSet;
+use rustc_hash::{FxHashMap, FxHashSet};
 use std::{
     fs,
     path::{Path, PathBuf},
@@ -12,7 +12,7 @@ use crate::{
     file_cache::FileOperationsRegistrator,
     source_registry::SourceId,
     typemap::{
-        ast::check_if_smart_pointer_return_inner_type,
+        ast::{check_if_smart_pointer_return_inner_type, DisplayToTokens},
         ty::RustType,
         typemap_macro::{FTypeConvRule, TypeMapConvRuleInfo},
         ForeignTypeInfo, RustTypeIdx, TypeMap,
@@ -186,6 +186,40 @@ pub(crate) fn rust_to_foreign_convert_method_inputs<
     Ok((code_deps, ret_code))
 }
 
+/// Several methods can be mapped to the same foreign name (via `alias`),
+/// but only if foreign language can distinguish them by types of arguments.
+/// `signatures` is method, its foreign name and foreign types of arguments
+pub(crate) fn check_overloads_distinguishable<'a>(
+    class: &ForeignClassInfo,
+    lang_name: &str,
+    signatures: impl Iterator<Item = (&'a ForeignMethod, String, Vec<String>)>,
+) -> Result<()> {
+    let mut known = FxHashMap::<(String, Vec<String>), &ForeignMethod>::default();
+    for (method, name, args) in signatures {
+        let signature = format!("{}({})", name, args.join(", "));
+        if let Some(prev) = known.insert((name, args), method) {
+            return Err(DiagnosticError::new(
+                class.src_id,
+                method.span(),
+                format!(
+                    "{} can not distinguish overloaded methods of class {}: \
+                     both `{}` and `{}` are mapped to `{}`",
+                    lang_name,
+                    class.name,
+                    DisplayToTokens(&prev.rust_id),
+                    DisplayToTokens(&method.rust_id),
+                    signature
+                ),
+            )
+            .add_span_note(
+                (class.src_id, prev.span()),
+                "previous method with the same signature",
+            ));
+        }
+    }
+    Ok(())
+}
+
 pub(crate) fn validate_cfg_options(
     rule: &TypeMapConvRuleInfo,
     avaible_opts: &FxHashSet<&'static str>,
@@ -1,14 +1,16 @@
+use heck::CamelCase;
 use proc_macro2::{Ident, Span, TokenStream};
-use quote::quote;
+use quote::{quote, ToTokens};
 use smol_str::SmolStr;
-use std::fmt;
+use std::{borrow::Cow, fmt};
 use syn::{parse_quote, spanned::Spanned, Type};
 
 use crate::{
     error::{DiagnosticError, Result, SourceIdSpan},
     source_registry::SourceId,
-    typemap::ast::DisplayToTokens,
-    SMART_PTR_COPY_TRAIT,
+    typemap::ast::{normalize_type, DisplayToTokens},
+    DEBUG_TRAIT, DISPLAY_TRAIT, EQ_TRAIT, FLAGS_DERIVE, HASH_TRAIT, ORD_TRAIT, PARTIAL_EQ_TRAIT,
+    PARTIAL_ORD_TRAIT, SMART_PTR_COPY_TRAIT,
 };
 
 #[derive(Debug, Clone)]
@@ -20,6 +22,205 @@ pub(crate) struct ForeignClassInfo {
     pub foreign_code: String,
     pub doc_comments: Vec<String>,
     pub derive_list: Vec<String>,
+    pub fields: Vec<ForeignField>,
+    pub consts: Vec<ForeignConst>,
+    /// Declared as `abstract class`, it has only methods,
+    /// derived classes implement them via Rust trait
+    pub is_abstract: bool,
+    /// Base class from `class Name extends Base`
+    pub base_class: Option<Ident>,
+    pub deprecated: Option<Deprecation>,
+    /// Helper generated for other items, like `ForeignEnumInfo::raw_class`,
+    /// it is not part of API, so it is hidden from users of foreign code
+    pub is_internal: bool,
+    /// Class generated for items declared at module level,
+    /// see `ForeignClassInfo::module_items_holder`
+    pub is_module_items_holder: bool,
+}
+
+/// Item marked with `#[deprecated(since = "...", note = "...")]`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct Deprecation {
+    pub(crate) since: Option<String>,
+    pub(crate) note: Option<String>,
+}
+
+impl Deprecation {
+    /// Human readable description, like "since 1.2: use bar instead"
+    pub(crate) fn message(&self) -> String {
+        match (&self.since, &self.note) {
+            (Some(since), Some(note)) => format!("since {}: {}", since, note),
+            (Some(since), None) => format!("since {}", since),
+            (None, Some(note)) => note.clone(),
+            (None, None) => String::new(),
+        }
+    }
+}
+
+/// Field declared as `field name: Type;`, accessors for it
+/// are generated as methods of class
+#[derive(Debug, Clone)]
+pub(crate) struct ForeignField {
+    pub(crate) name: Ident,
+    pub(crate) ty: Type,
+    pub(crate) readonly: bool,
+}
+
+/// Function declared at module level, see `foreign_fn!`
+#[derive(Debug, Clone)]
+pub(crate) struct ForeignFunction {
+    pub(crate) src_id: SourceId,
+    pub(crate) method: ForeignMethod,
+}
+
+/// Constant declared as `const NAME: Type = value;`,
+/// inside class or at module level
+#[derive(Debug, Clone)]
+pub(crate) struct ForeignConst {
+    pub(crate) src_id: SourceId,
+    pub(crate) name: Ident,
+    pub(crate) ty: Type,
+    /// Rust constant that should have the same value,
+    /// see `const NAME: Type = RustPath = value;`
+    pub(crate) rust_path: Option<syn::Path>,
+    pub(crate) value: ForeignConstValue,
+    pub(crate) doc_comments: Vec<String>,
+}
+
+/// Value of exported constant, only literals are supported,
+/// because value should be known during code generation
+#[derive(Debug, Clone, PartialEq)]
+pub enum ForeignConstValue {
+    Int(i128),
+    /// Digits of float literal, with `-` for negative value
+    Float(String),
+    Bool(bool),
+    Str(String),
+}
+
+impl ForeignConst {
+    pub(crate) fn span(&self) -> Span {
+        self.name.span()
+    }
+    /// Rust expression with value of constant, with type of constant
+    pub(crate) fn rust_value(&self) -> TokenStream {
+        let ty = &self.ty;
+        let value = match self.value {
+            ForeignConstValue::Int(x) => {
+                let lit = proc_macro2::Literal::i128_unsuffixed(x.abs());
+                if x < 0 {
+                    quote! { -#lit }
+                } else {
+                    quote! { #lit }
+                }
+            }
+            ForeignConstValue::Float(ref digits) => {
+                let lit: proc_macro2::Literal = digits
+                    .trim_start_matches('-')
+                    .parse()
+                    .expect("float literal should be valid");
+                if digits.starts_with('-') {
+                    quote! { -#lit }
+                } else {
+                    quote! { #lit }
+                }
+            }
+            ForeignConstValue::Bool(x) => quote! { #x },
+            ForeignConstValue::Str(ref x) => quote! { #x },
+        };
+        quote! { { let x: #ty = #value; x } }
+    }
+    /// Compile time check that value used in foreign code
+    /// match value of Rust constant
+    pub(crate) fn rust_code_to_check_value(&self) -> TokenStream {
+        let rust_path = match self.rust_path {
+            Some(ref x) => x,
+            None => return TokenStream::new(),
+        };
+        let cond = match self.value {
+            ForeignConstValue::Str(ref x) => {
+                let bytes = syn::LitByteStr::new(x.as_bytes(), self.span());
+                quote! { matches!(#rust_path.as_bytes(), #bytes) }
+            }
+            _ => {
+                let value = self.rust_value();
+                quote! { #rust_path == #value }
+            }
+        };
+        quote! {
+            const _: () = assert!(
+                #cond,
+                concat!("value of ", stringify!(#rust_path), " is not the same as in foreign code")
+            );
+        }
+    }
+    /// Check that type of constant is suitable for its value,
+    /// only integers, floats, `bool` and `&str` are supported
+    pub(crate) fn check_type(&self) -> Result<()> {
+        let ty_name = normalize_type(&self.ty);
+        let valid = match self.value {
+            ForeignConstValue::Int(x) => match int_type_range(ty_name) {
+                Some((min, max)) => {
+                    if x < min || x > max {
+                        return Err(DiagnosticError::new(
+                            self.src_id,
+                            self.span(),
+                            format!("value {} does not fit into {}", x, ty_name),
+                        ));
+                    }
+                    true
+                }
+                None => false,
+            },
+            ForeignConstValue::Float(_) => ty_name == "f32" || ty_name == "f64",
+            ForeignConstValue::Bool(_) => ty_name == "bool",
+            ForeignConstValue::Str(_) => ty_name == "& str",
+        };
+        if valid {
+            Ok(())
+        } else {
+            Err(DiagnosticError::new(
+                self.src_id,
+                self.ty.span(),
+                format!("type {} is not suitable for value of constant", ty_name),
+            ))
+        }
+    }
+}
+
+/// String literal for C-like languages (C++, Java),
+/// control characters are written as octal escapes
+pub(crate) fn c_like_string_literal(s: &str) -> String {
+    let mut ret = String::with_capacity(s.len() + 2);
+    ret.push('"');
+    for ch in s.chars() {
+        match ch {
+            '\\' => ret.push_str("\\\\"),
+            '"' => ret.push_str("\\\""),
+            '\n' => ret.push_str("\\n"),
+            '\r' => ret.push_str("\\r"),
+            '\t' => ret.push_str("\\t"),
+            ch if (ch as u32) < 0x20 => ret.push_str(&format!("\\{:03o}", ch as u32)),
+            ch => ret.push(ch),
+        }
+    }
+    ret.push('"');
+    ret
+}
+
+fn int_type_range(ty_name: &str) -> Option<(i128, i128)> {
+    let range = match ty_name {
+        "i8" => (i128::from(i8::MIN), i128::from(i8::MAX)),
+        "i16" => (i128::from(i16::MIN), i128::from(i16::MAX)),
+        "i32" => (i128::from(i32::MIN), i128::from(i32::MAX)),
+        "i64" | "isize" => (i128::from(i64::MIN), i128::from(i64::MAX)),
+        "u8" => (0, i128::from(u8::MAX)),
+        "u16" => (0, i128::from(u16::MAX)),
+        "u32" => (0, i128::from(u32::MAX)),
+        "u64" | "usize" => (0, i128::from(u64::MAX)),
+        _ => return None,
+    };
+    Some(range)
 }
 
 /// Two types instead of one, to simplify live to developer
@@ -32,10 +233,43 @@ pub(crate) struct SelfTypeDesc {
     pub constructor_ret_type: Type,
 }
 
+/// Default name of class that holds items declared at module level,
+/// for languages without free functions and constants
+pub(crate) const MODULE_ITEMS_CLASS: &str = "Globals";
+
 impl ForeignClassInfo {
     pub(crate) fn span(&self) -> Span {
         self.name.span()
     }
+    /// Class without `self_type` with items declared at module level,
+    /// functions become its static methods
+    pub(crate) fn module_items_holder(
+        name: &str,
+        consts: Vec<ForeignConst>,
+        fns: Vec<ForeignFunction>,
+    ) -> ForeignClassInfo {
+        let src_id = consts
+            .first()
+            .map(|x| x.src_id)
+            .or_else(|| fns.first().map(|x| x.src_id))
+            .unwrap_or_else(SourceId::none);
+        ForeignClassInfo {
+            src_id,
+            name: Ident::new(name, Span::call_site()),
+            methods: fns.into_iter().map(|x| x.method).collect(),
+            self_desc: None,
+            foreign_code: String::new(),
+            doc_comments: vec![],
+            derive_list: vec![],
+            fields: vec![],
+            consts,
+            is_abstract: false,
+            base_class: None,
+            deprecated: None,
+            is_internal: false,
+            is_module_items_holder: true,
+        }
+    }
     pub(crate) fn self_type_as_ty(&self) -> Type {
         self.self_desc
             .as_ref()
@@ -43,6 +277,24 @@ impl ForeignClassInfo {
             .unwrap_or_else(|| parse_quote! { () })
     }
     /// common for several language binding generator code
+    /// Methods of abstract base class are called via Rust trait,
+    /// so they are added to derived class as is, except ones that
+    /// derived class declares itself with the same name and number of arguments
+    pub(crate) fn inherit_methods(&mut self, base: &ForeignClassInfo) {
+        let overridden = |m: &ForeignMethod| {
+            self.methods.iter().any(|x| {
+                x.short_name() == m.short_name() && x.fn_decl.inputs.len() == m.fn_decl.inputs.len()
+            })
+        };
+        let inherited: Vec<ForeignMethod> = base
+            .methods
+            .iter()
+            .filter(|m| !overridden(m))
+            .cloned()
+            .collect();
+        self.methods.extend(inherited);
+    }
+
     pub(crate) fn validate_class(&self) -> Result<()> {
         let mut has_constructor = false;
         let mut has_methods = false;
@@ -55,7 +307,7 @@ impl ForeignClassInfo {
             }
         }
         let self_type_is_some = self.self_desc.is_some();
-        if !self_type_is_some && has_methods {
+        if !self_type_is_some && has_methods && !self.is_abstract {
             Err(DiagnosticError::new(
                 self.src_id,
                 self.span(),
@@ -84,8 +336,731 @@ impl ForeignClassInfo {
     pub fn clone_derived(&self) -> bool {
         self.derive_list.iter().any(|x| x == "Clone")
     }
+    pub(crate) fn has_derive(&self, name: &str) -> bool {
+        self.derive_list.iter().any(|x| x == name)
+    }
+    /// Traits of `self_type` that should be mapped to protocols of foreign language,
+    /// like `equals`/`hashCode` for Java, see `#[derive(Eq, Hash, Display)]`
+    pub(crate) fn derived_protocols(&self) -> Result<DerivedProtocols> {
+        let eq = self.has_derive(PARTIAL_EQ_TRAIT) || self.has_derive(EQ_TRAIT);
+        let to_string = if self.has_derive(DISPLAY_TRAIT) {
+            Some(DISPLAY_TRAIT)
+        } else if self.has_derive(DEBUG_TRAIT) {
+            Some(DEBUG_TRAIT)
+        } else {
+            None
+        };
+        let cmp = if self.has_derive(ORD_TRAIT) {
+            Some(ORD_TRAIT)
+        } else if self.has_derive(PARTIAL_ORD_TRAIT) {
+            Some(PARTIAL_ORD_TRAIT)
+        } else {
+            None
+        };
+        let protocols = DerivedProtocols {
+            eq,
+            hash: self.has_derive(HASH_TRAIT),
+            to_string,
+            cmp,
+        };
+        if protocols.is_empty() {
+            return Ok(protocols);
+        }
+        if self.self_desc.is_none() {
+            return Err(DiagnosticError::new(
+                self.src_id,
+                self.span(),
+                format!(
+                    "class {}: derive of {}, {}, {}, {} requires self_type",
+                    self.name, EQ_TRAIT, HASH_TRAIT, DISPLAY_TRAIT, ORD_TRAIT
+                ),
+            ));
+        }
+        if protocols.hash && !protocols.eq {
+            return Err(DiagnosticError::new(
+                self.src_id,
+                self.span(),
+                format!(
+                    "class {}: derive of {} requires derive of {} or {}",
+                    self.name, HASH_TRAIT, EQ_TRAIT, PARTIAL_EQ_TRAIT
+                ),
+            ));
+        }
+        Ok(protocols)
+    }
+    /// For languages where protocols are implemented in foreign code:
+    /// private methods that call Rust implementation of derived traits
+    pub(crate) fn with_derived_protocol_methods(&self) -> Result<Cow<'_, ForeignClassInfo>> {
+        let protocols = self.derived_protocols()?;
+        if protocols.is_empty() {
+            return Ok(Cow::Borrowed(self));
+        }
+        let self_type = self.self_type_as_ty();
+        let class_name = &self.name;
+        let span = self.span();
+        let method = |name: &str, with_other: bool, output: Type, block| {
+            let name = Ident::new(name, span);
+            let mut inputs = vec![FnArg::SelfArg(span, SelfTypeVariant::Rptr)];
+            if with_other {
+                inputs.push(FnArg::Default(NamedArg {
+                    name: "o".into(),
+                    span,
+                    ty: parse_quote!(&#self_type),
+                    default: None,
+                }));
+            }
+            ForeignMethod {
+                variant: MethodVariant::Method(SelfTypeVariant::Rptr),
+                rust_id: parse_quote!(#class_name::#name),
+                fn_decl: FnDecl {
+                    span,
+                    inputs,
+                    output: parse_quote!(-> #output),
+                },
+                name_alias: None,
+                access: MethodAccess::Private,
+                doc_comments: vec![],
+                inline_block: Some(block),
+                unknown_attrs: vec![],
+                property: None,
+                deprecated: None,
+            }
+        };
+        let mut class = self.clone();
+        if protocols.eq {
+            class.methods.push(method(
+                DERIVED_EQ_METHOD,
+                true,
+                parse_type!(bool),
+                parse_quote!({ PartialEq::eq(this, o) }),
+            ));
+        }
+        if protocols.hash {
+            class.methods.push(method(
+                DERIVED_HASH_METHOD,
+                false,
+                parse_type!(i64),
+                parse_quote!({
+                    use std::hash::{Hash, Hasher};
+                    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+                    this.hash(&mut hasher);
+                    hasher.finish() as i64
+                }),
+            ));
+        }
+        if let Some(to_string) = protocols.to_string {
+            let block = if to_string == DISPLAY_TRAIT {
+                parse_quote!({ format!("{}", this) })
+            } else {
+                parse_quote!({ format!("{:?}", this) })
+            };
+            class.methods.push(method(
+                DERIVED_TO_STRING_METHOD,
+                false,
+                parse_type!(String),
+                block,
+            ));
+        }
+        if let Some(cmp) = protocols.cmp {
+            // `None` of `partial_cmp` is mapped to 2
+            let block = if cmp == ORD_TRAIT {
+                parse_quote!({ Ord::cmp(this, o) as i32 })
+            } else {
+                parse_quote!({
+                    match PartialOrd::partial_cmp(this, o) {
+                        Some(x) => x as i32,
+                        None => 2,
+                    }
+                })
+            };
+            class.methods.push(method(
+                DERIVED_COMPARE_METHOD,
+                true,
+                parse_type!(i32),
+                block,
+            ));
+        }
+        Ok(Cow::Owned(class))
+    }
+    /// For languages without default values of arguments:
+    /// each method with default values of arguments is followed by
+    /// overloads without these arguments
+    pub(crate) fn with_default_args_overloads(&self) -> Cow<'_, ForeignClassInfo> {
+        if !self.methods.iter().any(ForeignMethod::has_default_args) {
+            return Cow::Borrowed(self);
+        }
+        let mut class = self.clone();
+        class.methods.clear();
+        for m in &self.methods {
+            class.methods.push(m.clone());
+            class.methods.extend(m.default_args_overloads());
+        }
+        Cow::Owned(class)
+    }
+    /// Classes that hold iterators returned by methods of this class,
+    /// see `iterator_item`
+    pub(crate) fn iterator_classes(&self) -> Vec<ForeignClassInfo> {
+        let mut classes = Vec::<ForeignClassInfo>::new();
+        for m in &self.methods {
+            let ret_ty = match m.fn_decl.output {
+                syn::ReturnType::Type(_, ref ty) => ty,
+                syn::ReturnType::Default => continue,
+            };
+            let item = match boxed_iterator_item(ret_ty) {
+                Some(x) => x,
+                None => continue,
+            };
+            let name = iterator_class_name(ret_ty, m.fn_decl.span);
+            if classes.iter().any(|x| x.name == name) {
+                continue;
+            }
+            let span = m.fn_decl.span;
+            let method = |method_name: &str, output: &Type, block| {
+                let method_name = Ident::new(method_name, span);
+                ForeignMethod {
+                    variant: MethodVariant::Method(SelfTypeVariant::RptrMut),
+                    rust_id: parse_quote!(#name::#method_name),
+                    fn_decl: FnDecl {
+                        span,
+                        inputs: vec![FnArg::SelfArg(span, SelfTypeVariant::RptrMut)],
+                        output: parse_quote!(-> #output),
+                    },
+                    name_alias: None,
+                    access: MethodAccess::Private,
+                    doc_comments: vec![],
+                    inline_block: Some(block),
+                    unknown_attrs: vec![],
+                    property: None,
+                    deprecated: None,
+                }
+            };
+            let self_type: Type = parse_quote!(std::iter::Peekable<#ret_ty>);
+            let methods = vec![
+                ForeignMethod {
+                    variant: MethodVariant::Constructor,
+                    rust_id: syn::Path {
+                        leading_colon: Some(parse_quote!(::)),
+                        segments: syn::punctuated::Punctuated::new(),
+                    },
+                    fn_decl: FnDecl {
+                        span,
+                        inputs: vec![],
+                        output: syn::ReturnType::Default,
+                    },
+                    name_alias: None,
+                    access: MethodAccess::Private,
+                    doc_comments: vec![],
+                    inline_block: None,
+                    unknown_attrs: vec![],
+                    property: None,
+                    deprecated: None,
+                },
+                method(
+                    ITERATOR_HAS_NEXT_METHOD,
+                    &parse_type!(bool),
+                    parse_quote!({ this.peek().is_some() }),
+                ),
+                method(
+                    ITERATOR_NEXT_METHOD,
+                    item,
+                    parse_quote!({ this.next().expect("no more elements in iterator") }),
+                ),
+            ];
+            classes.push(ForeignClassInfo {
+                src_id: self.src_id,
+                name,
+                methods,
+                self_desc: Some(SelfTypeDesc {
+                    self_type: self_type.clone(),
+                    constructor_ret_type: self_type,
+                }),
+                foreign_code: String::new(),
+                doc_comments: vec![format!(
+                    " Iterator over `{}`, elements can be traversed only once",
+                    DisplayToTokens(item)
+                )],
+                derive_list: vec![],
+                fields: vec![],
+                consts: vec![],
+                is_abstract: false,
+                base_class: None,
+                deprecated: None,
+                is_internal: false,
+                is_module_items_holder: false,
+            });
+        }
+        classes
+    }
+    /// `Some(Box<dyn Iterator<Item = T>>)` for class generated to hold iterator,
+    /// such class has `self_type` `std::iter::Peekable<Box<dyn Iterator<Item = T>>>`
+    pub(crate) fn iterator_type(&self) -> Option<&Type> {
+        let self_type = &self.self_desc.as_ref()?.self_type;
+        let last = match self_type {
+            Type::Path(syn::TypePath { qself: None, path }) => path.segments.last()?,
+            _ => return None,
+        };
+        if last.ident != "Peekable" {
+            return None;
+        }
+        match last.arguments {
+            syn::PathArguments::AngleBracketed(ref args) if args.args.len() == 1 => {
+                match args.args[0] {
+                    syn::GenericArgument::Type(ref ty) if boxed_iterator_item(ty).is_some() => {
+                        Some(ty)
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+    /// `Some(T)` for class generated to hold `Box<dyn Iterator<Item = T>>`
+    pub(crate) fn iterator_item(&self) -> Option<&Type> {
+        self.iterator_type().and_then(boxed_iterator_item)
+    }
+    /// For languages where result of async method is passed to foreign callback:
+    /// each method returning `impl Future<Output = T>` is replaced by private method,
+    /// see `ForeignMethod::async_start_method`. Private methods go last,
+    /// in the same order as async methods
+    pub(crate) fn with_async_methods(
+        &self,
+        spawner: Option<&syn::Expr>,
+    ) -> Result<Cow<'_, ForeignClassInfo>> {
+        if !self.methods.iter().any(|m| m.async_output().is_some()) {
+            return Ok(Cow::Borrowed(self));
+        }
+        let mut class = self.clone();
+        let (async_methods, methods): (Vec<_>, Vec<_>) = class
+            .methods
+            .into_iter()
+            .partition(|m| m.async_output().is_some());
+        class.methods = methods;
+        for m in &async_methods {
+            class
+                .methods
+                .push(m.async_start_method(self.src_id, spawner)?);
+        }
+        Ok(Cow::Owned(class))
+    }
+    /// Callbacks to receive results of async methods of this class
+    pub(crate) fn future_completers(&self) -> Vec<ForeignInterface> {
+        let mut completers = Vec::<ForeignInterface>::new();
+        for m in &self.methods {
+            if let Some(output) = m.async_output() {
+                let completer =
+                    future_completer(future_value_type(output), self.src_id, m.fn_decl.span);
+                if !completers.iter().any(|x| x.name == completer.name) {
+                    completers.push(completer);
+                }
+            }
+        }
+        completers
+    }
+    /// Callbacks that are converted to closures passed to methods of this class
+    pub(crate) fn closure_callbacks(&self) -> Vec<ForeignInterface> {
+        let mut callbacks = Vec::<ForeignInterface>::new();
+        for m in &self.methods {
+            for sig in m.closure_signatures() {
+                let callback = closure_callback(sig, self.src_id, m.fn_decl.span);
+                if !callbacks.iter().any(|x| x.name == callback.name) {
+                    callbacks.push(callback);
+                }
+            }
+        }
+        callbacks
+    }
+}
+
+/// `T` if `ty` is `Box<dyn Iterator<Item = T>>`,
+/// may be with additional bounds like `Box<dyn Iterator<Item = T> + Send>`
+pub(crate) fn boxed_iterator_item(ty: &Type) -> Option<&Type> {
+    let last = match ty {
+        Type::Path(syn::TypePath { qself: None, path }) => path.segments.last()?,
+        _ => return None,
+    };
+    if last.ident != "Box" {
+        return None;
+    }
+    let trait_obj = match last.arguments {
+        syn::PathArguments::AngleBracketed(ref args) if args.args.len() == 1 => {
+            match args.args[0] {
+                syn::GenericArgument::Type(Type::TraitObject(ref x)) => x,
+                _ => return None,
+            }
+        }
+        _ => return None,
+    };
+    let iter_trait = match trait_obj.bounds.first()? {
+        syn::TypeParamBound::Trait(x) => x.path.segments.last()?,
+        _ => return None,
+    };
+    if iter_trait.ident != "Iterator" {
+        return None;
+    }
+    match iter_trait.arguments {
+        syn::PathArguments::AngleBracketed(ref args) if args.args.len() == 1 => {
+            match args.args[0] {
+                syn::GenericArgument::Binding(ref b) if b.ident == "Item" => Some(&b.ty),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Name of class generated for `Box<dyn Iterator<Item = T>>`,
+/// identifiers of item type and additional bounds in camel case plus "Iterator",
+/// for example `Box<dyn Iterator<Item = Vec<u8>> + Send>` -> `VecU8SendIterator`
+pub(crate) fn iterator_class_name(boxed_iter: &Type, span: Span) -> Ident {
+    let mut name = String::new();
+    push_camel_case_idents(
+        boxed_iter.into_token_stream(),
+        &["dyn", "mut", "Box", "Iterator", "Item"],
+        &mut name,
+    );
+    name.push_str("Iterator");
+    Ident::new(&name, span)
+}
+
+/// Identifiers from `tokens`, except `skip` ones and lifetimes, in camel case
+fn push_camel_case_idents(tokens: TokenStream, skip: &[&str], name: &mut String) {
+    let mut lifetime = false;
+    for tt in tokens {
+        match tt {
+            proc_macro2::TokenTree::Group(g) => push_camel_case_idents(g.stream(), skip, name),
+            proc_macro2::TokenTree::Ident(id) => {
+                let id = id.to_string();
+                if !lifetime && !skip.contains(&&*id) {
+                    let mut chars = id.chars();
+                    if let Some(first) = chars.next() {
+                        name.extend(first.to_uppercase());
+                        name.push_str(chars.as_str());
+                    }
+                }
+                lifetime = false;
+            }
+            proc_macro2::TokenTree::Punct(p) => lifetime = p.as_char() == '\'',
+            proc_macro2::TokenTree::Literal(_) => lifetime = false,
+        }
+    }
+}
+
+/// `T` if `ty` is `impl Future<Output = T>`,
+/// may be with additional bounds like `impl Future<Output = T> + Send`
+pub(crate) fn future_output(ty: &Type) -> Option<&Type> {
+    let impl_trait = match ty {
+        Type::ImplTrait(x) => x,
+        _ => return None,
+    };
+    impl_trait.bounds.iter().find_map(|bound| {
+        let last = match bound {
+            syn::TypeParamBound::Trait(x) => x.path.segments.last()?,
+            _ => return None,
+        };
+        if last.ident != "Future" {
+            return None;
+        }
+        match last.arguments {
+            syn::PathArguments::AngleBracketed(ref args) if args.args.len() == 1 => {
+                match args.args[0] {
+                    syn::GenericArgument::Binding(ref b) if b.ident == "Output" => Some(&b.ty),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    })
+}
+
+/// `T` for `Result<T, E>`
+fn result_ok_type(ty: &Type) -> Option<&Type> {
+    let last = match ty {
+        Type::Path(syn::TypePath { qself: None, path }) => path.segments.last()?,
+        _ => return None,
+    };
+    if last.ident != "Result" {
+        return None;
+    }
+    match last.arguments {
+        syn::PathArguments::AngleBracketed(ref args) if args.args.len() == 2 => {
+            match args.args[0] {
+                syn::GenericArgument::Type(ref ty) => Some(ty),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
 }
 
+/// Value that completes foreign future for `impl Future<Output = output>`:
+/// `T` for `T` and `Result<T, E>`, `None` if `T` is `()`
+pub(crate) fn future_value_type(output: &Type) -> Option<&Type> {
+    let value = result_ok_type(output).unwrap_or(output);
+    match value {
+        Type::Tuple(t) if t.elems.is_empty() => None,
+        _ => Some(value),
+    }
+}
+
+/// Name of callback that receives `value` produced by future,
+/// for example `Vec<u8>` -> `VecU8FutureCompleter`
+pub(crate) fn future_completer_name(value: Option<&Type>, span: Span) -> Ident {
+    let mut name = String::new();
+    match value {
+        Some(value) => {
+            push_camel_case_idents(value.into_token_stream(), &["dyn", "mut"], &mut name)
+        }
+        None => name.push_str("Void"),
+    }
+    name.push_str("FutureCompleter");
+    Ident::new(&name, span)
+}
+
+/// Callback with `complete` and `fail` methods that receives result of future,
+/// implements `SwigFutureCompleter<T>` or `SwigVoidFutureCompleter` if there is no value
+pub(crate) fn future_completer(
+    value: Option<&Type>,
+    src_id: SourceId,
+    span: Span,
+) -> ForeignInterface {
+    let trait_name = Ident::new(
+        if value.is_some() {
+            FUTURE_COMPLETER_TRAIT
+        } else {
+            VOID_FUTURE_COMPLETER_TRAIT
+        },
+        span,
+    );
+    let method = |name: &str, arg: Option<(&str, Type)>| {
+        let name = Ident::new(name, span);
+        let mut inputs = vec![FnArg::SelfArg(span, SelfTypeVariant::Rptr)];
+        if let Some((arg_name, ty)) = arg {
+            inputs.push(FnArg::Default(NamedArg {
+                name: arg_name.into(),
+                span,
+                ty,
+                default: None,
+            }));
+        }
+        ForeignInterfaceMethod {
+            rust_name: parse_quote!(#trait_name::#name),
+            name,
+            fn_decl: FnDecl {
+                span,
+                inputs,
+                output: syn::ReturnType::Default,
+            },
+            doc_comments: vec![],
+            deprecated: None,
+        }
+    };
+    let (self_type, complete, doc) = match value {
+        Some(value) => (
+            parse_quote!(#trait_name<#value>),
+            method(FUTURE_COMPLETE_METHOD, Some(("value", value.clone()))),
+            format!(
+                " Receives `{}` produced by Rust future",
+                DisplayToTokens(value)
+            ),
+        ),
+        None => (
+            parse_quote!(#trait_name),
+            method(FUTURE_COMPLETE_METHOD, None),
+            " Receives notification about completion of Rust future".to_string(),
+        ),
+    };
+    ForeignInterface {
+        src_id,
+        name: future_completer_name(value, span),
+        self_type,
+        doc_comments: vec![doc],
+        items: vec![
+            complete,
+            method(FUTURE_FAIL_METHOD, Some(("error", parse_type!(String)))),
+        ],
+        closure: None,
+    }
+}
+
+/// `(A, B) -> R` if `ty` is `Box<dyn Fn(A, B) -> R>` or `&dyn Fn(A, B) -> R`,
+/// may be with `Send` bound like `Box<dyn Fn(A, B) -> R + Send>`
+pub(crate) fn closure_signature(ty: &Type) -> Option<&syn::ParenthesizedGenericArguments> {
+    closure_trait_object(ty)
+        .and_then(|x| fn_trait_signature(&x.bounds))
+        .map(|(sig, _)| sig)
+}
+
+/// `dyn Fn(..)` from `Box<dyn Fn(..)>` or `&dyn Fn(..)`
+fn closure_trait_object(ty: &Type) -> Option<&syn::TypeTraitObject> {
+    let trait_obj = match ty {
+        Type::Reference(syn::TypeReference {
+            mutability: None,
+            ref elem,
+            ..
+        }) => &**elem,
+        Type::Path(syn::TypePath { qself: None, path }) => {
+            let last = path.segments.last()?;
+            if last.ident != "Box" {
+                return None;
+            }
+            match last.arguments {
+                syn::PathArguments::AngleBracketed(ref args) if args.args.len() == 1 => {
+                    match args.args[0] {
+                        syn::GenericArgument::Type(ref x) => x,
+                        _ => return None,
+                    }
+                }
+                _ => return None,
+            }
+        }
+        _ => return None,
+    };
+    match trait_obj {
+        Type::TraitObject(ref x) => Some(x),
+        _ => None,
+    }
+}
+
+/// Signature of `Fn(A, B) -> R` if `bounds` are `Fn(A, B) -> R` plus may be `Send`
+/// and lifetimes, and flag is `Send` among bounds
+fn fn_trait_signature(
+    bounds: &syn::punctuated::Punctuated<syn::TypeParamBound, syn::Token![+]>,
+) -> Option<(&syn::ParenthesizedGenericArguments, bool)> {
+    let mut sig = None;
+    let mut send = false;
+    for (i, bound) in bounds.iter().enumerate() {
+        match bound {
+            syn::TypeParamBound::Trait(x) if i == 0 => {
+                let last = x.path.segments.last()?;
+                match last.arguments {
+                    syn::PathArguments::Parenthesized(ref args) if last.ident == "Fn" => {
+                        sig = Some(args)
+                    }
+                    _ => return None,
+                }
+            }
+            syn::TypeParamBound::Trait(x) if x.path.is_ident("Send") => send = true,
+            syn::TypeParamBound::Lifetime(_) => {}
+            _ => return None,
+        }
+    }
+    sig.map(|sig| (sig, send))
+}
+
+/// Closures are passed as `Box<dyn Fn(..) + Send>`, `Box<dyn Fn(..)>` or `&dyn Fn(..)`,
+/// so `impl Fn(..)` is replaced by `Box<dyn Fn(..) + Send>` that implements `Fn(..)`,
+/// and lifetimes are removed from bounds
+pub(crate) fn normalize_closure_type(ty: Type) -> Type {
+    let bounds = match ty {
+        Type::ImplTrait(ref x) => &x.bounds,
+        _ => match closure_trait_object(&ty) {
+            Some(x) => &x.bounds,
+            None => return ty,
+        },
+    };
+    let (sig, send) = match fn_trait_signature(bounds) {
+        Some((sig, send)) => (sig.clone(), send),
+        None => return ty,
+    };
+    match ty {
+        Type::Reference(_) => parse_quote!(&dyn Fn #sig),
+        Type::Path(_) if !send => parse_quote!(Box<dyn Fn #sig>),
+        _ => parse_quote!(Box<dyn Fn #sig + Send>),
+    }
+}
+
+/// Name of callback generated for closure, identifiers of arguments and output types
+/// in camel case plus "Closure", for example `Fn(i32, &str) -> bool` -> `I32StrToBoolClosure`
+pub(crate) fn closure_callback_name(sig: &syn::ParenthesizedGenericArguments, span: Span) -> Ident {
+    let mut name = String::new();
+    push_camel_case_idents(sig.inputs.to_token_stream(), &["dyn", "mut"], &mut name);
+    if let syn::ReturnType::Type(_, ref output) = sig.output {
+        name.push_str("To");
+        push_camel_case_idents(output.into_token_stream(), &["dyn", "mut"], &mut name);
+    }
+    if name.is_empty() {
+        name.push_str("Void");
+    }
+    name.push_str("Closure");
+    Ident::new(&name, span)
+}
+
+/// Callback with `call` method, it is converted to closure `Fn(A, B) -> R`
+/// via Rust trait with the same name, see `ForeignInterface::closure_glue`
+pub(crate) fn closure_callback(
+    sig: &syn::ParenthesizedGenericArguments,
+    src_id: SourceId,
+    span: Span,
+) -> ForeignInterface {
+    let name = closure_callback_name(sig, span);
+    let mut inputs = vec![FnArg::SelfArg(span, SelfTypeVariant::Rptr)];
+    inputs.extend(sig.inputs.iter().enumerate().map(|(i, ty)| {
+        FnArg::Default(NamedArg {
+            name: format!("a{}", i).into(),
+            span,
+            ty: ty.clone(),
+            default: None,
+        })
+    }));
+    let method_name = Ident::new(CLOSURE_CALL_METHOD, span);
+    ForeignInterface {
+        src_id,
+        self_type: parse_quote!(#name),
+        doc_comments: vec![format!(" Closure `Fn{}`", DisplayToTokens(sig))],
+        items: vec![ForeignInterfaceMethod {
+            rust_name: parse_quote!(#name::#method_name),
+            name: method_name,
+            fn_decl: FnDecl {
+                span,
+                inputs,
+                output: sig.output.clone(),
+            },
+            doc_comments: vec![],
+            deprecated: None,
+        }],
+        name,
+        closure: Some(Box::new(sig.clone())),
+    }
+}
+
+/// Derived traits of `self_type` that are mapped to protocols of foreign language
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct DerivedProtocols {
+    /// `PartialEq` or `Eq`
+    pub(crate) eq: bool,
+    pub(crate) hash: bool,
+    /// `Display` or `Debug`
+    pub(crate) to_string: Option<&'static str>,
+    /// `Ord` or `PartialOrd`
+    pub(crate) cmp: Option<&'static str>,
+}
+
+impl DerivedProtocols {
+    pub(crate) fn is_empty(&self) -> bool {
+        !self.eq && !self.hash && self.to_string.is_none() && self.cmp.is_none()
+    }
+}
+
+/// Names of private methods generated for derived traits
+pub(crate) const DERIVED_EQ_METHOD: &str = "rustEq";
+pub(crate) const DERIVED_HASH_METHOD: &str = "rustHash";
+pub(crate) const DERIVED_TO_STRING_METHOD: &str = "rustToString";
+pub(crate) const DERIVED_COMPARE_METHOD: &str = "rustCompare";
+/// Private methods of class generated for `Box<dyn Iterator<Item = T>>`,
+/// see `ForeignClassInfo::iterator_classes`
+pub(crate) const ITERATOR_HAS_NEXT_METHOD: &str = "rustHasNext";
+pub(crate) const ITERATOR_NEXT_METHOD: &str = "rustNext";
+/// Traits of callbacks that receive results of async methods, they are defined
+/// by "types map" of languages that use `ForeignClassInfo::with_async_methods`
+pub(crate) const FUTURE_COMPLETER_TRAIT: &str = "SwigFutureCompleter";
+pub(crate) const VOID_FUTURE_COMPLETER_TRAIT: &str = "SwigVoidFutureCompleter";
+pub(crate) const FUTURE_COMPLETE_METHOD: &str = "complete";
+pub(crate) const FUTURE_FAIL_METHOD: &str = "fail";
+/// Name of argument of private method that starts async method
+pub(crate) const FUTURE_COMPLETER_ARG: &str = "completer";
+/// Method of callback generated for closure, see `closure_callback`
+pub(crate) const CLOSURE_CALL_METHOD: &str = "call";
+
 #[derive(Debug, Clone)]
 pub(crate) struct ForeignMethod {
     pub(crate) variant: MethodVariant,
@@ -96,6 +1071,21 @@ pub(crate) struct ForeignMethod {
     pub(crate) doc_comments: Vec<String>,
     pub(crate) inline_block: Option<syn::Block>,
     pub(crate) unknown_attrs: Vec<String>,
+    pub(crate) property: Option<MethodProperty>,
+    pub(crate) deprecated: Option<Deprecation>,
+}
+
+/// Method is accessor of property, see `#[property]` attribute
+#[derive(Debug, Clone)]
+pub(crate) struct MethodProperty {
+    pub(crate) name: SmolStr,
+    pub(crate) accessor: PropertyAccessor,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum PropertyAccessor {
+    Getter,
+    Setter,
 }
 
 #[derive(Debug, Clone)]
@@ -128,6 +1118,8 @@ pub(crate) struct NamedArg {
     pub name: SmolStr,
     pub span: Span,
     pub ty: syn::Type,
+    /// `name: Type = default`
+    pub default: Option<syn::Expr>,
 }
 
 #[derive(Debug, Clone)]
@@ -169,6 +1161,196 @@ impl ForeignMethod {
             .map(|x| x.as_named_arg().unwrap().name.as_str())
     }
 
+    pub(crate) fn arg_defaults_without_self(&self) -> impl Iterator<Item = Option<&syn::Expr>> {
+        let skip = match self.variant {
+            MethodVariant::Method(_) => 1,
+            _ => 0,
+        };
+        self.fn_decl
+            .inputs
+            .iter()
+            .skip(skip)
+            .map(|x| x.as_named_arg().unwrap().default.as_ref())
+    }
+
+    pub(crate) fn has_default_args(&self) -> bool {
+        self.arg_defaults_without_self().any(|x| x.is_some())
+    }
+
+    /// Variants of method without trailing arguments that have default values,
+    /// from the longest to the shortest one. Default values are calculated
+    /// on Rust side, so it is not required to express them in foreign language
+    pub(crate) fn default_args_overloads(&self) -> Vec<ForeignMethod> {
+        // parser guarantees that arguments with default values are trailing
+        let n_defaults = self
+            .arg_defaults_without_self()
+            .filter(|x| x.is_some())
+            .count();
+        if n_defaults == 0 {
+            return Vec::new();
+        }
+        let call: TokenStream = self
+            .generate_code_to_call_rust_func()
+            .parse()
+            .expect("Internal error: can not parse code to call Rust function");
+        let n_inputs = self.fn_decl.inputs.len();
+        let mut ret = Vec::with_capacity(n_defaults);
+        for n_omit in 1..=n_defaults {
+            let (inputs, omitted) = self.fn_decl.inputs.split_at(n_inputs - n_omit);
+            let mut inputs = inputs.to_vec();
+            for arg in &mut inputs {
+                if let FnArg::Default(ref mut arg) = arg {
+                    arg.default = None;
+                }
+            }
+            let init_omitted = omitted.iter().map(|arg| {
+                let arg = arg.as_named_arg().unwrap();
+                let name = Ident::new(&arg.name, arg.span);
+                let default = arg.default.as_ref().unwrap();
+                quote!(let #name = #default;)
+            });
+            let inline_block: syn::Block = parse_quote!({
+                #(#init_omitted)*
+                #call
+            });
+            ret.push(ForeignMethod {
+                fn_decl: FnDecl {
+                    span: self.fn_decl.span,
+                    inputs,
+                    output: self.fn_decl.output.clone(),
+                },
+                inline_block: Some(inline_block),
+                ..self.clone()
+            });
+        }
+        ret
+    }
+
+    /// Signatures of closures accepted as arguments
+    pub(crate) fn closure_signatures(
+        &self,
+    ) -> impl Iterator<Item = &syn::ParenthesizedGenericArguments> {
+        self.fn_decl.inputs.iter().filter_map(|arg| match arg {
+            FnArg::Default(ref named_arg) => closure_signature(&named_arg.ty),
+            FnArg::SelfArg(..) => None,
+        })
+    }
+
+    /// `T` if method returns `impl Future<Output = T>`
+    pub(crate) fn async_output(&self) -> Option<&Type> {
+        match self.fn_decl.output {
+            syn::ReturnType::Type(_, ref ty) => future_output(ty),
+            syn::ReturnType::Default => None,
+        }
+    }
+
+    /// Private method `rustName` with additional `completer` argument instead of
+    /// async method `name`. It creates future, spawns it via `spawner`
+    /// and passes result to completer, `Err` is passed as string via `fail`
+    pub(crate) fn async_start_method(
+        &self,
+        src_id: SourceId,
+        spawner: Option<&syn::Expr>,
+    ) -> Result<ForeignMethod> {
+        let output = self
+            .async_output()
+            .expect("Internal error: async_start_method for not async method");
+        let spawner = spawner.ok_or_else(|| {
+            DiagnosticError::new(
+                src_id,
+                self.span(),
+                "method returns future, but there is no executor to run it,\n\
+                 you should set it via `Generator::async_spawner`",
+            )
+        })?;
+        if self
+            .arg_names_without_self()
+            .any(|x| x == FUTURE_COMPLETER_ARG)
+        {
+            return Err(DiagnosticError::new(
+                src_id,
+                self.span(),
+                format!(
+                    "argument name '{}' of async method is reserved for generated code",
+                    FUTURE_COMPLETER_ARG
+                ),
+            ));
+        }
+        let span = self.fn_decl.span;
+        let value = future_value_type(output);
+        let completer_ty: Type = match value {
+            Some(value) => parse_quote!(Box<dyn SwigFutureCompleter<#value>>),
+            None => parse_quote!(Box<dyn SwigVoidFutureCompleter>),
+        };
+        let completer = Ident::new(FUTURE_COMPLETER_ARG, span);
+        let complete = Ident::new(FUTURE_COMPLETE_METHOD, span);
+        let fail = Ident::new(FUTURE_FAIL_METHOD, span);
+        let (ok_pattern, complete_call) = match value {
+            Some(_) => (quote!(Ok(x)), quote!(#completer.#complete(x))),
+            None => (quote!(Ok(())), quote!(#completer.#complete())),
+        };
+        let await_and_complete = match (result_ok_type(output).is_some(), value.is_some()) {
+            (true, _) => quote! {
+                match future.await {
+                    #ok_pattern => #complete_call,
+                    Err(err) => #completer.#fail(err.to_string()),
+                }
+            },
+            (false, true) => quote!(#completer.#complete(future.await);),
+            (false, false) => quote! {
+                future.await;
+                #completer.#complete();
+            },
+        };
+        let call: TokenStream = self
+            .generate_code_to_call_rust_func()
+            .parse()
+            .expect("Internal error: can not parse code to call Rust function");
+        let inline_block: syn::Block = parse_quote!({
+            let future = #call;
+            #spawner(async move {
+                #await_and_complete
+            });
+        });
+        let mut rust_id = self.rust_id.clone();
+        if let Some(last) = rust_id.segments.last_mut() {
+            last.ident = Ident::new(
+                &format!("rust{}", self.short_name().to_camel_case()),
+                last.ident.span(),
+            );
+        }
+        // default values are handled by public method, and they
+        // are not allowed before `completer`
+        let mut inputs = self.fn_decl.inputs.clone();
+        for arg in &mut inputs {
+            if let FnArg::Default(ref mut arg) = arg {
+                arg.default = None;
+            }
+        }
+        inputs.push(FnArg::Default(NamedArg {
+            name: FUTURE_COMPLETER_ARG.into(),
+            span,
+            ty: completer_ty,
+            default: None,
+        }));
+        Ok(ForeignMethod {
+            variant: self.variant,
+            rust_id,
+            fn_decl: FnDecl {
+                span,
+                inputs,
+                output: syn::ReturnType::Default,
+            },
+            name_alias: None,
+            access: MethodAccess::Private,
+            doc_comments: vec![],
+            inline_block: Some(inline_block),
+            unknown_attrs: vec![],
+            property: None,
+            deprecated: None,
+        })
+    }
+
     pub(crate) fn generate_code_to_call_rust_func(&self) -> String {
         if let Some(ref code_block) = self.inline_block {
             format!("{}", DisplayToTokens(code_block))
@@ -192,7 +1374,7 @@ impl ForeignMethod {
 }
 
 #[derive(PartialEq, Clone, Copy, Debug)]
-pub(crate) enum MethodAccess {
+pub enum MethodAccess {
     Private,
     Public,
     Protected,
@@ -258,21 +1440,280 @@ impl ForeignEnumInfo {
     pub(crate) fn span(&self) -> Span {
         self.name.span()
     }
+    /// There are no data in variants, so enum can be mapped to integer
+    pub(crate) fn is_c_like(&self) -> bool {
+        self.items
+            .iter()
+            .all(|item| matches!(item.fields, syn::Fields::Unit))
+    }
+    /// Enum marked with `#[derive(Flags)]`, rust type is `bitflags` struct,
+    /// so items are bits and value of type is any combination of them
+    pub(crate) fn is_flags(&self) -> bool {
+        self.derive_list.iter().any(|x| x == FLAGS_DERIVE)
+    }
+    /// Integer values of items that are used in foreign code.
+    /// Items without explicit value follow the Rust rules: previous value plus one,
+    /// or the next bit above previous one for flags.
+    pub(crate) fn items_values(&self) -> Vec<i64> {
+        let is_flags = self.is_flags();
+        let mut prev: Option<i64> = None;
+        self.items
+            .iter()
+            .map(|item| {
+                let value = match (item.value, prev) {
+                    (Some(value), _) => value,
+                    (None, None) if is_flags => 1,
+                    (None, None) => 0,
+                    (None, Some(prev)) if is_flags => {
+                        (prev.max(0) as u64 + 1).next_power_of_two() as i64
+                    }
+                    (None, Some(prev)) => prev.wrapping_add(1),
+                };
+                prev = Some(value);
+                value
+            })
+            .collect()
+    }
+    /// Values of items as 32 bit integer, negative values are stored
+    /// as two's complement
+    pub(crate) fn items_values_as_u32(&self) -> Result<Vec<u32>> {
+        self.items
+            .iter()
+            .zip(self.items_values())
+            .map(|(item, value)| {
+                if value < i64::from(i32::MIN) || value > i64::from(u32::MAX) {
+                    Err(DiagnosticError::new(
+                        self.src_id,
+                        item.name.span(),
+                        format!("value {} of enum item does not fit into 32 bits", value),
+                    ))
+                } else {
+                    Ok(value as u32)
+                }
+            })
+            .collect()
+    }
+    /// Compile time check that values of items used in foreign code
+    /// match discriminants of Rust enum (or bits of flags),
+    /// items without explicit value are checked too, to catch wrong order of items
+    pub(crate) fn rust_code_to_check_items_values(&self) -> TokenStream {
+        let is_flags = self.is_flags();
+        let checks = self
+            .items
+            .iter()
+            .zip(self.items_values())
+            .map(|(item, value)| {
+                let rust_name = &item.rust_name;
+                let rust_value = if is_flags {
+                    quote! { #rust_name.bits() }
+                } else {
+                    quote! { #rust_name }
+                };
+                quote! {
+                    const _: () = assert!(
+                        #rust_value as i64 == #value,
+                        concat!("value of ", stringify!(#rust_name), " is not ", stringify!(#value))
+                    );
+                }
+            });
+        quote! { #(#checks)* }
+    }
+    pub(crate) fn raw_class_name(&self) -> String {
+        format!("{}Raw", self.name)
+    }
+    /// Data-carrying enum is passed between Rust and foreign language
+    /// as pointer to boxed value (`i64`). This class contains static methods
+    /// to create such value and to get tag and fields of it,
+    /// so all fields are converted via usual rules of type map.
+    pub(crate) fn raw_class(&self) -> ForeignClassInfo {
+        let enum_name = &self.name;
+        let span = self.span();
+        let raw_name = Ident::new(&self.raw_class_name(), span);
+        let static_method = |name: &str, inputs: Vec<(Ident, Type)>, output: Type, block| {
+            let name = Ident::new(name, span);
+            ForeignMethod {
+                variant: MethodVariant::StaticMethod,
+                rust_id: parse_quote!(#raw_name::#name),
+                fn_decl: FnDecl {
+                    span,
+                    inputs: inputs
+                        .into_iter()
+                        .map(|(name, ty)| {
+                            FnArg::Default(NamedArg {
+                                name: name.to_string().into(),
+                                span,
+                                ty,
+                                default: None,
+                            })
+                        })
+                        .collect(),
+                    output: if output == parse_type!(()) {
+                        syn::ReturnType::Default
+                    } else {
+                        parse_quote!(-> #output)
+                    },
+                },
+                name_alias: None,
+                access: MethodAccess::Public,
+                doc_comments: vec![],
+                inline_block: Some(block),
+                unknown_attrs: vec![],
+                property: None,
+                deprecated: None,
+            }
+        };
+        let raw = Ident::new("raw", span);
+        let raw_arg = || vec![(raw.clone(), parse_type!(i64))];
+        let deref_raw = quote!(unsafe { &*(#raw as *const #enum_name) });
+
+        let mut methods = Vec::with_capacity(self.items.len() * 2 + 2);
+        let tag_arms = self.items.iter().enumerate().map(|(i, item)| {
+            let pat = item.rust_pattern_without_bindings();
+            let i = i as i32;
+            quote!(#pat => #i)
+        });
+        methods.push(static_method(
+            ENUM_RAW_TAG,
+            raw_arg(),
+            parse_type!(i32),
+            parse_quote!({ match #deref_raw { #(#tag_arms),* } }),
+        ));
+        for item in &self.items {
+            let fields = item.fields();
+            let construct = item.rust_constructor();
+            methods.push(static_method(
+                &item.raw_constructor_name(),
+                fields
+                    .iter()
+                    .map(|(name, ty)| (name.clone(), (*ty).clone()))
+                    .collect(),
+                parse_type!(i64),
+                parse_quote!({ Box::into_raw(Box::new(#construct)) as i64 }),
+            ));
+            for (i, (name, ty)) in fields.iter().enumerate() {
+                let pat = item.rust_pattern_with_binding(i, name);
+                methods.push(static_method(
+                    &item.raw_getter_name(name),
+                    raw_arg(),
+                    (*ty).clone(),
+                    parse_quote!({
+                        match #deref_raw {
+                            #pat => Clone::clone(#name),
+                            #[allow(unreachable_patterns)]
+                            _ => unreachable!(),
+                        }
+                    }),
+                ));
+            }
+        }
+        methods.push(static_method(
+            ENUM_RAW_FREE,
+            raw_arg(),
+            parse_type!(()),
+            parse_quote!({ drop(unsafe { Box::from_raw(#raw as *mut #enum_name) }); }),
+        ));
+
+        ForeignClassInfo {
+            src_id: self.src_id,
+            name: raw_name,
+            methods,
+            self_desc: None,
+            foreign_code: String::new(),
+            doc_comments: vec![format!(
+                "Internal helper to pass `{}` between Rust and foreign code",
+                enum_name
+            )],
+            derive_list: vec![],
+            fields: vec![],
+            consts: vec![],
+            is_abstract: false,
+            base_class: None,
+            deprecated: None,
+            is_internal: true,
+            is_module_items_holder: false,
+        }
+    }
 }
 
+/// Name of static method of raw class that returns index of variant
+pub(crate) const ENUM_RAW_TAG: &str = "tag";
+/// Name of static method of raw class that frees value
+pub(crate) const ENUM_RAW_FREE: &str = "free";
+
 #[derive(Debug, Clone)]
 pub(crate) struct ForeignEnumItem {
     pub(crate) name: Ident,
     pub(crate) rust_name: syn::Path,
     pub(crate) doc_comments: Vec<String>,
+    /// `Unit` for enums mapped to integer
+    pub(crate) fields: syn::Fields,
+    /// Explicit discriminant, `None` for implicit one and for enums with data
+    pub(crate) value: Option<i64>,
+    pub(crate) deprecated: Option<Deprecation>,
 }
 
+impl ForeignEnumItem {
+    /// Fields of variant with names that are used in foreign code,
+    /// fields of tuple variant are named `field0`, `field1` and so on
+    pub(crate) fn fields(&self) -> Vec<(Ident, &Type)> {
+        self.fields
+            .iter()
+            .enumerate()
+            .map(|(i, f)| match f.ident {
+                Some(ref name) => (name.clone(), &f.ty),
+                None => (Ident::new(&format!("field{}", i), f.ty.span()), &f.ty),
+            })
+            .collect()
+    }
+    pub(crate) fn raw_constructor_name(&self) -> String {
+        format!("new_{}", self.name)
+    }
+    pub(crate) fn raw_getter_name(&self, field: impl fmt::Display) -> String {
+        format!("get_{}_{}", self.name, field)
+    }
+    /// Expression to create variant from variables named as fields
+    pub(crate) fn rust_constructor(&self) -> TokenStream {
+        let rust_name = &self.rust_name;
+        let names = self.fields().into_iter().map(|(name, _)| name);
+        match self.fields {
+            syn::Fields::Named(_) => quote!(#rust_name { #(#names),* }),
+            syn::Fields::Unnamed(_) => quote!(#rust_name(#(#names),*)),
+            syn::Fields::Unit => rust_name.to_token_stream(),
+        }
+    }
+    pub(crate) fn rust_pattern_without_bindings(&self) -> TokenStream {
+        let rust_name = &self.rust_name;
+        match self.fields {
+            syn::Fields::Named(_) => quote!(#rust_name { .. }),
+            syn::Fields::Unnamed(_) => quote!(#rust_name(..)),
+            syn::Fields::Unit => quote!(#rust_name),
+        }
+    }
+    /// Pattern that binds field with index `idx` to `binding`
+    pub(crate) fn rust_pattern_with_binding(&self, idx: usize, binding: &Ident) -> TokenStream {
+        let rust_name = &self.rust_name;
+        match self.fields {
+            // for named fields binding has the same name as field
+            syn::Fields::Named(_) => quote!(#rust_name { #binding, .. }),
+            syn::Fields::Unnamed(_) => {
+                let skip = (0..idx).map(|_| quote!(_));
+                quote!(#rust_name(#(#skip,)* #binding, ..))
+            }
+            syn::Fields::Unit => unreachable!(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
 pub(crate) struct ForeignInterface {
     pub(crate) src_id: SourceId,
     pub(crate) name: Ident,
     pub(crate) self_type: syn::TypeTraitObject,
     pub(crate) doc_comments: Vec<String>,
     pub(crate) items: Vec<ForeignInterfaceMethod>,
+    /// `(A, B) -> R` for callback generated for `Fn(A, B) -> R` closures,
+    /// see `closure_callback`
+    pub(crate) closure: Option<Box<syn::ParenthesizedGenericArguments>>,
 }
 
 impl ForeignInterface {
@@ -282,13 +1723,69 @@ impl ForeignInterface {
     pub(crate) fn src_id_span(&self) -> SourceIdSpan {
         (self.src_id, self.name.span())
     }
+    /// For callback generated for closures: Rust trait implemented by callback
+    /// and rules to convert `Box<dyn Trait>` to closure types, see `normalize_closure_type`
+    pub(crate) fn closure_glue(&self) -> Option<(TokenStream, String)> {
+        let sig = self.closure.as_ref()?;
+        let name = &self.name;
+        let args: Vec<Ident> = (0..sig.inputs.len())
+            .map(|i| Ident::new(&format!("a{}", i), self.span()))
+            .collect();
+        let arg_types = sig.inputs.iter();
+        let output = &sig.output;
+        let method_name = Ident::new(CLOSURE_CALL_METHOD, self.span());
+        let trait_code = quote! {
+            pub trait #name: Send {
+                fn #method_name(&self, #(#args: #arg_types),*) #output;
+            }
+        };
+        let args_with_types = args
+            .iter()
+            .zip(sig.inputs.iter())
+            .map(|(arg, ty)| format!("{}: {}", arg, DisplayToTokens(ty)))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let args = args
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(", ");
+        let sig = DisplayToTokens(&**sig);
+        let rules = format!(
+            r#"
+foreign_typemap!(
+    ($p:r_type) Box<dyn {name}> => Box<dyn Fn{sig} + Send> {{
+        $out = Box::new(move |{args_with_types}| $p.{method}({args}));
+    }};
+);
+foreign_typemap!(
+    ($p:r_type) Box<dyn Fn{sig} + Send> => Box<dyn Fn{sig}> {{
+        $out = $p;
+    }};
+);
+foreign_typemap!(
+    ($p:r_type) Box<dyn Fn{sig} + Send> => &dyn Fn{sig} {{
+        $out = &*$p;
+    }};
+);
+"#,
+            name = name,
+            sig = sig,
+            method = CLOSURE_CALL_METHOD,
+            args_with_types = args_with_types,
+            args = args,
+        );
+        Some((trait_code, rules))
+    }
 }
 
+#[derive(Debug, Clone)]
 pub(crate) struct ForeignInterfaceMethod {
     pub(crate) name: Ident,
     pub(crate) rust_name: syn::Path,
     pub(crate) fn_decl: FnDecl,
     pub(crate) doc_comments: Vec<String>,
+    pub(crate) deprecated: Option<Deprecation>,
 }
 
 impl ForeignInterfaceMethod {
@@ -301,8 +1798,129 @@ impl ForeignInterfaceMethod {
     }
 }
 
+#[derive(Clone)]
 pub(crate) enum ItemToExpand {
     Class(Box<ForeignClassInfo>),
     Interface(ForeignInterface),
     Enum(ForeignEnumInfo),
+    /// Constant at module level, see `foreign_const!`
+    Const(Box<ForeignConst>),
+    /// Function at module level, see `foreign_fn!`
+    Fn(Box<ForeignFunction>),
+}
+
+/// If there are items declared at module level, they are placed
+/// into class `holder_name`, so check that no other item has the same name
+pub(crate) fn check_module_items_holder_name(
+    items: &[ItemToExpand],
+    holder_name: &str,
+) -> Result<()> {
+    let module_item = items.iter().find_map(|item| match item {
+        ItemToExpand::Const(fconst) => Some((fconst.src_id, fconst.name.span())),
+        ItemToExpand::Fn(ffn) => Some((ffn.src_id, ffn.method.span())),
+        _ => None,
+    });
+    let module_item = match module_item {
+        Some(x) => x,
+        None => return Ok(()),
+    };
+    for item in items {
+        let (src_id, name) = match item {
+            ItemToExpand::Class(fclass) => (fclass.src_id, &fclass.name),
+            ItemToExpand::Interface(finterface) => (finterface.src_id, &finterface.name),
+            ItemToExpand::Enum(fenum) => (fenum.src_id, &fenum.name),
+            ItemToExpand::Const(_) | ItemToExpand::Fn(_) => continue,
+        };
+        if name == holder_name {
+            let mut err = DiagnosticError::new(
+                src_id,
+                name.span(),
+                format!(
+                    "name `{}` is reserved for class with module level items, \
+                     rename this item or choose other name for that class via `module_items_class`",
+                    holder_name
+                ),
+            );
+            err.span_note(module_item, "module level item declared here");
+            return Err(err);
+        }
+    }
+    Ok(())
+}
+
+/// Trait objects can not be created from foreign objects of unknown derived class,
+/// so traits of abstract classes can not be used in types of arguments and return values
+pub(crate) fn check_abstract_classes_usage(items: &[ItemToExpand]) -> Result<()> {
+    use syn::visit::{visit_trait_bound, Visit};
+
+    struct FindTrait<'a> {
+        traits: &'a [(&'a Ident, &'a ForeignClassInfo)],
+        found: Option<(Span, &'a Ident, &'a ForeignClassInfo)>,
+    }
+    impl<'ast> Visit<'ast> for FindTrait<'_> {
+        fn visit_trait_bound(&mut self, bound: &'ast syn::TraitBound) {
+            if let Some(seg) = bound.path.segments.last() {
+                if let Some((name, class)) = self.traits.iter().find(|(x, _)| **x == seg.ident) {
+                    self.found.get_or_insert((bound.span(), name, class));
+                }
+            }
+            visit_trait_bound(self, bound)
+        }
+    }
+
+    let mut traits = Vec::<(&Ident, &ForeignClassInfo)>::new();
+    for item in items {
+        if let ItemToExpand::Class(ref fclass) = item {
+            if !fclass.is_abstract {
+                continue;
+            }
+            for m in &fclass.methods {
+                let n = m.rust_id.segments.len();
+                if n >= 2 {
+                    traits.push((&m.rust_id.segments[n - 2].ident, fclass));
+                }
+            }
+        }
+    }
+    if traits.is_empty() {
+        return Ok(());
+    }
+    let fn_decls = items.iter().flat_map(|item| -> Vec<(SourceId, &FnDecl)> {
+        match item {
+            ItemToExpand::Class(ref x) => {
+                x.methods.iter().map(|m| (x.src_id, &m.fn_decl)).collect()
+            }
+            ItemToExpand::Fn(ref x) => vec![(x.src_id, &x.method.fn_decl)],
+            ItemToExpand::Interface(ref x) => {
+                x.items.iter().map(|m| (x.src_id, &m.fn_decl)).collect()
+            }
+            ItemToExpand::Const(_) | ItemToExpand::Enum(_) => vec![],
+        }
+    });
+    for (src_id, fn_decl) in fn_decls {
+        let mut find_trait = FindTrait {
+            traits: &traits,
+            found: None,
+        };
+        for arg in &fn_decl.inputs {
+            if let FnArg::Default(ref arg) = arg {
+                find_trait.visit_type(&arg.ty);
+            }
+        }
+        find_trait.visit_return_type(&fn_decl.output);
+        if let Some((sp, trait_name, class)) = find_trait.found {
+            let mut err = DiagnosticError::new(
+                src_id,
+                sp,
+                format!(
+                    "abstract class {} can not be used as type of argument or return value, \
+                     trait objects of {} are not supported",
+                    class.name, trait_name
+                ),
+            );
+            err.span_note((class.src_id, class.span()), "abstract class declared here");
+            return Err(err);
+        }
+    }
+    Ok(())
 }
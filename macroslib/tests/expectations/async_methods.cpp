@@ -0,0 +1,30 @@
+r#"#include "rust_future_completer.hpp""#;
+r#"class StringFutureCompleter {"#;
+r#"    virtual void complete(RustString value) noexcept = 0;"#;
+r#"    virtual void fail(RustString error) noexcept = 0;"#;
+r#"    void rustFetch(std::string_view url, std::unique_ptr<StringFutureCompleter> completer) const noexcept;"#;
+r#"    void rustClose(std::unique_ptr<VoidFutureCompleter> completer) noexcept;"#;
+r#"public:
+    //Downloads page
+    std::future<RustString> fetch(std::string_view url) const
+    {
+        using Completer = RustFutureCompleter<StringFutureCompleter, RustString, RustString>;
+        std::unique_ptr<Completer> completer{new Completer()};
+        auto future = completer->get_future();
+        this->rustFetch(url, std::move(completer));
+        return future;
+    }"#;
+r#"    std::future<int32_t> count() const
+    {
+        using Completer = RustFutureCompleter<I32FutureCompleter, int32_t, RustString>;"#;
+r#"    std::future<void> close()
+    {
+        using Completer = RustFutureCompleter<VoidFutureCompleter, void, RustString>;"#;
+r#"    static std::future<void> ping()
+    {
+        using Completer = RustFutureCompleter<VoidFutureCompleter, void, RustString>;
+        std::unique_ptr<Completer> completer{new Completer()};
+        auto future = completer->get_future();
+        rustPing(std::move(completer));
+        return future;
+    }"#;
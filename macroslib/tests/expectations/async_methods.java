@@ -0,0 +1,38 @@
+r#"public interface StringFutureCompleter {"#;
+r#"    void complete(@NonNull String value);"#;
+r#"    void fail(@NonNull String error);"#;
+r#"public interface VoidFutureCompleter {"#;
+r#"    void complete();"#;
+r#"    private final void rustFetch(@NonNull String url, @NonNull StringFutureCompleter completer) {"#;
+r#"    private static native void rustPing(@NonNull VoidFutureCompleter completer);"#;
+r#"    /**
+     * Downloads page
+     */
+    public final @NonNull java.util.concurrent.CompletableFuture<String> fetch(@NonNull String url) {
+        java.util.concurrent.CompletableFuture<String> future = new java.util.concurrent.CompletableFuture<String>();
+        rustFetch(url, new StringFutureCompleter() {
+            @Override
+            public void complete(@NonNull String value) {
+                future.complete(value);
+            }
+            @Override
+            public void fail(@NonNull String error) {
+                future.completeExceptionally(new Exception(error));
+            }
+        });
+        return future;
+    }"#;
+r#"    public final @NonNull java.util.concurrent.CompletableFuture<Integer> count() {
+        java.util.concurrent.CompletableFuture<Integer> future = new java.util.concurrent.CompletableFuture<Integer>();
+        rustCount(new I32FutureCompleter() {
+            @Override
+            public void complete(int value) {
+                future.complete(value);
+            }"#;
+r#"    public static @NonNull java.util.concurrent.CompletableFuture<Void> ping() {
+        java.util.concurrent.CompletableFuture<Void> future = new java.util.concurrent.CompletableFuture<Void>();
+        rustPing(new VoidFutureCompleter() {
+            @Override
+            public void complete() {
+                future.complete(null);
+            }"#;
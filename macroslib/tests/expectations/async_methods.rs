@@ -0,0 +1,9 @@
+foreign_class!(class Session {
+    self_type Session;
+    constructor Session::new() -> Session;
+    /// Downloads page
+    fn Session::fetch(&self, url: &str) -> impl Future<Output = Result<String, String>>;
+    fn Session::count(&self) -> impl Future<Output = i32>;
+    fn Session::close(&mut self) -> impl Future<Output = Result<(), String>>;
+    fn Session::ping() -> impl Future<Output = ()> + Send;
+});
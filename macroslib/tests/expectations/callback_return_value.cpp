@@ -0,0 +1,10 @@
+r#"    static int32_t c_calc(int32_t x, void *opaque)
+    {
+        assert(opaque != nullptr);
+        auto pi = static_cast<Calculator *>(opaque);
+
+        auto ret = pi->calc(x);
+        return ret;
+    }"#;
+r#"        auto ret = pi->is_ready();
+        return ret ? 1 : 0;"#;
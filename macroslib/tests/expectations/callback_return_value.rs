@@ -0,0 +1,9 @@
+foreign_callback!(callback Calculator {
+    self_type Calculator;
+    calc = Calculator::calc(&self, x: i32) -> i32;
+    is_ready = Calculator::is_ready(&self) -> bool;
+});
+
+foreign_class!(class Test {
+    fn f(_: Box<dyn Calculator>) -> i32;
+});
@@ -0,0 +1,7 @@
+r#"int32_t map(std::function<int32_t(int32_t)> f) const noexcept;"#;
+r#"void visit(std::function<void(int32_t, std::string_view)> f) const noexcept;"#;
+r#"void run(std::function<void()> f) const noexcept;"#;
+r#"static std::unique_ptr<I32ToI32Closure> from_function(std::function<int32_t(int32_t)> f)"#;
+r#"C_I32ToI32Closure tmp = I32ToI32Closure::to_c_interface(I32ToI32Closure::from_function(std::move(f)));"#;
+r#"        auto ret = pi->call(a0);
+        return ret;"#;
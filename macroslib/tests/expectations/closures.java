@@ -0,0 +1,9 @@
+r#"/**
+ * Closure `Fn(i32) -> i32`
+ */
+@FunctionalInterface
+public interface I32ToI32Closure {"#;
+r#"int call(int a0);"#;
+r#"public final int map(@NonNull I32ToI32Closure f)"#;
+r#"private static native void do_visit(long self, I32StrClosure f);"#;
+r#"public static native int apply(@NonNull I32ToI32Closure f);"#;
@@ -0,0 +1,11 @@
+foreign_class!(class Foo {
+    self_type Foo;
+    constructor Foo::new() -> Foo;
+    fn Foo::map(&self, f: Box<dyn Fn(i32) -> i32 + Send>) -> i32;
+    fn Foo::visit(&self, f: &dyn Fn(i32, &str));
+    fn Foo::each(&self, f: impl Fn(String) -> bool);
+    fn Foo::run(&self, f: Box<dyn Fn()>);
+});
+foreign_fn!(
+    fn apply(f: &dyn Fn(i32) -> i32) -> i32;
+);
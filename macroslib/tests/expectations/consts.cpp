@@ -0,0 +1,12 @@
+r#"#include <string_view>"#;
+r#"    //Max size of packet
+    static constexpr uint32_t MAX_SIZE = 1500;
+    static constexpr int64_t MIN_DELTA = -10000000000LL;
+    static constexpr double RATIO = 0.5;
+    static constexpr float SCALE = 2.0f;
+    static constexpr bool COMPRESSED = true;
+    static constexpr std::string_view NAME = "packet \"v1\"";"#;
+r#"using Globals = GlobalsWrapper<true>;"#;
+r#"    //Default port
+    static constexpr uint16_t DEFAULT_PORT = 8080;
+    static constexpr std::string_view VERSION = "1.0";"#;
@@ -0,0 +1,18 @@
+r#"    /**
+     * Max size of packet
+     */
+    public static final long MAX_SIZE = 1500L;
+    public static final long MIN_DELTA = -10000000000L;
+    public static final double RATIO = 0.5;
+    public static final float SCALE = 2.0f;
+    public static final boolean COMPRESSED = true;
+    public static final String NAME = "packet \"v1\"";"#;
+r#"public final class Globals {
+    /**
+     * Default port
+     */
+    public static final int DEFAULT_PORT = 8080;
+    public static final String VERSION = "1.0";
+
+    private Globals() {}
+}"#;
@@ -0,0 +1,18 @@
+foreign_class!(class Packet {
+    self_type Packet;
+    /// Max size of packet
+    const MAX_SIZE: u32 = Packet::MAX_SIZE = 1500;
+    const MIN_DELTA: i64 = -10000000000;
+    const RATIO: f64 = 0.5;
+    const SCALE: f32 = 2f32;
+    const COMPRESSED: bool = true;
+    const NAME: &str = Packet::NAME = "packet \"v1\"";
+    constructor Packet::new() -> Packet;
+    fn Packet::len(&self) -> u32;
+});
+
+foreign_const!(
+    /// Default port
+    const DEFAULT_PORT: u16 = 8080;
+    const VERSION: &str = "1.0";
+);
@@ -0,0 +1,16 @@
+r#"struct Event_Connected {
+    std::string peer;
+};"#;
+r#"using Event = std::variant<Event_Connected, Event_Moved, Event_Closed>;"#;
+r#"struct EventRawAccess {
+    static Event from_raw(int64_t raw) noexcept"#;
+r#"Event_Connected ret{ EventRaw::get_Connected_peer(raw).to_std_string() };"#;
+r#"Event_Moved ret{ EventRaw::get_Moved_field0(raw), EventRaw::get_Moved_field1(raw) };"#;
+r#"static int64_t to_raw(Event x) noexcept"#;
+r#"return EventRaw::new_Connected(v.peer);"#;
+r#"private:
+
+    static int32_t tag(int64_t raw) noexcept;"#;
+r#"friend struct EventRawAccess;"#;
+r#"return EventRawAccess::from_raw(ret);"#;
+r#"Connection_send(this->self_, EventRawAccess::to_raw(std::move(a0)));"#;
@@ -0,0 +1,14 @@
+r#"public abstract class Event {
+    private Event() {}"#;
+r#"case 1: return new Moved(EventRaw.get_Moved_field0(raw), EventRaw.get_Moved_field1(raw));"#;
+r#"public static final class Connected extends Event {
+        public Connected(@NonNull String peer) {
+            this.peer = peer;
+        }"#;
+r#"return EventRaw.new_Connected(peer);"#;
+r#"public final @NonNull String peer;"#;
+r#"Event convRet = Event.fromRaw(ret);"#;
+r#"long a00 = a0.toRaw();"#;
+r#"final class EventRaw {
+
+    public static native int tag(long raw);"#;
@@ -0,0 +1,15 @@
+foreign_enum!(
+    /// Connection events
+    enum Event {
+        Connected { peer: String } = Event::Connected,
+        Moved(i32, f64) = Event::Moved,
+        Closed = Event::Closed,
+    }
+);
+
+foreign_class!(class Connection {
+    self_type Connection;
+    constructor Connection::new() -> Connection;
+    fn Connection::poll(&mut self) -> Event;
+    fn Connection::send(&mut self, _: Event);
+});
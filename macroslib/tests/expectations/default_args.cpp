@@ -0,0 +1,3 @@
+r#"FooWrapper(std::string_view name, bool verbose = false) noexcept"#;
+r#"int32_t connect(std::string_view host, uint16_t port = 80, uint32_t timeout_ms = 5000) const noexcept;"#;
+r#"static double scale(double x = -1.5) noexcept;"#;
@@ -0,0 +1,7 @@
+r#"public final int connect(@NonNull String host, int port, long timeout_ms)"#;
+r#"public final int connect(@NonNull String host, int port)"#;
+r#"public final int connect(@NonNull String host)"#;
+r#"public Foo(@NonNull String name, boolean verbose)"#;
+r#"public Foo(@NonNull String name)"#;
+r#"public static native double scale(double x);"#;
+r#"public static native double scale();"#;
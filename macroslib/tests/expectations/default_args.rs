@@ -0,0 +1,8 @@
+foreign_class!(
+    class Foo {
+        self_type Foo;
+        constructor Foo::new(name: &str, verbose: bool = false) -> Foo;
+        fn Foo::connect(&self, host: &str, port: u16 = 80, timeout_ms: u32 = 5000) -> i32;
+        fn Foo::scale(x: f64 = -1.5) -> f64;
+    }
+);
@@ -0,0 +1,22 @@
+foreign_enum!(
+    enum Mode {
+        FAST = Mode::Fast,
+        SLOW = Mode::Slow,
+    }
+);
+
+foreign_class!(
+    class Boo {
+        self_type Boo;
+        constructor Boo::default() -> Boo;
+    }
+);
+
+foreign_class!(
+    class Foo {
+        self_type Foo;
+        constructor Foo::default() -> Foo;
+        fn Foo::set_mode(&mut self, mode: Mode = Mode::Fast, level: i32 = 1);
+        fn Foo::use_boo(&self, boo: Option<&Boo> = None) -> bool;
+    }
+);
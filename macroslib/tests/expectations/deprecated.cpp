@@ -0,0 +1,10 @@
+r#"using Old [[deprecated("since 1.2: use Foo")]] = OldWrapper<true>;
+using OldRef [[deprecated("since 1.2: use Foo")]] = OldWrapper<false>;"#;
+r#"    //Value of foo
+    [[deprecated("since 0.3: use get")]] int32_t value() const noexcept;"#;
+r#"    [[deprecated]] static int32_t create_default() noexcept;"#;
+r#"DARK_RED [[deprecated("use RED")]] = 1"#;
+r#"    [[deprecated("since 2.0")]] virtual void on_event(int32_t x) noexcept = 0;"#;
+r#"#pragma GCC diagnostic ignored "-Wdeprecated-declarations"
+#endif
+    static void c_on_event(int32_t x, void *opaque)"#;
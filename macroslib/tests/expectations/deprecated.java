@@ -0,0 +1,24 @@
+r#"/**
+ * Old implementation
+ * @deprecated since 1.2: use Foo
+ */
+@Deprecated
+public final class Old {"#;
+r#"    /**
+     * Value of foo
+     * @deprecated since 0.3: use get
+     */
+    @Deprecated
+    public final int value() {"#;
+r#"    private static native int do_get(long self);
+    @Deprecated
+    public static native int create_default();"#;
+r#"     * @deprecated use RED
+     */
+    @Deprecated
+    DARK_RED(1);"#;
+r#"    /**
+     * @deprecated since 2.0
+     */
+    @Deprecated
+    void on_event(int x);"#;
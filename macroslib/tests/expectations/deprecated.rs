@@ -0,0 +1,27 @@
+foreign_class!(
+/// Old implementation
+#[deprecated(since = "1.2", note = "use Foo")]
+class Old {
+    self_type Old;
+    constructor Old::new() -> Old;
+});
+foreign_class!(class Foo {
+    self_type Foo;
+    constructor Foo::new() -> Foo;
+    /// Value of foo
+    #[deprecated(since = "0.3", note = "use get")]
+    fn Foo::value(&self) -> i32;
+    fn Foo::get(&self) -> i32;
+    #[deprecated]
+    fn Foo::create_default() -> i32;
+});
+foreign_enum!(enum Color {
+    RED = Color::Red,
+    #[deprecated(note = "use RED")]
+    DARK_RED = Color::DarkRed,
+});
+foreign_callback!(callback Listener {
+    self_type Listener;
+    #[deprecated(since = "2.0")]
+    on_event = Listener::on_event(&self, x: i32);
+});
@@ -0,0 +1,29 @@
+r#"    template<bool OTHER_OWN_DATA>
+    bool operator==(const VersionWrapper<OTHER_OWN_DATA> &o) const noexcept
+    {
+        return this->self_ == o.self_ || rustEq(o.as_cref());
+    }"#;
+r#"    template<bool OTHER_OWN_DATA>
+    bool operator<=(const VersionWrapper<OTHER_OWN_DATA> &o) const noexcept
+    {
+        if (this->self_ == o.self_) {
+            return true;
+        }
+        const int32_t c = rustCompare(o.as_cref());
+        return c == -1 || c == 0;
+    }"#;
+r#"    friend std::ostream &operator<<(std::ostream &os, const VersionWrapper &o)
+    {
+        return os << o.rustToString().to_std_string();
+    }
+    template<typename> friend struct std::hash;"#;
+r#"namespace std {
+template<bool OWN_DATA>
+struct hash<org_examples::VersionWrapper<OWN_DATA>> {
+    std::size_t operator()(const org_examples::VersionWrapper<OWN_DATA> &o) const noexcept
+    {
+        return static_cast<std::size_t>(o.rustHash());
+    }
+};
+} // namespace std"#;
+r#"    friend std::ostream &operator<<(std::ostream &os, const PointWrapper &o)"#;
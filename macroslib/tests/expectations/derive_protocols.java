@@ -0,0 +1,31 @@
+r#"public final class Version implements Comparable<Version> {"#;
+r#"    @Override
+    public boolean equals(Object obj) {
+        if (this == obj)
+            return true;
+        if (!(obj instanceof Version))
+            return false;
+        Version other = (Version) obj;
+        if (mNativeObj == other.mNativeObj)
+            return true;
+        return rustEq(other);
+    }
+
+    @Override
+    public int hashCode() {
+        long h = rustHash();
+        return (int) (h ^ (h >>> 32));
+    }
+
+    @Override
+    public String toString() {
+        return rustToString();
+    }
+
+    @Override
+    public int compareTo(Version other) {
+        if (mNativeObj == other.mNativeObj)
+            return 0;
+        return rustCompare(other);
+    }"#;
+r#"public final class Point {"#;
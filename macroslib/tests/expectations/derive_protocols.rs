@@ -0,0 +1,17 @@
+foreign_class!(
+    #[derive(PartialEq, Hash, Display, Ord)]
+    class Version {
+        self_type Version;
+        constructor Version::new(major: u32, minor: u32) -> Version;
+        fn Version::major(&self) -> u32;
+    }
+);
+
+foreign_class!(
+    #[derive(Eq, Debug, PartialOrd)]
+    class Point {
+        self_type Point;
+        constructor Point::new(x: f64, y: f64) -> Arc<Mutex<Point>>;
+        fn Point::x(&self) -> f64;
+    }
+);
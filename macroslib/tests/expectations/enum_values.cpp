@@ -0,0 +1,6 @@
+r#"enum Opcode {
+PING = 16,
+PONG = 17,
+CLOSE = 127
+
+};"#;
@@ -0,0 +1,12 @@
+r#"public enum Opcode {
+    PING(16),
+    PONG(17),
+    CLOSE(127);"#;
+r#"/*package*/ static Opcode fromInt(int x) {
+        switch (x) {
+            case 16: return PING;
+            case 17: return PONG;
+            case 127: return CLOSE;
+            default: throw new Error("Invalid value for enum Opcode: " + x);
+        }
+    }"#;
@@ -0,0 +1,13 @@
+foreign_enum!(
+    enum Opcode {
+        PING = Opcode::Ping = 0x10,
+        PONG = Opcode::Pong,
+        CLOSE = Opcode::Close = 0x7f,
+    }
+);
+
+foreign_class!(class Frame {
+    self_type Frame;
+    constructor Frame::new(_: Opcode) -> Frame;
+    fn Frame::opcode(&self) -> Opcode;
+});
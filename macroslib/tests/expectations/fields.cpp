@@ -0,0 +1,3 @@
+r#"double x() const noexcept;"#;
+r#"void set_x(double x) noexcept;"#;
+r#"uint64_t id() const noexcept;"#;
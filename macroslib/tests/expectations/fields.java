@@ -0,0 +1,3 @@
+r#"public final double getX() {"#;
+r#"public final void setX(double x) {"#;
+r#"public final long getId() {"#;
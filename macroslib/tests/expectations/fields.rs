@@ -0,0 +1,9 @@
+foreign_class!(
+    class Point {
+        self_type Point;
+        constructor Point::new() -> Point;
+        field x: f64;
+        #[readonly]
+        field id: u64;
+    }
+);
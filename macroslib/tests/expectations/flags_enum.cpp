@@ -0,0 +1,21 @@
+r#"enum class Permissions : uint32_t {
+READ = 1,
+WRITE = 2,
+EXEC = 16
+
+};"#;
+r#"inline Permissions operator|(Permissions a, Permissions b)
+{
+    return static_cast<Permissions>(static_cast<uint32_t>(a) | static_cast<uint32_t>(b));
+}"#;
+r#"inline Permissions &operator&=(Permissions &a, Permissions b)
+{
+    a = a & b;
+    return a;
+}"#;
+r#"inline Permissions operator~(Permissions a)
+{
+    return static_cast<Permissions>(~static_cast<uint32_t>(a));
+}"#;
+r#"FileWrapper(std::string_view a0, Permissions a1) noexcept"#;
+r#"Permissions permissions() const noexcept;"#;
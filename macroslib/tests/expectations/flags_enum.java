@@ -0,0 +1,23 @@
+r#"public enum Permissions {
+    READ(1),
+    WRITE(2),
+    EXEC(16);"#;
+r#"public static java.util.EnumSet<Permissions> fromBits(int bits) {
+        java.util.EnumSet<Permissions> ret = java.util.EnumSet.noneOf(Permissions.class);
+        for (Permissions x : values()) {
+            if (x.value != 0 && (bits & x.value) == x.value) {
+                ret.add(x);
+            }
+        }
+        return ret;
+    }"#;
+r#"public static int toBits(java.util.Set<Permissions> set) {
+        int bits = 0;
+        for (Permissions x : set) {
+            bits |= x.value;
+        }
+        return bits;
+    }"#;
+r#"public File(@NonNull String a0, @NonNull java.util.EnumSet<Permissions> a1) {
+        int a10 = Permissions.toBits(a1);"#;
+r#"java.util.EnumSet<Permissions> convRet = Permissions.fromBits(ret);"#;
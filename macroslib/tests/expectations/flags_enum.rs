@@ -0,0 +1,15 @@
+foreign_enum!(
+    /// Access rights
+    #[derive(Flags)]
+    enum Permissions {
+        READ = Permissions::READ,
+        WRITE = Permissions::WRITE,
+        EXEC = Permissions::EXEC = 0x10,
+    }
+);
+
+foreign_class!(class File {
+    self_type File;
+    constructor File::open(_: &str, _: Permissions) -> File;
+    fn File::permissions(&self) -> Permissions;
+});
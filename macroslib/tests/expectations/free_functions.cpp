@@ -0,0 +1,16 @@
+r#"    //Sum of two numbers
+    inline int32_t add(int32_t a, int32_t b = 1) noexcept;"#;
+r#"    inline int32_t add(int32_t a, int32_t b) noexcept
+    {
+
+        int32_t ret = Globals_add(a, b);
+        return ret;
+    }"#;
+r#"    inline Calc make_calc() noexcept;"#;
+r#"    inline void write_log(std::string_view msg) noexcept
+    {
+
+        Globals_write_log(CRustStrView{ msg.data(), msg.size() });
+    }"#;
+r#"    //Sum of two numbers
+    int32_t Globals_add(int32_t a, int32_t b);"#;
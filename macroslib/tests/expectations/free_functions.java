@@ -0,0 +1,8 @@
+r#"public final class Globals {
+    /**
+     * Sum of two numbers
+     */
+    public static native int add(int a, int b);"#;
+r#"    public static native @NonNull String greet(@NonNull String name);"#;
+r#"    public static @NonNull Calc make_calc() {"#;
+r#"    public static native void write_log(@NonNull String msg);"#;
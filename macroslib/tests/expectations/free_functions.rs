@@ -0,0 +1,13 @@
+foreign_class!(class Calc {
+    self_type Calc;
+    constructor Calc::new() -> Calc;
+    fn Calc::get(&self) -> i32;
+});
+
+foreign_fn!(
+    /// Sum of two numbers
+    fn add(a: i32, b: i32 = 1) -> i32;
+    fn greet(name: &str) -> String;
+    fn make_calc() -> Calc;
+    fn utils::log(msg: &str); alias write_log;
+);
@@ -0,0 +1,16 @@
+r#"class Shape {
+public:
+    virtual ~Shape() noexcept = default;
+    //Area of shape
+    virtual double area() const noexcept = 0;
+
+    virtual RustString name() const noexcept = 0;
+
+    virtual RustString describe(std::string_view prefix) const noexcept = 0;
+};"#;
+r#"#include "Shape.hpp""#;
+r#"template<bool OWN_DATA>
+class CircleWrapper : public Shape {"#;
+r#"template<bool OWN_DATA>
+class SquareWrapper : public Shape {"#;
+r#"double area() const noexcept;"#;
@@ -0,0 +1,17 @@
+r#"public abstract class Shape {
+    /**
+     * Area of shape
+     */
+    public abstract double area();
+
+    public abstract @NonNull String name();
+
+    public abstract @NonNull String describe(@NonNull String prefix);
+}"#;
+r#"public final class Circle extends Shape {"#;
+r#"public final class Square extends Shape {"#;
+r#"public final double area() {
+        double ret = do_area(mNativeObj);
+
+        return ret;
+    }"#;
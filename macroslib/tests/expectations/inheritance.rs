@@ -0,0 +1,18 @@
+foreign_class!(
+/// Geometric shape
+abstract class Shape {
+    /// Area of shape
+    fn ShapeTrait::area(&self) -> f64;
+    fn ShapeTrait::name(&self) -> String;
+    fn ShapeTrait::describe(&self, prefix: &str) -> String;
+});
+foreign_class!(class Circle extends Shape {
+    self_type Circle;
+    constructor Circle::new(r: f64) -> Circle;
+    fn Circle::radius(&self) -> f64;
+});
+foreign_class!(class Square extends Shape {
+    self_type Square;
+    constructor Square::new(side: f64) -> Square;
+    fn Square::name(&self) -> String;
+});
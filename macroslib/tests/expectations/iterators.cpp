@@ -0,0 +1,9 @@
+r#"#include "rust_foreign_iterator.hpp""#;
+r#"public:
+    using iterator = RustForeignIterator<BooIteratorWrapper>;
+    friend iterator;
+    iterator begin() { return iterator{ this }; }
+    iterator end() noexcept { return iterator{}; }"#;
+r#"    Boo rustNext() noexcept;"#;
+r#"    StringIterator names() const noexcept;"#;
+r#"    static I32SendIterator range(int32_t n) noexcept;"#;
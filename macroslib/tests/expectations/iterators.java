@@ -0,0 +1,33 @@
+r#"public final class StringIterator implements java.util.Iterator<String>, Iterable<String> {"#;
+r#"public final class BooIterator implements java.util.Iterator<Boo>, Iterable<Boo> {"#;
+r#"public final class I32SendIterator implements java.util.Iterator<Integer>, Iterable<Integer> {"#;
+r#"    @Override
+    public boolean hasNext() {
+        return rustHasNext();
+    }
+
+    @Override
+    public Boo next() {
+        if (!rustHasNext())
+            throw new java.util.NoSuchElementException();
+        return rustNext();
+    }
+
+    /**
+     * Elements can be traversed only once, so only one call is allowed
+     * @throws IllegalStateException if iterator was already requested
+     */
+    @Override
+    public java.util.Iterator<Boo> iterator() {
+        if (iteratorTaken)
+            throw new IllegalStateException("elements of Rust iterator can be traversed only once");
+        iteratorTaken = true;
+        return this;
+    }"#;
+r#"    public final @NonNull StringIterator names() {
+        long ret = do_names(mNativeObj);
+        StringIterator convRet = new StringIterator(InternalPointerMarker.RAW_PTR, ret);
+
+        return convRet;
+    }"#;
+r#"    public static @NonNull I32SendIterator range(int n) {"#;
@@ -0,0 +1,12 @@
+foreign_class!(class Boo {
+    self_type Boo;
+    constructor Boo::new() -> Boo;
+});
+
+foreign_class!(class Foo {
+    self_type Foo;
+    constructor Foo::new() -> Foo;
+    fn Foo::names(&self) -> Box<dyn Iterator<Item = String>>;
+    fn Foo::boos(&self) -> Box<dyn Iterator<Item = Boo>>;
+    fn Foo::range(n: i32) -> Box<dyn Iterator<Item = i32> + Send>;
+});
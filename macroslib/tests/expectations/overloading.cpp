@@ -0,0 +1,10 @@
+r#"int32_t Calc_add__add_int(CalcOpaque * const self, int32_t x);"#;
+r#"int32_t Calc_add__add_str(CalcOpaque * const self, struct CRustStrView x);"#;
+r#"int32_t Calc_add__add_pair(CalcOpaque * const self, int32_t x, int32_t y);"#;
+r#"CalcOpaque * Calc_parse__parse_int(int32_t x);"#;
+r#"CalcOpaque * Calc_parse__parse_str(struct CRustStrView x);"#;
+r#"int32_t Calc_scale__scale__0(int32_t x);"#;
+r#"int32_t Calc_scale__scale__1(struct CRustStrView x);"#;
+r#"int32_t add(int32_t x) noexcept;"#;
+r#"int32_t add(std::string_view x) noexcept;"#;
+r#"int32_t add(int32_t x, int32_t y) noexcept;"#;
@@ -0,0 +1,10 @@
+r#"public Calc()"#;
+r#"public Calc(int start)"#;
+r#"public final int add(int x)"#;
+r#"private static native int do_add(long self, int x);"#;
+r#"public final int add(@NonNull String x)"#;
+r#"private static native int do_add(long self, @NonNull String x);"#;
+r#"public final int add(int x, int y)"#;
+r#"private static native int do_add(long self, int x, int y);"#;
+r#"public static @NonNull Calc parse(int x)"#;
+r#"public static @NonNull Calc parse(@NonNull String x)"#;
@@ -0,0 +1,12 @@
+foreign_class!(class Calc {
+    self_type Calc;
+    constructor Calc::new() -> Calc;
+    constructor Calc::with_start(start: i32) -> Calc;
+    fn Calc::add_int(&mut self, x: i32) -> i32; alias add;
+    fn Calc::add_str(&mut self, x: &str) -> i32; alias add;
+    fn Calc::add_pair(&mut self, x: i32, y: i32) -> i32; alias add;
+    fn Calc::parse_int(x: i32) -> Calc; alias parse;
+    fn Calc::parse_str(x: &str) -> Calc; alias parse;
+    fn int_ops::scale(x: i32) -> i32; alias scale;
+    fn str_ops::scale(x: &str) -> i32; alias scale;
+});
@@ -0,0 +1,6 @@
+r#"double lineWidth() const noexcept;"#;
+r#"void setLineWidth(double a0) noexcept;"#;
+r#"bool visible() const noexcept;"#;
+r#"void setVisible(bool a0) noexcept;"#;
+r#"int32_t id() const noexcept;"#;
+r#"void doSomething() const noexcept;"#;
@@ -0,0 +1,6 @@
+r#"public final double getLineWidth()"#;
+r#"public final void setLineWidth(double a0)"#;
+r#"public final boolean isVisible()"#;
+r#"public final void setVisible(boolean a0)"#;
+r#"public final int getId()"#;
+r#"public final void doSomething()"#;
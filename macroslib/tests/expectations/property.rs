@@ -0,0 +1,16 @@
+foreign_class!(
+    #[derive(camelCaseAliases)]
+    class Foo {
+        self_type Foo;
+        constructor Foo::new() -> Foo;
+        #[property]
+        fn Foo::line_width(&self) -> f64;
+        fn Foo::set_line_width(&mut self, _: f64);
+        #[property]
+        fn Foo::is_visible(&self) -> bool;
+        fn Foo::set_visible(&mut self, _: bool);
+        #[property]
+        fn Foo::get_id(&self) -> i32;
+        fn Foo::do_something(&self);
+    }
+);
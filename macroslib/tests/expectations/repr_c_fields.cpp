@@ -0,0 +1,16 @@
+r#"struct PointReprC {
+        double x;
+        uint64_t id;
+    };"#;
+r#"double x() const noexcept
+    {
+        return reinterpret_cast<const PointReprC *>(this->self_)->x;
+    }"#;
+r#"void set_x(double x) noexcept
+    {
+        reinterpret_cast<PointReprC *>(this->self_)->x = x;
+    }"#;
+r#"uint64_t id() const noexcept
+    {
+        return reinterpret_cast<const PointReprC *>(this->self_)->id;
+    }"#;
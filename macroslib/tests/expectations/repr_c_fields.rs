@@ -0,0 +1,10 @@
+foreign_class!(
+    #[derive(ReprC)]
+    class Point {
+        self_type Point;
+        constructor Point::new() -> Point;
+        field x: f64;
+        #[readonly]
+        field id: u64;
+    }
+);
@@ -1,10 +1,16 @@
 use std::{
+    cell::RefCell,
     ffi::OsString,
     fs, panic,
     path::{Path, PathBuf},
+    rc::Rc,
 };
 
-use flapigen::{rustfmt_cnt, CppConfig, Generator, JavaConfig, LanguageConfig, RustEdition};
+use flapigen::{
+    backend::{BackendContext, BackendError, ConvDirection, Item, LanguageBackend},
+    rustfmt_cnt, ApiCheckMode, CppConfig, Generator, JavaConfig, JsonDiagnosticsOutput,
+    LanguageConfig, MethodVariant, PythonConfig, RustEdition,
+};
 use log::warn;
 use syn::Token;
 use tempfile::tempdir;
@@ -134,6 +140,1380 @@ foreign_interface! (interface FooBar {
     }
 }
 
+#[test]
+fn test_try_expand_err() {
+    let _ = env_logger::try_init();
+    let tmp_dir = tempdir().expect("Can not create tmp directory");
+    let rust_src_path = tmp_dir.path().join("src.rs");
+    fs::write(
+        &rust_src_path,
+        r#"
+foreign_class!(class Foo {
+    self_type Foo;
+    constructor Foo::new() -> Foo;
+    method Foo::f(&self) -> i32
+});
+"#,
+    )
+    .unwrap();
+    let err = Generator::new(LanguageConfig::JavaConfig(JavaConfig::new(
+        tmp_dir.path().into(),
+        "org.example".into(),
+    )))
+    .with_pointer_target_width(64)
+    .try_expand(
+        "test_try_expand_err",
+        &rust_src_path,
+        tmp_dir.path().join("test.rs"),
+    )
+    .expect_err("try_expand should fail");
+    let span = err.message.span.expect("error without location");
+    assert!(span.file_name.starts_with("test_try_expand_err: "));
+    assert_eq!(6, span.start.line);
+    assert!(!err.message.message.is_empty());
+}
+
+#[test]
+fn test_async_method_without_spawner() {
+    let _ = env_logger::try_init();
+    let tmp_dir = tempdir().expect("Can not create tmp directory");
+    let rust_src_path = tmp_dir.path().join("src.rs");
+    fs::write(
+        &rust_src_path,
+        r#"
+foreign_class!(class Foo {
+    self_type Foo;
+    constructor Foo::new() -> Foo;
+    fn Foo::f(&self) -> impl Future<Output = i32>;
+});
+"#,
+    )
+    .unwrap();
+    for lang in &[ForeignLang::Java, ForeignLang::Cpp] {
+        let lang_config = match lang {
+            ForeignLang::Java => LanguageConfig::JavaConfig(JavaConfig::new(
+                tmp_dir.path().into(),
+                "org.example".into(),
+            )),
+            ForeignLang::Cpp => LanguageConfig::CppConfig(CppConfig::new(
+                tmp_dir.path().into(),
+                "org_examples".into(),
+            )),
+        };
+        let err = Generator::new(lang_config)
+            .with_pointer_target_width(64)
+            .try_expand(
+                "test_async_method_without_spawner",
+                &rust_src_path,
+                tmp_dir.path().join("test.rs"),
+            )
+            .expect_err("try_expand should fail");
+        assert!(err.message.message.contains("Generator::async_spawner"));
+    }
+}
+
+#[test]
+fn test_indistinguishable_overloads() {
+    let _ = env_logger::try_init();
+    let tmp_dir = tempdir().expect("Can not create tmp directory");
+    let rust_src_path = tmp_dir.path().join("src.rs");
+    fs::write(
+        &rust_src_path,
+        r#"
+foreign_class!(class Foo {
+    self_type Foo;
+    constructor Foo::new() -> Foo;
+    fn Foo::f_string(&self, x: String); alias f;
+    fn Foo::f_str(&self, x: &str); alias f;
+});
+"#,
+    )
+    .unwrap();
+    for lang in &[ForeignLang::Java, ForeignLang::Cpp] {
+        let lang_config = match lang {
+            ForeignLang::Java => LanguageConfig::JavaConfig(JavaConfig::new(
+                tmp_dir.path().into(),
+                "org.example".into(),
+            )),
+            ForeignLang::Cpp => LanguageConfig::CppConfig(CppConfig::new(
+                tmp_dir.path().into(),
+                "org_examples".into(),
+            )),
+        };
+        let err = Generator::new(lang_config)
+            .with_pointer_target_width(64)
+            .try_expand(
+                "test_indistinguishable_overloads",
+                &rust_src_path,
+                tmp_dir.path().join("test.rs"),
+            )
+            .expect_err("try_expand should fail");
+        assert!(err
+            .message
+            .message
+            .contains("can not distinguish overloaded methods of class Foo"));
+    }
+}
+
+#[test]
+fn test_inheritance_errors() {
+    let _ = env_logger::try_init();
+    let tmp_dir = tempdir().expect("Can not create tmp directory");
+    let rust_src_path = tmp_dir.path().join("src.rs");
+    let expand = |lang: ForeignLang, src: &str| {
+        fs::write(&rust_src_path, src).unwrap();
+        let lang_config = match lang {
+            ForeignLang::Java => LanguageConfig::JavaConfig(JavaConfig::new(
+                tmp_dir.path().into(),
+                "org.example".into(),
+            )),
+            ForeignLang::Cpp => LanguageConfig::CppConfig(CppConfig::new(
+                tmp_dir.path().into(),
+                "org_examples".into(),
+            )),
+        };
+        let err = Generator::new(lang_config)
+            .with_pointer_target_width(64)
+            .try_expand(
+                "test_inheritance_errors",
+                &rust_src_path,
+                tmp_dir.path().join("test.rs"),
+            )
+            .expect_err("try_expand should fail");
+        err.message.message
+    };
+    for lang in &[ForeignLang::Java, ForeignLang::Cpp] {
+        let msg = expand(
+            *lang,
+            r#"
+foreign_class!(class Shape {
+    self_type Shape;
+    constructor Shape::new() -> Shape;
+});
+foreign_class!(class Circle extends Shape {
+    self_type Circle;
+    constructor Circle::new() -> Circle;
+});
+"#,
+        );
+        assert!(msg.contains("class Shape is not abstract"));
+
+        for sig in &[
+            "fn sum_area(&self, other: &dyn ShapeTrait) -> f64;",
+            "fn total(shapes: Vec<&dyn ShapeTrait>) -> f64;",
+            "fn largest(&self) -> Box<dyn ShapeTrait>;",
+        ] {
+            let msg = expand(
+                *lang,
+                &format!(
+                    r#"
+foreign_class!(abstract class Shape {{
+    fn ShapeTrait::area(&self) -> f64;
+}});
+foreign_class!(class Circle extends Shape {{
+    self_type Circle;
+    constructor Circle::new() -> Circle;
+    {}
+}});
+"#,
+                    sig
+                ),
+            );
+            assert!(msg.contains("abstract class Shape can not be used as type of argument"));
+        }
+    }
+    let msg = expand(
+        ForeignLang::Cpp,
+        r#"
+foreign_class!(abstract class Shape {
+    fn ShapeTrait::scale(&mut self, k: f64);
+});
+"#,
+    );
+    assert!(msg.contains("should take `&self`"));
+
+    let msg = expand(
+        ForeignLang::Cpp,
+        r#"
+foreign_class!(abstract class Shape {
+    fn ShapeTrait::name(&self) -> String;
+});
+foreign_class!(class Square extends Shape {
+    self_type Square;
+    constructor Square::new() -> Square;
+    fn Square::name(&mut self) -> String;
+});
+"#,
+    );
+    assert!(msg.contains("method name of class Square overrides method of abstract class Shape"));
+}
+
+#[test]
+fn test_module_items_class_name() {
+    let _ = env_logger::try_init();
+    let tmp_dir = tempdir().expect("Can not create tmp directory");
+    let rust_src_path = tmp_dir.path().join("src.rs");
+    fs::write(
+        &rust_src_path,
+        r#"
+foreign_class!(class Globals {
+    self_type Globals;
+    constructor Globals::new() -> Globals;
+});
+foreign_fn!(fn f(x: i32) -> i32;);
+"#,
+    )
+    .unwrap();
+    let expand = |lang: ForeignLang, module_items_class: Option<&str>| {
+        let lang_config = match lang {
+            ForeignLang::Java => {
+                let mut cfg = JavaConfig::new(tmp_dir.path().into(), "org.example".into());
+                if let Some(name) = module_items_class {
+                    cfg = cfg.module_items_class(name.into());
+                }
+                LanguageConfig::JavaConfig(cfg)
+            }
+            ForeignLang::Cpp => {
+                let mut cfg = CppConfig::new(tmp_dir.path().into(), "org_examples".into());
+                if let Some(name) = module_items_class {
+                    cfg = cfg.module_items_class(name.into());
+                }
+                LanguageConfig::CppConfig(cfg)
+            }
+        };
+        Generator::new(lang_config)
+            .with_pointer_target_width(64)
+            .try_expand(
+                "test_module_items_class_name",
+                &rust_src_path,
+                tmp_dir.path().join("test.rs"),
+            )
+    };
+    for lang in &[ForeignLang::Java, ForeignLang::Cpp] {
+        let err = expand(*lang, None).expect_err("try_expand should fail");
+        assert!(err
+            .message
+            .message
+            .contains("name `Globals` is reserved for class with module level items"));
+        expand(*lang, Some("Module")).expect("try_expand failed");
+    }
+    let java_code = fs::read_to_string(tmp_dir.path().join("Module.java")).unwrap();
+    assert!(java_code.contains("public static native int f(int x);"));
+    let cpp_code = fs::read_to_string(tmp_dir.path().join("c_module_functions.h")).unwrap();
+    assert!(cpp_code.contains("int32_t Module_f(int32_t x);"));
+    // there are no constants, so class is not required
+    assert!(!tmp_dir.path().join("Module.hpp").exists());
+}
+
+#[test]
+fn test_json_diagnostics() {
+    let _ = env_logger::try_init();
+    let tmp_dir = tempdir().expect("Can not create tmp directory");
+    let rust_src_path = tmp_dir.path().join("src.rs");
+    fs::write(
+        &rust_src_path,
+        r#"
+foreign_class!(class Foo {
+    self_type Foo;
+    constructor Foo::new() -> Foo;
+    method Foo::f(&self) -> i32;
+    fn Foo::g(&self) -> i32
+});
+"#,
+    )
+    .unwrap();
+    let rust_code_path = tmp_dir.path().join("test.rs");
+    let ret = Generator::new(LanguageConfig::JavaConfig(JavaConfig::new(
+        tmp_dir.path().into(),
+        "org.example".into(),
+    )))
+    .with_pointer_target_width(64)
+    .json_diagnostics(JsonDiagnosticsOutput::FileNextToOutput)
+    .try_expand("test_json_diagnostics", &rust_src_path, &rust_code_path);
+    assert!(ret.is_err());
+    let json = fs::read_to_string(tmp_dir.path().join("test.rs.diagnostics.json")).unwrap();
+    let lines: Vec<&str> = json.lines().collect();
+    assert_eq!(2, lines.len());
+    assert!(lines[0].starts_with(r#"{"message":"method deprecated, use \"fn\" instead","code":null,"level":"warning","spans":[{"file_name":"test_json_diagnostics: "#));
+    assert!(lines[0].contains(r#""line_start":5,"line_end":5,"column_start":5,"column_end":11,"is_primary":true,"text":[{"text":"    method Foo::f(&self) -> i32;","highlight_start":5,"highlight_end":11}]"#));
+    assert!(lines[1].starts_with(r#"{"message":"expected `;`","code":null,"level":"error","#));
+}
+
+#[test]
+fn test_several_languages() {
+    let _ = env_logger::try_init();
+    let tmp_dir = tempdir().expect("Can not create tmp directory");
+    let java_dir = tmp_dir.path().join("java");
+    let cpp_dir = tmp_dir.path().join("cpp");
+    fs::create_dir(&java_dir).unwrap();
+    fs::create_dir(&cpp_dir).unwrap();
+    let rust_src_path = tmp_dir.path().join("src.rs");
+    fs::write(
+        &rust_src_path,
+        r#"
+foreign_class!(class Foo {
+    self_type Foo;
+    constructor Foo::new(_: i32) -> Foo;
+    fn Foo::f(&self, _: i32) -> i32;
+});
+"#,
+    )
+    .unwrap();
+    let rust_code_path = tmp_dir.path().join("test.rs");
+    Generator::new(LanguageConfig::JavaConfig(JavaConfig::new(
+        java_dir.clone(),
+        "org.example".into(),
+    )))
+    .add_language(LanguageConfig::CppConfig(CppConfig::new(
+        cpp_dir.clone(),
+        "org_examples".into(),
+    )))
+    .with_pointer_target_width(64)
+    .expand("test_several_languages", &rust_src_path, &rust_code_path);
+
+    let rust_code = fs::read_to_string(&rust_code_path).unwrap();
+    let java_mod_pos = rust_code
+        .find("pub mod flapigen_java {")
+        .expect("no module for java");
+    let cpp_mod_pos = rust_code
+        .find("pub mod flapigen_cpp {")
+        .expect("no module for c++");
+    let java_init_pos = rust_code
+        .find("fn Java_org_example_Foo_init")
+        .expect("no java constructor");
+    let cpp_new_pos = rust_code.find("fn Foo_new").expect("no c++ constructor");
+    assert!(java_mod_pos < java_init_pos && java_init_pos < cpp_mod_pos);
+    assert!(cpp_mod_pos < cpp_new_pos);
+    assert_eq!(1, rust_code.matches("fn JNI_OnLoad").count());
+
+    let java_code = collect_code_in_dir(&java_dir, &[".java"]).unwrap();
+    assert!(java_code.contains("public final class Foo"));
+    let cpp_code = collect_code_in_dir(&cpp_dir, &[".h", ".hpp"]).unwrap();
+    assert!(cpp_code.contains("class FooWrapper"));
+}
+
+#[test]
+fn test_several_languages_per_language_items() {
+    let _ = env_logger::try_init();
+    let tmp_dir = tempdir().expect("Can not create tmp directory");
+    let java_dir = tmp_dir.path().join("java");
+    let cpp_dir = tmp_dir.path().join("cpp");
+    fs::create_dir(&java_dir).unwrap();
+    fs::create_dir(&cpp_dir).unwrap();
+    let rust_src_path = tmp_dir.path().join("src.rs");
+    fs::write(
+        &rust_src_path,
+        r#"
+#[cfg(flapigen_lang = "java")]
+foreign_typemap!(
+    ($p:r_type) Meters => jint {
+        $out = $p.0 as jint;
+    };
+);
+#[cfg(flapigen_lang = "cpp")]
+foreign_typemap!(
+    ($p:r_type) Meters => i64 {
+        $out = $p.0 as i64;
+    };
+);
+foreign_class!(class Foo {
+    self_type Foo;
+    constructor Foo::new() -> Foo;
+    fn Foo::dist(&self) -> Meters;
+    #[property]
+    fn Foo::line_width(&self) -> f64;
+});
+#[cfg(flapigen_lang = "java")]
+foreign_class!(class JavaOnly {
+    fn JavaOnly::f();
+});
+"#,
+    )
+    .unwrap();
+    let rust_code_path = tmp_dir.path().join("test.rs");
+    Generator::new(LanguageConfig::JavaConfig(JavaConfig::new(
+        java_dir.clone(),
+        "org.example".into(),
+    )))
+    .add_language(LanguageConfig::CppConfig(CppConfig::new(
+        cpp_dir.clone(),
+        "org_examples".into(),
+    )))
+    .with_pointer_target_width(64)
+    .expand(
+        "test_several_languages_per_language_items",
+        &rust_src_path,
+        &rust_code_path,
+    );
+
+    let rust_code = fs::read_to_string(&rust_code_path).unwrap();
+    let cpp_mod_pos = rust_code
+        .find("pub mod flapigen_cpp {")
+        .expect("no module for c++");
+    let (java_rust_code, cpp_rust_code) = rust_code.split_at(cpp_mod_pos);
+    assert!(java_rust_code.contains("as jint"));
+    assert!(!java_rust_code.contains("as i64"));
+    assert!(cpp_rust_code.contains("as i64"));
+    assert!(!cpp_rust_code.contains("as jint"));
+
+    let java_code = collect_code_in_dir(&java_dir, &[".java"]).unwrap();
+    assert!(java_code.contains("public final int dist()"));
+    assert!(java_code.contains("public final double getLineWidth()"));
+    assert!(java_code.contains("class JavaOnly"));
+    let cpp_code = collect_code_in_dir(&cpp_dir, &[".h", ".hpp"]).unwrap();
+    assert!(cpp_code.contains("int64_t dist() const"));
+    assert!(cpp_code.contains("double line_width() const"));
+    assert!(!cpp_code.contains("JavaOnly"));
+}
+
+#[test]
+fn test_cfg_attributes() {
+    let _ = env_logger::try_init();
+    let tmp_dir = tempdir().expect("Can not create tmp directory");
+    let java_dir = tmp_dir.path().join("java");
+    fs::create_dir(&java_dir).unwrap();
+    let rust_src_path = tmp_dir.path().join("src.rs");
+    fs::write(
+        &rust_src_path,
+        r#"
+foreign_class!(class Foo {
+    self_type Foo;
+    constructor Foo::new() -> Foo;
+    #[cfg(feature = "flapigen-never-enabled")]
+    fn Foo::disabled_method(&self) -> i32;
+    #[cfg(not(feature = "flapigen-never-enabled"))]
+    fn Foo::enabled_method(&self) -> i32;
+});
+
+#[cfg(feature = "flapigen-never-enabled")]
+foreign_class!(class Bar {
+    self_type Bar;
+    constructor Bar::new() -> Bar;
+});
+
+foreign_enum!(enum Mode {
+    FAST = Mode::Fast,
+    #[cfg(all(unix, feature = "flapigen-never-enabled"))]
+    DISABLED_ITEM = Mode::Disabled,
+});
+"#,
+    )
+    .unwrap();
+    let rust_code_path = tmp_dir.path().join("test.rs");
+    Generator::new(LanguageConfig::JavaConfig(JavaConfig::new(
+        java_dir.clone(),
+        "org.example".into(),
+    )))
+    .with_pointer_target_width(64)
+    .expand("test_cfg_attributes", &rust_src_path, &rust_code_path);
+
+    let rust_code = fs::read_to_string(&rust_code_path).unwrap();
+    let java_code = collect_code_in_dir(&java_dir, &[".java"]).unwrap();
+    for code in &[&rust_code, &java_code] {
+        assert!(code.contains("enabled_method"));
+        assert!(!code.contains("disabled_method"));
+        assert!(!code.contains("Bar"));
+        assert!(!code.contains("DISABLED_ITEM"));
+    }
+}
+
+#[test]
+fn test_deprecated_python() {
+    let _ = env_logger::try_init();
+    let tmp_dir = tempdir().expect("Can not create tmp directory");
+    let rust_src_path = tmp_dir.path().join("src.rs");
+    fs::write(
+        &rust_src_path,
+        r#"
+foreign_class!(
+#[deprecated(note = "use Bar")]
+class Foo {
+    self_type Foo;
+    constructor Foo::new() -> Foo;
+    #[deprecated(since = "0.3", note = "use g")]
+    fn Foo::f(&self) -> i32;
+    fn Foo::g(&self) -> i32;
+});
+"#,
+    )
+    .unwrap();
+    let rust_code_path = tmp_dir.path().join("test.rs");
+    Generator::new(LanguageConfig::PythonConfig(PythonConfig::new(
+        "test_deprecated".into(),
+    )))
+    .with_pointer_target_width(64)
+    .expand("test_deprecated_python", &rust_src_path, &rust_code_path);
+
+    let rust_code = fs::read_to_string(&rust_code_path).unwrap();
+    assert_eq!(2, rust_code.matches("DeprecationWarning").count());
+    assert!(rust_code.contains(r#""Foo is deprecated: use Bar""#));
+    assert!(rust_code.contains(r#""Foo.f is deprecated: since 0.3: use g""#));
+}
+
+#[test]
+fn test_closures_python() {
+    let _ = env_logger::try_init();
+    let tmp_dir = tempdir().expect("Can not create tmp directory");
+    let rust_src_path = tmp_dir.path().join("src.rs");
+    fs::write(
+        &rust_src_path,
+        r#"
+foreign_class!(class Foo {
+    self_type Foo;
+    constructor Foo::new() -> Foo;
+    fn Foo::map(&self, f: impl Fn(i32) -> i32) -> i32;
+    fn Foo::visit(&self, f: &dyn Fn(i32, &str));
+});
+"#,
+    )
+    .unwrap();
+    let rust_code_path = tmp_dir.path().join("test.rs");
+    Generator::new(LanguageConfig::PythonConfig(PythonConfig::new(
+        "test_closures".into(),
+    )))
+    .with_pointer_target_width(64)
+    .expand("test_closures_python", &rust_src_path, &rust_code_path);
+
+    let rust_code = fs::read_to_string(&rust_code_path).unwrap();
+    assert_eq!(2, rust_code.matches("ObjectProtocol :: call").count());
+    assert!(rust_code.contains("as Box < dyn Fn (i32) -> i32 + Send >"));
+    assert!(rust_code.contains("as & dyn Fn (i32 , & str)"));
+}
+
+#[test]
+fn test_python_same_method_names() {
+    let _ = env_logger::try_init();
+    let tmp_dir = tempdir().expect("Can not create tmp directory");
+    let rust_src_path = tmp_dir.path().join("src.rs");
+    fs::write(
+        &rust_src_path,
+        r#"
+foreign_class!(class Calc {
+    self_type Calc;
+    constructor Calc::new() -> Calc;
+    fn Calc::add_int(&mut self, x: i32) -> i32; alias add;
+    fn Calc::add_str(&mut self, x: &str) -> i32; alias add;
+});
+"#,
+    )
+    .unwrap();
+    let err = Generator::new(LanguageConfig::PythonConfig(PythonConfig::new(
+        "test_overloading".into(),
+    )))
+    .with_pointer_target_width(64)
+    .try_expand(
+        "test_python_same_method_names",
+        &rust_src_path,
+        tmp_dir.path().join("test.rs"),
+    )
+    .expect_err("try_expand should fail");
+    assert!(err
+        .message
+        .message
+        .contains("Python has no overloading, several methods have the same name `add`"));
+}
+
+#[test]
+fn test_python_default_of_optional_arg() {
+    let _ = env_logger::try_init();
+    let tmp_dir = tempdir().expect("Can not create tmp directory");
+    let rust_src_path = tmp_dir.path().join("src.rs");
+    fs::write(
+        &rust_src_path,
+        r#"
+foreign_class!(class Boo {
+    self_type Boo;
+    constructor Boo::default() -> Boo;
+});
+foreign_class!(class Foo {
+    fn Foo::f(boo: Option<&Boo> = Some(&BOO));
+});
+"#,
+    )
+    .unwrap();
+    let err = Generator::new(LanguageConfig::PythonConfig(PythonConfig::new(
+        "test_default_of_optional_arg".into(),
+    )))
+    .with_pointer_target_width(64)
+    .try_expand(
+        "test_python_default_of_optional_arg",
+        &rust_src_path,
+        tmp_dir.path().join("test.rs"),
+    )
+    .expect_err("try_expand should fail");
+    assert!(err
+        .message
+        .message
+        .contains("of optional argument with conversion is not supported for Python"));
+}
+
+#[test]
+fn test_python_module_fns_holder_name() {
+    let _ = env_logger::try_init();
+    let tmp_dir = tempdir().expect("Can not create tmp directory");
+    let rust_src_path = tmp_dir.path().join("src.rs");
+    fs::write(
+        &rust_src_path,
+        r#"
+foreign_class!(class ModuleFns {
+    self_type ModuleFns;
+    constructor ModuleFns::new() -> ModuleFns;
+});
+foreign_fn!(fn f(x: i32) -> i32;);
+"#,
+    )
+    .unwrap();
+    let out_path = tmp_dir.path().join("test.rs");
+    Generator::new(LanguageConfig::PythonConfig(PythonConfig::new(
+        "test_module_fns".into(),
+    )))
+    .with_pointer_target_width(64)
+    .rustfmt_bindings(true)
+    .try_expand(
+        "test_python_module_fns_holder_name",
+        &rust_src_path,
+        &out_path,
+    )
+    .expect("try_expand failed");
+    let code = fs::read_to_string(&out_path).unwrap();
+    assert_eq!(code.matches("mod py_module_fns {").count(), 1);
+    assert!(code.contains("mod py_module_fns0 {"));
+}
+
+#[test]
+fn test_closure_callback_name_conflicts() {
+    let _ = env_logger::try_init();
+    let tmp_dir = tempdir().expect("Can not create tmp directory");
+    let rust_src_path = tmp_dir.path().join("src.rs");
+    let expand = |src: &str| {
+        fs::write(&rust_src_path, src).unwrap();
+        let err = Generator::new(LanguageConfig::JavaConfig(JavaConfig::new(
+            tmp_dir.path().into(),
+            "org.example".into(),
+        )))
+        .with_pointer_target_width(64)
+        .try_expand(
+            "test_closure_callback_name_conflicts",
+            &rust_src_path,
+            tmp_dir.path().join("test.rs"),
+        )
+        .expect_err("try_expand should fail");
+        err.message.message
+    };
+    let msg = expand(
+        r#"
+foreign_class!(class I32Closure {
+    self_type I32Closure;
+    constructor I32Closure::new() -> I32Closure;
+    fn I32Closure::f(&self, f: &dyn Fn(i32));
+});
+"#,
+    );
+    assert!(msg.contains(
+        "callback I32Closure generated for closure `Fn(i32)` conflicts with item with the same name"
+    ));
+    let msg = expand(
+        r#"
+foreign_class!(class Foo {
+    self_type Foo;
+    constructor Foo::new() -> Foo;
+    fn Foo::f(&self, f: &dyn Fn(Option<String>));
+    fn Foo::g(&self, f: &dyn Fn(Option, String));
+});
+"#,
+    );
+    assert!(msg.contains("callback OptionStringClosure generated for closure"));
+    assert!(msg.contains("conflicts with callback for closure"));
+}
+
+#[test]
+fn test_export_model() {
+    let _ = env_logger::try_init();
+    let tmp_dir = tempdir().expect("Can not create tmp directory");
+    let java_dir = tmp_dir.path().join("java");
+    let cpp_dir = tmp_dir.path().join("cpp");
+    fs::create_dir(&java_dir).unwrap();
+    fs::create_dir(&cpp_dir).unwrap();
+    let rust_src_path = tmp_dir.path().join("src.rs");
+    fs::write(
+        &rust_src_path,
+        r#"
+foreign_enum!(enum Mode {
+    /// fast mode
+    FAST = Mode::Fast,
+    SLOW = Mode::Slow,
+});
+
+foreign_callback!(callback Observer {
+    self_type Observer;
+    onEvent = Observer::on_event(&self, x: i32) -> bool;
+});
+
+foreign_class!(class Foo {
+    self_type Foo;
+    constructor Foo::new(_: i32) -> Foo;
+    fn Foo::f(&mut self, a: i32, b: f64) -> bool;
+    private fn Foo::g(mode: Mode);
+});
+
+foreign_fn!(fn sum(a: i32, b: i32) -> i32;);
+"#,
+    )
+    .unwrap();
+    let rust_code_path = tmp_dir.path().join("test.rs");
+    let model_path = tmp_dir.path().join("model.json");
+    Generator::new(LanguageConfig::JavaConfig(JavaConfig::new(
+        java_dir,
+        "org.example".into(),
+    )))
+    .add_language(LanguageConfig::CppConfig(CppConfig::new(
+        cpp_dir,
+        "org_examples".into(),
+    )))
+    .with_pointer_target_width(64)
+    .export_model(&model_path)
+    .expand("test_export_model", &rust_src_path, &rust_code_path);
+
+    let model = fs::read_to_string(&model_path).unwrap();
+    assert!(model.starts_with(r#"{"format_version":1,"languages":["java","cpp"],"items":["#));
+    assert!(model.contains(
+        r#"{"name":"FAST","rust_path":"Mode::Fast","value":0,"doc_comments":[" fast mode"]}"#
+    ));
+    assert!(
+        model.contains(r#""return":{"rust":"bool","foreign":{"java":"boolean","cpp":"bool"}}}]}"#)
+    );
+    assert!(model.contains(
+        r#"{"name":"f","rust_path":"Foo::f","variant":"method","self_variant":"&mut self","access":"public","doc_comments":[],"args":[{"name":"a","type":{"rust":"i32","foreign":{"java":"int","cpp":"int32_t"}}},{"name":"b","type":{"rust":"f64","foreign":{"java":"double","cpp":"double"}}}]"#
+    ));
+    assert!(model.contains(
+        r#"{"name":"g","rust_path":"Foo::g","variant":"static_method","self_variant":null,"access":"private""#
+    ));
+    assert!(model.contains(
+        r#"{"kind":"fn","name":"sum","rust_path":"sum","variant":"static_method","self_variant":null,"access":"public""#
+    ));
+}
+
+#[test]
+fn test_api_compatibility_check() {
+    let _ = env_logger::try_init();
+    let tmp_dir = tempdir().expect("Can not create tmp directory");
+    let rust_src_path = tmp_dir.path().join("src.rs");
+    let rust_code_path = tmp_dir.path().join("test.rs");
+    let baseline_path = tmp_dir.path().join("api.json");
+    let generator = |src: &str, mode: Option<ApiCheckMode>| {
+        fs::write(&rust_src_path, src).unwrap();
+        let generator = Generator::new(LanguageConfig::CppConfig(CppConfig::new(
+            tmp_dir.path().join("cpp"),
+            "org_examples".into(),
+        )))
+        .with_pointer_target_width(64);
+        let generator = match mode {
+            Some(mode) => generator.check_api_compatibility(&baseline_path, mode),
+            None => generator.export_model(&baseline_path),
+        };
+        generator.try_expand(
+            "test_api_compatibility_check",
+            &rust_src_path,
+            &rust_code_path,
+        )
+    };
+    fs::create_dir(tmp_dir.path().join("cpp")).unwrap();
+    generator(
+        r#"
+foreign_class!(class Foo {
+    fn f(_: i32) -> i32;
+    fn g();
+});
+"#,
+        None,
+    )
+    .unwrap();
+
+    let new_src = r#"
+foreign_class!(class Foo {
+    fn g();
+    fn h();
+});
+"#;
+    let err = generator(new_src, Some(ApiCheckMode::Error)).unwrap_err();
+    assert!(err
+        .message
+        .message
+        .contains("method f removed from class Foo"));
+    assert!(!err.message.message.contains("method h"));
+    generator(new_src, Some(ApiCheckMode::Warn)).unwrap();
+}
+
+#[test]
+fn test_conv_graph_dot_and_explain() {
+    let _ = env_logger::try_init();
+    let tmp_dir = tempdir().expect("Can not create tmp directory");
+    let java_dir = tmp_dir.path().join("java");
+    let dot_dir = tmp_dir.path().join("dot");
+    let rust_src_path = tmp_dir.path().join("src.rs");
+    let rust_code_path = tmp_dir.path().join("test.rs");
+    fs::create_dir(&java_dir).unwrap();
+    fs::write(
+        &rust_src_path,
+        r#"
+foreign_class!(class Boo {
+    fn f(_: Vec<Foo>);
+});
+"#,
+    )
+    .unwrap();
+    let expand = |dot_dir: &Path| {
+        Generator::new(LanguageConfig::JavaConfig(JavaConfig::new(
+            java_dir.clone(),
+            "com.example".into(),
+        )))
+        .with_pointer_target_width(64)
+        .conv_graph_dot_output(dot_dir)
+        .explain_conversion_failures(true)
+        .try_expand(
+            "test_conv_graph_dot_and_explain",
+            &rust_src_path,
+            &rust_code_path,
+        )
+        .unwrap_err()
+    };
+    let err = expand(&dot_dir);
+    assert!(err
+        .message
+        .message
+        .contains("Do not know conversation from Java type to such rust type 'Vec < Foo >'"));
+    let explanation = &err
+        .notes
+        .iter()
+        .find(|x| x.message.contains("explanation of conversation"))
+        .expect("no explanation")
+        .message;
+    assert!(explanation.contains("candidate generic rules:"));
+    assert!(explanation
+        .contains("trait bound `T: SwigForeignClass + Clone` not satisfied for T = `Foo`"));
+
+    let dot = fs::read_to_string(dot_dir.join("java-conv-graph.dot")).unwrap();
+    assert!(dot.starts_with("digraph conv_graph {"));
+    assert!(dot.contains("[label=\"short\", shape=ellipse]"));
+    assert!(dot.contains("style=dashed"));
+
+    // failure of graph writing should not hide conversion error
+    let not_dir = tmp_dir.path().join("not_dir");
+    fs::write(&not_dir, "").unwrap();
+    let err = expand(&not_dir);
+    assert!(err
+        .message
+        .message
+        .contains("Do not know conversation from Java type to such rust type 'Vec < Foo >'"));
+}
+
+#[test]
+fn test_explain_python() {
+    let _ = env_logger::try_init();
+    let tmp_dir = tempdir().expect("Can not create tmp directory");
+    let rust_src_path = tmp_dir.path().join("src.rs");
+    fs::write(
+        &rust_src_path,
+        r#"
+foreign_class!(class Boo {
+    fn f(_: Foo);
+    fn g() -> Foo;
+});
+"#,
+    )
+    .unwrap();
+    let expand = |explain: bool| {
+        Generator::new(LanguageConfig::PythonConfig(PythonConfig::new(
+            "test_explain".into(),
+        )))
+        .with_pointer_target_width(64)
+        .explain_conversion_failures(explain)
+        .try_expand(
+            "test_explain_python",
+            &rust_src_path,
+            tmp_dir.path().join("test.rs"),
+        )
+        .unwrap_err()
+    };
+    let err = expand(true);
+    assert!(err
+        .message
+        .message
+        .contains("Unsupported argument type: Foo"));
+    assert!(err.notes.iter().any(|x| x
+        .message
+        .contains("explanation of conversation from foreign to Rust for type `Foo`")));
+
+    let err = expand(false);
+    assert!(err
+        .message
+        .message
+        .contains("Unsupported argument type: Foo"));
+    assert!(!err
+        .notes
+        .iter()
+        .any(|x| x.message.contains("explanation of conversation")));
+}
+
+#[test]
+fn test_type_maps_lint() {
+    let _ = env_logger::try_init();
+    let tmp_dir = tempdir().expect("Can not create tmp directory");
+    let rust_src_path = tmp_dir.path().join("src.rs");
+    let rust_code_path = tmp_dir.path().join("test.rs");
+    fs::write(
+        &rust_src_path,
+        r#"
+foreign_class!(class Foo {
+    fn f(_: i32) -> &str;
+});
+"#,
+    )
+    .unwrap();
+    Generator::new(LanguageConfig::JavaConfig(JavaConfig::new(
+        tmp_dir.path().into(),
+        "org.example".into(),
+    )))
+    .with_pointer_target_width(64)
+    .json_diagnostics(JsonDiagnosticsOutput::FileNextToOutput)
+    .lint_type_maps(true)
+    .merge_type_map(
+        "user_typemap",
+        r#"
+foreign_typemap!(
+    ($p:r_type) JavaString => &str {
+        $out = $p.to_str();
+    };
+);
+foreign_typemap!(
+    ($p:r_type) MetersA => Meters {
+        $out = Meters($p.0);
+    };
+);
+foreign_typemap!(
+    ($p:r_type) MetersB => Meters {
+        $out = Meters($p.0);
+    };
+);
+foreign_typemap!(
+    ($p:r_type) i32 => MetersA {
+        $out = MetersA($p);
+    };
+);
+foreign_typemap!(
+    ($p:r_type) i32 => MetersB {
+        $out = MetersB($p);
+    };
+);
+foreign_typemap!(
+    ($p:r_type) <T: SwigForeignClass> Option<Vec<T>> => internal_aliases::JForeignObjectsArray<T> {
+        $out = convert_opt_vec(env, $p);
+    };
+    ($p:f_type, option = "NullAnotations") => "swig_f_type!(T) []";
+);
+"#,
+    )
+    .try_expand("test_type_maps_lint", &rust_src_path, &rust_code_path)
+    .unwrap();
+    let json = fs::read_to_string(tmp_dir.path().join("test.rs.diagnostics.json")).unwrap();
+    let lints: Vec<&str> = json
+        .lines()
+        .filter(|l| !l.contains("mapping types"))
+        .collect();
+    assert_eq!(4, lints.len());
+    let find = |msg: &str| {
+        *lints
+            .iter()
+            .find(|l| l.contains(msg))
+            .unwrap_or_else(|| panic!("no lint with message: {}", msg))
+    };
+    let override_lint =
+        find("rule for conversation `JavaString` -> `& str` overrides builtin rule");
+    assert!(override_lint.contains(r#""file_name":"user_typemap""#));
+    assert!(override_lint.contains(r#""file_name":"jni-include.rs""#));
+    let ambiguous =
+        find("two equally short conversation paths from foreign type `int` to `Meters`");
+    assert!(ambiguous.contains("another path uses this rule"));
+    find("generic rule can never match any registered type");
+    find("rule is gated by option NullAnotations that no configuration enables");
+
+    Generator::new(LanguageConfig::JavaConfig(JavaConfig::new(
+        tmp_dir.path().into(),
+        "org.example".into(),
+    )))
+    .with_pointer_target_width(64)
+    .json_diagnostics(JsonDiagnosticsOutput::FileNextToOutput)
+    .merge_type_map(
+        "user_typemap",
+        r#"
+foreign_typemap!(
+    ($p:r_type) JavaString => &str {
+        $out = $p.to_str();
+    };
+);
+"#,
+    )
+    .try_expand("test_type_maps_lint", &rust_src_path, &rust_code_path)
+    .unwrap();
+    let json = fs::read_to_string(tmp_dir.path().join("test.rs.diagnostics.json")).unwrap();
+    // lint is disabled by default
+    assert!(!json.contains("overrides builtin rule"));
+}
+
+#[test]
+fn test_annotated_sources() {
+    let _ = env_logger::try_init();
+    let tmp_dir = tempdir().expect("Can not create tmp directory");
+    let java_dir = tmp_dir.path().join("java");
+    let crate_src_dir = tmp_dir.path().join("src");
+    fs::create_dir(&java_dir).unwrap();
+    fs::create_dir_all(crate_src_dir.join("nested")).unwrap();
+    fs::write(
+        crate_src_dir.join("nested").join("foo.rs"),
+        r#"
+pub struct Foo(i32);
+
+#[cfg_attr(flapigen, flapigen::export)]
+impl Foo {
+    pub fn new(x: i32) -> Self {
+        Foo(x)
+    }
+    /// get x
+    pub fn x(&self) -> i32 {
+        self.0
+    }
+    pub fn set_mode(&mut self, _: Mode) {}
+}
+
+#[cfg_attr(flapigen, flapigen::export)]
+pub enum Mode {
+    Fast,
+    Slow,
+}
+"#,
+    )
+    .unwrap();
+    fs::write(
+        crate_src_dir.join("observer.rs"),
+        r#"
+#[cfg_attr(flapigen, flapigen::callback)]
+pub trait Observer {
+    fn on_event(&self, x: i32);
+}
+
+pub mod util {
+    #[cfg_attr(flapigen, flapigen::export)]
+    pub fn helper() -> i32 {
+        1
+    }
+}
+"#,
+    )
+    .unwrap();
+    let glue_path = tmp_dir.path().join("glue.rs.in");
+    fs::write(&glue_path, "").unwrap();
+    let rust_code_path = tmp_dir.path().join("test.rs");
+    Generator::new(LanguageConfig::JavaConfig(JavaConfig::new(
+        java_dir.clone(),
+        "org.example".into(),
+    )))
+    .with_pointer_target_width(64)
+    .scan_annotated_sources(&crate_src_dir)
+    .expand("test_annotated_sources", &glue_path, &rust_code_path);
+
+    let rust_code = fs::read_to_string(&rust_code_path).unwrap();
+    assert!(rust_code.contains("nested :: foo :: Foo :: new"));
+    assert!(rust_code.contains("nested :: foo :: Mode :: Fast"));
+    assert!(rust_code.contains("observer :: util :: helper"));
+    assert!(rust_code.contains("impl observer :: Observer for JavaCallback"));
+    let java_code = collect_code_in_dir(&java_dir, &[".java"]).unwrap();
+    assert!(java_code.contains("public final class Foo"));
+    assert!(java_code.contains("public Foo(int x)"));
+    assert!(java_code.contains("* get x"));
+    assert!(java_code.contains("public final int x()"));
+    assert!(java_code.contains("public final void set_mode(Mode a0)"));
+    assert!(java_code.contains("public enum Mode"));
+    assert!(java_code.contains("public interface Observer"));
+}
+
+#[test]
+fn test_glue_with_include_and_mod() {
+    let _ = env_logger::try_init();
+    let tmp_dir = tempdir().expect("Can not create tmp directory");
+    let cpp_dir = tmp_dir.path().join("cpp");
+    let glue_dir = tmp_dir.path().join("glue");
+    fs::create_dir(&cpp_dir).unwrap();
+    fs::create_dir_all(glue_dir.join("sub")).unwrap();
+    let glue_path = glue_dir.join("glue.rs.in");
+    fs::write(
+        &glue_path,
+        r#"
+include!("enums.rs.in");
+mod sub;
+#[path = "other_name.rs"]
+pub(crate) mod other;
+mod inline {
+    foreign_class!(class Inline {
+        fn inline_f() -> i32;
+    });
+}
+"#,
+    )
+    .unwrap();
+    fs::write(
+        glue_dir.join("enums.rs.in"),
+        r#"
+foreign_enum!(enum Mode {
+    FAST = Mode::Fast,
+});
+"#,
+    )
+    .unwrap();
+    fs::write(
+        glue_dir.join("sub.rs"),
+        r#"
+struct InSub;
+mod nested;
+foreign_class!(class Sub {
+    fn sub_f(_: Mode) -> i32;
+});
+"#,
+    )
+    .unwrap();
+    fs::write(
+        glue_dir.join("sub").join("nested.rs"),
+        r#"
+foreign_class!(class Nested {
+    fn nested_f() -> i32;
+});
+"#,
+    )
+    .unwrap();
+    let other_path = glue_dir.join("other_name.rs");
+    fs::write(&other_path, "struct InOther;").unwrap();
+    let rust_code_path = tmp_dir.path().join("test.rs");
+    let generator = || {
+        Generator::new(LanguageConfig::CppConfig(CppConfig::new(
+            cpp_dir.clone(),
+            "org_examples".into(),
+        )))
+        .with_pointer_target_width(64)
+        .rustfmt_bindings(true)
+    };
+    generator().expand(
+        "test_glue_with_include_and_mod",
+        &glue_path,
+        &rust_code_path,
+    );
+
+    let rust_code = fs::read_to_string(&rust_code_path).unwrap();
+    assert!(rust_code.contains("pub(crate) mod other {\n    struct InOther;\n}"));
+    // code for items is generated inside of module where they are declared
+    let sub_start = rust_code.find("mod sub {\n    struct InSub;\n").unwrap();
+    let sub_len = rust_code[sub_start..].find("\n}\n").unwrap();
+    let sub_code = &rust_code[sub_start..sub_start + sub_len];
+    assert!(sub_code.contains("pub extern \"C\" fn Sub_sub_f("));
+    assert!(sub_code.contains("    mod nested {\n        #[allow("));
+    assert!(sub_code.contains("pub extern \"C\" fn Nested_nested_f("));
+    assert!(sub_code.contains("pub(crate) use super::nested::flapigen_cpp::*;"));
+    assert!(rust_code.contains("pub(crate) use self::sub::flapigen_cpp::*;"));
+    assert!(rust_code.contains("pub(crate) use self::inline::flapigen_cpp::*;"));
+    assert!(!rust_code.contains("other::flapigen_cpp"));
+    let cpp_code = collect_code_in_dir(&cpp_dir, &[".h", ".hpp"]).unwrap();
+    for name in &["Mode", "Sub", "Nested", "Inline"] {
+        assert!(
+            cpp_code.contains(&format!("class {}", name))
+                || cpp_code.contains(&format!("enum {}", name)),
+            "no {} in generated code",
+            name
+        );
+    }
+
+    fs::write(
+        &other_path,
+        "foreign_class!(class Bad { fn f(_: UnknownType); });",
+    )
+    .unwrap();
+    let err = generator()
+        .try_expand(
+            "test_glue_with_include_and_mod",
+            &glue_path,
+            &rust_code_path,
+        )
+        .unwrap_err();
+    let span = err.message.span.expect("no location of error");
+    assert!(span.file_name.ends_with("other_name.rs"));
+
+    fs::write(&other_path, r#"include!("glue.rs.in");"#).unwrap();
+    let err = generator()
+        .try_expand(
+            "test_glue_with_include_and_mod",
+            &glue_path,
+            &rust_code_path,
+        )
+        .unwrap_err();
+    assert!(err.message.message.contains("already included"));
+
+    fs::write(&other_path, r#"include!("sub/../enums.rs.in");"#).unwrap();
+    let err = generator()
+        .try_expand(
+            "test_glue_with_include_and_mod",
+            &glue_path,
+            &rust_code_path,
+        )
+        .unwrap_err();
+    assert!(err.message.message.contains("already included"));
+}
+
+struct ToyBackend {
+    name: &'static str,
+    foreign_code: Rc<RefCell<String>>,
+}
+
+impl LanguageBackend for ToyBackend {
+    fn name(&self) -> &str {
+        self.name
+    }
+    fn type_map_code(&self) -> String {
+        r#"
+foreign_typemap!(
+    (r_type) i32;
+    (f_type) "toy_int";
+);
+foreign_typemap!(
+    ($p:r_type) bool => u8 {
+        $out = if $p { 1 } else { 0 };
+    };
+    ($p:f_type) => "toy_bool";
+    ($p:r_type) bool <= u8 {
+        $out = $p != 0;
+    };
+    ($p:f_type) <= "toy_bool";
+);
+"#
+        .into()
+    }
+    fn expand_items(
+        &self,
+        ctx: &mut BackendContext,
+        items: &[Item],
+    ) -> Result<Vec<proc_macro2::TokenStream>, BackendError> {
+        let mut ret = Vec::new();
+        let mut out = self.foreign_code.borrow_mut();
+        for item in items {
+            let class = match item {
+                Item::Class(class) => class,
+                _ => continue,
+            };
+            out.push_str(&format!("class {} {{\n", class.name()));
+            for method in class.methods() {
+                let mut args = Vec::new();
+                for arg in method.args() {
+                    let ftype = ctx
+                        .map_to_foreign(arg.ty, ConvDirection::ForeignToRust, method.location())
+                        .ok_or_else(|| {
+                            BackendError::new(method.location(), "unknown argument type")
+                        })?;
+                    args.push(format!("{} {}", ftype.name, arg.name));
+                }
+                let ret_type = match method.output() {
+                    Some(ty) => {
+                        ctx.map_to_foreign(ty, ConvDirection::RustToForeign, method.location())
+                            .ok_or_else(|| {
+                                BackendError::new(method.location(), "unknown return type")
+                            })?
+                            .name
+                    }
+                    None => "void".into(),
+                };
+                let prefix = if method.variant() == MethodVariant::StaticMethod {
+                    "static "
+                } else {
+                    ""
+                };
+                out.push_str(&format!(
+                    "    {}{} {}({});\n",
+                    prefix,
+                    ret_type,
+                    method.name(),
+                    args.join(", ")
+                ));
+                let func_name = syn::Ident::new(
+                    &format!("toy_{}_{}", class.name(), method.name()),
+                    proc_macro2::Span::call_site(),
+                );
+                ret.push(quote::quote! { pub fn #func_name() {} });
+            }
+            out.push_str("}\n");
+        }
+        Ok(ret)
+    }
+}
+
+#[test]
+fn test_custom_backend() {
+    let _ = env_logger::try_init();
+    let tmp_dir = tempdir().expect("Can not create tmp directory");
+    let rust_src_path = tmp_dir.path().join("src.rs");
+    fs::write(
+        &rust_src_path,
+        r#"
+foreign_class!(class Foo {
+    fn f(_: i32, flag: bool) -> bool;
+    fn g();
+});
+"#,
+    )
+    .unwrap();
+    let rust_code_path = tmp_dir.path().join("test.rs");
+    let foreign_code = Rc::new(RefCell::new(String::new()));
+    Generator::new(LanguageConfig::Custom(Box::new(ToyBackend {
+        name: "toy",
+        foreign_code: foreign_code.clone(),
+    })))
+    .with_pointer_target_width(64)
+    .expand("test_custom_backend", &rust_src_path, &rust_code_path);
+    assert_eq!(
+        "class Foo {\n    static toy_bool f(toy_int a0, toy_bool flag);\n    static void g();\n}\n",
+        *foreign_code.borrow()
+    );
+    let rust_code = fs::read_to_string(&rust_code_path).unwrap();
+    assert!(rust_code.contains("fn toy_Foo_f"));
+    assert!(rust_code.contains("fn toy_Foo_g"));
+
+    fs::write(
+        &rust_src_path,
+        r#"
+foreign_class!(class Foo {
+    fn f(_: f64);
+});
+"#,
+    )
+    .unwrap();
+    let err = Generator::new(LanguageConfig::Custom(Box::new(ToyBackend {
+        name: "toy",
+        foreign_code: Rc::new(RefCell::new(String::new())),
+    })))
+    .with_pointer_target_width(64)
+    .try_expand("test_custom_backend", &rust_src_path, &rust_code_path)
+    .unwrap_err();
+    assert_eq!("unknown argument type", err.message.message);
+
+    for name in &["toy-lang", "toy lang"] {
+        let err = Generator::new(LanguageConfig::Custom(Box::new(ToyBackend {
+            name,
+            foreign_code: Rc::new(RefCell::new(String::new())),
+        })))
+        .with_pointer_target_width(64)
+        .try_expand("test_custom_backend", &rust_src_path, &rust_code_path)
+        .unwrap_err();
+        assert!(err
+            .message
+            .message
+            .contains(&format!("name `{}` of language backend is invalid", name)));
+    }
+}
+
 #[test]
 fn test_expectations_parse_without_self_type_err() {
     let _ = env_logger::try_init();
@@ -594,7 +1974,8 @@ fn parse_code(test_name: &str, rust_src: Source, lang: ForeignLang) -> Result<Co
                 JavaConfig::new(tmp_dir.path().into(), "org.example".into())
                     .use_null_annotation_from_package("android.support.annotation".into()),
             ))
-            .with_pointer_target_width(64);
+            .with_pointer_target_width(64)
+            .async_spawner("tokio::spawn");
 
             (swig_gen, &[".java"])
         }
@@ -603,7 +1984,8 @@ fn parse_code(test_name: &str, rust_src: Source, lang: ForeignLang) -> Result<Co
                 tmp_dir.path().into(),
                 "org_examples".into(),
             )))
-            .with_pointer_target_width(64);
+            .with_pointer_target_width(64)
+            .async_spawner("tokio::spawn");
             (swig_gen, &[".h", ".hpp"])
         }
     };
@@ -694,6 +2076,37 @@ fn check_expectation(test_name: &str, test_case: &Path, lang: ForeignLang) -> bo
     }
 }
 
+/// Python glue is only Rust code, so patterns from `.python_rs` are searched in it,
+/// whitespaces are ignored because code inside `py_class!` can not be formatted
+fn check_python_expectation(test_name: &str, test_case: &Path) -> bool {
+    let expectation = new_path(test_case, ".python_rs");
+    if !expectation.exists() {
+        return false;
+    }
+    let tmp_dir = tempdir().expect("Can not create tmp directory");
+    let rust_code_path = tmp_dir.path().join("test.rs");
+    Generator::new(LanguageConfig::PythonConfig(PythonConfig::new(
+        test_name.into(),
+    )))
+    .with_pointer_target_width(64)
+    .async_spawner("tokio::spawn")
+    .expand(test_name, test_case, &rust_code_path);
+    let without_spaces =
+        |code: &str| -> String { code.chars().filter(|ch| !ch.is_whitespace()).collect() };
+    let rust_code = fs::read_to_string(&rust_code_path).unwrap();
+    let pats = parse_code_expectation(&expectation).expect("parsing of patterns failed");
+    for pat in pats {
+        assert!(
+            without_spaces(&rust_code).contains(&without_spaces(&pat)),
+            "test {}: pattern not found in Python glue:\n{}\nglue:\n{}",
+            test_name,
+            pat,
+            rust_code
+        );
+    }
+    true
+}
+
 fn rustfmt_without_errors(rust_code: String) -> String {
     let rust_code2 = rust_code.clone();
     match rustfmt_cnt(rust_code.into_bytes(), RustEdition::Edition2018) {